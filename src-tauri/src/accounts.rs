@@ -0,0 +1,484 @@
+//! Saved Steam accounts
+//!
+//! Accounts are listed in `accounts.json` in the app data directory; their
+//! passwords go to the OS credential store (see `login_store`) and nowhere else,
+//! since jobs run unattended and can't ask for a passphrase. Without a store an
+//! account is saved without a password and relies on its cached login. Each
+//! account keeps its own DepotDownloader auth cache under `.auth/<username>`.
+//! Jobs reference an account by ID, and an AppID can have a default account used
+//! by jobs that don't name one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::depot_runner::{resolve_auth_cache_dir, sanitize_auth_username, JobMetadata};
+use crate::job_host::JobHost;
+use crate::login_store::{KeyringStore, LoginData, SecretStore};
+
+const ACCOUNTS_FILE_NAME: &str = "accounts.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedAccount {
+    pub id: String,
+    /// Label shown in the account picker
+    pub name: String,
+    pub username: String,
+}
+
+/// Saved accounts and per-AppID defaults (`accounts.json`)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSettings {
+    #[serde(default)]
+    pub accounts: Vec<SavedAccount>,
+    /// AppID → account ID
+    #[serde(default)]
+    pub default_accounts: BTreeMap<String, String>,
+}
+
+impl AccountSettings {
+    pub fn find(&self, account_id: &str) -> Option<&SavedAccount> {
+        let account_id = account_id.trim();
+        self.accounts
+            .iter()
+            .find(|account| account.id.eq_ignore_ascii_case(account_id))
+    }
+
+    /// Adds an account; the username must not already have one
+    pub fn add(&mut self, name: &str, username: &str) -> Result<SavedAccount, String> {
+        let username = username.trim();
+        if username.is_empty() {
+            return Err("Username is required.".to_string());
+        }
+        let id = account_id_for(username);
+        if let Some(existing) = self.find(&id) {
+            return Err(format!(
+                "Account {} is already saved as \"{}\"",
+                username, existing.name
+            ));
+        }
+
+        let name = name.trim();
+        let account = SavedAccount {
+            id,
+            name: if name.is_empty() { username } else { name }.to_string(),
+            username: username.to_string(),
+        };
+        self.accounts.push(account.clone());
+        Ok(account)
+    }
+
+    /// Removes an account along with any AppID defaults pointing at it
+    pub fn remove(&mut self, account_id: &str) -> Option<SavedAccount> {
+        let index = self
+            .accounts
+            .iter()
+            .position(|account| account.id.eq_ignore_ascii_case(account_id.trim()))?;
+        let removed = self.accounts.remove(index);
+        self.default_accounts
+            .retain(|_, id| !id.eq_ignore_ascii_case(&removed.id));
+        Some(removed)
+    }
+
+    /// Sets (or with `None`, clears) the default account for an AppID
+    pub fn set_default(&mut self, app_id: &str, account_id: Option<&str>) -> Result<(), String> {
+        let app_id = app_id.trim();
+        if app_id.is_empty() || !app_id.chars().all(|ch| ch.is_ascii_digit()) {
+            return Err(format!("Invalid AppID: {}", app_id));
+        }
+        match account_id.map(str::trim).filter(|id| !id.is_empty()) {
+            Some(account_id) => {
+                let id = self
+                    .find(account_id)
+                    .map(|account| account.id.clone())
+                    .ok_or_else(|| format!("Unknown account: {}", account_id))?;
+                self.default_accounts.insert(app_id.to_string(), id);
+            }
+            None => {
+                self.default_accounts.remove(app_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Account a job logs in with: the one it names, otherwise the AppID's default
+    /// for jobs that don't bring their own login (no username, no QR)
+    pub fn account_for_job(&self, job: &JobMetadata) -> Result<Option<&SavedAccount>, String> {
+        let requested = job.account_id.trim();
+        if !requested.is_empty() {
+            return self
+                .find(requested)
+                .map(Some)
+                .ok_or_else(|| format!("Unknown account: {}", requested));
+        }
+        if job.qr_enabled || !job.username.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(self
+            .default_accounts
+            .get(job.app_id.trim())
+            .and_then(|id| self.find(id)))
+    }
+}
+
+/// Account IDs are the username as sanitized for auth cache directories,
+/// lowercased as Steam usernames are case-insensitive
+fn account_id_for(username: &str) -> String {
+    sanitize_auth_username(username).to_ascii_lowercase()
+}
+
+fn secret_key(account_id: &str) -> String {
+    format!("account:{}", account_id)
+}
+
+fn accounts_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.app_data_dir()?.join(ACCOUNTS_FILE_NAME))
+}
+
+fn read_accounts_file(path: &Path) -> Result<AccountSettings, String> {
+    if !path.exists() {
+        return Ok(AccountSettings::default());
+    }
+
+    let json = fs::read_to_string(path).map_err(|e| format!("Failed to read accounts: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse accounts: {}", e))
+}
+
+fn write_accounts_file(path: &Path, settings: &AccountSettings) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize accounts: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write accounts: {}", e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write accounts: {}", e))
+}
+
+pub fn load_account_settings(host: &dyn JobHost) -> Result<AccountSettings, String> {
+    read_accounts_file(&accounts_path(host)?)
+}
+
+/// Fills in the login of the job's account (see `AccountSettings::account_for_job`).
+/// Without a saved password the account's cached login is used; an account with
+/// neither can't log in unattended and fails the job up front.
+fn apply_account(
+    settings: &AccountSettings,
+    store: &dyn SecretStore,
+    auth_cached: &dyn Fn(&str) -> bool,
+    job: &JobMetadata,
+) -> Result<(JobMetadata, Option<String>), String> {
+    let Some(account) = settings.account_for_job(job)? else {
+        return Ok((job.clone(), None));
+    };
+
+    let mut resolved = job.clone();
+    resolved.account_id = account.id.clone();
+    resolved.username = account.username.clone();
+    resolved.password.clear();
+    let mut note = format!("Using saved account \"{}\" ({})", account.name, account.username);
+
+    let store_error = match store.get(&secret_key(&account.id)) {
+        Ok(Some(password)) => {
+            resolved.password = password;
+            return Ok((resolved, Some(note)));
+        }
+        Ok(None) => None,
+        Err(err) => Some(err),
+    };
+    if !auth_cached(&account.username) {
+        return Err(format!(
+            "Saved account \"{}\" has no password{} and no cached login. Log in with it once so the login is cached, or add it again with a password.",
+            account.name,
+            store_error
+                .map(|err| format!(" available ({})", err))
+                .unwrap_or_default()
+        ));
+    }
+    note.push_str(" with its cached login");
+    Ok((resolved, Some(note)))
+}
+
+/// Resolves the saved account a job logs in with. Returns the job with the
+/// account's username and password filled in, and a log line naming the account.
+pub fn resolve_job_account(
+    host: &dyn JobHost,
+    job: &JobMetadata,
+) -> Result<(JobMetadata, Option<String>), String> {
+    let settings = load_account_settings(host)?;
+    let auth_cached = |username: &str| {
+        resolve_auth_cache_dir(host, username)
+            .map(|dir| dir.is_dir())
+            .unwrap_or(false)
+    };
+    apply_account(&settings, &KeyringStore, &auth_cached, job)
+}
+
+/// Puts a one-off login (typed into the login panel, or a cached QR login) on a
+/// job. Logins are never part of the job itself; a job names either a saved
+/// account or brings a login, not both.
+pub fn apply_login(job: &mut JobMetadata, login: Option<LoginData>) -> Result<(), String> {
+    let Some(login) = login.filter(|login| !login.username.trim().is_empty()) else {
+        return Ok(());
+    };
+    if !job.account_id.trim().is_empty() {
+        return Err("A job can log in with a saved account or its own login, not both".to_string());
+    }
+    job.username = login.username.trim().to_string();
+    job.password = login.password;
+    Ok(())
+}
+
+/// A saved account as shown in settings
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    #[serde(flatten)]
+    pub account: SavedAccount,
+    /// The credential store holds a password for this account
+    pub password_saved: bool,
+    /// DepotDownloader has a cached login for this account
+    pub auth_cached: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountList {
+    pub accounts: Vec<AccountInfo>,
+    pub default_accounts: BTreeMap<String, String>,
+}
+
+#[tauri::command]
+pub fn list_saved_accounts(app_handle: AppHandle) -> Result<AccountList, String> {
+    let settings = load_account_settings(&app_handle)?;
+    let accounts = settings
+        .accounts
+        .into_iter()
+        .map(|account| {
+            let password_saved = matches!(
+                KeyringStore.get(&secret_key(&account.id)),
+                Ok(Some(_))
+            );
+            let auth_cached = resolve_auth_cache_dir(&app_handle, &account.username)
+                .map(|dir| dir.is_dir())
+                .unwrap_or(false);
+            AccountInfo {
+                account,
+                password_saved,
+                auth_cached,
+            }
+        })
+        .collect();
+    Ok(AccountList {
+        accounts,
+        default_accounts: settings.default_accounts,
+    })
+}
+
+/// Saves an account. The password is optional: without one, jobs rely on the
+/// account's cached login. It is only ever kept in the credential store.
+#[tauri::command]
+pub fn add_saved_account(
+    app_handle: AppHandle,
+    name: String,
+    username: String,
+    password: Option<String>,
+) -> Result<SavedAccount, String> {
+    let path = accounts_path(&app_handle)?;
+    let mut settings = read_accounts_file(&path)?;
+    let account = settings.add(&name, &username)?;
+
+    if let Some(password) = password.filter(|password| !password.is_empty()) {
+        KeyringStore
+            .set(&secret_key(&account.id), &password)
+            .map_err(|err| {
+                format!(
+                    "{}. Account passwords are only kept in the credential store; add the account without a password and log in with it once so the login is cached.",
+                    err
+                )
+            })?;
+    }
+
+    write_accounts_file(&path, &settings)?;
+    Ok(account)
+}
+
+/// Removes an account, its saved password and its cached login
+#[tauri::command]
+pub fn remove_saved_account(app_handle: AppHandle, account_id: String) -> Result<(), String> {
+    let path = accounts_path(&app_handle)?;
+    let mut settings = read_accounts_file(&path)?;
+    let account = settings
+        .remove(&account_id)
+        .ok_or_else(|| format!("Unknown account: {}", account_id))?;
+    write_accounts_file(&path, &settings)?;
+
+    // An unavailable store can't be holding the password
+    if let Err(err) = KeyringStore.delete(&secret_key(&account.id)) {
+        eprintln!("{err}");
+    }
+    let cache_dir = resolve_auth_cache_dir(&app_handle, &account.username)?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir).map_err(|e| {
+            format!(
+                "Failed to remove auth cache {}: {}",
+                cache_dir.display(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Sets the account jobs for `app_id` use by default; `None` clears it
+#[tauri::command]
+pub fn set_default_account(
+    app_handle: AppHandle,
+    app_id: String,
+    account_id: Option<String>,
+) -> Result<(), String> {
+    let path = accounts_path(&app_handle)?;
+    let mut settings = read_accounts_file(&path)?;
+    settings.set_default(&app_id, account_id.as_deref())?;
+    write_accounts_file(&path, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::login_store::test_support::MemoryStore;
+
+    fn job(app_id: &str) -> JobMetadata {
+        JobMetadata {
+            app_id: app_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_derives_ids_and_rejects_duplicates() {
+        let mut settings = AccountSettings::default();
+        let account = settings.add("", " Builder@Team ").unwrap();
+        assert_eq!(account.id, "builder_team");
+        assert_eq!(account.name, "Builder@Team");
+        assert!(settings.add("Other", "builder@team").is_err());
+        assert!(settings.add("Blank", "  ").is_err());
+        assert_eq!(settings.find("BUILDER_TEAM"), Some(&account));
+    }
+
+    #[test]
+    fn test_defaults_follow_their_account() {
+        let mut settings = AccountSettings::default();
+        settings.add("Main", "main").unwrap();
+        settings.add("QA", "qa").unwrap();
+        settings.set_default("480", Some("qa")).unwrap();
+        settings.set_default("570", Some("main")).unwrap();
+        assert!(settings.set_default("730", Some("missing")).is_err());
+        assert!(settings.set_default("not-an-app", Some("qa")).is_err());
+
+        assert_eq!(settings.remove("qa").unwrap().username, "qa");
+        assert!(settings.remove("qa").is_none());
+        assert_eq!(settings.default_accounts.len(), 1);
+        settings.set_default("570", None).unwrap();
+        assert!(settings.default_accounts.is_empty());
+    }
+
+    #[test]
+    fn test_account_for_job_prefers_explicit_then_default() {
+        let mut settings = AccountSettings::default();
+        settings.add("Main", "main").unwrap();
+        settings.add("QA", "qa").unwrap();
+        settings.set_default("480", Some("qa")).unwrap();
+
+        let pick = |job: &JobMetadata| {
+            settings
+                .account_for_job(job)
+                .unwrap()
+                .map(|account| account.id.clone())
+        };
+        assert_eq!(pick(&job("480")), Some("qa".to_string()));
+        assert_eq!(pick(&job("570")), None);
+
+        let mut explicit = job("480");
+        explicit.account_id = "main".to_string();
+        assert_eq!(pick(&explicit), Some("main".to_string()));
+
+        let mut own_login = job("480");
+        own_login.username = "someone".to_string();
+        assert_eq!(pick(&own_login), None);
+        let mut qr = job("480");
+        qr.qr_enabled = true;
+        assert_eq!(pick(&qr), None);
+
+        let mut unknown = job("480");
+        unknown.account_id = "gone".to_string();
+        assert!(settings.account_for_job(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_apply_account_fills_in_credentials() {
+        let mut settings = AccountSettings::default();
+        settings.add("Main", "Main").unwrap();
+        settings.add("Cache only", "cached").unwrap();
+        settings.add("Nothing saved", "empty").unwrap();
+        let store = MemoryStore::default();
+        store.set(&secret_key("main"), "hunter2").unwrap();
+        let auth_cached = |username: &str| username == "cached";
+
+        let mut with_password = job("480");
+        with_password.account_id = "main".to_string();
+        with_password.username = "ignored".to_string();
+        let (resolved, note) = apply_account(&settings, &store, &auth_cached, &with_password).unwrap();
+        assert_eq!(resolved.username, "Main");
+        assert_eq!(resolved.password, "hunter2");
+        assert!(note.unwrap().contains("\"Main\""));
+
+        let mut cached = job("480");
+        cached.account_id = "cached".to_string();
+        let (resolved, _) = apply_account(&settings, &store, &auth_cached, &cached).unwrap();
+        assert_eq!(resolved.username, "cached");
+        assert!(resolved.password.is_empty());
+
+        let mut nothing = job("480");
+        nothing.account_id = "empty".to_string();
+        assert!(apply_account(&settings, &store, &auth_cached, &nothing).is_err());
+        // A store that can't be read counts as no password
+        let locked = MemoryStore::new(false);
+        assert!(apply_account(&settings, &locked, &auth_cached, &with_password).is_err());
+        assert!(apply_account(&settings, &locked, &auth_cached, &cached).is_ok());
+
+        let (anonymous, note) = apply_account(&settings, &store, &auth_cached, &job("570")).unwrap();
+        assert!(anonymous.username.is_empty());
+        assert!(note.is_none());
+    }
+
+    #[test]
+    fn test_apply_login_fills_in_a_one_off_login() {
+        let login = |username: &str| {
+            Some(LoginData {
+                username: username.to_string(),
+                password: "hunter2".to_string(),
+            })
+        };
+
+        let mut own = job("480");
+        apply_login(&mut own, login(" builder ")).unwrap();
+        assert_eq!(own.username, "builder");
+        assert_eq!(own.password, "hunter2");
+
+        let mut blank = job("480");
+        apply_login(&mut blank, login("  ")).unwrap();
+        assert!(blank.username.is_empty() && blank.password.is_empty());
+
+        let mut both = job("480");
+        both.account_id = "main".to_string();
+        assert!(apply_login(&mut both, login("builder")).is_err());
+        assert!(apply_login(&mut both, None).is_ok());
+    }
+}
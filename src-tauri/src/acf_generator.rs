@@ -10,57 +10,10 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use crate::job_metadata::JobMetadataFile;
+use crate::depot_manifest::DepotManifest;
+use crate::job_metadata::{DepotInfo, JobMetadataFile};
 use crate::steam_api::{get_shared_depot_owner, is_shared_depot};
-
-/// VDF (Valve Data Format) builder for generating properly formatted .acf files
-struct VdfBuilder {
-    content: String,
-    indent_level: usize,
-}
-
-impl VdfBuilder {
-    fn new() -> Self {
-        Self {
-            content: String::new(),
-            indent_level: 0,
-        }
-    }
-
-    /// Adds indentation tabs for the current level
-    fn indent(&mut self) {
-        for _ in 0..self.indent_level {
-            self.content.push('\t');
-        }
-    }
-
-    /// Writes a key-value pair: "key"		"value"
-    fn key_value(&mut self, key: &str, value: &str) {
-        self.indent();
-        self.content.push_str(&format!("\"{}\"\t\t\"{}\"\n", key, value));
-    }
-
-    /// Opens a new section: "name"\n{\n
-    fn open_section(&mut self, name: &str) {
-        self.indent();
-        self.content.push_str(&format!("\"{}\"\n", name));
-        self.indent();
-        self.content.push_str("{\n");
-        self.indent_level += 1;
-    }
-
-    /// Closes the current section: }\n
-    fn close_section(&mut self) {
-        self.indent_level = self.indent_level.saturating_sub(1);
-        self.indent();
-        self.content.push_str("}\n");
-    }
-
-    /// Returns the built VDF content
-    fn build(self) -> String {
-        self.content
-    }
-}
+use crate::vdf::{self, VdfObject, VdfValue};
 
 /// Calculates the total size of all files in a directory recursively
 fn calculate_size_on_disk(path: &Path) -> u64 {
@@ -88,27 +41,37 @@ fn calculate_size_on_disk(path: &Path) -> u64 {
     total_size
 }
 
-/// Generates the content for an appmanifest .acf file
+/// Manifest ID to record for a depot: the decoded manifest's ID, then the pinned
+/// manifest if one was requested, otherwise the ID from job metadata
+fn installed_manifest_id(depot: &DepotInfo, manifests: &HashMap<String, DepotManifest>) -> String {
+    manifests
+        .get(&depot.depot_id)
+        .and_then(|manifest| manifest.manifest_id)
+        .map(|id| id.to_string())
+        .or_else(|| depot.manifest_id_used.clone())
+        .unwrap_or_else(|| depot.manifest_id.clone())
+}
+
+/// Builds the `AppState` tree for an appmanifest .acf file
 ///
 /// # Arguments
 /// * `metadata` - Job metadata containing app/depot/build information
 /// * `common_dir` - Path to the steamapps/common directory (for size calculation)
 /// * `install_dir_name` - Name of the installation directory (relative path component)
-/// * `manifest_map` - Map of depot_id → actual manifest_id (extracted from .manifest filenames)
+/// * `manifests` - Map of depot_id → decoded depot manifest (from depotcache/)
 ///
 /// # Returns
-/// The complete .acf file content as a string
-fn generate_acf_content(
+/// The document root, holding a single `AppState` section
+fn build_acf(
     metadata: &JobMetadataFile,
     common_dir: &Path,
     install_dir_name: &str,
-    manifest_map: &HashMap<String, String>,
-) -> String {
-    let mut vdf = VdfBuilder::new();
-
-    // Get Unix timestamp from build datetime, or use current time as fallback
+    manifests: &HashMap<String, DepotManifest>,
+) -> VdfObject {
+    // Get Unix timestamp from build datetime, then the newest manifest, or use current time as fallback
     let last_updated = metadata
         .build_datetime_utc
+        .or_else(|| manifests.values().filter_map(|m| m.creation_time).max())
         .map(|dt| dt.timestamp())
         .unwrap_or_else(|| chrono::Utc::now().timestamp());
 
@@ -121,100 +84,108 @@ fn generate_acf_content(
         .iter()
         .partition(|d| is_shared_depot(&d.depot_id));
 
-    // Calculate per-depot sizes
+    // Per-depot sizes come from the manifest; depots without one are measured on disk
     let depot_sizes: HashMap<String, u64> = metadata
         .depots
         .iter()
         .map(|d| {
-            let depot_dir = common_dir.join(&d.depot_name);
-            (d.depot_id.clone(), calculate_size_on_disk(&depot_dir))
+            let size = match manifests.get(&d.depot_id) {
+                Some(manifest) => manifest.content_size(),
+                None => calculate_size_on_disk(&common_dir.join(&d.depot_name)),
+            };
+            (d.depot_id.clone(), size)
         })
         .collect();
 
-    vdf.open_section("AppState");
+    let mut app_state = VdfObject::new();
 
     // Core app information
-    vdf.key_value("appid", &metadata.appid);
-    vdf.key_value("universe", "1");  // lowercase per Steam format
-    vdf.key_value("name", &metadata.game_name);
-    vdf.key_value("StateFlags", "4"); // 4 = Fully Installed
-    vdf.key_value("installdir", install_dir_name);
-    vdf.key_value("LastUpdated", &last_updated.to_string());
-    vdf.key_value("UpdateResult", "0");
-    vdf.key_value("SizeOnDisk", &size_on_disk.to_string());
-    vdf.key_value("buildid", &metadata.build_id);
+    app_state.push_str("appid", &metadata.appid);
+    app_state.push_str("universe", "1");  // lowercase per Steam format
+    app_state.push_str("name", &metadata.game_name);
+    app_state.push_str("StateFlags", "4"); // 4 = Fully Installed
+    app_state.push_str("installdir", install_dir_name);
+    app_state.push_str("LastUpdated", &last_updated.to_string());
+    app_state.push_str("UpdateResult", "0");
+    app_state.push_str("SizeOnDisk", &size_on_disk.to_string());
+    app_state.push_str("buildid", &metadata.build_id);
 
     // PRIVACY: LastOwner is ALWAYS "0" to prevent deanonymization
-    vdf.key_value("LastOwner", "0");
+    app_state.push_str("LastOwner", "0");
 
     // Download state (complete)
-    vdf.key_value("BytesToDownload", "0");
-    vdf.key_value("BytesDownloaded", "0");
+    app_state.push_str("BytesToDownload", "0");
+    app_state.push_str("BytesDownloaded", "0");
 
     // Update behavior
-    vdf.key_value("AutoUpdateBehavior", "0");
-    vdf.key_value("AllowOtherDownloadsWhileRunning", "0");
-    vdf.key_value("ScheduledAutoUpdate", "0");
+    app_state.push_str("AutoUpdateBehavior", "0");
+    app_state.push_str("AllowOtherDownloadsWhileRunning", "0");
+    app_state.push_str("ScheduledAutoUpdate", "0");
 
     // User configuration
-    vdf.open_section("UserConfig");
-    vdf.key_value("language", "english");
-    vdf.close_section();
+    let mut user_config = VdfObject::new();
+    user_config.push_str("language", metadata.language.as_deref().unwrap_or("english"));
+    app_state.push("UserConfig", VdfValue::Section(user_config));
 
     // InstalledDepots section - only regular (non-shared) depots
-    vdf.open_section("InstalledDepots");
+    let mut installed_depots = VdfObject::new();
     for depot in &regular_depots {
-        // Use manifest from manifest_map (extracted from .manifest filename), then fall back
-        // to manifest_id_used if set, otherwise use manifest_id from metadata
-        let manifest = manifest_map
-            .get(&depot.depot_id)
-            .or(depot.manifest_id_used.as_ref())
-            .unwrap_or(&depot.manifest_id);
-
+        let manifest = installed_manifest_id(depot, manifests);
         let depot_size = depot_sizes.get(&depot.depot_id).copied().unwrap_or(0);
 
-        vdf.open_section(&depot.depot_id);
-        vdf.key_value("manifest", manifest);
-        vdf.key_value("size", &depot_size.to_string());
-        vdf.close_section();
+        let mut entry = VdfObject::new();
+        entry.push_str("manifest", &manifest);
+        entry.push_str("size", &depot_size.to_string());
+        installed_depots.push(&depot.depot_id, VdfValue::Section(entry));
     }
-    vdf.close_section();
+    app_state.push("InstalledDepots", VdfValue::Section(installed_depots));
 
     // SharedDepots section - shared depots with their owner appids
     if !shared_depots.is_empty() {
-        vdf.open_section("SharedDepots");
+        let mut shared = VdfObject::new();
         for depot in &shared_depots {
             let owner_appid = get_shared_depot_owner(&depot.depot_id);
-            vdf.key_value(&depot.depot_id, owner_appid);
+            shared.push_str(&depot.depot_id, owner_appid);
         }
-        vdf.close_section();
+        app_state.push("SharedDepots", VdfValue::Section(shared));
     }
 
     // MountedDepots section (all depots, both regular and shared)
-    vdf.open_section("MountedDepots");
+    let mut mounted = VdfObject::new();
     for depot in &metadata.depots {
-        let manifest = manifest_map
-            .get(&depot.depot_id)
-            .or(depot.manifest_id_used.as_ref())
-            .unwrap_or(&depot.manifest_id);
-
-        vdf.key_value(&depot.depot_id, manifest);
+        let manifest = installed_manifest_id(depot, manifests);
+        mounted.push_str(&depot.depot_id, &manifest);
     }
-    vdf.close_section();
+    app_state.push("MountedDepots", VdfValue::Section(mounted));
 
-    vdf.close_section(); // Close AppState
+    let mut root = VdfObject::new();
+    root.push("AppState", VdfValue::Section(app_state));
+    root
+}
 
-    vdf.build()
+/// Overlays a freshly generated ACF onto one that is already installed.
+///
+/// Keys Steam added that we don't write (staging state, beta branch, install
+/// scripts) are kept. The depot lists are ours alone, so they are replaced
+/// rather than merged to avoid leaving stale depots behind.
+pub fn merge_acf(existing: &mut VdfObject, generated: &VdfObject) {
+    if let Some(app_state) = existing.get_section_mut("AppState") {
+        for section in ["InstalledDepots", "SharedDepots", "MountedDepots"] {
+            app_state.remove(section);
+        }
+    }
+    existing.merge(generated);
 }
 
-/// Writes the appmanifest .acf file to the steamapps directory
+/// Writes the appmanifest .acf file to the steamapps directory, merging into
+/// an existing appmanifest for the same app when there is one
 ///
 /// # Arguments
 /// * `steamapps_dir` - Path to the steamapps directory
 /// * `metadata` - Job metadata containing app/depot/build information
 /// * `common_dir` - Path to the steamapps/common directory (for size calculation)
 /// * `install_dir_name` - Name of the installation directory (the game folder name)
-/// * `manifest_map` - Map of depot_id → actual manifest_id (extracted from .manifest filenames)
+/// * `manifests` - Map of depot_id → decoded depot manifest
 ///
 /// # Returns
 /// * `Ok(())` on success
@@ -224,16 +195,26 @@ pub fn write_acf_file(
     metadata: &JobMetadataFile,
     common_dir: &Path,
     install_dir_name: &str,
-    manifest_map: &HashMap<String, String>,
+    manifests: &HashMap<String, DepotManifest>,
 ) -> Result<(), String> {
-    // Generate .acf content (calculate size from entire common directory)
-    let acf_content = generate_acf_content(metadata, common_dir, install_dir_name, manifest_map);
+    // Build the .acf tree (calculate size from entire common directory)
+    let mut acf = build_acf(metadata, common_dir, install_dir_name, manifests);
 
     // Write to appmanifest_<appid>.acf
     let acf_filename = format!("appmanifest_{}.acf", metadata.appid);
     let acf_path = steamapps_dir.join(&acf_filename);
 
-    fs::write(&acf_path, acf_content)
+    // An unreadable existing file is replaced outright
+    if let Some(mut existing) = fs::read_to_string(&acf_path)
+        .ok()
+        .and_then(|text| vdf::parse(&text).ok())
+        .filter(|existing| existing.get_section("AppState").is_some())
+    {
+        merge_acf(&mut existing, &acf);
+        acf = existing;
+    }
+
+    fs::write(&acf_path, vdf::to_string(&acf))
         .map_err(|e| format!("Failed to write {}: {}", acf_filename, e))?;
 
     Ok(())
@@ -242,9 +223,18 @@ pub fn write_acf_file(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::job_metadata::{BuildIdSource, DepotInfo};
+    use crate::job_metadata::BuildIdSource;
     use chrono::TimeZone;
 
+    fn generate_acf_content(
+        metadata: &JobMetadataFile,
+        common_dir: &Path,
+        install_dir_name: &str,
+        manifests: &HashMap<String, DepotManifest>,
+    ) -> String {
+        vdf::to_string(&build_acf(metadata, common_dir, install_dir_name, manifests))
+    }
+
     fn create_test_metadata() -> JobMetadataFile {
         JobMetadataFile::new(
             "test-job-id".to_string(),
@@ -266,26 +256,59 @@ mod tests {
     }
 
     #[test]
-    fn test_vdf_builder_key_value() {
-        let mut vdf = VdfBuilder::new();
-        vdf.key_value("appid", "12345");
-        assert_eq!(vdf.build(), "\"appid\"\t\t\"12345\"\n");
+    fn test_vdf_key_value() {
+        let mut root = VdfObject::new();
+        root.push_str("appid", "12345");
+        assert_eq!(vdf::to_string(&root), "\"appid\"\t\t\"12345\"\n");
     }
 
     #[test]
-    fn test_vdf_builder_section() {
-        let mut vdf = VdfBuilder::new();
-        vdf.open_section("AppState");
-        vdf.key_value("appid", "12345");
-        vdf.close_section();
+    fn test_vdf_section() {
+        let mut section = VdfObject::new();
+        section.push_str("appid", "12345");
+        let mut root = VdfObject::new();
+        root.push("AppState", VdfValue::Section(section));
 
-        let result = vdf.build();
+        let result = vdf::to_string(&root);
         assert!(result.contains("\"AppState\"\n"));
         assert!(result.contains("{\n"));
         assert!(result.contains("\t\"appid\"\t\t\"12345\"\n"));
         assert!(result.contains("}\n"));
     }
 
+    #[test]
+    fn test_game_name_with_quotes_round_trips() {
+        let mut metadata = create_test_metadata();
+        metadata.game_name = "The \"Quoted\" Game \\ Edition".to_string();
+        let content = generate_acf_content(&metadata, Path::new("/tmp/test"), "Test Game", &HashMap::new());
+
+        assert!(content.contains("\"name\"\t\t\"The \\\"Quoted\\\" Game \\\\ Edition\""));
+        let parsed = vdf::parse(&content).unwrap();
+        assert_eq!(
+            parsed.get_path(&["AppState"]).unwrap().get_str("name"),
+            Some(metadata.game_name.as_str())
+        );
+    }
+
+    #[test]
+    fn test_merge_acf_keeps_steam_keys_and_replaces_depots() {
+        let metadata = create_test_metadata();
+        let generated = build_acf(&metadata, Path::new("/tmp/test"), "Test Game", &HashMap::new());
+        let mut existing = vdf::parse(
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"47410\"\n\t\"buildid\"\t\t\"1\"\n\t\"StagingSize\"\t\t\"0\"\n\t\"InstalledDepots\"\n\t{\n\t\t\"99999\"\n\t\t{\n\t\t\t\"manifest\"\t\t\"1\"\n\t\t}\n\t}\n}\n",
+        )
+        .unwrap();
+
+        merge_acf(&mut existing, &generated);
+
+        let app_state = existing.get_section("AppState").unwrap();
+        assert_eq!(app_state.get_str("buildid"), Some("3354190"));
+        assert_eq!(app_state.get_str("StagingSize"), Some("0"));
+        let depots = app_state.get_section("InstalledDepots").unwrap();
+        assert!(depots.get("99999").is_none());
+        assert!(depots.get("47411").is_some());
+    }
+
     #[test]
     fn test_generate_acf_content_has_required_fields() {
         let metadata = create_test_metadata();
@@ -337,12 +360,41 @@ mod tests {
         let metadata = create_test_metadata();
         let mut manifest_map = HashMap::new();
         // Override the manifest ID with a different value
-        manifest_map.insert("47411".to_string(), "1234567890123456789".to_string());
+        manifest_map.insert(
+            "47411".to_string(),
+            DepotManifest {
+                depot_id: Some(47411),
+                manifest_id: Some(1234567890123456789),
+                original_size: 4096,
+                ..Default::default()
+            },
+        );
 
         let content = generate_acf_content(&metadata, Path::new("/tmp/test"), "Test Game", &manifest_map);
 
-        // Should use manifest ID from map, not from metadata
+        // Should use manifest ID and size from the decoded manifest, not from metadata
         assert!(content.contains("\"manifest\"\t\t\"1234567890123456789\""));
         assert!(!content.contains("\"manifest\"\t\t\"6777399203159127119\""));
+        assert!(content.contains("\"size\"\t\t\"4096\""));
+    }
+
+    #[test]
+    fn test_last_updated_falls_back_to_manifest_creation_time() {
+        let mut metadata = create_test_metadata();
+        metadata.build_datetime_utc = None;
+        let mut manifest_map = HashMap::new();
+        manifest_map.insert(
+            "47411".to_string(),
+            DepotManifest {
+                creation_time: Some(chrono::Utc.with_ymd_and_hms(2025, 2, 24, 22, 2, 36).unwrap()),
+                ..Default::default()
+            },
+        );
+
+        let content = generate_acf_content(&metadata, Path::new("/tmp/test"), "Test Game", &manifest_map);
+
+        assert!(content.contains("\"LastUpdated\"\t\t\"1740434556\""));
+        // No manifest ID in the manifest: keep the one from job metadata
+        assert!(content.contains("\"manifest\"\t\t\"6777399203159127119\""));
     }
 }
@@ -0,0 +1,265 @@
+//! Archive formats for compressed job outputs
+//!
+//! `7z` and `zip` are written by 7-Zip. `tar.zst` and `tar.xz` are written
+//! in-process (see `tar_archive`), since stock 7-Zip builds can't produce zstd
+//! and would need two passes for a compressed tarball. Any format can be split
+//! into volumes named `<archive>.001`, `<archive>.002`, ... for upload limits.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "7z")]
+    SevenZip,
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    #[serde(rename = "tar.xz")]
+    TarXz,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 4] = [
+        ArchiveFormat::SevenZip,
+        ArchiveFormat::Zip,
+        ArchiveFormat::TarZst,
+        ArchiveFormat::TarXz,
+    ];
+
+    /// File extension without the leading dot ("7z", "tar.zst", ...)
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::SevenZip => "7z",
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarZst => "tar.zst",
+            ArchiveFormat::TarXz => "tar.xz",
+        }
+    }
+
+    /// Parses a user-supplied format name; a leading dot is allowed
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let normalized = value.trim().trim_start_matches('.').to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == normalized)
+            .ok_or_else(|| {
+                format!(
+                    "Unsupported archive format: {} (expected {})",
+                    value,
+                    Self::supported_list()
+                )
+            })
+    }
+
+    /// Whether 7-Zip writes this format (otherwise it is written natively)
+    pub fn uses_7zip(self) -> bool {
+        matches!(self, ArchiveFormat::SevenZip | ArchiveFormat::Zip)
+    }
+
+    /// Whether the format can be password-protected
+    pub fn supports_password(self) -> bool {
+        self.uses_7zip()
+    }
+
+    /// 7-Zip `-t` type name
+    pub fn seven_zip_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::SevenZip => "7z",
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarZst | ArchiveFormat::TarXz => "tar",
+        }
+    }
+
+    /// Detects the archive format from a file name, if it has a supported extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|format| {
+            file_name
+                .strip_suffix(format.extension())
+                .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+        })
+    }
+
+    fn supported_list() -> String {
+        Self::ALL
+            .iter()
+            .map(|format| format.extension())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Strips a supported archive extension: `Game.Build.1.tar.zst` → `Game.Build.1`.
+/// Volumes of a split archive (`Game.Build.1.7z.001`) are handled too.
+pub fn strip_archive_extension(archive_path: &Path) -> Option<PathBuf> {
+    let file_name = archive_path.file_name()?.to_str()?;
+    let archive_name = split_volume_suffix(file_name)
+        .map(|(archive_name, _)| archive_name)
+        .unwrap_or(file_name);
+    let format = ArchiveFormat::from_path(Path::new(archive_name))?;
+    let stem = &archive_name[..archive_name.len() - format.extension().len() - 1];
+    Some(archive_path.with_file_name(stem))
+}
+
+/// Path of one volume of a split archive: `Game.7z` → `Game.7z.001` (1-based, like 7-Zip)
+pub fn volume_path(archive_path: &Path, index: u32) -> PathBuf {
+    let mut file_name = archive_path
+        .file_name()
+        .unwrap_or(archive_path.as_os_str())
+        .to_os_string();
+    file_name.push(format!(".{:03}", index));
+    archive_path.with_file_name(file_name)
+}
+
+/// Splits `Game.7z.001` into (`Game.7z`, 1); `None` for names without a volume suffix
+fn split_volume_suffix(file_name: &str) -> Option<(&str, u32)> {
+    let (archive_name, suffix) = file_name.rsplit_once('.')?;
+    if archive_name.is_empty() || suffix.len() < 3 || !suffix.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    Some((archive_name, suffix.parse().ok()?))
+}
+
+/// Archive path a volume belongs to: `Game.7z.001` → `Game.7z`; other paths are returned as-is
+pub fn volume_set_path(path: &Path) -> PathBuf {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(split_volume_suffix)
+        .map(|(archive_name, _)| path.with_file_name(archive_name))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Lists the volumes of a split archive that exist on disk, in volume order
+pub fn existing_volumes(archive_path: &Path) -> Vec<PathBuf> {
+    let Some(archive_name) = archive_path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    let parent = match archive_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+
+    let mut volumes: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let (name, index) = split_volume_suffix(file_name.to_str()?)?;
+            (name == archive_name).then(|| (index, archive_path.with_file_name(&file_name)))
+        })
+        .collect();
+    volumes.sort_by_key(|(index, _)| *index);
+    volumes.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Whether an archive exists, either as a single file or as a set of volumes
+pub fn archive_exists(archive_path: &Path) -> bool {
+    archive_path.exists() || !existing_volumes(archive_path).is_empty()
+}
+
+/// Removes every volume of a split archive
+pub fn remove_volumes(archive_path: &Path) -> Result<(), String> {
+    for volume in existing_volumes(archive_path) {
+        fs::remove_file(&volume)
+            .map_err(|e| format!("Failed to remove archive volume {}: {}", volume.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Parses a volume size such as `4g`, `2000m` or `700` (megabytes) into megabytes
+pub fn parse_volume_size(value: &str) -> Result<u64, String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    let normalized = normalized.strip_suffix('b').unwrap_or(&normalized);
+    let (digits, multiplier) = if let Some(digits) = normalized.strip_suffix('g') {
+        (digits, 1024)
+    } else if let Some(digits) = normalized.strip_suffix('m') {
+        (digits, 1)
+    } else {
+        (normalized, 1)
+    };
+
+    match digits.trim().parse::<u64>() {
+        Ok(size) if size > 0 => size
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("Volume size is too large: {}", value)),
+        _ => Err(format!(
+            "Invalid volume size: {} (expected e.g. 4g or 2000m)",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_archive_extension_handles_every_format() {
+        let output = Path::new("outputs/Game.Build.123.Windows.public");
+        for format in ArchiveFormat::ALL {
+            let archive = PathBuf::from(format!(
+                "outputs/Game.Build.123.Windows.public.{}",
+                format.extension()
+            ));
+            assert_eq!(ArchiveFormat::from_path(&archive), Some(format));
+            assert_eq!(strip_archive_extension(&archive).as_deref(), Some(output));
+        }
+    }
+
+    #[test]
+    fn test_volumes_follow_seven_zip_naming() {
+        let archive = Path::new("outputs/Game.Build.123.Windows.public.7z");
+        let volume = volume_path(archive, 2);
+        assert_eq!(
+            volume,
+            PathBuf::from("outputs/Game.Build.123.Windows.public.7z.002")
+        );
+        assert_eq!(
+            strip_archive_extension(&volume),
+            Some(PathBuf::from("outputs/Game.Build.123.Windows.public"))
+        );
+        assert_eq!(volume_set_path(&volume), archive);
+        assert_eq!(volume_set_path(archive), archive);
+        assert_eq!(split_volume_suffix("Game.7z.1"), None);
+        assert_eq!(split_volume_suffix("Game.7z.1000"), Some(("Game.7z", 1000)));
+    }
+
+    #[test]
+    fn test_parse_volume_size_accepts_units() {
+        assert_eq!(parse_volume_size("4g"), Ok(4096));
+        assert_eq!(parse_volume_size("2000M"), Ok(2000));
+        assert_eq!(parse_volume_size("700"), Ok(700));
+        assert_eq!(parse_volume_size("2GB"), Ok(2048));
+        assert!(parse_volume_size("0").is_err());
+        assert!(parse_volume_size("big").is_err());
+    }
+
+    #[test]
+    fn test_from_path_rejects_unknown_and_bare_extensions() {
+        assert_eq!(ArchiveFormat::from_path(Path::new("Game.Build.1")), None);
+        assert_eq!(ArchiveFormat::from_path(Path::new("Game.rar")), None);
+        assert_eq!(ArchiveFormat::from_path(Path::new("7z")), None);
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("Game.TAR.ZST")),
+            Some(ArchiveFormat::TarZst)
+        );
+    }
+
+    #[test]
+    fn test_parse_accepts_extensions_and_serde_names_match() {
+        assert_eq!(ArchiveFormat::parse(".zip"), Ok(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::parse("TAR.ZST"), Ok(ArchiveFormat::TarZst));
+        assert!(ArchiveFormat::parse("rar").is_err());
+
+        for format in ArchiveFormat::ALL {
+            let json = serde_json::to_string(&format).unwrap();
+            assert_eq!(json, format!("\"{}\"", format.extension()));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Post-compression archive verification
+//!
+//! Before the uncompressed output is deleted, the archive is read back in full
+//! (`7z t` for 7z/zip, the tar reader for tarballs) and its file count and total
+//! size are compared with the source folder. A truncated or corrupt archive
+//! fails the job and the folder is kept.
+
+use std::fs;
+use std::path::Path;
+
+use crate::archive_format::ArchiveFormat;
+use crate::job_host::SharedJobHost;
+use crate::tar_archive::read_tar_archive_stats;
+use crate::zip_runner::{run_7zip_blocking_captured, SevenZipRunnerState};
+
+/// File count and total uncompressed size of an archive or folder
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveStats {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Tests `archive_path` (the archive, or the first volume of a split set)
+/// and checks that it holds exactly the files in `source_dir`
+pub fn verify_archive(
+    host: &SharedJobHost,
+    zip_state: &SevenZipRunnerState,
+    source_dir: &Path,
+    archive_path: &Path,
+    format: ArchiveFormat,
+    password: Option<&str>,
+    job_id: &str,
+) -> Result<ArchiveStats, String> {
+    // 7-Zip follows symlinks when archiving; the tar writer stores them as links
+    let expected = source_stats(source_dir, format.uses_7zip())?;
+
+    let actual = if format.uses_7zip() {
+        let mut args = vec!["t".to_string(), "-bsp1".to_string()];
+        if let Some(password) = password.filter(|password| !password.is_empty()) {
+            args.push(format!("-p{}", password));
+        }
+        args.push(archive_path.to_string_lossy().to_string());
+
+        let (exit_code, lines) = run_7zip_blocking_captured(host, zip_state, args, job_id)?;
+        if exit_code != 0 {
+            return Err(format!("7-Zip test exited with code {}", exit_code));
+        }
+        parse_7z_test_summary(&lines)
+            .ok_or_else(|| "7-Zip test did not report a file summary".to_string())?
+    } else {
+        read_tar_archive_stats(host, zip_state, archive_path, format, expected.bytes)?
+    };
+
+    if actual != expected {
+        return Err(format!(
+            "Archive contents don't match the source: {} files / {} bytes archived, {} files / {} bytes on disk",
+            actual.files, actual.bytes, expected.files, expected.bytes
+        ));
+    }
+
+    Ok(actual)
+}
+
+/// Counts files (and their total size) below `dir`, not counting directories
+pub fn source_stats(dir: &Path, follow_symlinks: bool) -> Result<ArchiveStats, String> {
+    let mut stats = ArchiveStats::default();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current)
+            .map_err(|e| format!("Failed to read {}: {}", current.display(), e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to read {}: {}", current.display(), e))?
+                .path();
+            let metadata = if follow_symlinks {
+                fs::metadata(&path)
+            } else {
+                fs::symlink_metadata(&path)
+            }
+            .map_err(|e| format!("Failed to inspect {}: {}", path.display(), e))?;
+
+            if metadata.is_dir() {
+                pending.push(path);
+            } else {
+                stats.files += 1;
+                if metadata.is_file() {
+                    stats.bytes += metadata.len();
+                }
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Reads the `Files:` / `Size:` summary 7-Zip prints after `t`.
+/// 7-Zip leaves out `Files:` when the archive holds a single file.
+fn parse_7z_test_summary(lines: &[String]) -> Option<ArchiveStats> {
+    let mut files = None;
+    let mut bytes = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().parse::<u64>().ok();
+        match key.trim() {
+            "Files" => files = value,
+            "Size" => bytes = value,
+            _ => {}
+        }
+    }
+
+    let bytes = bytes?;
+    Some(ArchiveStats {
+        files: files.unwrap_or(1),
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(output: &str) -> Vec<String> {
+        output.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parses_7z_test_summary() {
+        let output = lines(
+            "Testing archive: Game.Build.1.7z\n--\nPath = Game.Build.1.7z\nType = 7z\n\nEverything is Ok\n\nFolders: 4\nFiles: 12\nSize:       734003200\nCompressed: 210763776",
+        );
+        assert_eq!(
+            parse_7z_test_summary(&output),
+            Some(ArchiveStats {
+                files: 12,
+                bytes: 734003200
+            })
+        );
+    }
+
+    #[test]
+    fn test_single_file_summary_and_missing_summary() {
+        let single = lines("Everything is Ok\n\nSize:       42\nCompressed: 30");
+        assert_eq!(
+            parse_7z_test_summary(&single),
+            Some(ArchiveStats { files: 1, bytes: 42 })
+        );
+        assert_eq!(parse_7z_test_summary(&lines("ERROR: Data Error")), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Checksum manifests for finished outputs
+//!
+//! Writes `<output>.sha256` (and optionally `.b3` / `.sfv`) next to the archive
+//! or folder. Archives are hashed as a whole, one line per volume when split;
+//! uncompressed outputs list every file under `steamapps/common`. Paths are
+//! relative to the manifest, so `sha256sum -c` / `b3sum -c` work from the
+//! outputs directory.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::archive_format::{existing_volumes, strip_archive_extension, volume_set_path};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Blake3,
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    /// Manifest extension; `.sfv` is the usual home for CRC32 lists
+    pub fn manifest_extension(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Blake3 => "b3",
+            ChecksumAlgorithm::Crc32 => "sfv",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Ok(ChecksumAlgorithm::Sha256),
+            "blake3" | "b3" => Ok(ChecksumAlgorithm::Blake3),
+            "crc32" | "crc" | "sfv" => Ok(ChecksumAlgorithm::Crc32),
+            other => Err(format!(
+                "Unknown checksum algorithm: {} (expected sha256, blake3 or crc32)",
+                other
+            )),
+        }
+    }
+}
+
+/// Checksums written for a new job unless it picks its own
+pub fn default_checksum_algorithms() -> Vec<ChecksumAlgorithm> {
+    vec![ChecksumAlgorithm::Sha256]
+}
+
+/// Hashes of one file; algorithms that weren't requested are left empty
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileChecksum {
+    /// Path relative to the manifest directory, with `/` separators
+    pub name: String,
+    pub sha256: String,
+    pub blake3: String,
+    pub crc32: String,
+}
+
+impl FileChecksum {
+    fn value(&self, algorithm: ChecksumAlgorithm) -> &str {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => &self.sha256,
+            ChecksumAlgorithm::Blake3 => &self.blake3,
+            ChecksumAlgorithm::Crc32 => &self.crc32,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ChecksumSummary {
+    /// Whether the output was an archive (as opposed to a folder)
+    pub is_archive: bool,
+    pub files: Vec<FileChecksum>,
+    pub manifest_paths: Vec<PathBuf>,
+}
+
+impl ChecksumSummary {
+    /// Archive hash for template tokens: the bare hash for a single archive, or
+    /// one `<hash>  <volume>` line per volume. Empty for folder outputs.
+    pub fn archive_token(&self, algorithm: ChecksumAlgorithm) -> String {
+        if !self.is_archive {
+            return String::new();
+        }
+        match self.files.as_slice() {
+            [single] => single.value(algorithm).to_string(),
+            files => files
+                .iter()
+                .filter(|file| !file.value(algorithm).is_empty())
+                .map(|file| format!("{}  {}", file.value(algorithm), file.name))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Hashes `output_path` (archive, first volume of a split archive, or output
+/// folder) and writes one manifest per algorithm next to it
+pub fn write_checksum_manifests(
+    output_path: &Path,
+    algorithms: &[ChecksumAlgorithm],
+) -> Result<ChecksumSummary, String> {
+    let mut summary = ChecksumSummary::default();
+    if algorithms.is_empty() {
+        return Ok(summary);
+    }
+
+    let manifest_dir = output_path
+        .parent()
+        .ok_or_else(|| "Output path missing parent directory".to_string())?;
+    let (base_path, files) = if output_path.is_dir() {
+        let common_dir = output_path.join("steamapps").join("common");
+        let mut files = Vec::new();
+        if common_dir.is_dir() {
+            collect_files(&common_dir, &mut files)?;
+        }
+        (output_path.to_path_buf(), files)
+    } else {
+        summary.is_archive = true;
+        let archive_path = volume_set_path(output_path);
+        let volumes = existing_volumes(&archive_path);
+        let files = if volumes.is_empty() {
+            vec![output_path.to_path_buf()]
+        } else {
+            volumes
+        };
+        let base_path = strip_archive_extension(&archive_path).unwrap_or(archive_path);
+        (base_path, files)
+    };
+
+    for file in &files {
+        let mut checksum = hash_file(file, algorithms)?;
+        checksum.name = manifest_name(file, manifest_dir)?;
+        summary.files.push(checksum);
+    }
+
+    let base_name = base_path
+        .file_name()
+        .ok_or_else(|| "Invalid output path".to_string())?
+        .to_string_lossy()
+        .to_string();
+    for algorithm in algorithms {
+        let manifest_path =
+            manifest_dir.join(format!("{}.{}", base_name, algorithm.manifest_extension()));
+        fs::write(&manifest_path, render_manifest(*algorithm, &summary.files))
+            .map_err(|e| format!("Failed to write {}: {}", manifest_path.display(), e))?;
+        summary.manifest_paths.push(manifest_path);
+    }
+
+    Ok(summary)
+}
+
+/// Renders a manifest in `sha256sum`/`b3sum` format, or SFV for CRC32
+pub fn render_manifest(algorithm: ChecksumAlgorithm, files: &[FileChecksum]) -> String {
+    let mut output = String::new();
+    if algorithm == ChecksumAlgorithm::Crc32 {
+        output.push_str("; Generated by OmniPacker\n");
+    }
+    for file in files {
+        let hash = file.value(algorithm);
+        match algorithm {
+            ChecksumAlgorithm::Crc32 => output.push_str(&format!("{} {}\n", file.name, hash)),
+            _ => {
+                // coreutils marks lines with escaped names by a leading backslash
+                if file.name.contains(['\\', '\n', '\r']) {
+                    let escaped = file
+                        .name
+                        .replace('\\', "\\\\")
+                        .replace('\n', "\\n")
+                        .replace('\r', "\\r");
+                    output.push_str(&format!("\\{}  {}\n", hash, escaped));
+                } else {
+                    output.push_str(&format!("{}  {}\n", hash, file.name));
+                }
+            }
+        }
+    }
+    output
+}
+
+fn hash_file(path: &Path, algorithms: &[ChecksumAlgorithm]) -> Result<FileChecksum, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    hash_reader(file, algorithms).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Hashes a stream with every requested algorithm in a single pass
+fn hash_reader<R: Read>(
+    mut reader: R,
+    algorithms: &[ChecksumAlgorithm],
+) -> std::io::Result<FileChecksum> {
+    let wants = |algorithm| algorithms.contains(&algorithm);
+    let mut sha256 = wants(ChecksumAlgorithm::Sha256).then(Sha256::new);
+    let mut blake3 = wants(ChecksumAlgorithm::Blake3).then(blake3::Hasher::new);
+    let mut crc32 = wants(ChecksumAlgorithm::Crc32).then(crc32fast::Hasher::new);
+
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        if let Some(hasher) = sha256.as_mut() {
+            hasher.update(chunk);
+        }
+        if let Some(hasher) = blake3.as_mut() {
+            hasher.update(chunk);
+        }
+        if let Some(hasher) = crc32.as_mut() {
+            hasher.update(chunk);
+        }
+    }
+
+    Ok(FileChecksum {
+        name: String::new(),
+        sha256: sha256.map(|hasher| to_hex(&hasher.finalize())).unwrap_or_default(),
+        blake3: blake3
+            .map(|hasher| hasher.finalize().to_hex().to_string())
+            .unwrap_or_default(),
+        crc32: crc32
+            .map(|hasher| format!("{:08X}", hasher.finalize()))
+            .unwrap_or_default(),
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Lists files below `dir` in a stable order
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn manifest_name(path: &Path, manifest_dir: &Path) -> Result<String, String> {
+    let relative = path
+        .strip_prefix(manifest_dir)
+        .map_err(|_| format!("{} is outside {}", path.display(), manifest_dir.display()))?;
+    Ok(relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checksum(name: &str, sha256: &str) -> FileChecksum {
+        FileChecksum {
+            name: name.to_string(),
+            sha256: sha256.to_string(),
+            crc32: "DEADBEEF".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_renders_sha256sum_and_sfv_formats() {
+        let files = vec![checksum("Game.7z.001", "aa"), checksum("Game.7z.002", "bb")];
+        assert_eq!(
+            render_manifest(ChecksumAlgorithm::Sha256, &files),
+            "aa  Game.7z.001\nbb  Game.7z.002\n"
+        );
+        assert!(render_manifest(ChecksumAlgorithm::Crc32, &files)
+            .ends_with("Game.7z.001 DEADBEEF\nGame.7z.002 DEADBEEF\n"));
+
+        let odd = vec![checksum("dir\\file", "cc")];
+        assert_eq!(
+            render_manifest(ChecksumAlgorithm::Sha256, &odd),
+            "\\cc  dir\\\\file\n"
+        );
+    }
+
+    #[test]
+    fn test_archive_token_lists_volumes() {
+        let mut summary = ChecksumSummary {
+            is_archive: true,
+            files: vec![checksum("Game.7z", "aa")],
+            manifest_paths: Vec::new(),
+        };
+        assert_eq!(summary.archive_token(ChecksumAlgorithm::Sha256), "aa");
+        assert_eq!(summary.archive_token(ChecksumAlgorithm::Blake3), "");
+
+        summary.files.push(checksum("Game.7z.002", "bb"));
+        summary.files[0].name = "Game.7z.001".to_string();
+        assert_eq!(
+            summary.archive_token(ChecksumAlgorithm::Sha256),
+            "aa  Game.7z.001\nbb  Game.7z.002"
+        );
+
+        summary.is_archive = false;
+        assert_eq!(summary.archive_token(ChecksumAlgorithm::Sha256), "");
+    }
+
+    #[test]
+    fn test_hashes_known_digests() {
+        let checksum = hash_reader(
+            &b"abc"[..],
+            &[ChecksumAlgorithm::Sha256, ChecksumAlgorithm::Crc32],
+        )
+        .unwrap();
+        assert_eq!(
+            checksum.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(checksum.crc32, "352441C2");
+        assert!(checksum.blake3.is_empty());
+    }
+}
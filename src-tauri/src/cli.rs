@@ -0,0 +1,1036 @@
+//! Headless command-line mode
+//!
+//! `omnipacker --headless --app <appid> [options]` runs a single job through the
+//! same pipeline as the GUI (preflight, download, metadata, finalization,
+//! compression, template) without creating a window. Logs go to stdout and the
+//! process exit code reports the outcome, so jobs can be scripted on build boxes
+//! without a display.
+
+use std::io::{BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use crate::archive_format::{parse_volume_size, ArchiveFormat};
+use crate::checksums::ChecksumAlgorithm;
+use crate::depot_runner::{
+    begin_job, depot_selection_args, discover_depots_blocking, run_job_pipeline,
+    run_update_pack_pipeline, validate_archive_options, write_password_line,
+    write_steam_guard_code, DepotManifestPin, DepotRunnerState, JobMetadata,
+};
+use crate::job_host::{status_line, JobHost, SharedJobHost};
+use crate::job_log::{append_job_log, append_job_status};
+use crate::output_conflict::OutputConflictChoice;
+use crate::output_dir::resolve_downloads_dir_with_fallback;
+use crate::redaction::{apply_redaction_settings, redact_log_line};
+use crate::steam_library::{find_steam_libraries, InstallMode, SteamInstallOptions};
+use crate::zip_runner::SevenZipRunnerState;
+
+pub const HEADLESS_FLAG: &str = "--headless";
+
+/// Must match `identifier` in tauri.conf.json so the CLI shares app data with the GUI
+const APP_IDENTIFIER: &str = "com.omnipacker.app";
+/// Must match `productName` in tauri.conf.json (Linux resource directory name)
+const PRODUCT_NAME: &str = "OmniPacker";
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_JOB_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage: omnipacker --headless --app <appid> [options]
+       omnipacker --headless --update-from <old> --update-to <new> [packaging options]
+
+Options:
+  --app <appid>              Steam AppID to download (required)
+  --os <os>                  windows-x64 | windows-x86 | linux | macos-x64 | macos-arm64
+                             (default: windows-x64)
+  --platforms <os,os,...>    Download several platforms in one job (one output each)
+  --release-notes            With --platforms, also write combined release notes
+  --branch <name>            Branch to download (default: public)
+  --auth <mode>              anonymous | password | qr | account
+                             (default: account when --account is set, password when
+                             --username is set, else anonymous; anonymous jobs use the
+                             app's default account when one is set)
+  --account <id>             Log in with a saved account
+  --username <name>          Steam username (reuses the saved auth cache when present)
+  --skip-compression         Keep the uncompressed output folder
+  --resume                   Continue an interrupted download of the same app/branch/OS
+  --validate                 Check downloaded files against the depot manifests before packing
+  --pin <depot>:<manifest>   Download a specific manifest for a depot (repeatable)
+  --depots <id,id,...>       Only download these depots
+  --language <name>          Steam language for language depots (e.g. german, schinese)
+  --low-violence             Select low-violence depots
+  --list-depots              List the depots available for the job and exit
+  --archive-format <format>  7z | zip | tar.zst | tar.xz (default: 7z)
+  --volume-size <size>       Split the archive into volumes (e.g. 4g, 2000m)
+  --compression-profile <n>  store | fast | balanced | ultra | adaptive, or a saved profile
+                             (default: the saved default, else adaptive)
+  --checksums <list>         Checksum manifests: sha256,blake3,crc32 or none (default: sha256)
+  --encrypt-archive          Encrypt the archive with OMNIPACKER_COMPRESSION_PASSWORD
+                             (7z and zip only)
+  --on-conflict <choice>     overwrite | copy | cancel when the output exists (default: cancel)
+                             A build found already packed after preflight is asked about
+                             the same way; cancel skips its download
+  --skip-packed              Always skip builds that are already packed
+  --install-to <library>     Install the output into a Steam library folder, or `auto` for
+                             Steam's main library
+  --install-mode <mode>      copy | move into the library (default: copy; move needs
+                             --skip-compression)
+  --install-overwrite        Replace an existing appmanifest for the app in the library
+  --steam-path <path>        Steam install directory used to find libraries
+  --list-libraries           List the Steam libraries found and exit
+  --update-from <path>       Build an update pack from this build (output folder, staging
+                             directory or folder of .manifest files)...
+  --update-to <path>         ...to this one (output folder or staging directory)
+  --downloads-dir <path>     Override the downloads directory
+  --resource-dir <path>      Directory containing binaries/<platform>/ sidecars
+  --help                     Show this help
+
+Passwords are never taken on the command line: set OMNIPACKER_PASSWORD, or type
+the password on stdin when DepotDownloader asks for it. Steam Guard codes can be
+typed on stdin while the job is running. The archive password is read from
+OMNIPACKER_COMPRESSION_PASSWORD.
+
+Exit codes: 0 success, 1 job failed, 2 invalid arguments.";
+
+/// Returns true when the process was started with `--headless`
+pub fn headless_requested() -> bool {
+    std::env::args().any(|arg| arg == HEADLESS_FLAG)
+}
+
+/// Parsed command-line options for a headless run
+#[derive(Debug)]
+struct CliOptions {
+    job: JobMetadata,
+    on_conflict: OutputConflictChoice,
+    downloads_dir: Option<PathBuf>,
+    resource_dir: Option<PathBuf>,
+    show_help: bool,
+    list_depots: bool,
+    list_libraries: bool,
+    steam_path: Option<PathBuf>,
+    /// Old and new build for an update pack
+    update_pack: Option<(PathBuf, PathBuf)>,
+}
+
+/// Runs a single job headlessly and returns the process exit code
+pub fn run_headless() -> i32 {
+    attach_parent_console();
+
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| arg != HEADLESS_FLAG)
+        .collect();
+
+    let options = match parse_args(
+        &args,
+        std::env::var("OMNIPACKER_PASSWORD").ok(),
+        std::env::var("OMNIPACKER_COMPRESSION_PASSWORD").ok(),
+    ) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("Error: {err}\n");
+            eprintln!("{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    if options.show_help {
+        println!("{USAGE}");
+        return EXIT_SUCCESS;
+    }
+
+    if options.list_libraries {
+        return match find_steam_libraries(options.steam_path.as_deref()) {
+            Ok(libraries) => {
+                for library in &libraries {
+                    println!(
+                        "{}\t{}\t{} apps{}",
+                        library.path.display(),
+                        library
+                            .free_bytes
+                            .map(|bytes| format!("{} bytes free", bytes))
+                            .unwrap_or_else(|| "free space unknown".to_string()),
+                        library.apps.len(),
+                        if library.label.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" ({})", library.label)
+                        }
+                    );
+                }
+                EXIT_SUCCESS
+            }
+            Err(err) => {
+                eprintln!("Error: {err}");
+                EXIT_JOB_FAILED
+            }
+        };
+    }
+
+    let host = match CliHost::new(&options) {
+        Ok(host) => host,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return EXIT_JOB_FAILED;
+        }
+    };
+    let host: SharedJobHost = Arc::new(host);
+    if let Err(err) = apply_redaction_settings(host.as_ref()) {
+        eprintln!("Failed to load redaction settings, using defaults: {err}");
+    }
+
+    let state = DepotRunnerState::new();
+    let zip_state = SevenZipRunnerState::new();
+
+    if options.list_depots {
+        spawn_stdin_forwarder(state.clone());
+        return match discover_depots_blocking(&host, &state, &options.job) {
+            Ok(result) => {
+                for depot in &result.depots {
+                    let mut flags = Vec::new();
+                    if result.primary_depot_id.as_deref() == Some(depot.depot_id.as_str()) {
+                        flags.push("primary");
+                    }
+                    if depot.shared {
+                        flags.push("shared");
+                    }
+                    println!(
+                        "{}\t{}\t{}{}",
+                        depot.depot_id,
+                        depot.manifest_id,
+                        depot.depot_name.as_deref().unwrap_or("-"),
+                        if flags.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" ({})", flags.join(", "))
+                        }
+                    );
+                }
+                EXIT_SUCCESS
+            }
+            Err(err) => {
+                eprintln!("Depot discovery failed: {err}");
+                EXIT_JOB_FAILED
+            }
+        };
+    }
+
+    let job_id = match begin_job(&state) {
+        Ok(job_id) => job_id,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return EXIT_JOB_FAILED;
+        }
+    };
+
+    spawn_stdin_forwarder(state.clone());
+
+    host.emit_status("starting", None, &job_id);
+    if let Some((old_path, new_path)) = options.update_pack.as_ref() {
+        return match run_update_pack_pipeline(
+            &host,
+            &state,
+            &zip_state,
+            &options.job,
+            old_path,
+            new_path,
+            &job_id,
+        ) {
+            Ok(output_path) => {
+                println!("Output: {}", output_path.display());
+                EXIT_SUCCESS
+            }
+            Err(err) => {
+                eprintln!("Update pack failed: {err}");
+                EXIT_JOB_FAILED
+            }
+        };
+    }
+    match run_job_pipeline(&host, &state, &zip_state, &options.job, &job_id) {
+        Ok(output_paths) => {
+            for output_path in output_paths {
+                println!("Output: {}", output_path.display());
+            }
+            EXIT_SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Job failed: {err}");
+            EXIT_JOB_FAILED
+        }
+    }
+}
+
+fn parse_args(
+    args: &[String],
+    env_password: Option<String>,
+    env_compression_password: Option<String>,
+) -> Result<CliOptions, String> {
+    let mut job = JobMetadata {
+        os: "Windows x64".to_string(),
+        branch: "public".to_string(),
+        ..Default::default()
+    };
+    let mut auth_mode: Option<String> = None;
+    let mut on_conflict = OutputConflictChoice::Cancel;
+    let mut downloads_dir = None;
+    let mut resource_dir = None;
+    let mut show_help = false;
+    let mut list_depots = false;
+    let mut list_libraries = false;
+    let mut install_to: Option<String> = None;
+    let mut install_mode: Option<InstallMode> = None;
+    let mut install_overwrite = false;
+    let mut steam_path: Option<PathBuf> = None;
+    let mut update_from: Option<PathBuf> = None;
+    let mut update_to: Option<PathBuf> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {name}"))
+        };
+
+        match arg.as_str() {
+            "--app" => job.app_id = value("--app")?.trim().to_string(),
+            "--os" => job.os = parse_os(&value("--os")?)?,
+            "--platforms" => {
+                job.platforms = value("--platforms")?
+                    .split(',')
+                    .filter(|os| !os.trim().is_empty())
+                    .map(parse_os)
+                    .collect::<Result<_, _>>()?
+            }
+            "--release-notes" => job.combined_release_notes = true,
+            "--branch" => job.branch = value("--branch")?.trim().to_string(),
+            "--auth" => auth_mode = Some(value("--auth")?.to_ascii_lowercase()),
+            "--account" => job.account_id = value("--account")?.trim().to_string(),
+            "--username" => job.username = value("--username")?.trim().to_string(),
+            "--password" => {
+                return Err(
+                    "--password is no longer supported (it leaks into the process list); \
+                     set OMNIPACKER_PASSWORD or type the password when prompted"
+                        .to_string(),
+                )
+            }
+            "--skip-compression" => job.skip_compression = true,
+            "--resume" => job.resume = true,
+            "--validate" => job.validate_content = true,
+            "--pin" => job
+                .manifest_pins
+                .push(parse_manifest_pin(&value("--pin")?)?),
+            "--depots" => job.depot_ids = parse_depot_list(&value("--depots")?),
+            "--list-depots" => list_depots = true,
+            "--language" => job.language = value("--language")?,
+            "--low-violence" => job.low_violence = true,
+            "--archive-format" => {
+                job.archive_format = ArchiveFormat::parse(&value("--archive-format")?)?
+            }
+            "--compression-profile" => {
+                job.compression_profile = value("--compression-profile")?.trim().to_string()
+            }
+            "--volume-size" => {
+                job.volume_size_mb = Some(parse_volume_size(&value("--volume-size")?)?)
+            }
+            "--checksums" => job.checksums = Some(parse_checksums(&value("--checksums")?)?),
+            "--compression-password" => {
+                return Err(
+                    "--compression-password is no longer supported (it leaks into the process \
+                     list); set OMNIPACKER_COMPRESSION_PASSWORD and pass --encrypt-archive"
+                        .to_string(),
+                )
+            }
+            "--encrypt-archive" => {
+                job.compression_password = env_compression_password
+                    .clone()
+                    .filter(|password| !password.is_empty())
+                    .ok_or_else(|| {
+                        "--encrypt-archive requires OMNIPACKER_COMPRESSION_PASSWORD".to_string()
+                    })?;
+                job.compression_password_enabled = true;
+            }
+            "--on-conflict" => on_conflict = parse_conflict_choice(&value("--on-conflict")?)?,
+            "--skip-packed" => job.skip_packed = true,
+            "--install-to" => install_to = Some(value("--install-to")?.trim().to_string()),
+            "--install-mode" => install_mode = Some(InstallMode::parse(&value("--install-mode")?)?),
+            "--install-overwrite" => install_overwrite = true,
+            "--steam-path" => steam_path = Some(PathBuf::from(value("--steam-path")?)),
+            "--list-libraries" => list_libraries = true,
+            "--update-from" => update_from = Some(PathBuf::from(value("--update-from")?)),
+            "--update-to" => update_to = Some(PathBuf::from(value("--update-to")?)),
+            "--downloads-dir" => downloads_dir = Some(PathBuf::from(value("--downloads-dir")?)),
+            "--resource-dir" => resource_dir = Some(PathBuf::from(value("--resource-dir")?)),
+            "--debug" => {}
+            "-h" | "--help" => show_help = true,
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    if show_help || list_libraries {
+        return Ok(CliOptions {
+            job,
+            on_conflict,
+            downloads_dir,
+            resource_dir,
+            show_help,
+            list_depots,
+            list_libraries,
+            steam_path,
+            update_pack: None,
+        });
+    }
+
+    let update_pack = match (update_from, update_to) {
+        (Some(old_path), Some(new_path)) => Some((old_path, new_path)),
+        (None, None) => None,
+        _ => return Err("--update-from and --update-to must be used together".to_string()),
+    };
+    if update_pack.is_some() {
+        validate_archive_options(&job)?;
+        return Ok(CliOptions {
+            job,
+            on_conflict,
+            downloads_dir,
+            resource_dir,
+            show_help,
+            list_depots,
+            list_libraries,
+            steam_path,
+            update_pack,
+        });
+    }
+
+    if job.app_id.is_empty() {
+        return Err("--app is required".to_string());
+    }
+    if !job.app_id.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(format!("Invalid AppID: {}", job.app_id));
+    }
+    if job.branch.is_empty() {
+        job.branch = "public".to_string();
+    }
+    match install_to {
+        Some(library) if !library.is_empty() => {
+            job.steam_install = Some(SteamInstallOptions {
+                library_path: if library.eq_ignore_ascii_case("auto") {
+                    String::new()
+                } else {
+                    library
+                },
+                steam_path: steam_path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                mode: install_mode.unwrap_or_default(),
+                overwrite: install_overwrite,
+            });
+        }
+        Some(_) => return Err("Missing value for --install-to".to_string()),
+        None if install_mode.is_some() || install_overwrite => {
+            return Err("--install-mode and --install-overwrite require --install-to".to_string())
+        }
+        None => {}
+    }
+    depot_selection_args(&job)?;
+    validate_archive_options(&job)?;
+
+    let auth_mode = auth_mode.unwrap_or_else(|| {
+        if !job.account_id.is_empty() {
+            "account".to_string()
+        } else if job.username.is_empty() {
+            "anonymous".to_string()
+        } else {
+            "password".to_string()
+        }
+    });
+    if auth_mode != "account" && !job.account_id.is_empty() {
+        return Err(format!(
+            "--account can't be combined with --auth {auth_mode}"
+        ));
+    }
+
+    match auth_mode.as_str() {
+        "anonymous" => {
+            job.username.clear();
+        }
+        "password" => {
+            if job.username.is_empty() {
+                return Err("--auth password requires --username".to_string());
+            }
+            job.password = env_password.unwrap_or_default();
+        }
+        "qr" => job.qr_enabled = true,
+        "account" => {
+            if job.account_id.is_empty() {
+                return Err("--auth account requires --account".to_string());
+            }
+            job.username.clear();
+        }
+        other => return Err(format!("Unknown auth mode: {other}")),
+    }
+
+    Ok(CliOptions {
+        job,
+        on_conflict,
+        downloads_dir,
+        resource_dir,
+        show_help,
+        list_depots,
+        list_libraries,
+        steam_path,
+        update_pack,
+    })
+}
+
+/// Maps CLI OS names (and the GUI labels) to the OS labels used by `JobMetadata`
+fn parse_os(value: &str) -> Result<String, String> {
+    let os = match value.trim().to_ascii_lowercase().as_str() {
+        "windows-x64" | "win64" | "windows x64" | "windows" => "Windows x64",
+        "windows-x86" | "win32" | "windows x86" => "Windows x86",
+        "linux" | "linux64" | "linux-x64" => "Linux",
+        "macos-x64" | "macos64" | "macos x64" | "macos" => "macOS x64",
+        "macos-arm64" | "macosarm64" | "macos arm64" => "macOS arm64",
+        _ => return Err(format!("Unknown OS: {value}")),
+    };
+    Ok(os.to_string())
+}
+
+/// Parses a comma-separated depot list
+fn parse_depot_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// Parses a `<depot>:<manifest>` pin
+fn parse_manifest_pin(value: &str) -> Result<DepotManifestPin, String> {
+    let (depot_id, manifest_id) = value
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid --pin value (expected <depot>:<manifest>): {value}"))?;
+    Ok(DepotManifestPin {
+        depot_id: depot_id.trim().to_string(),
+        manifest_id: manifest_id.trim().to_string(),
+    })
+}
+
+fn parse_conflict_choice(value: &str) -> Result<OutputConflictChoice, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "overwrite" => Ok(OutputConflictChoice::Overwrite),
+        "copy" => Ok(OutputConflictChoice::Copy),
+        "cancel" => Ok(OutputConflictChoice::Cancel),
+        _ => Err(format!("Unknown conflict choice: {value}")),
+    }
+}
+
+/// Parses `sha256,blake3,crc32`; `none` disables checksum manifests
+fn parse_checksums(value: &str) -> Result<Vec<ChecksumAlgorithm>, String> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let mut algorithms = Vec::new();
+    for name in value.split(',').filter(|name| !name.trim().is_empty()) {
+        let algorithm = ChecksumAlgorithm::parse(name)?;
+        if !algorithms.contains(&algorithm) {
+            algorithms.push(algorithm);
+        }
+    }
+    if algorithms.is_empty() {
+        return Err("Missing value for --checksums".to_string());
+    }
+    Ok(algorithms)
+}
+
+/// Forwards lines typed on stdin to DepotDownloader (Steam Guard codes, passwords).
+/// Only a terminal counts as someone able to answer a password prompt; once
+/// stdin closes, prompts fail the job instead of waiting.
+fn spawn_stdin_forwarder(state: DepotRunnerState) {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        state.set_interactive();
+    }
+    thread::spawn(move || {
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if state.awaiting_password() {
+                if let Err(err) = write_password_line(&state, &line) {
+                    eprintln!("Failed to submit password: {err}");
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Err(err) = write_steam_guard_code(&state, &line) {
+                eprintln!("Failed to submit Steam Guard code: {err}");
+            }
+        }
+        state.end_interactive();
+    });
+}
+
+/// Attaches to the parent console on Windows, where release builds use the GUI subsystem
+fn attach_parent_console() {
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+/// Job host that prints everything to stdout
+struct CliHost {
+    downloads_dir: PathBuf,
+    app_data_dir: PathBuf,
+    resource_dirs: Vec<PathBuf>,
+    on_conflict: OutputConflictChoice,
+}
+
+impl CliHost {
+    fn new(options: &CliOptions) -> Result<Self, String> {
+        let app_data_dir = dirs::data_dir()
+            .map(|dir| dir.join(APP_IDENTIFIER))
+            .ok_or_else(|| "Failed to resolve app data directory".to_string())?;
+
+        let downloads_dir = match options.downloads_dir.as_ref() {
+            Some(dir) => {
+                std::fs::create_dir_all(dir).map_err(|err| {
+                    format!(
+                        "Failed to create downloads directory {}: {err}",
+                        dir.display()
+                    )
+                })?;
+                dir.clone()
+            }
+            None => resolve_downloads_dir_with_fallback(app_data_dir.join("downloads"))?,
+        };
+
+        let resource_dirs = match options.resource_dir.as_ref() {
+            Some(dir) => vec![dir.clone()],
+            None => default_resource_dirs(),
+        };
+
+        Ok(Self {
+            downloads_dir,
+            app_data_dir,
+            resource_dirs,
+            on_conflict: options.on_conflict.clone(),
+        })
+    }
+}
+
+/// Candidate resource directories for an installed or portable build
+fn default_resource_dirs() -> Vec<PathBuf> {
+    let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    else {
+        return Vec::new();
+    };
+
+    let mut dirs = vec![exe_dir.clone()];
+    if let Some(prefix) = exe_dir.parent() {
+        // Linux packages: <prefix>/bin/omnipacker + <prefix>/lib/OmniPacker/
+        dirs.push(prefix.join("lib").join(PRODUCT_NAME));
+        // macOS bundles: Contents/MacOS/omnipacker + Contents/Resources/
+        dirs.push(prefix.join("Resources"));
+    }
+    dirs
+}
+
+impl JobHost for CliHost {
+    fn emit_log(&self, stream: &str, line: &str, job_id: &str) {
+        let line = redact_log_line(line);
+        append_job_log(self, job_id, stream, &line);
+        println!("[{stream}] {line}");
+    }
+
+    fn emit_status(&self, status: &str, code: Option<i32>, job_id: &str) {
+        append_job_status(self, job_id, status, code);
+        println!("[status] {}", status_line(status, code));
+    }
+
+    fn emit_7z_log(&self, stream: &str, line: &str, job_id: &str) {
+        let line = redact_log_line(line);
+        append_job_log(self, job_id, &format!("7z:{stream}"), &line);
+        println!("[7z:{stream}] {line}");
+    }
+
+    fn emit_7z_progress(&self, percent: u8) {
+        if percent.is_multiple_of(10) {
+            println!("[7z] {percent}%");
+        }
+    }
+
+    fn downloads_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.downloads_dir.clone())
+    }
+
+    fn app_data_dir(&self) -> Result<PathBuf, String> {
+        Ok(self.app_data_dir.clone())
+    }
+
+    fn resource_path(&self, relative: &str) -> Result<PathBuf, String> {
+        self.resource_dirs
+            .iter()
+            .map(|dir| dir.join(relative))
+            .find(|path| path.exists())
+            .or_else(|| self.resource_dirs.first().map(|dir| dir.join(relative)))
+            .ok_or_else(|| "Failed to resolve resource directory".to_string())
+    }
+
+    fn resolve_output_conflict(
+        &self,
+        job_id: &str,
+        output_path: &Path,
+    ) -> Result<OutputConflictChoice, String> {
+        self.emit_log(
+            "system",
+            &format!(
+                "Output already exists: {} (resolving with --on-conflict {:?})",
+                output_path.display(),
+                self.on_conflict
+            ),
+            job_id,
+        );
+        Ok(self.on_conflict.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_defaults_to_anonymous() {
+        let options = parse_args(&args(&["--app", "480"]), None, None).unwrap();
+        assert_eq!(options.job.app_id, "480");
+        assert_eq!(options.job.os, "Windows x64");
+        assert_eq!(options.job.branch, "public");
+        assert!(options.job.username.is_empty());
+        assert!(!options.job.qr_enabled);
+    }
+
+    #[test]
+    fn test_parse_args_password_from_env() {
+        let options = parse_args(
+            &args(&["--app", "480", "--os", "linux", "--username", "builder"]),
+            Some("secret".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.job.os, "Linux");
+        assert_eq!(options.job.username, "builder");
+        assert_eq!(options.job.password, "secret");
+    }
+
+    #[test]
+    fn test_parse_args_rejects_password_flag() {
+        let with_flag = [
+            "--app",
+            "480",
+            "--username",
+            "builder",
+            "--password",
+            "secret",
+        ];
+        let err = parse_args(&args(&with_flag), None, None).unwrap_err();
+        assert!(err.contains("OMNIPACKER_PASSWORD"));
+    }
+
+    #[test]
+    fn test_parse_args_saved_account() {
+        let options = parse_args(
+            &args(&[
+                "--app",
+                "480",
+                "--account",
+                "builder",
+                "--username",
+                "ignored",
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.job.account_id, "builder");
+        assert!(options.job.username.is_empty());
+
+        assert!(parse_args(&args(&["--app", "480", "--auth", "account"]), None, None).is_err());
+        let with_qr = ["--app", "480", "--account", "builder", "--auth", "qr"];
+        assert!(parse_args(&args(&with_qr), None, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_qr_and_conflict() {
+        let options = parse_args(
+            &args(&["--app", "480", "--auth", "qr", "--on-conflict", "copy"]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(options.job.qr_enabled);
+        assert!(matches!(options.on_conflict, OutputConflictChoice::Copy));
+    }
+
+    #[test]
+    fn test_parse_args_skip_packed() {
+        let options = parse_args(
+            &args(&[
+                "--app",
+                "480",
+                "--on-conflict",
+                "overwrite",
+                "--skip-packed",
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(options.job.skip_packed);
+        assert!(matches!(
+            options.on_conflict,
+            OutputConflictChoice::Overwrite
+        ));
+    }
+
+    #[test]
+    fn test_parse_args_manifest_pins() {
+        let options = parse_args(
+            &args(&["--app", "480", "--pin", "481:123", "--pin", "482:456"]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.job.manifest_pins.len(), 2);
+        assert_eq!(options.job.manifest_pins[1].depot_id, "482");
+        assert_eq!(options.job.manifest_pins[1].manifest_id, "456");
+
+        assert!(parse_args(&args(&["--app", "480", "--pin", "481"]), None, None).is_err());
+        assert!(parse_args(&args(&["--app", "480", "--pin", "481:x"]), None, None).is_err());
+        assert!(parse_args(
+            &args(&["--app", "480", "--pin", "481:1", "--pin", "481:2"]),
+            None,
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_args_depot_selection() {
+        let options = parse_args(
+            &args(&["--app", "480", "--depots", "481, 482", "--list-depots"]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.job.depot_ids, vec!["481", "482"]);
+        assert!(options.list_depots);
+
+        assert!(parse_args(&args(&["--app", "480", "--depots", "481,abc"]), None, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_platforms() {
+        let options = parse_args(
+            &args(&[
+                "--app",
+                "480",
+                "--platforms",
+                "windows-x64,linux,macos-x64,linux",
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            options.job.platform_list(),
+            vec!["Windows x64", "Linux", "macOS x64"]
+        );
+        assert!(parse_args(
+            &args(&["--app", "480", "--platforms", "linux,amiga"]),
+            None,
+            None
+        )
+        .is_err());
+
+        let single = parse_args(&args(&["--app", "480", "--os", "linux"]), None, None).unwrap();
+        assert_eq!(single.job.platform_list(), vec!["Linux"]);
+    }
+
+    #[test]
+    fn test_parse_args_rejects_invalid_input() {
+        assert!(parse_args(&args(&[]), None, None).is_err());
+        assert!(parse_args(&args(&["--app", "abc"]), None, None).is_err());
+        assert!(parse_args(&args(&["--app", "480", "--os", "amiga"]), None, None).is_err());
+        assert!(parse_args(&args(&["--app", "480", "--auth", "password"]), None, None).is_err());
+        assert!(parse_args(&args(&["--app"]), None, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_archive_format() {
+        let options = parse_args(&args(&["--app", "480"]), None, None).unwrap();
+        assert_eq!(options.job.archive_format, ArchiveFormat::SevenZip);
+
+        let options = parse_args(
+            &args(&["--app", "480", "--archive-format", "tar.zst"]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.job.archive_format, ArchiveFormat::TarZst);
+
+        let pw = || Some("pw".to_string());
+        let zip = [
+            "--app",
+            "480",
+            "--archive-format",
+            "zip",
+            "--encrypt-archive",
+        ];
+        let encrypted = parse_args(&args(&zip), None, pw()).unwrap();
+        assert!(encrypted.job.compression_password_enabled);
+        assert_eq!(encrypted.job.compression_password, "pw");
+        assert!(parse_args(&args(&zip), None, None).is_err());
+
+        let xz = [
+            "--app",
+            "480",
+            "--archive-format",
+            "tar.xz",
+            "--encrypt-archive",
+        ];
+        assert!(parse_args(&args(&xz), None, pw()).is_err());
+
+        // The password itself is never taken from argv
+        let flag = ["--app", "480", "--compression-password", "pw"];
+        let err = parse_args(&args(&flag), None, None).unwrap_err();
+        assert!(err.contains("OMNIPACKER_COMPRESSION_PASSWORD"));
+        assert!(parse_args(
+            &args(&["--app", "480", "--archive-format", "rar"]),
+            None,
+            None
+        )
+        .is_err());
+
+        let split =
+            parse_args(&args(&["--app", "480", "--volume-size", "4g"]), None, None).unwrap();
+        assert_eq!(split.job.volume_size_mb, Some(4096));
+        assert!(parse_args(&args(&["--app", "480", "--volume-size", "0"]), None, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_checksums() {
+        let default = parse_args(&args(&["--app", "480"]), None, None).unwrap();
+        assert_eq!(
+            default.job.checksum_algorithms(),
+            vec![ChecksumAlgorithm::Sha256]
+        );
+
+        let both = parse_args(
+            &args(&["--app", "480", "--checksums", "blake3,CRC32"]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            both.job.checksum_algorithms(),
+            vec![ChecksumAlgorithm::Blake3, ChecksumAlgorithm::Crc32]
+        );
+
+        let none = parse_args(&args(&["--app", "480", "--checksums", "none"]), None, None).unwrap();
+        assert!(none.job.checksum_algorithms().is_empty());
+        assert!(parse_args(&args(&["--app", "480", "--checksums", "md5"]), None, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_install_to_library() {
+        let auto =
+            parse_args(&args(&["--app", "480", "--install-to", "auto"]), None, None).unwrap();
+        let install = auto.job.steam_install.unwrap();
+        assert!(install.library_path.is_empty());
+        assert_eq!(install.mode, InstallMode::Copy);
+        assert!(!install.overwrite);
+
+        let moved = parse_args(
+            &args(&[
+                "--app",
+                "480",
+                "--install-to",
+                "/games/SteamLibrary",
+                "--install-mode",
+                "move",
+                "--install-overwrite",
+                "--skip-compression",
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+        let install = moved.job.steam_install.unwrap();
+        assert_eq!(install.library_path, "/games/SteamLibrary");
+        assert_eq!(install.mode, InstallMode::Move);
+        assert!(install.overwrite);
+
+        // Moving would leave nothing to compress
+        assert!(parse_args(
+            &args(&[
+                "--app",
+                "480",
+                "--install-to",
+                "auto",
+                "--install-mode",
+                "move"
+            ]),
+            None,
+            None
+        )
+        .is_err());
+        assert!(parse_args(&args(&["--app", "480", "--install-overwrite"]), None, None).is_err());
+        assert!(
+            parse_args(&args(&["--list-libraries"]), None, None)
+                .unwrap()
+                .list_libraries
+        );
+    }
+
+    #[test]
+    fn test_parse_args_update_pack() {
+        let options = parse_args(
+            &args(&[
+                "--update-from",
+                "old",
+                "--update-to",
+                "new",
+                "--archive-format",
+                "zip",
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            options.update_pack,
+            Some((PathBuf::from("old"), PathBuf::from("new")))
+        );
+        assert_eq!(options.job.archive_format, ArchiveFormat::Zip);
+
+        assert!(parse_args(&args(&["--update-from", "old"]), None, None).is_err());
+        assert!(parse_args(&args(&["--app", "480"]), None, None)
+            .unwrap()
+            .update_pack
+            .is_none());
+    }
+}
@@ -0,0 +1,439 @@
+//! Named compression profiles
+//!
+//! A profile overrides parts of the adaptive compression settings (level,
+//! method, dictionary, solid blocks, filters, thread cap). Anything a profile
+//! leaves unset keeps the adaptive value, and a job without a profile uses the
+//! adaptive behaviour unchanged. Built-in profiles can be overridden or extended
+//! by saving profiles to `compression_profiles.json` in the app data directory.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+use crate::archive_format::ArchiveFormat;
+use crate::job_host::JobHost;
+
+const PROFILES_FILE_NAME: &str = "compression_profiles.json";
+
+/// Profile name that selects the adaptive default explicitly
+pub const ADAPTIVE_PROFILE: &str = "adaptive";
+
+/// Compression method for the 7z format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionMethod {
+    Lzma2,
+    /// Needs a 7-Zip build with zstd support (e.g. 7-Zip-zstd); the bundled 7-Zip
+    /// has none, so 7z and zip jobs reject it (tar.zst is zstd already)
+    Zstd,
+    /// Store without compression
+    Copy,
+}
+
+impl CompressionMethod {
+    /// 7-Zip method name for `-m0=`
+    pub fn seven_zip_name(self) -> &'static str {
+        match self {
+            CompressionMethod::Lzma2 => "LZMA2",
+            CompressionMethod::Zstd => "zstd",
+            CompressionMethod::Copy => "Copy",
+        }
+    }
+}
+
+/// Executable filter for the 7z format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFilter {
+    Off,
+    Bcj,
+    Bcj2,
+}
+
+impl CompressionFilter {
+    /// 7-Zip filter name for `-mf=`
+    pub fn seven_zip_name(self) -> &'static str {
+        match self {
+            CompressionFilter::Off => "off",
+            CompressionFilter::Bcj => "BCJ",
+            CompressionFilter::Bcj2 => "BCJ2",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressionProfile {
+    pub name: String,
+    /// Compression level 0-9 (`-mx`); unset keeps ultra (9)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<CompressionMethod>,
+    /// Dictionary size in 7-Zip notation (e.g. "256m", "1536m"); unset adapts to free memory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary_size: Option<String>,
+    /// Solid block size in 7-Zip notation ("off", "on", "4g", ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solid_block_size: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<CompressionFilter>,
+    /// Upper bound for the adaptive thread count
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_threads: Option<usize>,
+}
+
+impl CompressionProfile {
+    /// Checks that every override is something 7-Zip will accept
+    pub fn validate(&self) -> Result<(), String> {
+        static SIZE_PATTERN: OnceLock<Regex> = OnceLock::new();
+        static SOLID_PATTERN: OnceLock<Regex> = OnceLock::new();
+        let size_pattern =
+            SIZE_PATTERN.get_or_init(|| Regex::new(r"^[1-9][0-9]*[bkmg]?$").unwrap());
+        let solid_pattern = SOLID_PATTERN
+            .get_or_init(|| Regex::new(r"^(on|off|[1-9][0-9]*[fbkmgt]?)$").unwrap());
+
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Compression profile name is required".to_string());
+        }
+        if name.eq_ignore_ascii_case(ADAPTIVE_PROFILE) {
+            return Err(format!("\"{}\" is reserved for the default profile", name));
+        }
+        if let Some(level) = self.level {
+            if level > 9 {
+                return Err(format!(
+                    "Compression profile {}: level must be 0-9, got {}",
+                    name, level
+                ));
+            }
+        }
+        if let Some(size) = self.dictionary_size.as_deref() {
+            if !size_pattern.is_match(&size.to_ascii_lowercase()) {
+                return Err(format!(
+                    "Compression profile {}: invalid dictionary size {}",
+                    name, size
+                ));
+            }
+        }
+        if let Some(size) = self.solid_block_size.as_deref() {
+            if !solid_pattern.is_match(&size.to_ascii_lowercase()) {
+                return Err(format!(
+                    "Compression profile {}: invalid solid block size {}",
+                    name, size
+                ));
+            }
+        }
+        if self.max_threads == Some(0) {
+            return Err(format!(
+                "Compression profile {}: thread cap must be at least 1",
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rejects overrides the bundled 7-Zip can't honour for `format`
+    pub fn check_format(&self, format: ArchiveFormat) -> Result<(), String> {
+        if self.method == Some(CompressionMethod::Zstd)
+            && matches!(format, ArchiveFormat::SevenZip | ArchiveFormat::Zip)
+        {
+            return Err(format!(
+                "Compression profile {}: the bundled 7-Zip can't write zstd into {} archives; use tar.zst instead",
+                self.name,
+                format.extension()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pinned dictionary size in bytes, if any
+    pub fn dictionary_bytes(&self) -> Option<u64> {
+        let size = self.dictionary_size.as_deref()?.to_ascii_lowercase();
+        let (digits, unit) = match size.chars().last()? {
+            'b' => (&size[..size.len() - 1], 1),
+            'k' => (&size[..size.len() - 1], 1024),
+            'm' => (&size[..size.len() - 1], 1024 * 1024),
+            'g' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+            // 7-Zip reads a bare number as a power of two
+            _ => return size.parse::<u32>().ok().and_then(|exp| 1u64.checked_shl(exp)),
+        };
+        digits.parse::<u64>().ok()?.checked_mul(unit)
+    }
+
+    /// Compression level with the adaptive default (ultra) filled in
+    pub fn effective_level(&self) -> u8 {
+        if self.method == Some(CompressionMethod::Copy) {
+            0
+        } else {
+            self.level.unwrap_or(9)
+        }
+    }
+
+    /// Applies the thread cap to an adaptively chosen thread count
+    pub fn cap_threads(&self, threads: usize) -> usize {
+        match self.max_threads {
+            Some(cap) => threads.min(cap).max(1),
+            None => threads,
+        }
+    }
+}
+
+/// Profiles that ship with the app
+pub fn builtin_profiles() -> Vec<CompressionProfile> {
+    vec![
+        CompressionProfile {
+            name: "store".to_string(),
+            level: Some(0),
+            method: Some(CompressionMethod::Copy),
+            ..Default::default()
+        },
+        CompressionProfile {
+            name: "fast".to_string(),
+            level: Some(1),
+            ..Default::default()
+        },
+        CompressionProfile {
+            name: "balanced".to_string(),
+            level: Some(5),
+            ..Default::default()
+        },
+        CompressionProfile {
+            name: "ultra".to_string(),
+            level: Some(9),
+            method: Some(CompressionMethod::Lzma2),
+            dictionary_size: Some("1024m".to_string()),
+            solid_block_size: Some("on".to_string()),
+            filter: Some(CompressionFilter::Bcj2),
+            // A 1 GB dictionary needs roughly 11 GB of RAM per thread, so it is
+            // capped to what the machine can spare (see `compression_budget`)
+            max_threads: Some(2),
+        },
+    ]
+}
+
+/// Saved profile settings (`compression_profiles.json`)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressionProfileSettings {
+    /// Profile used when a job doesn't pick one; unset means adaptive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    /// User profiles; a profile named like a built-in replaces it
+    #[serde(default)]
+    pub profiles: Vec<CompressionProfile>,
+}
+
+impl CompressionProfileSettings {
+    /// Built-in profiles merged with the saved ones, saved profiles winning by name
+    pub fn all_profiles(&self) -> Vec<CompressionProfile> {
+        let mut profiles = builtin_profiles();
+        for saved in &self.profiles {
+            match profiles
+                .iter_mut()
+                .find(|profile| profile.name.eq_ignore_ascii_case(&saved.name))
+            {
+                Some(existing) => *existing = saved.clone(),
+                None => profiles.push(saved.clone()),
+            }
+        }
+        profiles
+    }
+
+    /// Resolves the profile for a job: the job's own choice, then the saved default.
+    /// `None` means the adaptive default.
+    pub fn resolve(&self, requested: Option<&str>) -> Result<Option<CompressionProfile>, String> {
+        let name = requested
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .or_else(|| self.default_profile.as_deref().map(str::trim))
+            .filter(|name| !name.is_empty());
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        if name.eq_ignore_ascii_case(ADAPTIVE_PROFILE) {
+            return Ok(None);
+        }
+
+        self.all_profiles()
+            .into_iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+            .map(Some)
+            .ok_or_else(|| format!("Unknown compression profile: {}", name))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (index, profile) in self.profiles.iter().enumerate() {
+            profile.validate()?;
+            if self.profiles[..index]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&profile.name))
+            {
+                return Err(format!("Duplicate compression profile: {}", profile.name));
+            }
+        }
+        self.resolve(None).map(|_| ())
+    }
+}
+
+fn profiles_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.app_data_dir()?.join(PROFILES_FILE_NAME))
+}
+
+fn read_profiles_file(path: &Path) -> Result<CompressionProfileSettings, String> {
+    if !path.exists() {
+        return Ok(CompressionProfileSettings::default());
+    }
+
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read compression profiles: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse compression profiles: {}", e))
+}
+
+/// Loads saved profile settings for the pipeline
+pub fn load_compression_settings(host: &dyn JobHost) -> Result<CompressionProfileSettings, String> {
+    read_profiles_file(&profiles_path(host)?)
+}
+
+/// Profiles as shown in settings: the saved default plus built-in and saved profiles
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressionProfileList {
+    pub default_profile: Option<String>,
+    pub profiles: Vec<CompressionProfile>,
+    pub builtin_names: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_compression_profiles(app_handle: AppHandle) -> Result<CompressionProfileList, String> {
+    let settings = load_compression_settings(&app_handle)?;
+    Ok(CompressionProfileList {
+        default_profile: settings.default_profile.clone(),
+        profiles: settings.all_profiles(),
+        builtin_names: builtin_profiles()
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect(),
+    })
+}
+
+/// Saves user profiles and the default profile
+#[tauri::command]
+pub fn save_compression_profiles(
+    app_handle: AppHandle,
+    settings: CompressionProfileSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    let path = profiles_path(&app_handle)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize compression profiles: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write compression profiles: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles_are_valid() {
+        for profile in builtin_profiles() {
+            profile.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_resolve_prefers_job_choice_then_default() {
+        let settings = CompressionProfileSettings {
+            default_profile: Some("fast".to_string()),
+            profiles: Vec::new(),
+        };
+        assert_eq!(settings.resolve(Some("Store")).unwrap().unwrap().name, "store");
+        assert_eq!(settings.resolve(None).unwrap().unwrap().name, "fast");
+        assert_eq!(settings.resolve(Some("adaptive")).unwrap(), None);
+        assert!(settings.resolve(Some("missing")).is_err());
+
+        assert_eq!(CompressionProfileSettings::default().resolve(None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_saved_profiles_override_builtins_by_name() {
+        let settings = CompressionProfileSettings {
+            default_profile: None,
+            profiles: vec![
+                CompressionProfile {
+                    name: "Fast".to_string(),
+                    level: Some(3),
+                    ..Default::default()
+                },
+                CompressionProfile {
+                    name: "archive".to_string(),
+                    solid_block_size: Some("4g".to_string()),
+                    ..Default::default()
+                },
+            ],
+        };
+        let profiles = settings.all_profiles();
+        assert_eq!(profiles.len(), builtin_profiles().len() + 1);
+        assert_eq!(settings.resolve(Some("fast")).unwrap().unwrap().level, Some(3));
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_overrides() {
+        let profile = |edit: fn(&mut CompressionProfile)| {
+            let mut profile = CompressionProfile {
+                name: "custom".to_string(),
+                ..Default::default()
+            };
+            edit(&mut profile);
+            profile.validate()
+        };
+        assert!(profile(|_| {}).is_ok());
+        assert!(profile(|p| p.level = Some(10)).is_err());
+        assert!(profile(|p| p.dictionary_size = Some("huge".to_string())).is_err());
+        assert!(profile(|p| p.solid_block_size = Some("-ms=on".to_string())).is_err());
+        assert!(profile(|p| p.max_threads = Some(0)).is_err());
+        assert!(profile(|p| p.name = "adaptive".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_zstd_is_rejected_for_formats_7zip_writes() {
+        let zstd = CompressionProfile {
+            name: "zstd".to_string(),
+            method: Some(CompressionMethod::Zstd),
+            ..Default::default()
+        };
+        assert!(zstd.validate().is_ok());
+        assert!(zstd.check_format(ArchiveFormat::SevenZip).is_err());
+        assert!(zstd.check_format(ArchiveFormat::Zip).is_err());
+        assert!(zstd.check_format(ArchiveFormat::TarZst).is_ok());
+        assert!(builtin_profiles()
+            .iter()
+            .all(|profile| profile.check_format(ArchiveFormat::SevenZip).is_ok()));
+    }
+
+    #[test]
+    fn test_dictionary_sizes_parse_to_bytes() {
+        let size = |value: &str| {
+            CompressionProfile {
+                dictionary_size: Some(value.to_string()),
+                ..Default::default()
+            }
+            .dictionary_bytes()
+        };
+        assert_eq!(size("1024m"), Some(1024 * 1024 * 1024));
+        assert_eq!(size("64K"), Some(64 * 1024));
+        assert_eq!(size("2g"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(size("24"), Some(16 * 1024 * 1024));
+        assert_eq!(CompressionProfile::default().dictionary_bytes(), None);
+    }
+}
@@ -1,6 +1,8 @@
 use std::io::{self, Write};
 use tauri::State;
 
+use crate::redaction::redact_log_line;
+
 #[derive(Clone)]
 pub struct DebugConsoleState {
     enabled: bool,
@@ -15,12 +17,13 @@ impl DebugConsoleState {
         self.enabled
     }
 
+    /// Prints a line that has already been redacted
     pub fn write_line(&self, line: &str) {
         if !self.enabled {
             return;
         }
         let mut stdout = io::stdout();
-        let _ = writeln!(stdout, "{line}");
+        let _ = writeln!(stdout, "{}", line);
         let _ = stdout.flush();
     }
 }
@@ -32,5 +35,5 @@ pub fn debug_console_enabled(state: State<DebugConsoleState>) -> bool {
 
 #[tauri::command]
 pub fn debug_console_log(state: State<DebugConsoleState>, line: String) {
-    state.write_line(&line);
+    state.write_line(&redact_log_line(&line));
 }
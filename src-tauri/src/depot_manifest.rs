@@ -0,0 +1,465 @@
+//! Steam depot manifest decoder
+//!
+//! DepotDownloader saves each depot's manifest as `.DepotDownloader/*.manifest`
+//! (later copied into the output's `depotcache/`). The file is a sequence of
+//! sections, each a little-endian `u32` magic and `u32` length followed by a
+//! protobuf message (`ContentManifestPayload`, `ContentManifestMetadata`,
+//! `ContentManifestSignature`), terminated by an end-of-manifest magic.
+//!
+//! These files are the authoritative source for a download's depot IDs,
+//! manifest IDs, creation times and file lists.
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PAYLOAD_MAGIC: u32 = 0x71F6_17D0;
+const METADATA_MAGIC: u32 = 0x1F48_12BE;
+const SIGNATURE_MAGIC: u32 = 0x1B81_B817;
+const END_OF_MANIFEST_MAGIC: u32 = 0x32C4_15AB;
+/// Pre-protobuf manifest format, long retired by Steam
+const LEGACY_MAGIC: u32 = 0x1634_9781;
+
+const FLAG_DIRECTORY: u32 = 0x40;
+const FLAG_SYMLINK: u32 = 0x200;
+
+/// One chunk of a file, as listed in the manifest
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ManifestChunk {
+    /// SHA-1 of the uncompressed chunk data
+    pub sha: Vec<u8>,
+    pub offset: u64,
+    pub original_size: u32,
+}
+
+/// One file, directory or symlink listed in the manifest
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ManifestFile {
+    /// Path relative to the depot root, with `/` separators
+    pub name: String,
+    pub size: u64,
+    pub flags: u32,
+    /// SHA-1 of the whole file
+    pub sha_content: Vec<u8>,
+    pub chunks: Vec<ManifestChunk>,
+    pub link_target: Option<String>,
+}
+
+impl ManifestFile {
+    pub fn is_directory(&self) -> bool {
+        self.flags & FLAG_DIRECTORY != 0
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.flags & FLAG_SYMLINK != 0 || self.link_target.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DepotManifest {
+    pub depot_id: Option<u32>,
+    /// Manifest ID (`gid_manifest`)
+    pub manifest_id: Option<u64>,
+    pub creation_time: Option<DateTime<Utc>>,
+    pub filenames_encrypted: bool,
+    /// Uncompressed size of the depot as recorded in the metadata section
+    pub original_size: u64,
+    pub files: Vec<ManifestFile>,
+}
+
+impl DepotManifest {
+    /// Installed size of the depot; sums the file list when the metadata has no size
+    pub fn content_size(&self) -> u64 {
+        if self.original_size > 0 {
+            return self.original_size;
+        }
+        self.files
+            .iter()
+            .filter(|file| !file.is_directory() && !file.is_symlink())
+            .map(|file| file.size)
+            .sum()
+    }
+
+    /// Name Steam uses for the manifest in `depotcache/`: `<depot>_<manifest>.manifest`
+    pub fn depotcache_file_name(&self) -> Option<String> {
+        Some(format!("{}_{}.manifest", self.depot_id?, self.manifest_id?))
+    }
+}
+
+/// Finds the manifest DepotDownloader saved for a download folder
+/// (`<folder>/.DepotDownloader/*.manifest`). When an interrupted download was
+/// resumed at a newer build there can be several; the newest one wins.
+pub fn find_saved_manifest(download_dir: &Path) -> Option<PathBuf> {
+    let entries = fs::read_dir(download_dir.join(".DepotDownloader")).ok()?;
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "manifest"))
+        .max_by_key(|path| {
+            (
+                fs::metadata(path).and_then(|metadata| metadata.modified()).ok(),
+                path.clone(),
+            )
+        })
+}
+
+/// Reads and decodes a `.manifest` file
+pub fn read_depot_manifest(path: &Path) -> Result<DepotManifest, String> {
+    let data = fs::read(path)
+        .map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+    parse_depot_manifest(&data)
+        .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))
+}
+
+/// Decodes the binary manifest format written by SteamKit/DepotDownloader
+pub fn parse_depot_manifest(data: &[u8]) -> Result<DepotManifest, String> {
+    if data.starts_with(b"PK\x03\x04") {
+        return Err("manifest is still zip-compressed".to_string());
+    }
+
+    let mut manifest = DepotManifest::default();
+    let mut saw_payload = false;
+    let mut pos = 0;
+    loop {
+        let magic = read_u32_le(data, &mut pos).ok_or("unexpected end of manifest")?;
+        if magic == END_OF_MANIFEST_MAGIC {
+            break;
+        }
+        if magic == LEGACY_MAGIC {
+            return Err("legacy (pre-protobuf) manifests are not supported".to_string());
+        }
+
+        let length = read_u32_le(data, &mut pos).ok_or("unexpected end of manifest")? as usize;
+        let section = data
+            .get(pos..pos + length)
+            .ok_or("manifest section is truncated")?;
+        pos += length;
+
+        match magic {
+            PAYLOAD_MAGIC => {
+                manifest.files = parse_payload(section)?;
+                saw_payload = true;
+            }
+            METADATA_MAGIC => parse_metadata(section, &mut manifest)?,
+            SIGNATURE_MAGIC => {}
+            other => return Err(format!("unknown manifest section 0x{:08X}", other)),
+        }
+    }
+
+    if !saw_payload {
+        return Err("manifest has no file list".to_string());
+    }
+    Ok(manifest)
+}
+
+fn read_u32_le(data: &[u8], pos: &mut usize) -> Option<u32> {
+    let bytes = data.get(*pos..*pos + 4)?;
+    *pos += 4;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// `ContentManifestPayload { repeated FileMapping mappings = 1; }`
+fn parse_payload(data: &[u8]) -> Result<Vec<ManifestFile>, String> {
+    let mut files = Vec::new();
+    let mut reader = WireReader::new(data);
+    while let Some((field, value)) = reader.next_field()? {
+        if field == 1 {
+            files.push(parse_file_mapping(value.bytes()?)?);
+        }
+    }
+    Ok(files)
+}
+
+fn parse_file_mapping(data: &[u8]) -> Result<ManifestFile, String> {
+    let mut file = ManifestFile::default();
+    let mut reader = WireReader::new(data);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => file.name = value.string()?.replace('\\', "/"),
+            2 => file.size = value.varint()?,
+            3 => file.flags = value.varint()? as u32,
+            5 => file.sha_content = value.bytes()?.to_vec(),
+            6 => file.chunks.push(parse_chunk(value.bytes()?)?),
+            7 => {
+                let target = value.string()?;
+                if !target.is_empty() {
+                    file.link_target = Some(target);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(file)
+}
+
+fn parse_chunk(data: &[u8]) -> Result<ManifestChunk, String> {
+    let mut chunk = ManifestChunk::default();
+    let mut reader = WireReader::new(data);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => chunk.sha = value.bytes()?.to_vec(),
+            3 => chunk.offset = value.varint()?,
+            4 => chunk.original_size = value.varint()? as u32,
+            _ => {}
+        }
+    }
+    Ok(chunk)
+}
+
+/// `ContentManifestMetadata`; only the fields the app uses are kept
+fn parse_metadata(data: &[u8], manifest: &mut DepotManifest) -> Result<(), String> {
+    let mut reader = WireReader::new(data);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => manifest.depot_id = Some(value.varint()? as u32),
+            2 => manifest.manifest_id = Some(value.varint()?),
+            3 => {
+                manifest.creation_time = Utc.timestamp_opt(value.varint()? as i64, 0).single()
+            }
+            4 => manifest.filenames_encrypted = value.varint()? != 0,
+            5 => manifest.original_size = value.varint()?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Value of one protobuf field; fixed-width values aren't used by any field we read
+enum WireValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+impl<'a> WireValue<'a> {
+    fn varint(&self) -> Result<u64, String> {
+        match self {
+            WireValue::Varint(value) => Ok(*value),
+            _ => Err("expected a varint field".to_string()),
+        }
+    }
+
+    fn bytes(&self) -> Result<&'a [u8], String> {
+        match self {
+            WireValue::Bytes(bytes) => Ok(bytes),
+            _ => Err("expected a length-delimited field".to_string()),
+        }
+    }
+
+    fn string(&self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).to_string())
+    }
+}
+
+/// Minimal protobuf wire-format reader
+struct WireReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> WireReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u32, WireValue<'a>)>, String> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = (key >> 3) as u32;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                WireValue::Fixed
+            }
+            2 => {
+                let length = self.varint()? as usize;
+                WireValue::Bytes(self.take(length)?)
+            }
+            5 => {
+                self.take(4)?;
+                WireValue::Fixed
+            }
+            other => return Err(format!("unsupported protobuf wire type {}", other)),
+        };
+        Ok(Some((field, value)))
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or("truncated protobuf varint")?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("protobuf varint is too long".to_string())
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("truncated protobuf field")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    //! Encoder for building manifests in tests
+
+    fn varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    pub fn varint_field(out: &mut Vec<u8>, field: u32, value: u64) {
+        varint(out, u64::from(field) << 3);
+        varint(out, value);
+    }
+
+    pub fn bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+        varint(out, (u64::from(field) << 3) | 2);
+        varint(out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+
+    pub fn section(out: &mut Vec<u8>, magic: u32, body: &[u8]) {
+        out.extend_from_slice(&magic.to_le_bytes());
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+    }
+
+    /// A file mapping with a single chunk covering `sha`/`size`
+    pub fn file_mapping(name: &str, size: u64, flags: u32, chunk_sha: &[u8]) -> Vec<u8> {
+        let mut file = Vec::new();
+        bytes_field(&mut file, 1, name.as_bytes());
+        varint_field(&mut file, 2, size);
+        varint_field(&mut file, 3, u64::from(flags));
+        if size > 0 {
+            let mut chunk = Vec::new();
+            bytes_field(&mut chunk, 1, chunk_sha);
+            chunk.extend_from_slice(&[0x15, 1, 2, 3, 4]); // crc, fixed32
+            varint_field(&mut chunk, 3, 0);
+            varint_field(&mut chunk, 4, size);
+            bytes_field(&mut file, 6, &chunk);
+        }
+        file
+    }
+
+    /// A complete manifest file holding `mappings`
+    pub fn manifest(depot_id: u32, mappings: &[Vec<u8>]) -> Vec<u8> {
+        manifest_with_id(depot_id, 0, 0, mappings)
+    }
+
+    /// A complete manifest file with a manifest ID and creation time (omitted when 0)
+    pub fn manifest_with_id(
+        depot_id: u32,
+        manifest_id: u64,
+        creation_time: u32,
+        mappings: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for mapping in mappings {
+            bytes_field(&mut payload, 1, mapping);
+        }
+        let mut metadata = Vec::new();
+        varint_field(&mut metadata, 1, u64::from(depot_id));
+        if manifest_id != 0 {
+            varint_field(&mut metadata, 2, manifest_id);
+        }
+        if creation_time != 0 {
+            varint_field(&mut metadata, 3, u64::from(creation_time));
+        }
+
+        let mut out = Vec::new();
+        section(&mut out, super::PAYLOAD_MAGIC, &payload);
+        section(&mut out, super::METADATA_MAGIC, &metadata);
+        section(&mut out, super::SIGNATURE_MAGIC, &[]);
+        out.extend_from_slice(&super::END_OF_MANIFEST_MAGIC.to_le_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::*;
+    use super::*;
+
+    #[test]
+    fn test_parses_files_chunks_and_metadata() {
+        let data = manifest(
+            2923301,
+            &[
+                file_mapping("bin\\game.exe", 5, 0x20, &[0xAB; 20]),
+                file_mapping("saves", 0, FLAG_DIRECTORY, &[]),
+            ],
+        );
+        let manifest = parse_depot_manifest(&data).unwrap();
+
+        assert_eq!(manifest.depot_id, Some(2923301));
+        assert!(!manifest.filenames_encrypted);
+        assert_eq!(manifest.files.len(), 2);
+
+        let exe = &manifest.files[0];
+        assert_eq!(exe.name, "bin/game.exe");
+        assert_eq!(exe.size, 5);
+        assert!(!exe.is_directory());
+        assert_eq!(
+            exe.chunks,
+            vec![ManifestChunk {
+                sha: vec![0xAB; 20],
+                offset: 0,
+                original_size: 5
+            }]
+        );
+        assert!(manifest.files[1].is_directory());
+        assert_eq!(manifest.manifest_id, None);
+        assert_eq!(manifest.depotcache_file_name(), None);
+        assert_eq!(manifest.content_size(), 5);
+    }
+
+    #[test]
+    fn test_parses_manifest_id_and_creation_time() {
+        let data = manifest_with_id(
+            2923301,
+            4851806656204679952,
+            1740434556,
+            &[file_mapping("a", 3, 0, &[0; 20])],
+        );
+        let manifest = parse_depot_manifest(&data).unwrap();
+
+        assert_eq!(manifest.manifest_id, Some(4851806656204679952));
+        assert_eq!(
+            manifest.creation_time,
+            Some(Utc.with_ymd_and_hms(2025, 2, 24, 22, 2, 36).unwrap())
+        );
+        assert_eq!(
+            manifest.depotcache_file_name().as_deref(),
+            Some("2923301_4851806656204679952.manifest")
+        );
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_foreign_data() {
+        let data = manifest(1, &[file_mapping("a", 1, 0, &[0; 20])]);
+        assert!(parse_depot_manifest(&data[..data.len() - 4]).is_err());
+        assert!(parse_depot_manifest(&data[..10]).is_err());
+        assert!(parse_depot_manifest(b"PK\x03\x04rest").is_err());
+        assert!(parse_depot_manifest(&LEGACY_MAGIC.to_le_bytes()).is_err());
+        assert!(parse_depot_manifest(&END_OF_MANIFEST_MAGIC.to_le_bytes()).is_err());
+    }
+}
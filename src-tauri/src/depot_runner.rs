@@ -1,7 +1,8 @@
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     io::{Read, Write},
     path::{Path, PathBuf},
     process::{Child, ChildStdin, Command, Stdio},
@@ -9,33 +10,71 @@ use std::{
     thread,
     time::Duration,
 };
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Manager, State};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
-use crate::debug_console::DebugConsoleState;
-use crate::job_finalization::{finalize_job, resolve_archive_path};
+use crate::accounts::{apply_login, resolve_job_account};
+use crate::archive_format::{archive_exists, existing_volumes, remove_volumes, ArchiveFormat};
+use crate::archive_verification::verify_archive;
+use crate::checksums::{default_checksum_algorithms, write_checksum_manifests, ChecksumAlgorithm};
+use crate::compression_profile::{
+    load_compression_settings, CompressionProfile, ADAPTIVE_PROFILE,
+};
+use crate::depot_manifest::{find_saved_manifest, read_depot_manifest};
+use crate::depot_validation::{log_validation_reports, validate_staging_depots};
+use crate::job_finalization::{
+    finalize_job, find_packed_output, remove_existing_archive, remove_existing_output, resolve_archive_path,
+    resolve_copy_output_path,
+};
+use crate::job_host::{JobHost, SharedJobHost};
+use crate::job_log::job_log_path;
 use crate::job_metadata::{BuildIdSource, DepotInfo, JobMetadataFile};
-use crate::job_staging::{cleanup_staging_dir, create_staging_dir, generate_job_id};
-use crate::manifest_preflight::{build_preflight_args, parse_preflight_output};
-use crate::output_dir::resolve_downloads_dir;
-use crate::steam_api::fetch_app_info;
+use crate::job_staging::{
+    adopt_staging_dir, cleanup_staging_dir, create_staging_dir, find_resumable_staging,
+    generate_job_id, has_depot_content, prune_superseded_manifest_dirs, ResumableStaging,
+    StagingInfo,
+};
+use crate::login_store::LoginData;
+use crate::manifest_preflight::{
+    apply_saved_manifests, build_preflight_args, parse_preflight_output, PreflightResult,
+};
+use crate::output_conflict::OutputConflictChoice;
+use crate::output_history::{read_history, record_history, HistoryEntry};
+use crate::redaction::{redact_password_args, remember_username};
+use crate::steam_api::{fetch_app_info, sanitize_game_name};
+use crate::steam_library::{install_to_library, InstallMode, SteamInstallOptions};
 use crate::steamdb_api::fetch_build_date;
-use crate::template_metadata::{TemplateMetadata, TemplateMetadataState};
-use crate::template_renderer::write_template_file;
+use crate::tar_archive::write_tar_archive;
+use crate::template_metadata::TemplateMetadata;
+use crate::template_renderer::{render_combined_release_notes, write_template_file};
 use crate::template_store::load_template_data_internal;
+use crate::update_pack::{
+    check_compatible, diff_builds, load_build, update_pack_name, write_update_pack,
+};
+use crate::vdf::{self, VdfValue};
 use crate::zip_runner::{calculate_7z_compression_args, run_7zip_blocking, SevenZipRunnerState};
 
-/// Metadata for a download job, received from the frontend
-#[derive(Clone, Debug, Deserialize)]
+/// Metadata for a download job, received from the frontend (or built by the headless CLI).
+/// Secrets are never serialized, so queued jobs can be persisted without them.
+/// Jobs log in through a saved account (`account_id`); the Steam login itself is
+/// filled in when the job runs and is never read from or written to job files.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JobMetadata {
     pub app_id: String,
     pub os: String,
     pub branch: String,
+    /// Steam login, from the job's account or a one-off login (see `accounts::apply_login`)
+    #[serde(skip)]
     pub username: String,
+    #[serde(skip)]
     pub password: String,
+    /// Saved account (see `accounts`) to log in with.
+    /// Empty uses the AppID's default account when the job has no login of its own.
+    #[serde(default)]
+    pub account_id: String,
     pub qr_enabled: bool,
     #[serde(default)]
     #[allow(dead_code)] // Forwarded from the frontend; reserved for future auth caching control.
@@ -44,24 +83,259 @@ pub struct JobMetadata {
     pub skip_compression: bool,
     #[serde(default)]
     pub compression_password_enabled: bool,
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     pub compression_password: String,
+    /// Archive format used when compression is enabled
+    #[serde(default)]
+    pub archive_format: ArchiveFormat,
+    /// Split the archive into volumes of this many megabytes (`.001`, `.002`, ...)
+    #[serde(default)]
+    pub volume_size_mb: Option<u64>,
+    /// Compression profile name; empty uses the saved default (adaptive if none)
+    #[serde(default)]
+    pub compression_profile: String,
+    /// Checksum manifests written next to the output; unset means SHA-256 only,
+    /// an empty list writes none
+    #[serde(default)]
+    pub checksums: Option<Vec<ChecksumAlgorithm>>,
+    /// Have DepotDownloader re-check existing files, then validate every depot
+    /// against its manifest before finalizing
+    #[serde(default)]
+    pub validate_content: bool,
+    /// Continue the most recent interrupted download for the same app/branch/OS, if any
+    #[serde(default)]
+    pub resume: bool,
+    /// Historical manifests to download instead of the branch's latest build
+    #[serde(default)]
+    pub manifest_pins: Vec<DepotManifestPin>,
+    /// Depots to download; empty downloads every depot DepotDownloader selects for the OS
+    #[serde(default)]
+    pub depot_ids: Vec<String>,
+    /// Steam language for language-specific depots (e.g. "german"); empty uses English
+    #[serde(default)]
+    pub language: String,
+    /// Select low-violence depots where the app provides them
+    #[serde(default)]
+    pub low_violence: bool,
+    /// Fan out to several platforms (OS labels like `os`); when set, `os` is ignored
+    /// and one output is produced per platform
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    /// For multi-platform jobs, also write one release-notes file listing every platform
+    #[serde(default)]
+    pub combined_release_notes: bool,
+    /// Install the finalized output into a Steam library
+    #[serde(default)]
+    pub steam_install: Option<SteamInstallOptions>,
+    /// Skip the download without asking when preflight finds the build already packed
+    #[serde(default)]
+    pub skip_packed: bool,
+}
+
+impl JobMetadata {
+    /// Returns the platforms this job downloads, in order and without duplicates
+    pub fn platform_list(&self) -> Vec<String> {
+        let mut platforms: Vec<String> = Vec::new();
+        for os in &self.platforms {
+            let os = os.trim();
+            if !os.is_empty() && !platforms.iter().any(|existing| existing == os) {
+                platforms.push(os.to_string());
+            }
+        }
+        if platforms.is_empty() {
+            platforms.push(self.os.clone());
+        }
+        platforms
+    }
+
+    /// Returns the checksum algorithms to write manifests for
+    pub fn checksum_algorithms(&self) -> Vec<ChecksumAlgorithm> {
+        self.checksums
+            .clone()
+            .unwrap_or_else(default_checksum_algorithms)
+    }
+
+    /// Returns the compression password, if one is enabled and non-blank
+    pub fn compression_password(&self) -> Option<&str> {
+        if self.compression_password_enabled && !self.compression_password.trim().is_empty() {
+            Some(self.compression_password.as_str())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the requested Steam language, or `None` for DepotDownloader's default (English)
+    pub fn language(&self) -> Option<String> {
+        let language = self.language.trim().to_ascii_lowercase();
+        if language.is_empty() {
+            None
+        } else {
+            Some(language)
+        }
+    }
+}
+
+/// Pins a depot to a specific (usually historical) manifest
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepotManifestPin {
+    pub depot_id: String,
+    pub manifest_id: String,
+}
+
+/// Rejects compression settings the chosen archive format can't honour
+pub fn validate_archive_options(job: &JobMetadata) -> Result<(), String> {
+    if !job.skip_compression
+        && job.compression_password().is_some()
+        && !job.archive_format.supports_password()
+    {
+        return Err(format!(
+            "{} archives can't be password-protected; use 7z or zip",
+            job.archive_format.extension()
+        ));
+    }
+    if job.volume_size_mb == Some(0) {
+        return Err("Volume size must be at least 1 MB".to_string());
+    }
+    if let Some(install) = job.steam_install.as_ref() {
+        if install.mode == InstallMode::Move && !job.skip_compression {
+            return Err(
+                "Moving the output into a Steam library leaves nothing to compress; copy it or skip compression"
+                    .to_string(),
+            );
+        }
+        if job.platform_list().len() > 1 {
+            return Err("Only single-platform jobs can be installed into a Steam library".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Looks up the job's compression profile, falling back to the saved default.
+/// `None` means the adaptive settings.
+fn resolve_compression_profile(
+    host: &dyn JobHost,
+    job: &JobMetadata,
+) -> Result<Option<CompressionProfile>, String> {
+    if job.skip_compression {
+        return Ok(None);
+    }
+    let profile =
+        load_compression_settings(host)?.resolve(Some(job.compression_profile.as_str()))?;
+    if let Some(profile) = &profile {
+        profile.check_format(job.archive_format)?;
+    }
+    Ok(profile)
+}
+
+/// Validates manifest pins: numeric IDs and at most one pin per depot
+pub fn validate_manifest_pins(pins: &[DepotManifestPin]) -> Result<(), String> {
+    let is_numeric = |value: &str| !value.is_empty() && value.chars().all(|ch| ch.is_ascii_digit());
+    let mut seen = std::collections::HashSet::new();
+    for pin in pins {
+        if !is_numeric(&pin.depot_id) {
+            return Err(format!("Invalid depot ID in manifest pin: {}", pin.depot_id));
+        }
+        if !is_numeric(&pin.manifest_id) {
+            return Err(format!(
+                "Invalid manifest ID for depot {}: {}",
+                pin.depot_id, pin.manifest_id
+            ));
+        }
+        if !seen.insert(pin.depot_id.as_str()) {
+            return Err(format!("Depot {} is pinned more than once", pin.depot_id));
+        }
+    }
+    Ok(())
+}
+
+/// Builds DepotDownloader depot selection arguments from the job: `-language`,
+/// `-lowviolence` and `-depot <ids...> [-manifest <ids...>]`. DepotDownloader pairs
+/// the depot and manifest lists by position, so when pins are used every selected
+/// depot must be pinned.
+pub fn depot_selection_args(job: &JobMetadata) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+
+    if let Some(language) = job.language() {
+        if !language.chars().all(|ch| ch.is_ascii_lowercase() || ch == '_') {
+            return Err(format!("Invalid language: {}", job.language));
+        }
+        args.push("-language".to_string());
+        args.push(language);
+    }
+    if job.low_violence {
+        args.push("-lowviolence".to_string());
+    }
+
+    validate_manifest_pins(&job.manifest_pins)?;
+    if let Some(depot_id) = job
+        .depot_ids
+        .iter()
+        .find(|id| id.is_empty() || !id.chars().all(|ch| ch.is_ascii_digit()))
+    {
+        return Err(format!("Invalid depot ID in selection: {}", depot_id));
+    }
+
+    let pins: Vec<&DepotManifestPin> = if job.depot_ids.is_empty() {
+        job.manifest_pins.iter().collect()
+    } else {
+        job.manifest_pins
+            .iter()
+            .filter(|pin| job.depot_ids.contains(&pin.depot_id))
+            .collect()
+    };
+
+    if pins.is_empty() {
+        if !job.depot_ids.is_empty() {
+            args.push("-depot".to_string());
+            args.extend(job.depot_ids.iter().cloned());
+        }
+        return Ok(args);
+    }
+
+    if let Some(unpinned) = job
+        .depot_ids
+        .iter()
+        .find(|id| !pins.iter().any(|pin| &pin.depot_id == *id))
+    {
+        return Err(format!(
+            "Depot {} is selected but not pinned. When pinning manifests, every selected depot needs a pin.",
+            unpinned
+        ));
+    }
+
+    args.push("-depot".to_string());
+    args.extend(pins.iter().map(|pin| pin.depot_id.clone()));
+    args.push("-manifest".to_string());
+    args.extend(pins.iter().map(|pin| pin.manifest_id.clone()));
+    Ok(args)
 }
 
 /// Internal state tracking the running job
 struct RunningJobState {
     child: Option<Child>,
     stdin: Option<ChildStdin>,
+    // Password to answer DepotDownloader's prompt with, so it never appears in argv
+    pending_password: Option<String>,
+    // Someone at stdin can answer prompts the job has no answer for (headless CLI)
+    interactive: bool,
+    // DepotDownloader asked for a password nobody could give; the process was stopped
+    password_unavailable: bool,
+    // DepotDownloader is waiting for the user to type a password at stdin
+    awaiting_password: bool,
     job_id: Option<String>,
-    build_datetime_utc: Option<DateTime<Utc>>,
-    // Track per-depot timestamps and depot-manifest mappings during download
-    depot_timestamps: std::collections::HashMap<String, DateTime<Utc>>,
-    manifest_to_depot: std::collections::HashMap<String, String>,
-    manifest_timestamps: std::collections::HashMap<String, DateTime<Utc>>, // manifest_id -> timestamp
+    // Staging directory name of the platform being downloaded (`<job_id>_<platform>`
+    // for multi-platform jobs), so cancelling cleans up the right folder
+    staging_id: Option<String>,
+    // Last depot mentioned in DepotDownloader output, for attributing appinfo names
     last_depot_mentioned: Option<String>,
     auth_username: Option<String>,
     // Track depot names from preflight (depot_id -> depot_name)
     depot_names: std::collections::HashMap<String, String>,
+    // Build ID reported by preflight, for the up-to-date check
+    preflight_build_id: Option<String>,
+    // Manifest IDs resolved by preflight (depot_id -> manifest_id)
+    preflight_manifest_ids: HashMap<String, String>,
     // Join handles for log reader threads (to ensure all logs are parsed before metadata derivation)
     log_reader_threads: Option<(thread::JoinHandle<()>, thread::JoinHandle<()>)>,
 }
@@ -77,34 +351,48 @@ impl DepotRunnerState {
             inner: Arc::new(Mutex::new(RunningJobState {
                 child: None,
                 stdin: None,
+                pending_password: None,
+                interactive: false,
+                password_unavailable: false,
+                awaiting_password: false,
                 job_id: None,
-                build_datetime_utc: None,
-                depot_timestamps: std::collections::HashMap::new(),
-                manifest_to_depot: std::collections::HashMap::new(),
-                manifest_timestamps: std::collections::HashMap::new(),
+                staging_id: None,
                 last_depot_mentioned: None,
                 auth_username: None,
                 depot_names: std::collections::HashMap::new(),
+                preflight_build_id: None,
+                preflight_manifest_ids: HashMap::new(),
                 log_reader_threads: None,
             })),
         }
     }
-}
 
-#[derive(Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct StatusPayload {
-    status: String,
-    code: Option<i32>,
-    job_id: String,
-}
+    /// Marks stdin as attended, so password prompts the job can't answer are
+    /// left for the user to type instead of failing the job
+    pub fn set_interactive(&self) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.interactive = true;
+        }
+    }
 
-#[derive(Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct LogPayload {
-    stream: String,
-    line: String,
-    job_id: String,
+    /// Marks stdin as closed. A password prompt still waiting for the user
+    /// can no longer be answered, so DepotDownloader is stopped.
+    pub fn end_interactive(&self) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.interactive = false;
+            if std::mem::take(&mut guard.awaiting_password) {
+                stop_at_password_prompt(&mut guard);
+            }
+        }
+    }
+
+    /// Whether DepotDownloader is waiting for the user to type a password
+    pub fn awaiting_password(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|guard| guard.awaiting_password)
+            .unwrap_or(false)
+    }
 }
 
 /// Determines the platform-specific subdirectory name for binaries
@@ -142,7 +430,7 @@ fn get_platform_subdir() -> &'static str {
     return "unknown";
 }
 
-pub fn resolve_depotdownloader_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+pub fn resolve_depotdownloader_path(host: &dyn JobHost) -> Result<PathBuf, String> {
     // Determine platform-specific binary name with extension
     #[cfg(windows)]
     let binary_name = "DepotDownloader.exe";
@@ -151,13 +439,9 @@ pub fn resolve_depotdownloader_path(app_handle: &AppHandle) -> Result<PathBuf, S
 
     let platform_subdir = get_platform_subdir();
 
-    // Use Tauri's path resolution with platform-specific subdirectory
-    let sidecar_path = app_handle
-        .path()
-        .resolve(
-            format!("binaries/{}/{}", platform_subdir, binary_name),
-            tauri::path::BaseDirectory::Resource,
-        )
+    // Resolve via the host's resources directory with platform-specific subdirectory
+    let sidecar_path = host
+        .resource_path(&format!("binaries/{}/{}", platform_subdir, binary_name))
         .map_err(|e| format!("Failed to resolve DepotDownloader sidecar: {}", e))?;
 
     if !sidecar_path.exists() {
@@ -192,9 +476,11 @@ fn map_os_selection(os: &str) -> (&'static str, &'static str) {
 
 /// Derives metadata from downloaded content (for QR auth case where preflight was skipped)
 fn derive_metadata_from_download(
-    app_handle: &AppHandle,
+    host: &dyn JobHost,
+    state_handle: &Arc<Mutex<RunningJobState>>,
     job: &JobMetadata,
     job_id: &str,
+    staging_id: &str,
     staging_dir: &std::path::Path,
 ) -> Result<(), String> {
     use std::fs;
@@ -209,6 +495,7 @@ fn derive_metadata_from_download(
     let mut depots = Vec::new();
     let mut primary_depot_id = String::new();
     let mut build_id = String::new();
+    let mut manifest_times: HashMap<String, DateTime<Utc>> = HashMap::new();
 
     // Scan depots directory
     for entry in fs::read_dir(&depots_dir)
@@ -228,6 +515,11 @@ fn derive_metadata_from_download(
             continue;
         }
 
+        // Only record depots the user selected
+        if !job.depot_ids.is_empty() && !job.depot_ids.contains(&depot_id) {
+            continue;
+        }
+
         // Find manifest directory
         let manifest_dirs: Vec<_> = fs::read_dir(&depot_path)
             .map_err(|e| format!("Failed to read depot {}: {}", depot_id, e))?
@@ -236,24 +528,55 @@ fn derive_metadata_from_download(
             .collect();
 
         if let Some(manifest_entry) = manifest_dirs.first() {
-            let manifest_id = manifest_entry.file_name().to_string_lossy().to_string();
-
-            // Use first manifest as build ID if not set
+            // DepotDownloader names the download folder after the app build
+            let download_dir_name = manifest_entry.file_name().to_string_lossy().to_string();
             if build_id.is_empty() {
-                build_id = manifest_id.clone();
+                build_id = download_dir_name.clone();
             }
 
+            // Depot manifest ID and creation time come from the saved manifest
+            let manifest = find_saved_manifest(&manifest_entry.path())
+                .ok_or_else(|| "no .manifest file was saved".to_string())
+                .and_then(|path| read_depot_manifest(&path));
+            let manifest_id = match manifest {
+                Ok(manifest) => {
+                    if let Some(created) = manifest.creation_time {
+                        manifest_times.insert(depot_id.clone(), created);
+                    }
+                    manifest
+                        .manifest_id
+                        .map(|id| id.to_string())
+                        .unwrap_or_else(|| download_dir_name.clone())
+                }
+                Err(err) => {
+                    emit_log(
+                        host,
+                        "system",
+                        &format!("Could not read the manifest for depot {}: {}", depot_id, err),
+                        job_id,
+                    );
+                    download_dir_name.clone()
+                }
+            };
+
             // Use first NON-SHARED depot as primary
             use crate::steam_api::is_shared_depot;
             if primary_depot_id.is_empty() && !is_shared_depot(&depot_id) {
                 primary_depot_id = depot_id.clone();
             }
 
+            // Record the pinned manifest when this depot was downloaded at a specific version
+            let manifest_id_used = job
+                .manifest_pins
+                .iter()
+                .find(|pin| pin.depot_id == depot_id)
+                .map(|pin| pin.manifest_id.clone());
+
             depots.push(DepotInfo {
                 depot_id: depot_id.clone(),
                 depot_name: format!("depot_{}", depot_id), // Fallback name - will be enhanced below
                 manifest_id,
-                manifest_id_used: None,
+                manifest_id_used,
             });
         }
     }
@@ -269,15 +592,11 @@ fn derive_metadata_from_download(
 
     // Enhance depot names using proper naming strategy
     // First, try to get depot names from preflight (if available)
-    let preflight_depot_names = {
-        app_handle
-            .state::<DepotRunnerState>()
-            .inner
-            .lock()
-            .ok()
-            .map(|guard| guard.depot_names.clone())
-            .unwrap_or_default()
-    };
+    let preflight_depot_names = state_handle
+        .lock()
+        .ok()
+        .map(|guard| guard.depot_names.clone())
+        .unwrap_or_default();
 
     use crate::steam_api::get_depot_name;
     for depot in &mut depots {
@@ -297,14 +616,7 @@ fn derive_metadata_from_download(
     // Normalize platform using the same logic as metadata_resolver
     let platform_normalized = map_platform_for_output(&job.os);
 
-    // Get build timestamp - PRIMARY: SteamDB API, FALLBACK: manifest timestamps
-    // Find the primary depot's manifest ID first
-    let primary_manifest_id = depots
-        .iter()
-        .find(|d| d.depot_id == primary_depot_id)
-        .map(|d| d.manifest_id.clone());
-
-    // PRIMARY: Query SteamDB for build release date
+    // Get build timestamp - PRIMARY: SteamDB API, FALLBACK: manifest creation times
     let mut build_datetime_utc = match fetch_build_date(&job.app_id, Some(&build_id)) {
         Ok(timestamp) => {
             eprintln!("[STEAMDB] Got build date for app {}: {}", job.app_id, timestamp);
@@ -316,34 +628,17 @@ fn derive_metadata_from_download(
         }
     };
 
-    // FALLBACK: Use manifest timestamps from download if SteamDB failed
+    // FALLBACK: the primary depot's manifest, else the newest manifest of the build
     if build_datetime_utc.is_none() {
-        let state_handle = app_handle.state::<DepotRunnerState>().inner.clone();
-        build_datetime_utc = state_handle.lock().ok().and_then(|guard| {
-            // Try timeupdated if captured from DepotDownloader output
-            if let Some(ts) = guard.build_datetime_utc {
-                return Some(ts);
-            }
-
-            // Try manifest timestamp for primary depot
-            if let Some(ref manifest_id) = primary_manifest_id {
-                if let Some(ts) = guard.manifest_timestamps.get(manifest_id) {
-                    return Some(*ts);
-                }
-            }
-
-            // Try depot timestamps
-            if let Some(ts) = guard.depot_timestamps.get(&primary_depot_id) {
-                return Some(*ts);
-            }
-
-            None
-        });
+        build_datetime_utc = manifest_times
+            .get(&primary_depot_id)
+            .copied()
+            .or_else(|| manifest_times.values().max().copied());
     }
 
     // Create job metadata
-    let job_metadata = JobMetadataFile::new(
-        job_id.to_string(),
+    let mut job_metadata = JobMetadataFile::new(
+        staging_id.to_string(),
         job.app_id.clone(),
         branch_normalized,
         platform_normalized,
@@ -354,12 +649,17 @@ fn derive_metadata_from_download(
         build_datetime_utc,
         depots,
     );
+    job_metadata.language = job.language();
+    job_metadata.low_violence = job.low_violence;
+    job_metadata.log_path = job_log_path(host, job_id)
+        .ok()
+        .map(|path| path.to_string_lossy().to_string());
 
     // Write job.json
     job_metadata.write_to_dir(staging_dir)?;
 
     emit_log(
-        app_handle,
+        host,
         "system",
         "Metadata derived from download output",
         job_id,
@@ -402,58 +702,103 @@ fn redact_7z_password_args(args: &[String]) -> Vec<String> {
         .collect()
 }
 
-/// Compresses the finalized output folder using 7-Zip.
-/// On success, deletes the uncompressed folder and returns the archive path.
-/// On failure, leaves the folder intact and returns an error.
+/// Compresses the finalized output folder using 7-Zip, or the native tar writer
+/// for tar formats.
+/// Returns the archive path (the first volume for split archives). The folder is
+/// always left in place; it is only removed after `verify_archive` passes.
+/// On failure, partial archives are removed.
 fn compress_output(
-    app_handle: &AppHandle,
+    host: &SharedJobHost,
+    zip_state: &SevenZipRunnerState,
     output_path: &std::path::Path,
     job_id: &str,
-    compression_password: Option<&str>,
+    job: &JobMetadata,
+    profile: Option<&CompressionProfile>,
 ) -> Result<std::path::PathBuf, String> {
-    let archive_path = resolve_archive_path(output_path);
+    let archive_format = job.archive_format;
+    let volume_size_mb = job.volume_size_mb;
+    let archive_path = resolve_archive_path(output_path, archive_format);
 
-    if archive_path.exists() {
+    if archive_exists(&archive_path) {
         return Err(format!(
             "Archive already exists: {}",
             archive_path.display()
         ));
     }
 
-    let args =
-        calculate_7z_compression_args(output_path, &archive_path, compression_password);
-    let redacted_args = redact_7z_password_args(&args);
+    if archive_format.uses_7zip() {
+        let args = calculate_7z_compression_args(
+            output_path,
+            &archive_path,
+            job.compression_password(),
+            archive_format,
+            volume_size_mb,
+            profile,
+        );
+        let redacted_args = redact_7z_password_args(&args);
 
-    emit_log(
-        app_handle,
-        "system",
-        &format!("7-Zip command: 7zz {}", redacted_args.join(" ")),
-        job_id,
-    );
+        emit_log(
+            host,
+            "system",
+            &format!("7-Zip command: 7zz {}", redacted_args.join(" ")),
+            job_id,
+        );
 
-    let zip_state = app_handle.state::<SevenZipRunnerState>();
-    let exit_code = match run_7zip_blocking(app_handle, &zip_state, args) {
-        Ok(code) => code,
-        Err(err) => {
-            let _ = std::fs::remove_file(&archive_path);
-            return Err(err);
-        }
-    };
+        let exit_code = match run_7zip_blocking(host, zip_state, args, job_id) {
+            Ok(code) => code,
+            Err(err) => {
+                remove_partial_archive(&archive_path);
+                return Err(err);
+            }
+        };
 
-    if exit_code != 0 {
-        // Clean up partial archive if it exists
-        let _ = std::fs::remove_file(&archive_path);
-        return Err(format!("7-Zip exited with code {}", exit_code));
+        if exit_code != 0 {
+            // Clean up partial archive (or volumes) if they exist
+            remove_partial_archive(&archive_path);
+            return Err(format!("7-Zip exited with code {}", exit_code));
+        }
+    } else if let Err(err) = write_tar_archive(
+        host,
+        zip_state,
+        output_path,
+        &archive_path,
+        archive_format,
+        volume_size_mb,
+        profile,
+        job_id,
+    ) {
+        remove_partial_archive(&archive_path);
+        return Err(err);
     }
 
-    if !archive_path.exists() {
+    // Split archives are reported by their first volume
+    let archive_path = if volume_size_mb.is_some() {
+        let volumes = existing_volumes(&archive_path);
+        let Some(first_volume) = volumes.first().cloned() else {
+            return Err("Archive volumes not found after compression".to_string());
+        };
+        emit_log(
+            host,
+            "system",
+            &format!("Archive split into {} volume(s).", volumes.len()),
+            job_id,
+        );
+        first_volume
+    } else if archive_path.exists() {
+        archive_path
+    } else {
         return Err("Archive not found after compression".to_string());
-    }
+    };
+
+    Ok(archive_path)
+}
 
+/// Removes the uncompressed output folder once its archive has been verified
+fn remove_uncompressed_output(host: &SharedJobHost, output_path: &Path, job_id: &str) {
     // Default behavior: Remove uncompressed folder after successful compression
     // Future: Make this configurable via settings (keep_uncompressed)
     emit_log(
-        app_handle,
+        host,
         "system",
         "Removing uncompressed folder...",
         job_id,
@@ -461,7 +806,7 @@ fn compress_output(
 
     if let Err(e) = std::fs::remove_dir_all(output_path) {
         emit_log(
-            app_handle,
+            host,
             "system",
             &format!(
                 "Warning: Failed to remove folder: {}. Archive still created successfully.",
@@ -472,14 +817,18 @@ fn compress_output(
         // Don't fail job - archive was created successfully
     } else {
         emit_log(
-            app_handle,
+            host,
             "system",
             "Uncompressed folder removed.",
             job_id,
         );
     }
+}
 
-    Ok(archive_path)
+/// Removes a partially written archive and any volumes written so far
+fn remove_partial_archive(archive_path: &Path) {
+    let _ = std::fs::remove_file(archive_path);
+    let _ = remove_volumes(archive_path);
 }
 
 /// Builds DepotDownloader command-line arguments from job metadata
@@ -502,16 +851,19 @@ fn build_depot_args(job: &JobMetadata) -> Result<Vec<String>, String> {
     args.push("-osarch".to_string());
     args.push(arch.to_string());
 
+    args.extend(depot_selection_args(job)?);
+
+    if job.validate_content {
+        args.push("-validate".to_string());
+    }
+
     if job.qr_enabled {
         args.push("-qr".to_string());
     } else if !job.username.is_empty() {
+        // The password is typed into DepotDownloader's prompt (see `stdin_password`)
+        // rather than passed as -password, where other local users could read it
         args.push("-username".to_string());
         args.push(job.username.clone());
-
-        if !job.password.is_empty() {
-            args.push("-password".to_string());
-            args.push(job.password.clone());
-        }
         args.push("-remember-password".to_string());
     }
     // If both username and password are empty, attempt anonymous download (no auth args)
@@ -519,6 +871,63 @@ fn build_depot_args(job: &JobMetadata) -> Result<Vec<String>, String> {
     Ok(args)
 }
 
+/// Start of DepotDownloader's password prompt (`Enter account password for "<user>": `)
+const PASSWORD_PROMPT: &str = "Enter account password for";
+
+const PASSWORD_UNAVAILABLE_ERROR: &str = "DepotDownloader asked for a password, but none is available. Save a password for the account, or log in once so the login is cached.";
+
+/// Password to type into DepotDownloader's password prompt, if the job logs in with one
+fn stdin_password(job: &JobMetadata) -> Option<String> {
+    if job.qr_enabled || job.username.trim().is_empty() || job.password.is_empty() {
+        None
+    } else {
+        Some(job.password.clone())
+    }
+}
+
+/// Answers DepotDownloader's password prompt once it appears in `output`.
+/// The password is handed over only once; returns whether the prompt was seen.
+/// With no password to give and nobody at stdin, DepotDownloader is stopped
+/// rather than left waiting, and the job fails with `PASSWORD_UNAVAILABLE_ERROR`.
+fn maybe_answer_password_prompt(
+    state_handle: &Arc<Mutex<RunningJobState>>,
+    output: &[u8],
+    job_id: &str,
+) -> bool {
+    if !output
+        .windows(PASSWORD_PROMPT.len())
+        .any(|window| window == PASSWORD_PROMPT.as_bytes())
+    {
+        return false;
+    }
+
+    if let Ok(mut guard) = state_handle.lock() {
+        if guard.job_id.as_deref() == Some(job_id) {
+            match guard.pending_password.take() {
+                Some(password) => {
+                    if let Some(stdin) = guard.stdin.as_mut() {
+                        let _ = stdin
+                            .write_all(password.as_bytes())
+                            .and_then(|()| stdin.write_all(b"\n"))
+                            .and_then(|()| stdin.flush());
+                    }
+                }
+                None if !guard.interactive => stop_at_password_prompt(&mut guard),
+                None => guard.awaiting_password = true,
+            }
+        }
+    }
+    true
+}
+
+/// Stops DepotDownloader at a password prompt nobody can answer
+fn stop_at_password_prompt(guard: &mut RunningJobState) {
+    guard.password_unavailable = true;
+    if let Some(child) = guard.child.as_mut() {
+        let _ = child.kill();
+    }
+}
+
 const AUTH_ROOT_FILES: &[&str] = &["sentry.bin", "config.json", "loginusers.vdf"];
 const AUTH_CONFIG_FILES: &[&str] = &["loginusers.vdf", "config.vdf", "config.json", "sentry.bin"];
 
@@ -586,7 +995,7 @@ fn collect_auth_files(root: &Path) -> Result<Vec<(PathBuf, PathBuf)>, String> {
     Ok(files)
 }
 
-fn sanitize_auth_username(username: &str) -> String {
+pub fn sanitize_auth_username(username: &str) -> String {
     let mut sanitized = String::with_capacity(username.len());
     for ch in username.chars() {
         if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
@@ -603,14 +1012,50 @@ fn sanitize_auth_username(username: &str) -> String {
     }
 }
 
-fn resolve_auth_cache_dir(app_handle: &AppHandle, username: &str) -> Result<PathBuf, String> {
-    let downloads_dir = resolve_downloads_dir(app_handle)?;
+/// Account names a cached Steam config belongs to, read from
+/// `config/loginusers.vdf` (`users/<steamid>/AccountName`) and
+/// `config/config.vdf` (`InstallConfigStore/Software/Valve/Steam/Accounts`)
+fn cached_auth_accounts(cache_dir: &Path) -> Vec<String> {
+    let mut accounts: Vec<String> = Vec::new();
+    let read = |name: &str| {
+        std::fs::read_to_string(cache_dir.join("config").join(name))
+            .ok()
+            .and_then(|text| vdf::parse(&text).ok())
+    };
+
+    if let Some(users) = read("loginusers.vdf")
+        .as_ref()
+        .and_then(|root| root.get_section("users").cloned())
+    {
+        for (_, user) in users.entries() {
+            if let VdfValue::Section(user) = user {
+                if let Some(name) = user.get_str("AccountName") {
+                    accounts.push(name.to_string());
+                }
+            }
+        }
+    }
+    if let Some(config) = read("config.vdf") {
+        if let Some(known) =
+            config.get_path(&["InstallConfigStore", "Software", "Valve", "Steam", "Accounts"])
+        {
+            accounts.extend(known.entries().iter().map(|(name, _)| name.clone()));
+        }
+    }
+
+    accounts.sort_by_key(|name| name.to_ascii_lowercase());
+    accounts.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    accounts
+}
+
+pub fn resolve_auth_cache_dir(host: &dyn JobHost, username: &str) -> Result<PathBuf, String> {
+    let downloads_dir = host.downloads_dir()?;
     let auth_root = downloads_dir.join(".auth");
     Ok(auth_root.join(sanitize_auth_username(username)))
 }
 
 fn restore_auth_cache(
-    app_handle: &AppHandle,
+    host: &dyn JobHost,
     username: &str,
     target_dir: &Path,
     job_id: &str,
@@ -618,11 +1063,25 @@ fn restore_auth_cache(
     if username.trim().is_empty() {
         return Ok(());
     }
-    let cache_dir = resolve_auth_cache_dir(app_handle, username)?;
+    let cache_dir = resolve_auth_cache_dir(host, username)?;
     if !cache_dir.exists() {
         return Ok(());
     }
 
+    let accounts = cached_auth_accounts(&cache_dir);
+    if !accounts.is_empty() && !accounts.iter().any(|name| name.eq_ignore_ascii_case(username)) {
+        emit_log(
+            host,
+            "system",
+            &format!(
+                "Auth cache for {} lists other accounts ({}); Steam Guard may prompt again",
+                username,
+                accounts.join(", ")
+            ),
+            job_id,
+        );
+    }
+
     let entries = collect_auth_files(&cache_dir)?;
     let mut restored = Vec::new();
     for (path, rel_path) in entries {
@@ -638,7 +1097,7 @@ fn restore_auth_cache(
 
     if !restored.is_empty() {
         emit_log(
-            app_handle,
+            host,
             "system",
             &format!("Auth cache restored: {}", restored.join(", ")),
             job_id,
@@ -649,7 +1108,7 @@ fn restore_auth_cache(
 }
 
 fn persist_auth_cache(
-    app_handle: &AppHandle,
+    host: &dyn JobHost,
     username: &str,
     source_dir: &Path,
     job_id: &str,
@@ -657,7 +1116,7 @@ fn persist_auth_cache(
     if username.trim().is_empty() {
         return Ok(());
     }
-    let cache_dir = resolve_auth_cache_dir(app_handle, username)?;
+    let cache_dir = resolve_auth_cache_dir(host, username)?;
     std::fs::create_dir_all(&cache_dir)
         .map_err(|e| format!("Failed to create auth cache directory: {}", e))?;
 
@@ -676,7 +1135,7 @@ fn persist_auth_cache(
 
     if !persisted.is_empty() {
         emit_log(
-            app_handle,
+            host,
             "system",
             &format!("Auth cache saved: {}", persisted.join(", ")),
             job_id,
@@ -733,89 +1192,421 @@ fn maybe_update_auth_username(
     }
 }
 
+/// Resets the runner state for a new job and reserves its job ID.
+/// Fails if another job is already reserved or DepotDownloader is running.
+pub fn begin_job(state: &DepotRunnerState) -> Result<String, String> {
+    let mut guard = state
+        .inner
+        .lock()
+        .map_err(|_| "Failed to lock DepotDownloader state".to_string())?;
+
+    if guard.child.is_some() || guard.job_id.is_some() {
+        return Err("DepotDownloader is already running".to_string());
+    }
+    guard.last_depot_mentioned = None;
+    guard.auth_username = None;
+    guard.preflight_build_id = None;
+    guard.preflight_manifest_ids.clear();
+    guard.staging_id = None;
+
+    let job_id = generate_job_id();
+    guard.job_id = Some(job_id.clone());
+    Ok(job_id)
+}
+
 #[tauri::command]
 pub fn run_depotdownloader(
     app_handle: AppHandle,
     state: State<'_, DepotRunnerState>,
-    job: JobMetadata,
+    zip_state: State<'_, SevenZipRunnerState>,
+    mut job: JobMetadata,
+    login: Option<LoginData>,
 ) -> Result<String, String> {
-    let job_id = {
-        let mut guard = state
-            .inner
-            .lock()
-            .map_err(|_| "Failed to lock DepotDownloader state".to_string())?;
-
-        if guard.child.is_some() {
-            return Err("DepotDownloader is already running".to_string());
-        }
-        guard.build_datetime_utc = None;
-        guard.depot_timestamps.clear();
-        guard.manifest_to_depot.clear();
-        guard.manifest_timestamps.clear();
-        guard.last_depot_mentioned = None;
-        guard.auth_username = None;
+    apply_login(&mut job, login)?;
+    let job_id = begin_job(&state)?;
 
-        let job_id = generate_job_id();
-        guard.job_id = Some(job_id.clone());
-        job_id
-    };
-
-    emit_status(&app_handle, "starting", None, &job_id);
+    let host: SharedJobHost = Arc::new(app_handle);
+    emit_status(&host, "starting", None, &job_id);
 
-    let app_handle_clone = app_handle.clone();
-    let state_handle = state.inner.clone();
-    let job_clone = job.clone();
+    let state_clone = state.inner().clone();
+    let zip_state_clone = zip_state.inner().clone();
     let job_id_clone = job_id.clone();
 
     thread::spawn(move || {
-        run_depotdownloader_worker(app_handle_clone, state_handle, job_clone, job_id_clone);
+        // Failures are already reported through the host as log/status events
+        let _ = run_job_pipeline(&host, &state_clone, &zip_state_clone, &job, &job_id_clone);
     });
 
     Ok(job_id)
 }
 
-fn run_depotdownloader_worker(
-    app_handle: AppHandle,
-    state_handle: Arc<Mutex<RunningJobState>>,
-    job: JobMetadata,
-    job_id: String,
-) {
-    let path = match resolve_depotdownloader_path(&app_handle) {
-        Ok(path) => path,
+/// Runs a reserved job end to end and blocks until it finishes:
+/// preflight → DepotDownloader → metadata → finalization → compression → template.
+///
+/// Multi-platform jobs repeat the download and finalization for every platform
+/// (each in its own staging directory) while running preflight only once.
+///
+/// Progress is reported through `host`; the returned paths are the final outputs,
+/// one per platform (archive when compression succeeded, otherwise the output folder).
+pub fn run_job_pipeline(
+    host: &SharedJobHost,
+    state: &DepotRunnerState,
+    zip_state: &SevenZipRunnerState,
+    job: &JobMetadata,
+    job_id: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let (job, compression_profile) = match validate_archive_options(job)
+        .and_then(|()| resolve_compression_profile(host.as_ref(), job))
+        .and_then(|profile| {
+            resolve_job_account(host.as_ref(), job).map(|resolved| (resolved, profile))
+        }) {
+        Ok(((job, account_note), profile)) => {
+            if let Some(note) = account_note {
+                emit_log(host, "system", &note, job_id);
+            }
+            (job, profile)
+        }
         Err(err) => {
-            emit_status(&app_handle, "error", None, &job_id);
-            clear_runner_state(&state_handle, &job_id);
-            eprintln!("Failed to resolve DepotDownloader path: {err}");
-            return;
+            emit_log(host, "system", &err, job_id);
+            emit_status(host, "error", None, job_id);
+            clear_runner_state(&state.inner, job_id);
+            return Err(err);
         }
     };
+    let job = &job;
+
+    let platforms = job.platform_list();
+    let multi_platform = platforms.len() > 1;
+    let mut outputs = Vec::new();
+    let mut platform_metadata = Vec::new();
+    let mut skipped_platforms = 0usize;
+
+    for (index, os) in platforms.iter().enumerate() {
+        let mut platform_job = job.clone();
+        platform_job.os = os.clone();
+        let staging_id = if multi_platform {
+            format!("{}_{}", job_id, map_platform_for_output(os).to_ascii_lowercase())
+        } else {
+            job_id.to_string()
+        };
+
+        if multi_platform {
+            emit_log(
+                host,
+                "system",
+                &format!("=== Platform {}/{}: {} ===", index + 1, platforms.len(), os),
+                job_id,
+            );
+            reset_platform_state(&state.inner, job_id);
+        }
+
+        let started_at = Utc::now();
+        let (staging_dir, conflict_choice) =
+            match download_platform(host, state, &platform_job, job_id, &staging_id, index == 0) {
+                Ok(PlatformDownload::Staged {
+                    staging_dir,
+                    conflict_choice,
+                }) => (staging_dir, conflict_choice),
+                Ok(PlatformDownload::AlreadyPacked(existing)) => {
+                    outputs.push(existing);
+                    skipped_platforms += 1;
+                    continue;
+                }
+                Err(err) => {
+                    let mut history = HistoryEntry::new(
+                        job_id,
+                        &job.app_id,
+                        &job.branch,
+                        &map_platform_for_output(os),
+                        None,
+                        started_at,
+                    );
+                    history.fail(&err);
+                    record_output_history(host.as_ref(), &history, job_id);
+                    return Err(err);
+                }
+            };
+        let mut history = HistoryEntry::new(
+            job_id,
+            &job.app_id,
+            &job.branch,
+            &map_platform_for_output(os),
+            JobMetadataFile::read_from_dir(&staging_dir).ok(),
+            started_at,
+        );
+
+        match finalize_and_package(
+            host,
+            zip_state,
+            &platform_job,
+            job_id,
+            &staging_id,
+            &staging_dir,
+            compression_profile.as_ref(),
+            conflict_choice,
+        ) {
+            Ok((output_path, template_metadata)) => {
+                history.complete(&output_path, template_metadata.as_ref());
+                record_output_history(host.as_ref(), &history, job_id);
+                outputs.push(output_path);
+                platform_metadata.extend(template_metadata);
+            }
+            Err(err) => {
+                history.fail(&err);
+                record_output_history(host.as_ref(), &history, job_id);
+                clear_runner_state(&state.inner, job_id);
+                return Err(err);
+            }
+        }
+    }
+
+    if multi_platform && job.combined_release_notes {
+        match write_combined_release_notes(host.as_ref(), &platform_metadata) {
+            Ok(path) => emit_log(
+                host,
+                "system",
+                &format!("Combined release notes written: {}", path.display()),
+                job_id,
+            ),
+            Err(err) => emit_log(
+                host,
+                "system",
+                &format!("Failed to write combined release notes: {}", err),
+                job_id,
+            ),
+        }
+    }
+
+    // One terminal status per job: skipped only when every platform was already packed
+    if skipped_platforms == platforms.len() {
+        emit_status(host, "skipped", None, job_id);
+    } else {
+        emit_status(host, "completed", Some(0), job_id);
+    }
+    clear_runner_state(&state.inner, job_id);
+    Ok(outputs)
+}
+
+#[tauri::command]
+pub fn create_update_pack(
+    app_handle: AppHandle,
+    state: State<'_, DepotRunnerState>,
+    zip_state: State<'_, SevenZipRunnerState>,
+    old_path: String,
+    new_path: String,
+    job: JobMetadata,
+) -> Result<String, String> {
+    let job_id = begin_job(&state)?;
+
+    let host: SharedJobHost = Arc::new(app_handle);
+    emit_status(&host, "starting", None, &job_id);
+
+    let state_clone = state.inner().clone();
+    let zip_state_clone = zip_state.inner().clone();
+    let job_id_clone = job_id.clone();
+
+    thread::spawn(move || {
+        let _ = run_update_pack_pipeline(
+            &host,
+            &state_clone,
+            &zip_state_clone,
+            &job,
+            Path::new(&old_path),
+            Path::new(&new_path),
+            &job_id_clone,
+        );
+    });
+
+    Ok(job_id)
+}
+
+/// Builds a differential update pack from the build at `old_path` to the one at
+/// `new_path` and packages it like a regular output (archive, checksums, release
+/// notes). Only the job's packaging options are used.
+pub fn run_update_pack_pipeline(
+    host: &SharedJobHost,
+    state: &DepotRunnerState,
+    zip_state: &SevenZipRunnerState,
+    job: &JobMetadata,
+    old_path: &Path,
+    new_path: &Path,
+    job_id: &str,
+) -> Result<PathBuf, String> {
+    let result = build_update_pack(host, zip_state, job, old_path, new_path, job_id);
+    match &result {
+        Ok(_) => emit_status(host, "completed", Some(0), job_id),
+        Err(err) => {
+            emit_log(host, "system", &format!("Update pack failed: {}", err), job_id);
+            emit_status(host, "error", None, job_id);
+        }
+    }
+    clear_runner_state(&state.inner, job_id);
+    result
+}
+
+fn build_update_pack(
+    host: &SharedJobHost,
+    zip_state: &SevenZipRunnerState,
+    job: &JobMetadata,
+    old_path: &Path,
+    new_path: &Path,
+    job_id: &str,
+) -> Result<PathBuf, String> {
+    validate_archive_options(job)?;
+    let compression_profile = resolve_compression_profile(host.as_ref(), job)?;
+    let started_at = Utc::now();
+
+    emit_status(host, "diffing", None, job_id);
+    emit_log(
+        host,
+        "system",
+        &format!(
+            "Comparing builds: {} → {}",
+            old_path.display(),
+            new_path.display()
+        ),
+        job_id,
+    );
+    let old = load_build(old_path)?;
+    let new = load_build(new_path)?;
+    check_compatible(&old, &new)?;
+    let diff = diff_builds(&old, &new);
+    if diff.added.is_empty() && diff.changed.is_empty() && diff.removed.is_empty() {
+        return Err("The builds have identical files; there is nothing to pack".to_string());
+    }
+
+    let mut pack_dir = host
+        .downloads_dir()?
+        .join("outputs")
+        .join(update_pack_name(&old, &new));
+    let archive_format = (!job.skip_compression).then_some(job.archive_format);
+    let archive_path = archive_format.map(|format| resolve_archive_path(&pack_dir, format));
+    if pack_dir.exists() || archive_path.as_deref().is_some_and(archive_exists) {
+        match host.resolve_output_conflict(job_id, &pack_dir)? {
+            OutputConflictChoice::Overwrite => {
+                remove_existing_output(&pack_dir)?;
+                if let Some(path) = archive_path.as_ref() {
+                    remove_existing_archive(path)?;
+                }
+            }
+            OutputConflictChoice::Copy => {
+                pack_dir = resolve_copy_output_path(&pack_dir, archive_format)?
+            }
+            OutputConflictChoice::Cancel => {
+                return Err(format!(
+                    "Output already exists: {}. Job cancelled by user.",
+                    pack_dir.display()
+                ));
+            }
+        }
+    }
+
+    let summary = match write_update_pack(&old, &new, &diff, &pack_dir) {
+        Ok(summary) => summary,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&pack_dir);
+            return Err(err);
+        }
+    };
+    emit_log(
+        host,
+        "system",
+        &format!(
+            "Update pack {} → {}: {} added, {} changed, {} removed ({} bytes). Output: {}",
+            old.metadata.build_id,
+            new.metadata.build_id,
+            summary.added,
+            summary.changed,
+            summary.removed,
+            summary.bytes,
+            pack_dir.display()
+        ),
+        job_id,
+    );
+
+    let mut template_metadata = TemplateMetadata::from_job_metadata(&new.metadata);
+    template_metadata.previous_build_id = old.metadata.build_id.clone();
+    let final_output_path = package_output(
+        host,
+        zip_state,
+        job,
+        job_id,
+        &pack_dir,
+        compression_profile.as_ref(),
+        Some(&mut template_metadata),
+    )?;
+
+    let mut history = HistoryEntry::new(job_id, "", "", "", Some(new.metadata), started_at);
+    history.complete(&final_output_path, Some(&template_metadata));
+    record_output_history(host.as_ref(), &history, job_id);
+    Ok(final_output_path)
+}
+
+/// Outcome of `download_platform`
+enum PlatformDownload {
+    /// Downloaded into this staging directory. `conflict_choice` is the answer
+    /// already given for an existing output of the same build, if one was found.
+    Staged {
+        staging_dir: PathBuf,
+        conflict_choice: Option<OutputConflictChoice>,
+    },
+    /// The build is already packed here; nothing was downloaded
+    AlreadyPacked(PathBuf),
+}
+
+/// Downloads one platform of a job into the staging directory `staging_id` and
+/// derives its job.json. When preflight shows the build is already packed, the
+/// user is asked right away (or the job skips it, see `skip_packed`) instead of
+/// after the download. On failure the error is reported, staging is cleaned up
+/// (or kept for resume) and the runner is released.
+fn download_platform(
+    host: &SharedJobHost,
+    state: &DepotRunnerState,
+    job: &JobMetadata,
+    job_id: &str,
+    staging_id: &str,
+    run_preflight: bool,
+) -> Result<PlatformDownload, String> {
+    let state_handle = &state.inner;
 
-    let staging_dir = match create_staging_dir(&app_handle, &job_id) {
-        Ok(dir) => dir,
+    let path = match resolve_depotdownloader_path(host.as_ref()) {
+        Ok(path) => path,
+        Err(err) => {
+            emit_status(host, "error", None, job_id);
+            clear_runner_state(state_handle, job_id);
+            eprintln!("Failed to resolve DepotDownloader path: {err}");
+            return Err(err);
+        }
+    };
+
+    let (staging_dir, resumed) = match prepare_staging_dir(host.as_ref(), job, staging_id) {
+        Ok(prepared) => prepared,
         Err(err) => {
-            emit_status(&app_handle, "error", None, &job_id);
-            clear_runner_state(&state_handle, &job_id);
+            emit_status(host, "error", None, job_id);
+            clear_runner_state(state_handle, job_id);
             eprintln!("Failed to create staging directory: {err}");
-            return;
+            return Err(err);
         }
     };
 
     emit_log(
-        &app_handle,
+        host,
         "system",
         &format!("Job ID: {}", job_id),
-        &job_id,
+        job_id,
     );
     emit_log(
-        &app_handle,
+        host,
         "system",
         &format!("Staging directory: {}", staging_dir.display()),
-        &job_id,
+        job_id,
     );
 
     if let Ok(mut guard) = state_handle.lock() {
-        if guard.job_id.as_deref() == Some(&job_id) {
+        if guard.job_id.as_deref() == Some(job_id) {
+            guard.staging_id = Some(staging_id.to_string());
             let trimmed = job.username.trim();
+            remember_username(trimmed);
             guard.auth_username = if trimmed.is_empty() {
                 None
             } else {
@@ -824,82 +1615,129 @@ fn run_depotdownloader_worker(
         }
     }
 
-    if let Some(username) = resolve_auth_username(&state_handle, &job, &job_id) {
-        if let Err(err) = restore_auth_cache(&app_handle, &username, &staging_dir, &job_id) {
+    if let Some(username) = resolve_auth_username(state_handle, job, job_id) {
+        if let Err(err) = restore_auth_cache(host.as_ref(), &username, &staging_dir, job_id) {
             emit_log(
-                &app_handle,
+                host,
                 "system",
                 &format!("Failed to restore auth cache: {}", err),
-                &job_id,
+                job_id,
             );
         }
     }
 
-    let state_wrapper = DepotRunnerState {
-        inner: state_handle.clone(),
-    };
-
-    if let Err(err) =
-        run_preflight_before_download(&app_handle, &state_wrapper, &job, &job_id, &staging_dir)
-    {
+    if !run_preflight {
+        emit_log(
+            host,
+            "system",
+            "Reusing preflight results from the first platform.",
+            job_id,
+        );
+    } else if let Err(err) = run_preflight_before_download(host, state, job, job_id, &staging_dir) {
         emit_log(
-            &app_handle,
+            host,
             "system",
             &format!("Preflight failed: {err}"),
-            &job_id,
+            job_id,
         );
-        emit_status(&app_handle, "error", None, &job_id);
-        let _ = cleanup_staging_dir(&app_handle, &job_id);
-        clear_runner_state(&state_handle, &job_id);
-        return;
+        emit_status(host, "error", None, job_id);
+        cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+        clear_runner_state(state_handle, job_id);
+        return Err(format!("Preflight failed: {err}"));
     }
 
-    if let Some(username) = resolve_auth_username(&state_handle, &job, &job_id) {
-        if let Err(err) = restore_auth_cache(&app_handle, &username, &staging_dir, &job_id) {
+    if let Some(username) = resolve_auth_username(state_handle, job, job_id) {
+        if let Err(err) = restore_auth_cache(host.as_ref(), &username, &staging_dir, job_id) {
             emit_log(
-                &app_handle,
+                host,
                 "system",
                 &format!("Failed to restore auth cache: {}", err),
-                &job_id,
+                job_id,
             );
         }
     }
 
     if let Ok(guard) = state_handle.lock() {
         if guard.job_id.is_none() {
-            let _ = cleanup_staging_dir(&app_handle, &job_id);
-            return;
+            let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+            return Err("Job cancelled".to_string());
+        }
+    }
+
+    // === UP-TO-DATE CHECK ===
+    let mut conflict_choice = None;
+    if let Some(existing) = find_existing_build(host.as_ref(), state_handle, job, job_id) {
+        let choice = if job.skip_packed {
+            OutputConflictChoice::Cancel
+        } else {
+            emit_log(
+                host,
+                "system",
+                &format!(
+                    "This build is already packed: {}. Cancel skips the download; overwrite or copy downloads it again.",
+                    existing.display()
+                ),
+                job_id,
+            );
+            match host.resolve_output_conflict(job_id, &existing) {
+                Ok(choice) => choice,
+                Err(err) => {
+                    emit_log(host, "system", &err, job_id);
+                    emit_status(host, "error", None, job_id);
+                    cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+                    clear_runner_state(state_handle, job_id);
+                    return Err(err);
+                }
+            }
+        };
+        if let OutputConflictChoice::Cancel = choice {
+            emit_log(
+                host,
+                "system",
+                &format!(
+                    "Skipping download; the build is already packed at {}",
+                    existing.display()
+                ),
+                job_id,
+            );
+            cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+            return Ok(PlatformDownload::AlreadyPacked(existing));
         }
+        conflict_choice = Some(choice);
     }
+    // === END UP-TO-DATE CHECK ===
 
     emit_log(
-        &app_handle,
+        host,
         "system",
         "Starting DepotDownloader...",
-        &job_id,
+        job_id,
     );
 
-    let args = match build_depot_args(&job) {
+    let args = match build_depot_args(job) {
         Ok(args) => args,
         Err(err) => {
             emit_log(
-                &app_handle,
+                host,
                 "system",
                 &format!("Failed to build DepotDownloader args: {err}"),
-                &job_id,
+                job_id,
             );
-            emit_status(&app_handle, "error", None, &job_id);
-            let _ = cleanup_staging_dir(&app_handle, &job_id);
-            clear_runner_state(&state_handle, &job_id);
-            return;
+            emit_status(host, "error", None, job_id);
+            cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+            clear_runner_state(state_handle, job_id);
+            return Err(err);
         }
     };
 
     emit_log(
-        &app_handle,
+        host,
         "system",
-        &format!("DepotDownloader args: {}", args.join(" ")),
-        &job_id,
+        &format!(
+            "DepotDownloader args: {}",
+            redact_password_args(&args).join(" ")
+        ),
+        job_id,
     );
 
     let mut command = Command::new(&path);
@@ -916,16 +1754,16 @@ fn run_depotdownloader_worker(
     let mut child = match command.spawn() {
         Ok(child) => child,
         Err(err) => {
-            emit_status(&app_handle, "error", None, &job_id);
-            let _ = cleanup_staging_dir(&app_handle, &job_id);
-            clear_runner_state(&state_handle, &job_id);
+            emit_status(host, "error", None, job_id);
+            cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+            clear_runner_state(state_handle, job_id);
             emit_log(
-                &app_handle,
+                host,
                 "system",
                 &format!("Failed to spawn DepotDownloader: {err}"),
-                &job_id,
+                job_id,
             );
-            return;
+            return Err(format!("Failed to spawn DepotDownloader: {err}"));
         }
     };
 
@@ -936,26 +1774,29 @@ fn run_depotdownloader_worker(
     if let Ok(mut guard) = state_handle.lock() {
         guard.child = Some(child);
         guard.stdin = stdin;
-        guard.job_id = Some(job_id.clone());
+        guard.pending_password = stdin_password(job);
+        guard.password_unavailable = false;
+        guard.awaiting_password = false;
+        guard.job_id = Some(job_id.to_string());
     }
 
-    emit_status(&app_handle, "running", None, &job_id);
+    emit_status(host, "running", None, job_id);
 
     let stdout_handle = stdout.map(|stream| {
         spawn_log_reader(
-            app_handle.clone(),
+            host.clone(),
             stream,
             "stdout",
-            job_id.clone(),
+            job_id.to_string(),
             state_handle.clone(),
         )
     });
     let stderr_handle = stderr.map(|stream| {
         spawn_log_reader(
-            app_handle.clone(),
+            host.clone(),
             stream,
             "stderr",
-            job_id.clone(),
+            job_id.to_string(),
             state_handle.clone(),
         )
     });
@@ -966,25 +1807,21 @@ fn run_depotdownloader_worker(
         }
     }
 
-    let app_handle_clone = app_handle.clone();
-    let job_id_for_monitor = job_id.clone();
-    let job_for_monitor = job.clone();
-    let staging_dir_for_monitor = staging_dir.clone();
-
-    thread::spawn(move || loop {
+    let status = loop {
         let status = {
             let mut lock = match state_handle.lock() {
                 Ok(lock) => lock,
                 Err(_) => {
-                    emit_status(&app_handle_clone, "error", None, &job_id_for_monitor);
+                    emit_status(host, "error", None, job_id);
                     // Clean up staging on error
-                    let _ = cleanup_staging_dir(&app_handle_clone, &job_id_for_monitor);
-                    return;
+                    let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+                    return Err("Failed to lock DepotDownloader state".to_string());
                 }
             };
 
             let Some(child) = lock.child.as_mut() else {
-                return;
+                // Cancelled: cancel_depotdownloader already reported and cleaned up
+                return Err("Job cancelled".to_string());
             };
 
             match child.try_wait() {
@@ -995,260 +1832,666 @@ fn run_depotdownloader_worker(
                 Ok(None) => None,
                 Err(err) => {
                     lock.child = None;
-                    emit_status(&app_handle_clone, "error", None, &job_id_for_monitor);
+                    drop(lock);
+                    emit_status(host, "error", None, job_id);
                     eprintln!("Failed to wait on DepotDownloader: {err}");
                     // Clean up staging on error
-                    let _ = cleanup_staging_dir(&app_handle_clone, &job_id_for_monitor);
-                    clear_runner_state(&state_handle, &job_id_for_monitor);
-                    return;
+                    let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+                    clear_runner_state(state_handle, job_id);
+                    return Err(format!("Failed to wait on DepotDownloader: {err}"));
                 }
             }
         };
 
-        if let Some(status) = status {
-            let exit_code = status.code();
+        if let Some(status) = status {
+            break status;
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+
+    let exit_code = status.code();
+    if exit_code != Some(0) {
+        let password_unavailable = take_password_unavailable(state_handle);
+        // Failure: keep downloaded content for resume, otherwise cleanup staging
+        emit_status(host, "exited", exit_code, job_id);
+        if password_unavailable {
+            emit_log(host, "system", PASSWORD_UNAVAILABLE_ERROR, job_id);
+        }
+        emit_log(host, "system", "Job failed.", job_id);
+        if let Some(username) = resolve_auth_username(state_handle, job, job_id) {
+            if let Err(err) = persist_auth_cache(host.as_ref(), &username, &staging_dir, job_id) {
+                emit_log(
+                    host,
+                    "system",
+                    &format!("Failed to persist auth cache: {}", err),
+                    job_id,
+                );
+            }
+        }
+        cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+        clear_runner_state(state_handle, job_id);
+        return Err(match exit_code {
+            _ if password_unavailable => PASSWORD_UNAVAILABLE_ERROR.to_string(),
+            Some(code) => format!("DepotDownloader exited with code {}", code),
+            None => "DepotDownloader was terminated".to_string(),
+        });
+    }
+
+    // Success: Wait for log readers to finish, then derive metadata
+    emit_log(
+        host,
+        "system",
+        "Waiting for log processing to complete...",
+        job_id,
+    );
+
+    // CRITICAL: Wait for log reader threads to finish before deriving metadata
+    // This ensures all timestamps are captured before we look them up
+    if let Ok(mut guard) = state_handle.lock() {
+        if let Some((stdout_h, stderr_h)) = guard.log_reader_threads.take() {
+            drop(guard); // Release lock before joining
+            let _ = stdout_h.join();
+            let _ = stderr_h.join();
+        }
+    }
+
+    if let Some(username) = resolve_auth_username(state_handle, job, job_id) {
+        if let Err(err) = persist_auth_cache(host.as_ref(), &username, &staging_dir, job_id) {
+            emit_log(
+                host,
+                "system",
+                &format!("Failed to persist auth cache: {}", err),
+                job_id,
+            );
+        }
+    }
+
+    if resumed {
+        let resolved = resolved_manifest_ids(state_handle, job);
+        match prune_superseded_manifest_dirs(&staging_dir, &resolved) {
+            Ok(0) => {}
+            Ok(count) => emit_log(
+                host,
+                "system",
+                &format!("Removed {} superseded manifest directories from the resumed download.", count),
+                job_id,
+            ),
+            Err(err) => emit_log(
+                host,
+                "system",
+                &format!("Failed to prune superseded manifest directories: {}", err),
+                job_id,
+            ),
+        }
+    }
+
+    if job.validate_content {
+        emit_status(host, "validating", None, job_id);
+        emit_log(host, "system", "Validating depot content against manifests...", job_id);
+        let problems = match validate_staging_depots(&staging_dir) {
+            Ok(reports) => log_validation_reports(host.as_ref(), &reports, 25, job_id),
+            Err(err) => {
+                emit_log(host, "system", &format!("Depot validation failed: {}", err), job_id);
+                1
+            }
+        };
+        if problems > 0 {
+            emit_log(
+                host,
+                "system",
+                "Download does not match its manifests. Staging was kept; resume the job to repair it.",
+                job_id,
+            );
+            emit_status(host, "error", None, job_id);
+            cleanup_or_keep_staging(host.as_ref(), staging_id, &staging_dir);
+            clear_runner_state(state_handle, job_id);
+            return Err("Depot validation failed".to_string());
+        }
+    }
+
+    emit_log(
+        host,
+        "system",
+        "Deriving metadata from download output...",
+        job_id,
+    );
+
+    if let Err(err) =
+        derive_metadata_from_download(
+            host.as_ref(),
+            state_handle,
+            job,
+            job_id,
+            staging_id,
+            &staging_dir,
+        )
+    {
+        emit_log(
+            host,
+            "system",
+            &format!("Failed to derive metadata: {}", err),
+            job_id,
+        );
+        emit_status(host, "error", None, job_id);
+        let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+        clear_runner_state(state_handle, job_id);
+        return Err(format!("Failed to derive metadata: {}", err));
+    }
+
+    emit_log(
+        host,
+        "system",
+        "Download completed successfully. Finalizing output...",
+        job_id,
+    );
+    emit_status(host, "finalizing", None, job_id);
+
+    Ok(PlatformDownload::Staged {
+        staging_dir,
+        conflict_choice,
+    })
+}
+
+/// job.json fields that identify the output of `build_id` for a job (app, branch,
+/// platform, language, low violence), normalized the way the download derives them
+pub fn build_identity(job: &JobMetadata, job_id: &str, build_id: String) -> JobMetadataFile {
+    let mut metadata = JobMetadataFile::new(
+        job_id.to_string(),
+        job.app_id.clone(),
+        capitalize_first(&job.branch),
+        map_platform_for_output(&job.os),
+        String::new(),
+        String::new(),
+        build_id,
+        BuildIdSource::PrimaryManifestId,
+        None,
+        Vec::new(),
+    );
+    metadata.language = job.language();
+    metadata.low_violence = job.low_violence;
+    metadata
+}
+
+/// Looks for an existing output of the build a job is about to download, using the
+/// build ID preflight reported: first in the output history, then at the path
+/// finalization would write to. `None` when preflight reported no build.
+fn find_existing_build(
+    host: &dyn JobHost,
+    state_handle: &Arc<Mutex<RunningJobState>>,
+    job: &JobMetadata,
+    job_id: &str,
+) -> Option<PathBuf> {
+    let build_id = state_handle
+        .lock()
+        .ok()
+        .and_then(|guard| guard.preflight_build_id.clone());
+    let Some(build_id) = build_id else {
+        emit_log(
+            host,
+            "system",
+            "Preflight did not report a build ID; skipping the up-to-date check.",
+            job_id,
+        );
+        return None;
+    };
+
+    let mut metadata = build_identity(job, job_id, build_id);
+    let history = read_history(host).unwrap_or_default();
+    if let Some(existing) = history
+        .iter()
+        .rev()
+        .filter(|entry| entry.packs_build(&metadata))
+        .find_map(HistoryEntry::existing_output)
+    {
+        return Some(existing);
+    }
+
+    // The output name includes the game name, resolved the same way as for job.json
+    metadata.game_name = match fetch_app_info(&job.app_id) {
+        Ok(info) => info.name,
+        Err(_) => format!("app_{}", job.app_id),
+    };
+    let archive_format = (!job.skip_compression).then_some(job.archive_format);
+    match find_packed_output(host, &metadata, archive_format) {
+        Ok(existing) => existing,
+        Err(err) => {
+            emit_log(
+                host,
+                "system",
+                &format!("Up-to-date check failed: {}", err),
+                job_id,
+            );
+            None
+        }
+    }
+}
+
+/// Creates the staging directory for a job, or continues an interrupted download
+/// when the job asks to resume and a matching staging directory exists.
+/// Returns the staging directory and whether it was resumed.
+fn prepare_staging_dir(
+    host: &dyn JobHost,
+    job: &JobMetadata,
+    job_id: &str,
+) -> Result<(PathBuf, bool), String> {
+    let resumable = if job.resume {
+        find_resumable_staging(host, job, None)?
+    } else {
+        None
+    };
+
+    let Some(resumable) = resumable else {
+        let staging_dir = create_staging_dir(host, job_id)?;
+        StagingInfo::new(job).write_to_dir(&staging_dir)?;
+        return Ok((staging_dir, false));
+    };
+
+    let staging_dir = adopt_staging_dir(host, &resumable.staging_id, job_id)?;
+    let mut info = resumable.info;
+    info.updated_at = chrono::Utc::now();
+    info.write_to_dir(&staging_dir)?;
+    emit_log(
+        host,
+        "system",
+        &format!(
+            "Resuming interrupted download {} ({:.1} MB already downloaded).",
+            resumable.staging_id,
+            resumable.size_bytes as f64 / (1024.0 * 1024.0)
+        ),
+        job_id,
+    );
+    Ok((staging_dir, true))
+}
+
+/// Keeps a failed job's staging directory when it holds downloaded content so the
+/// download can be resumed later; otherwise removes it.
+fn cleanup_or_keep_staging(host: &dyn JobHost, job_id: &str, staging_dir: &Path) {
+    if has_depot_content(staging_dir) {
+        emit_log(
+            host,
+            "system",
+            &format!(
+                "Downloaded content kept for resume: {}",
+                staging_dir.display()
+            ),
+            job_id,
+        );
+    } else {
+        emit_log(host, "system", "Cleaning up staging directory.", job_id);
+        let _ = cleanup_staging_dir(host, job_id);
+    }
+}
+
+/// Returns the job ID the runner is currently working on, if any
+pub fn active_job_id(state: &DepotRunnerState) -> Option<String> {
+    state.inner.lock().ok().and_then(|guard| guard.job_id.clone())
+}
+
+/// Looks for an interrupted download the frontend can offer to continue
+#[tauri::command]
+pub fn find_resumable_download(
+    app_handle: AppHandle,
+    state: State<'_, DepotRunnerState>,
+    job: JobMetadata,
+) -> Result<Option<ResumableStaging>, String> {
+    let active = active_job_id(&state);
+    find_resumable_staging(&app_handle, &job, active.as_deref())
+}
+
+/// Deletes an interrupted download the user chose not to continue
+#[tauri::command]
+pub fn discard_resumable_download(
+    app_handle: AppHandle,
+    state: State<'_, DepotRunnerState>,
+    staging_id: String,
+) -> Result<(), String> {
+    if active_job_id(&state).as_deref() == Some(staging_id.as_str()) {
+        return Err("Cannot discard the staging directory of a running job".to_string());
+    }
+    cleanup_staging_dir(&app_handle, &staging_id)
+}
+
+/// Finalizes a downloaded platform, then compresses it and writes the template file.
+/// Staging is cleaned up whether finalization succeeds or not.
+/// Returns the final output and its template metadata.
+#[allow(clippy::too_many_arguments)]
+fn finalize_and_package(
+    host: &SharedJobHost,
+    zip_state: &SevenZipRunnerState,
+    job: &JobMetadata,
+    job_id: &str,
+    staging_id: &str,
+    staging_dir: &Path,
+    compression_profile: Option<&CompressionProfile>,
+    conflict_choice: Option<OutputConflictChoice>,
+) -> Result<(PathBuf, Option<TemplateMetadata>), String> {
+    let compression_enabled = !job.skip_compression;
+    let archive_format = compression_enabled.then_some(job.archive_format);
+    let output_path = match finalize_job(host.as_ref(), staging_id, archive_format, conflict_choice) {
+        Ok(output_path) => output_path,
+        Err(err) => {
+            emit_log(
+                host,
+                "system",
+                &format!("Finalization failed: {}", err),
+                job_id,
+            );
+            emit_status(host, "finalization_failed", None, job_id);
+            // Cleanup staging on finalization failure
+            let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+            return Err(format!("Finalization failed: {}", err));
+        }
+    };
+
+    emit_log(
+        host,
+        "system",
+        &format!("Finalization complete. Output: {}", output_path.display()),
+        job_id,
+    );
+    let mut template_metadata = JobMetadataFile::read_from_dir(staging_dir)
+        .ok()
+        .map(|metadata| TemplateMetadata::from_job_metadata(&metadata));
+
+    // === STEAM LIBRARY INSTALL ===
+    if let Some(install) = job.steam_install.as_ref() {
+        emit_status(host, "installing", None, job_id);
+        emit_log(host, "system", "Installing into Steam library...", job_id);
+        match install_to_library(&output_path, install) {
+            Ok(report) => {
+                emit_log(
+                    host,
+                    "system",
+                    &format!(
+                        "Installed into Steam library {} ({} bytes){}.",
+                        report.library_path.display(),
+                        report.bytes,
+                        if report.merged_appmanifest {
+                            ", existing appmanifest updated"
+                        } else {
+                            ""
+                        }
+                    ),
+                    job_id,
+                );
+                if install.mode == InstallMode::Move {
+                    // The output now lives in the library; there is nothing left to package
+                    if let Some(template_metadata) = template_metadata.as_ref() {
+                        host.set_template_metadata(template_metadata.clone());
+                    }
+                    let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+                    let installed_path = report
+                        .appmanifest_paths
+                        .first()
+                        .cloned()
+                        .unwrap_or(report.library_path);
+                    return Ok((installed_path, template_metadata));
+                }
+            }
+            Err(err) => {
+                emit_log(
+                    host,
+                    "system",
+                    &match install.mode {
+                        // A failed move explains which files are where
+                        InstallMode::Move => format!("Steam library install failed: {}.", err),
+                        InstallMode::Copy => format!(
+                            "Steam library install failed: {}. Output kept at {}.",
+                            err,
+                            output_path.display()
+                        ),
+                    },
+                    job_id,
+                );
+                emit_status(host, "error", None, job_id);
+                let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+                return Err(format!("Steam library install failed: {}", err));
+            }
+        }
+    }
+    // === END STEAM LIBRARY INSTALL ===
+
+    // Cleanup staging whether or not packaging succeeded
+    let result = package_output(
+        host,
+        zip_state,
+        job,
+        job_id,
+        &output_path,
+        compression_profile,
+        template_metadata.as_mut(),
+    );
+    let _ = cleanup_staging_dir(host.as_ref(), staging_id);
+
+    result.map(|final_output_path| (final_output_path, template_metadata))
+}
+
+/// Packages a finished output folder: compresses and verifies it (unless the job
+/// skips compression), writes checksum manifests and the release-notes file, and
+/// publishes `template_metadata`. Returns the archive, or the folder when it was
+/// not compressed. Only a failed archive verification is fatal.
+fn package_output(
+    host: &SharedJobHost,
+    zip_state: &SevenZipRunnerState,
+    job: &JobMetadata,
+    job_id: &str,
+    output_path: &Path,
+    compression_profile: Option<&CompressionProfile>,
+    mut template_metadata: Option<&mut TemplateMetadata>,
+) -> Result<PathBuf, String> {
+    // === COMPRESSION PHASE ===
+    let mut final_output_path = output_path.to_path_buf();
+    if job.skip_compression {
+        emit_log(
+            host,
+            "system",
+            "Compression skipped (disabled in settings).",
+            job_id,
+        );
+    } else {
+        emit_status(host, "compressing", None, job_id);
+        let compressor = if job.archive_format.uses_7zip() {
+            "7-Zip"
+        } else {
+            "the built-in tar writer"
+        };
+        emit_log(
+            host,
+            "system",
+            &format!(
+                "Starting {} compression with {} ({} profile)...",
+                job.archive_format.extension(),
+                compressor,
+                compression_profile.map_or(ADAPTIVE_PROFILE, |profile| profile.name.as_str())
+            ),
+            job_id,
+        );
 
-            if exit_code == Some(0) {
-                // Success: Wait for log readers to finish, then derive metadata
+        match compress_output(host, zip_state, output_path, job_id, job, compression_profile) {
+            Ok(archive_path) => {
                 emit_log(
-                    &app_handle_clone,
+                    host,
                     "system",
-                    "Waiting for log processing to complete...",
-                    &job_id_for_monitor,
+                    &format!("Compression complete: {}", archive_path.display()),
+                    job_id,
                 );
-
-                // CRITICAL: Wait for log reader threads to finish before deriving metadata
-                // This ensures all timestamps are captured before we look them up
-                if let Ok(mut guard) = state_handle.lock() {
-                    if let Some((stdout_h, stderr_h)) = guard.log_reader_threads.take() {
-                        drop(guard); // Release lock before joining
-                        let _ = stdout_h.join();
-                        let _ = stderr_h.join();
+                emit_log(host, "system", "Verifying archive...", job_id);
+
+                match verify_archive(
+                    host,
+                    zip_state,
+                    output_path,
+                    &archive_path,
+                    job.archive_format,
+                    job.compression_password(),
+                    job_id,
+                ) {
+                    Ok(stats) => {
+                        emit_log(
+                            host,
+                            "system",
+                            &format!(
+                                "Archive verified: {} files, {} bytes.",
+                                stats.files, stats.bytes
+                            ),
+                            job_id,
+                        );
+                        remove_uncompressed_output(host, output_path, job_id);
+                        final_output_path = archive_path;
                     }
-                }
-
-                if let Some(username) =
-                    resolve_auth_username(&state_handle, &job_for_monitor, &job_id_for_monitor)
-                {
-                    if let Err(err) = persist_auth_cache(
-                        &app_handle_clone,
-                        &username,
-                        &staging_dir_for_monitor,
-                        &job_id_for_monitor,
-                    ) {
+                    Err(err) => {
+                        // A bad archive must never replace the folder; drop it and fail the job
+                        remove_partial_archive(&resolve_archive_path(output_path, job.archive_format));
                         emit_log(
-                            &app_handle_clone,
+                            host,
                             "system",
-                            &format!("Failed to persist auth cache: {}", err),
-                            &job_id_for_monitor,
+                            &format!(
+                                "Archive verification failed: {}. The archive was removed; uncompressed output kept at {}.",
+                                err,
+                                output_path.display()
+                            ),
+                            job_id,
                         );
+                        emit_status(host, "error", None, job_id);
+                        return Err(format!("Archive verification failed: {}", err));
                     }
                 }
-
+            }
+            Err(err) => {
                 emit_log(
-                    &app_handle_clone,
+                    host,
                     "system",
-                    "Deriving metadata from download output...",
-                    &job_id_for_monitor,
+                    &format!("Compression failed: {}. Uncompressed output available.", err),
+                    job_id,
                 );
+            }
+        }
+    }
+    // === END COMPRESSION ===
 
-                if let Err(err) = derive_metadata_from_download(
-                    &app_handle_clone,
-                    &job_for_monitor,
-                    &job_id_for_monitor,
-                    &staging_dir_for_monitor,
-                ) {
+    // === CHECKSUMS ===
+    let checksum_algorithms = job.checksum_algorithms();
+    if !checksum_algorithms.is_empty() {
+        emit_log(host, "system", "Writing checksum manifests...", job_id);
+        match write_checksum_manifests(&final_output_path, &checksum_algorithms) {
+            Ok(summary) => {
+                for manifest_path in &summary.manifest_paths {
                     emit_log(
-                        &app_handle_clone,
+                        host,
                         "system",
-                        &format!("Failed to derive metadata: {}", err),
-                        &job_id_for_monitor,
+                        &format!("Checksums written: {}", manifest_path.display()),
+                        job_id,
                     );
-                    emit_status(&app_handle_clone, "error", None, &job_id_for_monitor);
-                    let _ = cleanup_staging_dir(&app_handle_clone, &job_id_for_monitor);
-                    clear_runner_state(&state_handle, &job_id_for_monitor);
-                    return;
                 }
-
+                if let Some(template_metadata) = template_metadata.as_mut() {
+                    template_metadata.archive_sha256 =
+                        summary.archive_token(ChecksumAlgorithm::Sha256);
+                    template_metadata.archive_blake3 =
+                        summary.archive_token(ChecksumAlgorithm::Blake3);
+                    template_metadata.archive_crc32 =
+                        summary.archive_token(ChecksumAlgorithm::Crc32);
+                    template_metadata.checksums_file = summary
+                        .manifest_paths
+                        .first()
+                        .and_then(|path| path.file_name())
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                }
+            }
+            Err(err) => {
                 emit_log(
-                    &app_handle_clone,
+                    host,
                     "system",
-                    "Download completed successfully. Finalizing output...",
-                    &job_id_for_monitor,
+                    &format!("Failed to write checksum manifests: {}", err),
+                    job_id,
                 );
-                emit_status(&app_handle_clone, "finalizing", None, &job_id_for_monitor);
+            }
+        }
+    }
+    // === END CHECKSUMS ===
 
-                let compression_enabled = !job_for_monitor.skip_compression;
-                match finalize_job(
-                    &app_handle_clone,
-                    &job_id_for_monitor,
-                    compression_enabled,
-                ) {
-                    Ok(output_path) => {
-                        emit_log(
-                            &app_handle_clone,
-                            "system",
-                            &format!("Finalization complete. Output: {}", output_path.display()),
-                            &job_id_for_monitor,
-                        );
-                        if let Ok(metadata) =
-                            JobMetadataFile::read_from_dir(&staging_dir_for_monitor)
-                        {
-                            let template_metadata = TemplateMetadata::from_job_metadata(&metadata);
-                            app_handle_clone
-                                .state::<TemplateMetadataState>()
-                                .set(template_metadata);
-                        }
+    let template_metadata = template_metadata.map(|metadata| &*metadata);
+    if let Some(template_metadata) = template_metadata {
+        host.set_template_metadata(template_metadata.clone());
+    }
 
-                        // === COMPRESSION PHASE ===
-                        let mut final_output_path = output_path.clone();
-                        if job_for_monitor.skip_compression {
-                            emit_log(
-                                &app_handle_clone,
-                                "system",
-                                "Compression skipped (disabled in settings).",
-                                &job_id_for_monitor,
-                            );
-                        } else {
-                            emit_status(&app_handle_clone, "compressing", None, &job_id_for_monitor);
-                            emit_log(
-                                &app_handle_clone,
-                                "system",
-                                "Starting compression with 7-Zip...",
-                                &job_id_for_monitor,
-                            );
-
-                            let compression_password =
-                                if job_for_monitor.compression_password_enabled {
-                                    let password =
-                                        job_for_monitor.compression_password.as_str();
-                                    if password.trim().is_empty() {
-                                        None
-                                    } else {
-                                        Some(password)
-                                    }
-                                } else {
-                                    None
-                                };
-
-                            match compress_output(
-                                &app_handle_clone,
-                                &output_path,
-                                &job_id_for_monitor,
-                                compression_password,
-                            ) {
-                                Ok(archive_path) => {
-                                    emit_log(
-                                        &app_handle_clone,
-                                        "system",
-                                        &format!("Compression complete: {}", archive_path.display()),
-                                        &job_id_for_monitor,
-                                    );
-                                    final_output_path = archive_path;
-                                }
-                                Err(err) => {
-                                    emit_log(
-                                        &app_handle_clone,
-                                        "system",
-                                        &format!("Compression failed: {}. Uncompressed output available.", err),
-                                        &job_id_for_monitor,
-                                    );
-                                }
-                            }
-                        }
-                        // === END COMPRESSION ===
-
-                        // === TEMPLATE GENERATION ===
-                        // Generate template text file with job metadata
-                        if let Some(template_metadata) = app_handle_clone
-                            .state::<TemplateMetadataState>()
-                            .get()
-                        {
-                            emit_log(
-                                &app_handle_clone,
-                                "system",
-                                "Generating template file...",
-                                &job_id_for_monitor,
-                            );
-
-                            // Load user's template (or use default)
-                            let template_blocks = load_template_data_internal(&app_handle_clone)
-                                .map(|payload| payload.blocks);
-
-                            let template_blocks_ref = template_blocks.as_ref().map(|v| v.as_slice());
-
-                            match write_template_file(&final_output_path, &template_metadata, template_blocks_ref) {
-                                Ok(()) => {
-                                    emit_log(
-                                        &app_handle_clone,
-                                        "system",
-                                        "Template file generated successfully.",
-                                        &job_id_for_monitor,
-                                    );
-                                }
-                                Err(err) => {
-                                    emit_log(
-                                        &app_handle_clone,
-                                        "system",
-                                        &format!("Failed to generate template file: {}", err),
-                                        &job_id_for_monitor,
-                                    );
-                                }
-                            }
-                        }
-                        // === END TEMPLATE GENERATION ===
+    // === TEMPLATE GENERATION ===
+    // Generate template text file with job metadata
+    if let Some(template_metadata) = template_metadata {
+        emit_log(
+            host,
+            "system",
+            "Generating template file...",
+            job_id,
+        );
 
-                        emit_status(&app_handle_clone, "completed", Some(0), &job_id_for_monitor);
-                        // Cleanup staging after successful finalization
-                        let _ = cleanup_staging_dir(&app_handle_clone, &job_id_for_monitor);
-                    }
-                    Err(err) => {
-                        emit_log(
-                            &app_handle_clone,
-                            "system",
-                            &format!("Finalization failed: {}", err),
-                            &job_id_for_monitor,
-                        );
-                        emit_status(&app_handle_clone, "finalization_failed", None, &job_id_for_monitor);
-                        // Cleanup staging on finalization failure
-                        let _ = cleanup_staging_dir(&app_handle_clone, &job_id_for_monitor);
-                    }
-                }
-            } else {
-                // Failure: cleanup staging
-                emit_status(&app_handle_clone, "exited", exit_code, &job_id_for_monitor);
+        // Load user's template (or use default)
+        let template_blocks =
+            load_template_data_internal(host.as_ref()).map(|payload| payload.blocks);
+
+        match write_template_file(&final_output_path, template_metadata, template_blocks.as_deref()) {
+            Ok(()) => {
                 emit_log(
-                    &app_handle_clone,
+                    host,
                     "system",
-                    "Job failed. Cleaning up staging directory.",
-                    &job_id_for_monitor,
+                    "Template file generated successfully.",
+                    job_id,
+                );
+            }
+            Err(err) => {
+                emit_log(
+                    host,
+                    "system",
+                    &format!("Failed to generate template file: {}", err),
+                    job_id,
                 );
-                if let Some(username) =
-                    resolve_auth_username(&state_handle, &job_for_monitor, &job_id_for_monitor)
-                {
-                    if let Err(err) = persist_auth_cache(
-                        &app_handle_clone,
-                        &username,
-                        &staging_dir_for_monitor,
-                        &job_id_for_monitor,
-                    ) {
-                        emit_log(
-                            &app_handle_clone,
-                            "system",
-                            &format!("Failed to persist auth cache: {}", err),
-                            &job_id_for_monitor,
-                        );
-                    }
-                }
-                let _ = cleanup_staging_dir(&app_handle_clone, &job_id_for_monitor);
             }
-
-            clear_runner_state(&state_handle, &job_id_for_monitor);
-            return;
         }
+    }
+    // === END TEMPLATE GENERATION ===
 
-        thread::sleep(Duration::from_millis(100));
-    });
+    Ok(final_output_path)
+}
+
+/// Appends a finished (or failed) output to the history catalogue
+fn record_output_history(host: &dyn JobHost, entry: &HistoryEntry, job_id: &str) {
+    if let Err(err) = record_history(host, entry) {
+        emit_log(
+            host,
+            "system",
+            &format!("Failed to record output history: {}", err),
+            job_id,
+        );
+    }
+}
+
+/// Writes a release-notes file covering every platform of a multi-platform job
+/// next to the outputs. Returns the path of the written file.
+fn write_combined_release_notes(
+    host: &dyn JobHost,
+    platforms: &[TemplateMetadata],
+) -> Result<PathBuf, String> {
+    let first = platforms
+        .first()
+        .ok_or_else(|| "No platform metadata available".to_string())?;
+    let outputs_dir = host.downloads_dir()?.join("outputs");
+    let language_suffix = if first.language.eq_ignore_ascii_case("english") {
+        String::new()
+    } else {
+        format!(".{}", first.language)
+    };
+    let file_name = format!(
+        "{}.Build.{}.{}{}.ReleaseNotes.txt",
+        sanitize_game_name(&first.game_name),
+        first.build_id,
+        first.branch,
+        language_suffix
+    );
+    let path = outputs_dir.join(file_name);
+    std::fs::write(&path, render_combined_release_notes(platforms))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
 }
 
 #[tauri::command]
@@ -1266,6 +2509,7 @@ pub fn cancel_depotdownloader(
         .job_id
         .clone()
         .unwrap_or_else(|| "unknown".to_string());
+    let staging_id = guard.staging_id.clone().unwrap_or_else(|| job_id.clone());
 
     let Some(child) = guard.child.as_mut() else {
         return Err("DepotDownloader is not running".to_string());
@@ -1281,11 +2525,10 @@ pub fn cancel_depotdownloader(
 
     guard.child = None;
     guard.stdin = None;
+    guard.pending_password = None;
+    guard.awaiting_password = false;
     guard.job_id = None;
-    guard.build_datetime_utc = None;
-    guard.depot_timestamps.clear();
-    guard.manifest_to_depot.clear();
-    guard.manifest_timestamps.clear();
+    guard.staging_id = None;
     guard.last_depot_mentioned = None;
     guard.auth_username = None;
 
@@ -1298,7 +2541,7 @@ pub fn cancel_depotdownloader(
         "Job cancelled. Cleaning up staging directory.",
         &job_id,
     );
-    let _ = cleanup_staging_dir(&app_handle, &job_id);
+    let _ = cleanup_staging_dir(&app_handle, &staging_id);
 
     Ok(())
 }
@@ -1308,6 +2551,11 @@ pub fn submit_steam_guard_code(
     code: String,
     state: State<'_, DepotRunnerState>,
 ) -> Result<(), String> {
+    write_steam_guard_code(&state, &code)
+}
+
+/// Writes a Steam Guard code to the running DepotDownloader's stdin
+pub fn write_steam_guard_code(state: &DepotRunnerState, code: &str) -> Result<(), String> {
     let trimmed = code.trim();
     if trimmed.is_empty() {
         return Err("Steam Guard code is empty".to_string());
@@ -1339,41 +2587,81 @@ pub fn submit_steam_guard_code(
     Ok(())
 }
 
-fn emit_status(app_handle: &AppHandle, status: &str, code: Option<i32>, job_id: &str) {
-    let _ = app_handle.emit(
-        "dd:status",
-        StatusPayload {
-            status: status.to_string(),
-            code,
-            job_id: job_id.to_string(),
-        },
-    );
+/// Answers DepotDownloader's password prompt with a line typed by the user.
+/// The line is passed on as typed, since passwords may start or end with spaces.
+pub fn write_password_line(state: &DepotRunnerState, password: &str) -> Result<(), String> {
+    let mut guard = state
+        .inner
+        .lock()
+        .map_err(|_| "Failed to lock DepotDownloader state".to_string())?;
+
+    if guard.child.is_none() {
+        return Err("DepotDownloader is not running".to_string());
+    }
+
+    let Some(stdin) = guard.stdin.as_mut() else {
+        return Err("DepotDownloader stdin is unavailable".to_string());
+    };
+
+    stdin
+        .write_all(password.as_bytes())
+        .and_then(|()| stdin.write_all(b"\n"))
+        .and_then(|()| stdin.flush())
+        .map_err(|err| format!("Failed to write password: {err}"))?;
+    guard.awaiting_password = false;
+
+    Ok(())
 }
 
-fn emit_log(app_handle: &AppHandle, stream: &str, line: &str, job_id: &str) {
-    let _ = app_handle.emit(
-        "dd:log",
-        LogPayload {
-            stream: stream.to_string(),
-            line: line.to_string(),
-            job_id: job_id.to_string(),
-        },
-    );
+fn emit_status(host: &dyn JobHost, status: &str, code: Option<i32>, job_id: &str) {
+    host.emit_status(status, code, job_id);
+}
+
+fn emit_log(host: &dyn JobHost, stream: &str, line: &str, job_id: &str) {
+    host.emit_log(stream, line, job_id);
+}
+
+/// Clears per-download tracking between the platforms of a multi-platform job.
+/// Depot names from the shared preflight are kept.
+fn reset_platform_state(state_handle: &Arc<Mutex<RunningJobState>>, job_id: &str) {
+    if let Ok(mut guard) = state_handle.lock() {
+        if guard.job_id.as_deref() == Some(job_id) {
+            guard.last_depot_mentioned = None;
+        }
+    }
+}
 
-    let debug_state = app_handle.state::<DebugConsoleState>();
-    if debug_state.enabled() {
-        debug_state.write_line(&format!("[{stream}] {line}"));
+/// Manifest IDs this run downloaded, per depot: pins first, then what preflight resolved
+fn resolved_manifest_ids(
+    state_handle: &Arc<Mutex<RunningJobState>>,
+    job: &JobMetadata,
+) -> HashMap<String, String> {
+    let mut resolved = state_handle
+        .lock()
+        .map(|guard| guard.preflight_manifest_ids.clone())
+        .unwrap_or_default();
+    for pin in &job.manifest_pins {
+        resolved.insert(pin.depot_id.clone(), pin.manifest_id.clone());
     }
+    resolved
+}
+
+/// Whether DepotDownloader was stopped at a password prompt nobody could answer
+fn take_password_unavailable(state_handle: &Arc<Mutex<RunningJobState>>) -> bool {
+    state_handle
+        .lock()
+        .map(|mut guard| std::mem::take(&mut guard.password_unavailable))
+        .unwrap_or(false)
 }
 
 fn clear_runner_state(state_handle: &Arc<Mutex<RunningJobState>>, job_id: &str) {
     if let Ok(mut guard) = state_handle.lock() {
         if guard.job_id.as_deref() == Some(job_id) {
             guard.job_id = None;
+            guard.staging_id = None;
             guard.stdin = None;
-            guard.build_datetime_utc = None;
-            guard.depot_timestamps.clear();
-            guard.manifest_to_depot.clear();
+            guard.pending_password = None;
+            guard.awaiting_password = false;
             guard.last_depot_mentioned = None;
             guard.auth_username = None;
         }
@@ -1381,11 +2669,11 @@ fn clear_runner_state(state_handle: &Arc<Mutex<RunningJobState>>, job_id: &str)
 }
 
 fn run_preflight_before_download(
-    app_handle: &AppHandle,
+    host: &SharedJobHost,
     state: &DepotRunnerState,
     job: &JobMetadata,
     job_id: &str,
-    staging_dir: &PathBuf,
+    staging_dir: &Path,
 ) -> Result<(), String> {
     if job.qr_enabled {
         // For QR auth, preflight can't run before download - SteamDB API will be used instead
@@ -1397,7 +2685,7 @@ fn run_preflight_before_download(
     let preflight_dir = staging_dir.join(".preflight");
     if let Err(err) = fs::create_dir_all(&preflight_dir) {
         emit_log(
-            app_handle,
+            host,
             "system",
             &format!("Preflight skipped: {}", err),
             job_id,
@@ -1405,12 +2693,42 @@ fn run_preflight_before_download(
         return Ok(());
     }
 
+    let result = run_manifest_preflight(host, state, job, job_id, &preflight_dir);
+    let _ = fs::remove_dir_all(&preflight_dir);
+    let Some(parsed) = result? else {
+        return Ok(());
+    };
+
+    if let Ok(mut guard) = state.inner.lock() {
+        if guard.job_id.as_deref() == Some(job_id) {
+            guard.preflight_build_id = parsed.build_id;
+            for depot in parsed.depots {
+                guard
+                    .preflight_manifest_ids
+                    .insert(depot.depot_id.clone(), depot.manifest_id);
+                if let Some(name) = depot.depot_name {
+                    guard.depot_names.insert(depot.depot_id, name);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs DepotDownloader with `-manifest-only` in `work_dir` and parses its output.
+/// Returns `None` when the job was cancelled or preflight produced nothing usable.
+fn run_manifest_preflight(
+    host: &SharedJobHost,
+    state: &DepotRunnerState,
+    job: &JobMetadata,
+    job_id: &str,
+    work_dir: &Path,
+) -> Result<Option<PreflightResult>, String> {
     if !job.username.trim().is_empty() {
-        if let Err(err) =
-            restore_auth_cache(app_handle, &job.username, &preflight_dir, job_id)
-        {
+        if let Err(err) = restore_auth_cache(host.as_ref(), &job.username, work_dir, job_id) {
             emit_log(
-                app_handle,
+                host,
                 "system",
                 &format!("Failed to restore auth cache: {}", err),
                 job_id,
@@ -1418,12 +2736,12 @@ fn run_preflight_before_download(
         }
     }
 
-    let dd_path = resolve_depotdownloader_path(app_handle)?;
+    let dd_path = resolve_depotdownloader_path(host.as_ref())?;
     let mut args = build_preflight_args(job)?;
     args.push("-manifest-only".to_string());
 
     emit_log(
-        app_handle,
+        host,
         "system",
         "Running preflight to resolve depot metadata...",
         job_id,
@@ -1431,7 +2749,7 @@ fn run_preflight_before_download(
 
     let mut command = Command::new(&dd_path);
     command.args(&args);
-    command.current_dir(&preflight_dir);
+    command.current_dir(work_dir);
     command.stdout(Stdio::piped());
     command.stderr(Stdio::piped());
     command.stdin(Stdio::piped());
@@ -1455,6 +2773,9 @@ fn run_preflight_before_download(
             .map_err(|_| "Failed to lock DepotDownloader state".to_string())?;
         guard.child = Some(child);
         guard.stdin = stdin;
+        guard.pending_password = stdin_password(job);
+        guard.password_unavailable = false;
+        guard.awaiting_password = false;
         guard.job_id = Some(job_id.to_string());
     }
 
@@ -1462,21 +2783,23 @@ fn run_preflight_before_download(
 
     let stdout_handle = stdout.map(|stream| {
         spawn_preflight_reader(
-            app_handle.clone(),
+            host.clone(),
             stream,
             "stdout",
             job_id.to_string(),
             output_lines.clone(),
+            state.inner.clone(),
         )
     });
 
     let stderr_handle = stderr.map(|stream| {
         spawn_preflight_reader(
-            app_handle.clone(),
+            host.clone(),
             stream,
             "stderr",
             job_id.to_string(),
             output_lines.clone(),
+            state.inner.clone(),
         )
     });
 
@@ -1488,33 +2811,34 @@ fn run_preflight_before_download(
                 .map_err(|_| "Failed to lock DepotDownloader state".to_string())?;
 
             if guard.job_id.as_deref() != Some(job_id) {
-                let _ = fs::remove_dir_all(&preflight_dir);
-                return Ok(());
+                return Ok(None);
             }
 
             let Some(child) = guard.child.as_mut() else {
-                let _ = fs::remove_dir_all(&preflight_dir);
-                return Ok(());
+                return Ok(None);
             };
 
             match child.try_wait() {
                 Ok(Some(status)) => {
                     guard.child = None;
                     guard.stdin = None;
+                    guard.pending_password = None;
+                    guard.awaiting_password = false;
                     Some(status)
                 }
                 Ok(None) => None,
                 Err(err) => {
                     guard.child = None;
                     guard.stdin = None;
+                    guard.pending_password = None;
+                    guard.awaiting_password = false;
                     emit_log(
-                        app_handle,
+                        host,
                         "system",
                         &format!("Preflight failed to wait: {err}"),
                         job_id,
                     );
-                    let _ = fs::remove_dir_all(&preflight_dir);
-                    return Ok(());
+                    return Ok(None);
                 }
             }
         };
@@ -1538,11 +2862,16 @@ fn run_preflight_before_download(
         .map_err(|_| "Failed to lock preflight output".to_string())?
         .clone();
 
-    let parsed = parse_preflight_output(&lines);
+    if take_password_unavailable(&state.inner) {
+        return Err(PASSWORD_UNAVAILABLE_ERROR.to_string());
+    }
+
+    let mut parsed = parse_preflight_output(&lines);
+    apply_saved_manifests(&mut parsed, work_dir);
 
     if !status.success() && parsed.depots.is_empty() {
         emit_log(
-            app_handle,
+            host,
             "system",
             &format!(
                 "Preflight failed with exit code {:?}. Continuing without preflight.",
@@ -1550,32 +2879,13 @@ fn run_preflight_before_download(
             ),
             job_id,
         );
-        let _ = fs::remove_dir_all(&preflight_dir);
-        return Ok(());
-    }
-
-    if let Ok(mut guard) = state.inner.lock() {
-        if guard.job_id.as_deref() == Some(job_id) {
-            if let Some(timestamp) = parsed.build_datetime_utc {
-                if guard.build_datetime_utc.is_none() {
-                    guard.build_datetime_utc = Some(timestamp);
-                }
-            }
-
-            for depot in parsed.depots {
-                if let Some(name) = depot.depot_name {
-                    guard.depot_names.insert(depot.depot_id, name);
-                }
-            }
-        }
+        return Ok(None);
     }
 
     if !job.username.trim().is_empty() {
-        if let Err(err) =
-            persist_auth_cache(app_handle, &job.username, &preflight_dir, job_id)
-        {
+        if let Err(err) = persist_auth_cache(host.as_ref(), &job.username, work_dir, job_id) {
             emit_log(
-                app_handle,
+                host,
                 "system",
                 &format!("Failed to persist auth cache: {}", err),
                 job_id,
@@ -1583,12 +2893,59 @@ fn run_preflight_before_download(
         }
     }
 
-    let _ = fs::remove_dir_all(&preflight_dir);
-    Ok(())
+    Ok(Some(parsed))
 }
 
-fn spawn_log_reader(
+/// Runs a preflight-only job that lists the depots DepotDownloader would download,
+/// so the user can choose a subset before queueing the real job.
+pub fn discover_depots_blocking(
+    host: &SharedJobHost,
+    state: &DepotRunnerState,
+    job: &JobMetadata,
+) -> Result<PreflightResult, String> {
+    if job.qr_enabled {
+        return Err("Depot discovery is not available with QR login".to_string());
+    }
+
+    // Discover every depot, not just a previous selection
+    let (mut job, _) = resolve_job_account(host.as_ref(), job)?;
+    job.depot_ids.clear();
+
+    let job_id = begin_job(state)?;
+    let result = create_staging_dir(host.as_ref(), &job_id).and_then(|staging_dir| {
+        let work_dir = staging_dir.join(".preflight");
+        std::fs::create_dir_all(&work_dir)
+            .map_err(|e| format!("Failed to create preflight directory: {}", e))?;
+        run_manifest_preflight(host, state, &job, &job_id, &work_dir)
+    });
+    let _ = cleanup_staging_dir(host.as_ref(), &job_id);
+    clear_runner_state(&state.inner, &job_id);
+
+    match result? {
+        Some(parsed) if !parsed.depots.is_empty() => Ok(parsed),
+        _ => Err("Preflight did not report any depots".to_string()),
+    }
+}
+
+/// Lists the depots available for a job without downloading them
+#[tauri::command]
+pub async fn discover_depots(
     app_handle: AppHandle,
+    mut job: JobMetadata,
+    login: Option<LoginData>,
+) -> Result<PreflightResult, String> {
+    apply_login(&mut job, login)?;
+    let state = app_handle.state::<DepotRunnerState>().inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let host: SharedJobHost = Arc::new(app_handle);
+        discover_depots_blocking(&host, &state, &job)
+    })
+    .await
+    .map_err(|e| format!("Depot discovery task failed: {}", e))?
+}
+
+fn spawn_log_reader(
+    host: SharedJobHost,
     stream: impl std::io::Read + Send + 'static,
     tag: &str,
     job_id: String,
@@ -1605,18 +2962,17 @@ fn spawn_log_reader(
         let mut buffer = [0u8; 1024];
         let mut pending: Vec<u8> = Vec::new();
         let mut prompt_emitted = false;
+        let mut password_prompted = false;
 
-        loop {
-            let n = match reader.read(&mut buffer) {
-                Ok(n) => n,
-                Err(_) => break,
-            };
-
+        while let Ok(n) = reader.read(&mut buffer) {
             if n == 0 {
                 break; // EOF
             }
 
             pending.extend_from_slice(&buffer[..n]);
+            if !password_prompted {
+                password_prompted = maybe_answer_password_prompt(&state_handle, &pending, &job_id);
+            }
 
             while let Some(pos) = pending.iter().position(|&byte| byte == b'\n') {
                 let mut line_bytes: Vec<u8> = pending.drain(..=pos).collect();
@@ -1627,9 +2983,9 @@ fn spawn_log_reader(
                     line_bytes.pop();
                 }
                 let line = decode_stream_bytes(&line_bytes);
-                emit_log(&app_handle, &stream_name, &line, &job_id);
+                emit_log(host.as_ref(), &stream_name, &line, &job_id);
                 maybe_update_auth_username(&state_handle, &line, &job_id);
-                maybe_store_build_datetime(&app_handle, &line, &job_id);
+                maybe_store_depot_name(&state_handle, &line, &job_id);
             }
 
             if !prompt_emitted
@@ -1642,7 +2998,7 @@ fn spawn_log_reader(
                     line_bytes.pop();
                 }
                 let line = decode_stream_bytes(&line_bytes);
-                emit_log(&app_handle, &stream_name, &line, &job_id);
+                emit_log(host.as_ref(), &stream_name, &line, &job_id);
                 maybe_update_auth_username(&state_handle, &line, &job_id);
                 prompt_emitted = true;
             }
@@ -1654,18 +3010,19 @@ fn spawn_log_reader(
                 line_bytes.pop();
             }
             let line = decode_stream_bytes(&line_bytes);
-            emit_log(&app_handle, &stream_name, &line, &job_id);
+            emit_log(host.as_ref(), &stream_name, &line, &job_id);
             maybe_update_auth_username(&state_handle, &line, &job_id);
         }
     })
 }
 
 fn spawn_preflight_reader(
-    app_handle: AppHandle,
+    host: SharedJobHost,
     stream: impl std::io::Read + Send + 'static,
     tag: &str,
     job_id: String,
     output: Arc<Mutex<Vec<String>>>,
+    state_handle: Arc<Mutex<RunningJobState>>,
 ) -> thread::JoinHandle<()> {
     let stream_name = tag.to_string();
     const EMAIL_PROMPT: &str =
@@ -1678,18 +3035,17 @@ fn spawn_preflight_reader(
         let mut buffer = [0u8; 1024];
         let mut pending: Vec<u8> = Vec::new();
         let mut prompt_emitted = false;
+        let mut password_prompted = false;
 
-        loop {
-            let n = match reader.read(&mut buffer) {
-                Ok(n) => n,
-                Err(_) => break,
-            };
-
+        while let Ok(n) = reader.read(&mut buffer) {
             if n == 0 {
                 break; // EOF
             }
 
             pending.extend_from_slice(&buffer[..n]);
+            if !password_prompted {
+                password_prompted = maybe_answer_password_prompt(&state_handle, &pending, &job_id);
+            }
 
             while let Some(pos) = pending.iter().position(|&byte| byte == b'\n') {
                 let mut line_bytes: Vec<u8> = pending.drain(..=pos).collect();
@@ -1703,7 +3059,7 @@ fn spawn_preflight_reader(
                 if let Ok(mut guard) = output.lock() {
                     guard.push(line.clone());
                 }
-                emit_log(&app_handle, &stream_name, &line, &job_id);
+                emit_log(host.as_ref(), &stream_name, &line, &job_id);
             }
 
             if !prompt_emitted
@@ -1719,7 +3075,7 @@ fn spawn_preflight_reader(
                 if let Ok(mut guard) = output.lock() {
                     guard.push(line.clone());
                 }
-                emit_log(&app_handle, &stream_name, &line, &job_id);
+                emit_log(host.as_ref(), &stream_name, &line, &job_id);
                 prompt_emitted = true;
             }
         }
@@ -1733,7 +3089,7 @@ fn spawn_preflight_reader(
             if let Ok(mut guard) = output.lock() {
                 guard.push(line.clone());
             }
-            emit_log(&app_handle, &stream_name, &line, &job_id);
+            emit_log(host.as_ref(), &stream_name, &line, &job_id);
         }
     })
 }
@@ -1749,16 +3105,17 @@ fn decode_stream_bytes(bytes: &[u8]) -> String {
     }
 }
 
-fn maybe_store_build_datetime(app_handle: &AppHandle, line: &str, job_id: &str) {
-    static DEPOT_MANIFEST_RE: OnceLock<Regex> = OnceLock::new();
+/// Tracks depot names announced in DepotDownloader output. Depot, manifest and
+/// timestamp data are read from the saved manifests instead (see `depot_manifest`).
+fn maybe_store_depot_name(
+    state_handle: &Arc<Mutex<RunningJobState>>,
+    line: &str,
+    job_id: &str,
+) {
     static DEPOT_RE: OnceLock<Regex> = OnceLock::new();
     static DEPOT_NAME_RE: OnceLock<Regex> = OnceLock::new();
     static APPINFO_NAME_RE: OnceLock<Regex> = OnceLock::new();
-    static MANIFEST_CREATIONTIME_RE: OnceLock<Regex> = OnceLock::new();
 
-    let depot_manifest = DEPOT_MANIFEST_RE.get_or_init(|| {
-        Regex::new(r"[Dd]epot\s+(\d+)\s*[-–]\s*[Mm]anifest\s+(\d+)").unwrap()
-    });
     let depot = DEPOT_RE.get_or_init(|| {
         Regex::new(r"[Dd]epot\s+(\d+)").unwrap()
     });
@@ -1768,11 +3125,8 @@ fn maybe_store_build_datetime(app_handle: &AppHandle, line: &str, job_id: &str)
     let appinfo_name = APPINFO_NAME_RE.get_or_init(|| {
         Regex::new(r#""name"\s+"([^"]+)""#).unwrap()
     });
-    let manifest_creationtime = MANIFEST_CREATIONTIME_RE.get_or_init(|| {
-        Regex::new(r"(?i)Manifest\s+(\d+)\s+\((.+?)\)").unwrap()
-    });
 
-    if let Ok(mut guard) = app_handle.state::<DepotRunnerState>().inner.lock() {
+    if let Ok(mut guard) = state_handle.lock() {
         if guard.job_id.as_deref() != Some(job_id) {
             return;
         }
@@ -1790,18 +3144,6 @@ fn maybe_store_build_datetime(app_handle: &AppHandle, line: &str, job_id: &str)
             }
         }
 
-        // Track depot-manifest pairs
-        if let Some(caps) = depot_manifest.captures(line) {
-            if let (Some(depot_id), Some(manifest_id)) = (
-                caps.get(1).map(|m| m.as_str().to_string()),
-                caps.get(2).map(|m| m.as_str().to_string()),
-            ) {
-                guard.manifest_to_depot.insert(manifest_id, depot_id.clone());
-                guard.last_depot_mentioned = Some(depot_id);
-                return;
-            }
-        }
-
         // Track appinfo name field (when in depot context)
         if let Some(caps) = appinfo_name.captures(line) {
             if let Some(depot_id) = guard.last_depot_mentioned.clone() {
@@ -1817,133 +3159,7 @@ fn maybe_store_build_datetime(app_handle: &AppHandle, line: &str, job_id: &str)
                 guard.last_depot_mentioned = Some(depot_id);
             }
         }
-
-        // Check for timeupdated (build release date) - captures for fallback if SteamDB fails
-        static TIMEUPDATED_ONLY_RE: OnceLock<Regex> = OnceLock::new();
-        let timeupdated_only = TIMEUPDATED_ONLY_RE.get_or_init(|| {
-            Regex::new(r"(?i)timeupdated[^0-9]*(\d{9,})").unwrap()
-        });
-        if let Some(caps) = timeupdated_only.captures(line) {
-            if let Some(epoch_str) = caps.get(1).map(|m| m.as_str()) {
-                if let Some(timestamp) = parse_epoch_timestamp(Some(epoch_str)) {
-                    guard.build_datetime_utc = Some(timestamp);
-                    return;
-                }
-            }
-        }
-
-        // Capture manifest timestamps (for per-depot tracking as fallback)
-        if let Some(caps) = manifest_creationtime.captures(line) {
-            if let (Some(manifest_id), Some(datetime_str)) = (
-                caps.get(1).map(|m| m.as_str().to_string()),
-                caps.get(2).map(|m| m.as_str()),
-            ) {
-                if let Some(timestamp) = parse_datetime_string(datetime_str) {
-                    guard.manifest_timestamps.insert(manifest_id.clone(), timestamp);
-
-                    if let Some(depot_id) = guard.manifest_to_depot.get(&manifest_id).cloned() {
-                        guard.depot_timestamps.insert(depot_id, timestamp);
-                    }
-                }
-            }
-        }
-
-        // FALLBACK: If no timeupdated was found yet, try other app-level timestamp patterns
-        // (but NOT manifest creation times - those are only for per-depot tracking)
-        if guard.build_datetime_utc.is_none() {
-            // Check for lastupdated or builddate patterns
-            static LASTUPDATED_ONLY_RE: OnceLock<Regex> = OnceLock::new();
-            static BUILDDATE_ONLY_RE: OnceLock<Regex> = OnceLock::new();
-            let lastupdated_only = LASTUPDATED_ONLY_RE.get_or_init(|| {
-                Regex::new(r"(?i)last\s*updated[^0-9]*(\d{9,})").unwrap()
-            });
-            let builddate_only = BUILDDATE_ONLY_RE.get_or_init(|| {
-                Regex::new(r"(?i)build(?:_|\s)*date[^0-9]*(\d{9,})").unwrap()
-            });
-
-            if let Some(caps) = lastupdated_only.captures(line) {
-                if let Some(timestamp) = parse_epoch_timestamp(caps.get(1).map(|m| m.as_str())) {
-                    guard.build_datetime_utc = Some(timestamp);
-                }
-            } else if let Some(caps) = builddate_only.captures(line) {
-                if let Some(timestamp) = parse_epoch_timestamp(caps.get(1).map(|m| m.as_str())) {
-                    guard.build_datetime_utc = Some(timestamp);
-                }
-            }
-        }
-    }
-}
-
-/// Parse a datetime string in various formats (.NET DateTime, ISO, etc.)
-fn parse_datetime_string(datetime_str: &str) -> Option<DateTime<Utc>> {
-    static ISO_RE: OnceLock<Regex> = OnceLock::new();
-    static DOTNET_DATETIME_RE: OnceLock<Regex> = OnceLock::new();
-
-    let iso = ISO_RE.get_or_init(|| {
-        Regex::new(r"(?i)(\d{4}-\d{2}-\d{2})[ T](\d{2}:\d{2}:\d{2})(?:\s*UTC|Z)?").unwrap()
-    });
-    let dotnet_datetime = DOTNET_DATETIME_RE.get_or_init(|| {
-        Regex::new(r"(\d{1,2})/(\d{1,2})/(\d{4})\s+(\d{1,2}):(\d{2}):(\d{2})(?:\s*([AP]M))?").unwrap()
-    });
-
-    // Try .NET DateTime format first (most common)
-    if let Some(parsed) = parse_dotnet_datetime_str(datetime_str, dotnet_datetime) {
-        return Some(parsed);
-    }
-
-    // Try ISO format
-    if let Some(iso_caps) = iso.captures(datetime_str) {
-        if let Some(parsed) = parse_iso_datetime(
-            iso_caps.get(1).map(|m| m.as_str()),
-            iso_caps.get(2).map(|m| m.as_str()),
-        ) {
-            return Some(parsed);
-        }
-    }
-
-    None
-}
-
-fn parse_epoch_timestamp(value: Option<&str>) -> Option<DateTime<Utc>> {
-    let seconds: i64 = value?.trim().parse().ok()?;
-    Utc.timestamp_opt(seconds, 0).single()
-}
-
-fn parse_iso_datetime(date: Option<&str>, time: Option<&str>) -> Option<DateTime<Utc>> {
-    let date = date?;
-    let time = time?;
-    let combined = format!("{} {}", date.trim(), time.trim());
-    let parsed = NaiveDateTime::parse_from_str(&combined, "%Y-%m-%d %H:%M:%S").ok()?;
-    Some(DateTime::from_naive_utc_and_offset(parsed, Utc))
-}
-
-fn parse_dotnet_datetime_str(text: &str, pattern: &Regex) -> Option<DateTime<Utc>> {
-    let caps = pattern.captures(text)?;
-
-    let month: u32 = caps.get(1)?.as_str().parse().ok()?;
-    let day: u32 = caps.get(2)?.as_str().parse().ok()?;
-    let year: i32 = caps.get(3)?.as_str().parse().ok()?;
-    let mut hour: u32 = caps.get(4)?.as_str().parse().ok()?;
-    let minute: u32 = caps.get(5)?.as_str().parse().ok()?;
-    let second: u32 = caps.get(6)?.as_str().parse().ok()?;
-
-    // Handle AM/PM if present
-    if let Some(ampm) = caps.get(7) {
-        let ampm_str = ampm.as_str();
-        if ampm_str.eq_ignore_ascii_case("PM") && hour < 12 {
-            hour += 12;
-        } else if ampm_str.eq_ignore_ascii_case("AM") && hour == 12 {
-            hour = 0;
-        }
     }
-
-    // Create NaiveDateTime
-    let naive = NaiveDateTime::new(
-        chrono::NaiveDate::from_ymd_opt(year, month, day)?,
-        chrono::NaiveTime::from_hms_opt(hour, minute, second)?,
-    );
-
-    Some(DateTime::from_naive_utc_and_offset(naive, Utc))
 }
 
 #[cfg(windows)]
@@ -1978,7 +3194,7 @@ fn decode_console_bytes(buf: &[u8]) -> String {
 }
 
 #[cfg(unix)]
-fn is_executable(path: &PathBuf) -> bool {
+fn is_executable(path: &Path) -> bool {
     use std::os::unix::fs::PermissionsExt;
 
     std::fs::metadata(path)
@@ -1987,6 +3203,6 @@ fn is_executable(path: &PathBuf) -> bool {
 }
 
 #[cfg(windows)]
-fn is_executable(path: &PathBuf) -> bool {
+fn is_executable(path: &Path) -> bool {
     path.is_file()
 }
@@ -0,0 +1,383 @@
+//! Depot content validation against Steam manifests
+//!
+//! Every file listed in a depot manifest must exist with the listed size, and
+//! each of its chunks must match the chunk's SHA-1. Runs on the staging
+//! `depots/` tree before finalization, or on a finished output folder
+//! (`steamapps/common/` checked against `depotcache/`).
+
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::depot_manifest::{
+    find_saved_manifest, read_depot_manifest, DepotManifest, ManifestChunk, ManifestFile,
+};
+use crate::job_host::JobHost;
+
+/// Files sampled per manifest when matching it to a `steamapps/common/` folder
+const FOLDER_MATCH_SAMPLE: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    /// Path relative to the depot root
+    pub path: String,
+    pub problem: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepotValidationReport {
+    pub depot_id: String,
+    pub manifest_path: PathBuf,
+    pub content_dir: PathBuf,
+    pub files_checked: u64,
+    pub bytes_checked: u64,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl DepotValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Checks every entry of `manifest` against the files below `content_dir`
+pub fn validate_depot_content(
+    content_dir: &Path,
+    manifest: &DepotManifest,
+) -> Result<DepotValidationReport, String> {
+    if manifest.filenames_encrypted {
+        return Err("manifest file names are still encrypted".to_string());
+    }
+
+    let mut report = DepotValidationReport {
+        depot_id: manifest.depot_id.map(|id| id.to_string()).unwrap_or_default(),
+        content_dir: content_dir.to_path_buf(),
+        ..Default::default()
+    };
+    let mut buffer = Vec::new();
+
+    for file in &manifest.files {
+        if let Some(problem) = check_manifest_file(content_dir, file, &mut buffer) {
+            report.issues.push(ValidationIssue {
+                path: file.name.clone(),
+                problem,
+            });
+        } else if !file.is_directory() && !file.is_symlink() {
+            report.files_checked += 1;
+            report.bytes_checked += file.size;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Returns a description of what is wrong with one manifest entry, if anything
+fn check_manifest_file(content_dir: &Path, file: &ManifestFile, buffer: &mut Vec<u8>) -> Option<String> {
+    let path = manifest_entry_path(content_dir, &file.name);
+
+    if file.is_symlink() {
+        return fs::symlink_metadata(&path)
+            .is_err()
+            .then(|| "missing symlink".to_string());
+    }
+
+    let Ok(metadata) = fs::metadata(&path) else {
+        return Some("missing".to_string());
+    };
+    if file.is_directory() {
+        return (!metadata.is_dir()).then(|| "expected a directory".to_string());
+    }
+    if !metadata.is_file() {
+        return Some("expected a file".to_string());
+    }
+    if metadata.len() != file.size {
+        return Some(format!(
+            "size mismatch: expected {} bytes, found {}",
+            file.size,
+            metadata.len()
+        ));
+    }
+
+    let mut handle = match File::open(&path) {
+        Ok(handle) => handle,
+        Err(err) => return Some(format!("unreadable: {}", err)),
+    };
+    if file.chunks.is_empty() {
+        // No chunk list; fall back to the whole-file hash when there is one
+        if file.size == 0 || file.sha_content.is_empty() {
+            return None;
+        }
+        return match sha1_of(&mut handle) {
+            Ok(sha) if sha == file.sha_content => None,
+            Ok(_) => Some("content does not match its SHA-1".to_string()),
+            Err(err) => Some(format!("unreadable: {}", err)),
+        };
+    }
+    match find_corrupt_chunk(&mut handle, &file.chunks, buffer) {
+        Ok(None) => None,
+        Ok(Some(offset)) => Some(format!("chunk at offset {} does not match its SHA-1", offset)),
+        Err(err) => Some(format!("unreadable: {}", err)),
+    }
+}
+
+fn sha1_of<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut hasher = Sha1::new();
+    std::io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Hashes each chunk and returns the offset of the first one whose SHA-1 differs
+fn find_corrupt_chunk<R: Read + Seek>(
+    reader: &mut R,
+    chunks: &[ManifestChunk],
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<Option<u64>> {
+    let mut chunks: Vec<&ManifestChunk> = chunks.iter().collect();
+    chunks.sort_by_key(|chunk| chunk.offset);
+
+    for chunk in chunks {
+        buffer.resize(chunk.original_size as usize, 0);
+        reader.seek(SeekFrom::Start(chunk.offset))?;
+        reader.read_exact(buffer)?;
+        if Sha1::digest(&buffer[..]).as_slice() != chunk.sha.as_slice() {
+            return Ok(Some(chunk.offset));
+        }
+    }
+    Ok(None)
+}
+
+pub fn manifest_entry_path(content_dir: &Path, name: &str) -> PathBuf {
+    name.split('/')
+        .filter(|part| !part.is_empty())
+        .fold(content_dir.to_path_buf(), |path, part| path.join(part))
+}
+
+/// Validates a staging directory's `depots/<depot_id>/<manifest_id>/` folders
+/// against the manifests DepotDownloader saved in each `.DepotDownloader/`
+pub fn validate_staging_depots(staging_dir: &Path) -> Result<Vec<DepotValidationReport>, String> {
+    let depots_dir = staging_dir.join("depots");
+    let mut reports = Vec::new();
+
+    for depot_entry in sorted_dirs(&depots_dir)? {
+        let depot_id = file_name(&depot_entry);
+        if depot_id == ".DepotDownloader" {
+            continue;
+        }
+
+        for content_dir in sorted_dirs(&depot_entry)? {
+            let manifest_path = find_saved_manifest(&content_dir).ok_or_else(|| {
+                format!("No manifest file found for depot {} in {}", depot_id, content_dir.display())
+            })?;
+            let manifest = read_depot_manifest(&manifest_path)?;
+            let mut report = validate_depot_content(&content_dir, &manifest)
+                .map_err(|e| format!("Depot {}: {}", depot_id, e))?;
+            report.depot_id = depot_id.clone();
+            report.manifest_path = manifest_path;
+            reports.push(report);
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Validates a finalized output folder: each manifest in `depotcache/` is
+/// checked against the `steamapps/common/` folder that holds its files
+pub fn validate_output_folder(output_dir: &Path) -> Result<Vec<DepotValidationReport>, String> {
+    let depotcache_dir = output_dir.join("depotcache");
+    let common_dir = output_dir.join("steamapps").join("common");
+    if !depotcache_dir.is_dir() || !common_dir.is_dir() {
+        return Err(format!(
+            "{} is not an OmniPacker output folder (missing depotcache/ or steamapps/common/)",
+            output_dir.display()
+        ));
+    }
+
+    let content_dirs = sorted_dirs(&common_dir)?;
+    let mut reports = Vec::new();
+    for manifest_path in manifest_files(&depotcache_dir)? {
+        let manifest = read_depot_manifest(&manifest_path)?;
+        let content_dir = best_content_dir(&content_dirs, &manifest)
+            .unwrap_or_else(|| common_dir.clone());
+        let mut report = validate_depot_content(&content_dir, &manifest)
+            .map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+        if report.depot_id.is_empty() {
+            report.depot_id = depot_id_from_manifest_name(&manifest_path);
+        }
+        report.manifest_path = manifest_path;
+        reports.push(report);
+    }
+
+    if reports.is_empty() {
+        return Err(format!("No manifest files found in {}", depotcache_dir.display()));
+    }
+    Ok(reports)
+}
+
+/// Picks the folder holding the most of the manifest's files
+pub fn best_content_dir(candidates: &[PathBuf], manifest: &DepotManifest) -> Option<PathBuf> {
+    let sample: Vec<&ManifestFile> = manifest
+        .files
+        .iter()
+        .filter(|file| !file.is_directory())
+        .take(FOLDER_MATCH_SAMPLE)
+        .collect();
+
+    candidates
+        .iter()
+        .map(|dir| {
+            let hits = sample
+                .iter()
+                .filter(|file| fs::symlink_metadata(manifest_entry_path(dir, &file.name)).is_ok())
+                .count();
+            (hits, dir)
+        })
+        .filter(|(hits, _)| *hits > 0)
+        .max_by_key(|(hits, _)| *hits)
+        .map(|(_, dir)| dir.clone())
+}
+
+pub fn manifest_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "manifest"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn sorted_dirs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+pub fn depot_id_from_manifest_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    stem.split_once('_').map(|(depot, _)| depot).unwrap_or(stem).to_string()
+}
+
+/// Writes a short summary of `reports` to the job log, listing at most
+/// `max_issues` problems per depot. Returns the total number of problems.
+pub fn log_validation_reports(
+    host: &dyn JobHost,
+    reports: &[DepotValidationReport],
+    max_issues: usize,
+    job_id: &str,
+) -> usize {
+    let mut total = 0;
+    for report in reports {
+        if report.is_valid() {
+            host.emit_log(
+                "system",
+                &format!(
+                    "Depot {} validated: {} files, {} bytes match the manifest.",
+                    report.depot_id, report.files_checked, report.bytes_checked
+                ),
+                job_id,
+            );
+            continue;
+        }
+
+        total += report.issues.len();
+        host.emit_log(
+            "system",
+            &format!(
+                "Depot {} failed validation: {} missing or corrupt entries.",
+                report.depot_id,
+                report.issues.len()
+            ),
+            job_id,
+        );
+        for issue in report.issues.iter().take(max_issues) {
+            host.emit_log("system", &format!("  {}: {}", issue.path, issue.problem), job_id);
+        }
+        if report.issues.len() > max_issues {
+            host.emit_log(
+                "system",
+                &format!("  ...and {} more", report.issues.len() - max_issues),
+                job_id,
+            );
+        }
+    }
+    total
+}
+
+/// Re-validates an existing output folder against its `depotcache/` manifests
+#[tauri::command]
+pub async fn validate_output(
+    app_handle: AppHandle,
+    path: String,
+) -> Result<Vec<DepotValidationReport>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let reports = validate_output_folder(Path::new(&path))?;
+        log_validation_reports(&app_handle, &reports, 50, "validation");
+        Ok(reports)
+    })
+    .await
+    .map_err(|e| format!("Validation task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn chunk(data: &[u8], offset: u64) -> ManifestChunk {
+        ManifestChunk {
+            sha: Sha1::digest(data).to_vec(),
+            offset,
+            original_size: data.len() as u32,
+        }
+    }
+
+    #[test]
+    fn test_finds_corrupt_chunk_by_offset() {
+        let content = b"hello world!";
+        let chunks = vec![chunk(b"world!", 6), chunk(b"hello ", 0)];
+        let mut buffer = Vec::new();
+        assert_eq!(
+            find_corrupt_chunk(&mut Cursor::new(&content[..]), &chunks, &mut buffer).unwrap(),
+            None
+        );
+
+        let damaged = b"hello w0rld!";
+        assert_eq!(
+            find_corrupt_chunk(&mut Cursor::new(&damaged[..]), &chunks, &mut buffer).unwrap(),
+            Some(6)
+        );
+
+        // A file shorter than its chunk list can't be read in full
+        assert!(find_corrupt_chunk(&mut Cursor::new(&content[..8]), &chunks, &mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_manifest_names_and_entry_paths() {
+        assert_eq!(
+            depot_id_from_manifest_name(Path::new("depotcache/228988_123456.manifest")),
+            "228988"
+        );
+        assert_eq!(
+            manifest_entry_path(Path::new("root"), "bin/x64/game.exe"),
+            Path::new("root").join("bin").join("x64").join("game.exe")
+        );
+    }
+}
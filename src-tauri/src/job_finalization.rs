@@ -2,13 +2,14 @@ use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
-use tauri::AppHandle;
 
 use crate::acf_generator;
+use crate::archive_format::{archive_exists, existing_volumes, remove_volumes, ArchiveFormat};
+use crate::depot_manifest::{find_saved_manifest, read_depot_manifest, DepotManifest};
+use crate::job_host::JobHost;
 use crate::job_metadata::JobMetadataFile;
 use crate::job_staging::resolve_staging_dir;
-use crate::output_conflict::{request_output_conflict_resolution, OutputConflictChoice};
-use crate::output_dir::resolve_downloads_dir;
+use crate::output_conflict::OutputConflictChoice;
 use crate::steam_api::sanitize_game_name;
 
 /// Finalizes a job by moving staging output to final output directory
@@ -16,9 +17,11 @@ use crate::steam_api::sanitize_game_name;
 /// This is the main entry point called after DepotDownloader exits successfully.
 ///
 /// # Arguments
-/// * `app_handle` - Tauri application handle
+/// * `host` - Job host (Tauri app or headless CLI)
 /// * `job_id` - Unique job identifier
-/// * `compression_enabled` - Whether compression runs after finalization
+/// * `archive_format` - Archive that compression will produce, or `None` when compression is off
+/// * `conflict_choice` - Answer already given for an existing output (see `find_packed_output`);
+///   `None` asks the host when a conflict is found
 ///
 /// # Returns
 /// * `Ok(PathBuf)` - Path to the final output directory
@@ -30,49 +33,37 @@ use crate::steam_api::sanitize_game_name;
 /// - Temp cleanup on error
 /// - Prompts if output already exists (overwrite/copy/cancel)
 pub fn finalize_job(
-    app_handle: &AppHandle,
+    host: &dyn JobHost,
     job_id: &str,
-    compression_enabled: bool,
+    archive_format: Option<ArchiveFormat>,
+    conflict_choice: Option<OutputConflictChoice>,
 ) -> Result<PathBuf, String> {
     // Step 1: Load job.json from staging
-    let staging_dir = resolve_staging_dir(app_handle, job_id)?;
+    let staging_dir = resolve_staging_dir(host, job_id)?;
     let job_metadata = load_and_validate_metadata(&staging_dir)?;
 
     // Step 2: Validate staging contents
     validate_staging_contents(&staging_dir)?;
 
     // Step 3: Compute final output path
-    let mut final_output_path = compute_final_output_path(app_handle, &job_metadata)?;
+    let mut final_output_path = compute_final_output_path(host, &job_metadata)?;
 
     // Step 4: Resolve output conflicts (overwrite/copy/cancel)
     let mut overwrite_existing = false;
-    let mut archive_path = if compression_enabled {
-        Some(resolve_archive_path(&final_output_path))
-    } else {
-        None
-    };
-    let output_exists = final_output_path.exists();
-    let archive_exists = archive_path
-        .as_ref()
-        .map(|path| path.exists())
-        .unwrap_or(false);
-
-    if output_exists || archive_exists {
-        let conflict_path = if output_exists {
-            final_output_path.clone()
-        } else {
-            archive_path
-                .clone()
-                .unwrap_or_else(|| final_output_path.clone())
+    let mut archive_path =
+        archive_format.map(|format| resolve_archive_path(&final_output_path, format));
+
+    if let Some(conflict_path) = existing_output_path(&final_output_path, archive_path.as_deref()) {
+        let choice = match conflict_choice {
+            Some(choice) => choice,
+            None => host.resolve_output_conflict(job_id, &conflict_path)?,
         };
-        match request_output_conflict_resolution(app_handle, job_id, &conflict_path)? {
+        match choice {
             OutputConflictChoice::Overwrite => overwrite_existing = true,
             OutputConflictChoice::Copy => {
-                final_output_path =
-                    resolve_copy_output_path(&final_output_path, compression_enabled)?;
-                if compression_enabled {
-                    archive_path = Some(resolve_archive_path(&final_output_path));
-                }
+                final_output_path = resolve_copy_output_path(&final_output_path, archive_format)?;
+                archive_path =
+                    archive_format.map(|format| resolve_archive_path(&final_output_path, format));
             }
             OutputConflictChoice::Cancel => {
                 return Err(format!(
@@ -84,7 +75,7 @@ pub fn finalize_job(
     }
 
     // Step 5: Build output in temp directory
-    let temp_output_path = build_temp_output(app_handle, job_id, &staging_dir, &job_metadata)?;
+    let temp_output_path = build_temp_output(host, job_id, &staging_dir, &job_metadata)?;
 
     // Step 6: Remove existing output if overwrite was selected
     if overwrite_existing {
@@ -138,30 +129,82 @@ fn validate_staging_contents(staging_dir: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Looks for an existing output of the build described by `metadata` (which only
+/// needs the fields that name the output), so a job can stop before downloading
+/// something that is already packed. Returns the folder or archive found.
+pub fn find_packed_output(
+    host: &dyn JobHost,
+    metadata: &JobMetadataFile,
+    archive_format: Option<ArchiveFormat>,
+) -> Result<Option<PathBuf>, String> {
+    let output_path = compute_final_output_path(host, metadata)?;
+    let archive_path = archive_format.map(|format| resolve_archive_path(&output_path, format));
+    Ok(existing_output_path(&output_path, archive_path.as_deref()))
+}
+
+/// The output folder if it exists, otherwise the archive (or its first volume)
+fn existing_output_path(output_path: &Path, archive_path: Option<&Path>) -> Option<PathBuf> {
+    if output_path.exists() {
+        return Some(output_path.to_path_buf());
+    }
+    archive_path
+        .filter(|path| archive_exists(path))
+        .map(existing_archive_path)
+}
+
 /// Step 3: Compute final output directory path
 fn compute_final_output_path(
-    app_handle: &AppHandle,
+    host: &dyn JobHost,
     metadata: &JobMetadataFile,
 ) -> Result<PathBuf, String> {
-    let downloads_dir = resolve_downloads_dir(app_handle)?;
+    let downloads_dir = host.downloads_dir()?;
     let outputs_dir = downloads_dir.join("outputs");
 
-    // Format: <GameNameSanitized>.Build.<BuildId>.<Platform>.<Branch>
+    Ok(outputs_dir.join(output_folder_name(metadata)))
+}
+
+/// Format: <GameNameSanitized>.Build.<BuildId>.<Platform>.<Branch>[.<Language>][.LowViolence]
+///
+/// English (the default) is left out so existing output names don't change.
+fn output_folder_name(metadata: &JobMetadataFile) -> String {
     let sanitized_name = sanitize_game_name(&metadata.game_name);
-    let folder_name = format!(
+    let mut folder_name = format!(
         "{}.Build.{}.{}.{}",
         sanitized_name, metadata.build_id, metadata.platform, metadata.branch
     );
 
-    Ok(outputs_dir.join(folder_name))
+    if let Some(language) = metadata
+        .language
+        .as_deref()
+        .filter(|language| !language.eq_ignore_ascii_case("english"))
+    {
+        folder_name.push('.');
+        folder_name.push_str(&language_label(language));
+    }
+    if metadata.low_violence {
+        folder_name.push_str(".LowViolence");
+    }
+
+    folder_name
+}
+
+/// Formats a Steam language name for display: "schinese" → "Schinese"
+pub fn language_label(language: &str) -> String {
+    let mut chars = language.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
 }
 
-pub fn resolve_archive_path(output_path: &Path) -> PathBuf {
+/// Appends the format's extension to an output folder path: `Game.Build.1` → `Game.Build.1.tar.zst`
+pub fn resolve_archive_path(output_path: &Path, format: ArchiveFormat) -> PathBuf {
     let file_name = output_path
         .file_name()
-        .unwrap_or_else(|| output_path.as_os_str());
+        .unwrap_or(output_path.as_os_str());
     let mut archive_name = OsString::from(file_name);
-    archive_name.push(".7z");
+    archive_name.push(".");
+    archive_name.push(format.extension());
 
     match output_path.parent() {
         Some(parent) => parent.join(archive_name),
@@ -171,12 +214,12 @@ pub fn resolve_archive_path(output_path: &Path) -> PathBuf {
 
 /// Step 5: Build output in temporary directory
 fn build_temp_output(
-    app_handle: &AppHandle,
+    host: &dyn JobHost,
     job_id: &str,
     staging_dir: &Path,
     metadata: &JobMetadataFile,
 ) -> Result<PathBuf, String> {
-    let downloads_dir = resolve_downloads_dir(app_handle)?;
+    let downloads_dir = host.downloads_dir()?;
     let outputs_dir = downloads_dir.join("outputs");
     let temp_dir = outputs_dir.join(format!(".tmp_{}", job_id));
 
@@ -191,21 +234,21 @@ fn build_temp_output(
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
 
     // Transform depots/ → steamapps/common/ and collect manifests → depotcache/
-    // Returns a map of depot_id → actual manifest_id (extracted from .manifest filenames)
-    let manifest_map = transform_depots_to_steamapps(staging_dir, &temp_dir, metadata)?;
+    // Returns a map of depot_id → decoded manifest
+    let manifests = transform_depots_to_steamapps(staging_dir, &temp_dir, metadata)?;
 
     // Generate appmanifest_<appid>.acf file
     let steamapps_dir = temp_dir.join("steamapps");
     let common_dir = steamapps_dir.join("common");
     let install_dir_name = sanitize_game_name(&metadata.game_name);
-    acf_generator::write_acf_file(&steamapps_dir, metadata, &common_dir, &install_dir_name, &manifest_map)?;
+    acf_generator::write_acf_file(&steamapps_dir, metadata, &common_dir, &install_dir_name, &manifests)?;
 
     Ok(temp_dir)
 }
 
-fn resolve_copy_output_path(
+pub fn resolve_copy_output_path(
     base_path: &Path,
-    compression_enabled: bool,
+    archive_format: Option<ArchiveFormat>,
 ) -> Result<PathBuf, String> {
 
     let parent = base_path
@@ -221,7 +264,7 @@ fn resolve_copy_output_path(
         if candidate.exists() {
             continue;
         }
-        if compression_enabled && resolve_archive_path(&candidate).exists() {
+        if archive_format.is_some_and(|format| archive_exists(&resolve_archive_path(&candidate, format))) {
             continue;
         }
         return Ok(candidate);
@@ -230,7 +273,7 @@ fn resolve_copy_output_path(
     Err("Unable to find available output copy name".to_string())
 }
 
-fn remove_existing_output(path: &Path) -> Result<(), String> {
+pub fn remove_existing_output(path: &Path) -> Result<(), String> {
     if !path.exists() {
         return Ok(());
     }
@@ -249,7 +292,20 @@ fn remove_existing_output(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn remove_existing_archive(path: &Path) -> Result<(), String> {
+/// The file to name in a conflict prompt: the archive itself, or the first volume of a split set
+fn existing_archive_path(archive_path: &Path) -> PathBuf {
+    if archive_path.exists() {
+        return archive_path.to_path_buf();
+    }
+    existing_volumes(archive_path)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| archive_path.to_path_buf())
+}
+
+/// Removes an existing archive along with any volumes of a split archive by the same name
+pub fn remove_existing_archive(path: &Path) -> Result<(), String> {
+    remove_volumes(path)?;
     if !path.exists() {
         return Ok(());
     }
@@ -273,21 +329,21 @@ fn remove_existing_archive(path: &Path) -> Result<(), String> {
 /// DepotDownloader creates: depots/<depotid>/<manifestid>/(files + .DepotDownloader/)
 /// We need to create:
 /// - steamapps/common/<DepotName>/(files, excluding .DepotDownloader/)
-/// - depotcache/*.manifest (collected from all .DepotDownloader/ directories)
+/// - depotcache/<depot>_<manifest>.manifest (one per depot, from .DepotDownloader/)
 ///
 /// # Returns
-/// A map of depot_id → actual manifest_id (extracted from .manifest filenames)
+/// A map of depot_id → decoded depot manifest
 fn transform_depots_to_steamapps(
     staging_dir: &Path,
     temp_dir: &Path,
     metadata: &JobMetadataFile,
-) -> Result<HashMap<String, String>, String> {
+) -> Result<HashMap<String, DepotManifest>, String> {
     let depots_dir = staging_dir.join("depots");
     let steamapps_common_dir = temp_dir.join("steamapps").join("common");
     let depotcache_dir = temp_dir.join("depotcache");
 
-    // Map of depot_id → actual manifest_id (extracted from .manifest filenames)
-    let mut manifest_map: HashMap<String, String> = HashMap::new();
+    // Map of depot_id → decoded manifest
+    let mut manifests: HashMap<String, DepotManifest> = HashMap::new();
 
     // Create directories
     fs::create_dir_all(&steamapps_common_dir)
@@ -343,28 +399,19 @@ fn transform_depots_to_steamapps(
         // Use the first manifest directory (there should only be one)
         let manifest_dir = manifest_dirs[0].path();
 
-        // Collect manifest files from .DepotDownloader/ subdirectory
-        let dd_dir = manifest_dir.join(".DepotDownloader");
-        if dd_dir.exists() {
-            for manifest_entry in fs::read_dir(&dd_dir)
-                .map_err(|e| format!("Failed to read .DepotDownloader directory: {}", e))?
-            {
-                let manifest_entry = manifest_entry
-                    .map_err(|e| format!("Failed to read manifest entry: {}", e))?;
-                let manifest_path = manifest_entry.path();
-
-                // Copy .manifest files (not .manifest.sha or staging/)
-                if manifest_path.is_file() && manifest_path.extension().map(|e| e == "manifest").unwrap_or(false) {
-                    let manifest_filename = manifest_entry.file_name().to_string_lossy().to_string();
-
-                    // Extract manifest ID from filename (format: {manifest_id}.manifest)
-                    if let Some(manifest_id) = manifest_filename.strip_suffix(".manifest") {
-                        manifest_map.insert(depot_id.clone(), manifest_id.to_string());
-                    }
-
-                    fs::copy(&manifest_path, depotcache_dir.join(&manifest_filename))
-                        .map_err(|e| format!("Failed to copy manifest file: {}", e))?;
-                }
+        // Copy the depot's manifest from .DepotDownloader/ into depotcache/ under the
+        // <depot>_<manifest>.manifest name Steam uses; one that can't be decoded is
+        // still copied under its original name
+        if let Some(manifest_path) = find_saved_manifest(&manifest_dir) {
+            let manifest = read_depot_manifest(&manifest_path).ok();
+            let manifest_filename = manifest
+                .as_ref()
+                .and_then(DepotManifest::depotcache_file_name)
+                .unwrap_or_else(|| manifest_entry_name(&manifest_path));
+            fs::copy(&manifest_path, depotcache_dir.join(&manifest_filename))
+                .map_err(|e| format!("Failed to copy manifest file: {}", e))?;
+            if let Some(manifest) = manifest {
+                manifests.insert(depot_id.clone(), manifest);
             }
         }
 
@@ -376,7 +423,13 @@ fn transform_depots_to_steamapps(
         })?;
     }
 
-    Ok(manifest_map)
+    Ok(manifests)
+}
+
+fn manifest_entry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
 }
 
 /// Step 6: Atomic rename from temp to final
@@ -443,3 +496,53 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job_metadata::BuildIdSource;
+
+    fn metadata() -> JobMetadataFile {
+        JobMetadataFile::new(
+            "job".to_string(),
+            "480".to_string(),
+            "Public".to_string(),
+            "Win64".to_string(),
+            "481".to_string(),
+            "Spacewar".to_string(),
+            "123".to_string(),
+            BuildIdSource::AppBuildid,
+            None,
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_output_folder_name_includes_language() {
+        let mut metadata = metadata();
+        assert_eq!(output_folder_name(&metadata), "Spacewar.Build.123.Win64.Public");
+
+        metadata.language = Some("english".to_string());
+        assert_eq!(output_folder_name(&metadata), "Spacewar.Build.123.Win64.Public");
+
+        metadata.language = Some("schinese".to_string());
+        metadata.low_violence = true;
+        assert_eq!(
+            output_folder_name(&metadata),
+            "Spacewar.Build.123.Win64.Public.Schinese.LowViolence"
+        );
+    }
+
+    #[test]
+    fn test_resolve_archive_path_appends_format_extension() {
+        let output = Path::new("outputs/Spacewar.Build.123.Win64.Public");
+        assert_eq!(
+            resolve_archive_path(output, ArchiveFormat::SevenZip),
+            PathBuf::from("outputs/Spacewar.Build.123.Win64.Public.7z")
+        );
+        assert_eq!(
+            resolve_archive_path(output, ArchiveFormat::TarZst),
+            PathBuf::from("outputs/Spacewar.Build.123.Win64.Public.tar.zst")
+        );
+    }
+}
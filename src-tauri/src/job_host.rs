@@ -0,0 +1,212 @@
+//! Host abstraction for the job pipeline
+//!
+//! The download → finalize → compress pipeline only needs a handful of things
+//! from its surroundings: somewhere to send log/status lines, a few resolved
+//! directories, and a way to decide output conflicts. `JobHost` captures that
+//! surface so the same pipeline can run inside the Tauri app (events to the
+//! webview) or headless from the command line (plain stdout).
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::debug_console::DebugConsoleState;
+use crate::job_log::{append_job_log, append_job_status};
+use crate::output_conflict::{request_output_conflict_resolution, OutputConflictChoice};
+use crate::output_dir::resolve_downloads_dir;
+use crate::redaction::redact_log_line;
+use crate::template_metadata::{TemplateMetadata, TemplateMetadataState};
+
+/// Shared, thread-safe handle to a job host
+pub type SharedJobHost = Arc<dyn JobHost>;
+
+pub trait JobHost: Send + Sync {
+    /// Emits a DepotDownloader/system log line for a job
+    fn emit_log(&self, stream: &str, line: &str, job_id: &str);
+
+    /// Emits a job status transition (starting, running, completed, ...)
+    fn emit_status(&self, status: &str, code: Option<i32>, job_id: &str);
+
+    /// Emits a 7-Zip log line for a job (empty for 7-Zip runs outside a job)
+    fn emit_7z_log(&self, stream: &str, line: &str, job_id: &str);
+
+    /// Emits 7-Zip progress (0-100)
+    fn emit_7z_progress(&self, percent: u8);
+
+    /// Root downloads directory (staging/, outputs/, logs/, .auth/ live below it)
+    fn downloads_dir(&self) -> Result<PathBuf, String>;
+
+    /// Application data directory (template.json, login.dat, ...)
+    fn app_data_dir(&self) -> Result<PathBuf, String>;
+
+    /// Resolves a path relative to the bundled resources directory
+    fn resource_path(&self, relative: &str) -> Result<PathBuf, String>;
+
+    /// Decides what to do when the final output already exists
+    fn resolve_output_conflict(
+        &self,
+        job_id: &str,
+        output_path: &Path,
+    ) -> Result<OutputConflictChoice, String>;
+
+    /// Publishes template metadata for the most recently finalized job
+    fn set_template_metadata(&self, _metadata: TemplateMetadata) {}
+}
+
+impl<T: JobHost + ?Sized> JobHost for Arc<T> {
+    fn emit_log(&self, stream: &str, line: &str, job_id: &str) {
+        (**self).emit_log(stream, line, job_id)
+    }
+
+    fn emit_status(&self, status: &str, code: Option<i32>, job_id: &str) {
+        (**self).emit_status(status, code, job_id)
+    }
+
+    fn emit_7z_log(&self, stream: &str, line: &str, job_id: &str) {
+        (**self).emit_7z_log(stream, line, job_id)
+    }
+
+    fn emit_7z_progress(&self, percent: u8) {
+        (**self).emit_7z_progress(percent)
+    }
+
+    fn downloads_dir(&self) -> Result<PathBuf, String> {
+        (**self).downloads_dir()
+    }
+
+    fn app_data_dir(&self) -> Result<PathBuf, String> {
+        (**self).app_data_dir()
+    }
+
+    fn resource_path(&self, relative: &str) -> Result<PathBuf, String> {
+        (**self).resource_path(relative)
+    }
+
+    fn resolve_output_conflict(
+        &self,
+        job_id: &str,
+        output_path: &Path,
+    ) -> Result<OutputConflictChoice, String> {
+        (**self).resolve_output_conflict(job_id, output_path)
+    }
+
+    fn set_template_metadata(&self, metadata: TemplateMetadata) {
+        (**self).set_template_metadata(metadata)
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusPayload {
+    status: String,
+    code: Option<i32>,
+    job_id: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogPayload {
+    stream: String,
+    line: String,
+    job_id: String,
+}
+
+#[derive(Clone, Serialize)]
+struct SevenZipLogPayload {
+    stream: String,
+    line: String,
+}
+
+#[derive(Clone, Serialize)]
+struct SevenZipProgressPayload {
+    percent: u8,
+}
+
+/// Status transition as written to the job log
+pub fn status_line(status: &str, code: Option<i32>) -> String {
+    match code {
+        Some(code) => format!("{status} ({code})"),
+        None => status.to_string(),
+    }
+}
+
+impl JobHost for AppHandle {
+    fn emit_log(&self, stream: &str, line: &str, job_id: &str) {
+        let line = redact_log_line(line);
+        append_job_log(self, job_id, stream, &line);
+        let _ = self.emit(
+            "dd:log",
+            LogPayload {
+                stream: stream.to_string(),
+                line: line.to_string(),
+                job_id: job_id.to_string(),
+            },
+        );
+
+        let debug_state = self.state::<DebugConsoleState>();
+        if debug_state.enabled() {
+            debug_state.write_line(&format!("[{stream}] {line}"));
+        }
+    }
+
+    fn emit_status(&self, status: &str, code: Option<i32>, job_id: &str) {
+        append_job_status(self, job_id, status, code);
+        let _ = self.emit(
+            "dd:status",
+            StatusPayload {
+                status: status.to_string(),
+                code,
+                job_id: job_id.to_string(),
+            },
+        );
+    }
+
+    fn emit_7z_log(&self, stream: &str, line: &str, job_id: &str) {
+        let line = redact_log_line(line);
+        append_job_log(self, job_id, &format!("7z:{stream}"), &line);
+        let debug_state = self.state::<DebugConsoleState>();
+        if debug_state.enabled() {
+            debug_state.write_line(&format!("[7z:{stream}] {line}"));
+        }
+        let _ = self.emit(
+            "7z:log",
+            SevenZipLogPayload {
+                stream: stream.to_string(),
+                line: line.to_string(),
+            },
+        );
+    }
+
+    fn emit_7z_progress(&self, percent: u8) {
+        let _ = self.emit("7z:progress", SevenZipProgressPayload { percent });
+    }
+
+    fn downloads_dir(&self) -> Result<PathBuf, String> {
+        resolve_downloads_dir(self)
+    }
+
+    fn app_data_dir(&self) -> Result<PathBuf, String> {
+        self.path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+    }
+
+    fn resource_path(&self, relative: &str) -> Result<PathBuf, String> {
+        self.path()
+            .resolve(relative, tauri::path::BaseDirectory::Resource)
+            .map_err(|e| format!("Failed to resolve resource {}: {}", relative, e))
+    }
+
+    fn resolve_output_conflict(
+        &self,
+        job_id: &str,
+        output_path: &Path,
+    ) -> Result<OutputConflictChoice, String> {
+        request_output_conflict_resolution(self, job_id, output_path)
+    }
+
+    fn set_template_metadata(&self, metadata: TemplateMetadata) {
+        self.state::<TemplateMetadataState>().set(metadata);
+    }
+}
@@ -0,0 +1,392 @@
+//! Per-job log files
+//!
+//! Everything a job logs (DepotDownloader output, 7-Zip output, system messages
+//! and status changes) is also appended to `logs/<job_id>.log` in the downloads
+//! directory, one timestamped line per entry with its stream tag. Lines arrive
+//! here already redacted (see `redaction`). A log growing past `MAX_LOG_BYTES`
+//! is rotated once to `<job_id>.log.1`. Each job's file stays open until the job
+//! reaches a final status. Whenever a job starts a new log, logs older than
+//! `LOG_RETENTION_DAYS` are deleted and only the newest `MAX_JOB_LOGS` kept.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+use crate::job_host::{status_line, JobHost};
+
+const LOGS_DIR: &str = "logs";
+const LOG_EXTENSION: &str = "log";
+/// Size at which a job log is rotated to `<job_id>.log.1`
+const MAX_LOG_BYTES: u64 = 32 * 1024 * 1024;
+const LOG_RETENTION_DAYS: i64 = 30;
+const MAX_JOB_LOGS: usize = 200;
+
+/// Directory job logs are written to
+pub fn job_logs_dir(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.downloads_dir()?.join(LOGS_DIR))
+}
+
+fn validate_log_job_id(job_id: &str) -> Result<(), String> {
+    let valid = !job_id.is_empty()
+        && !job_id.starts_with('.')
+        && job_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid job ID: {}", job_id))
+    }
+}
+
+fn log_file_path(logs_dir: &Path, job_id: &str) -> PathBuf {
+    logs_dir.join(format!("{}.{}", job_id, LOG_EXTENSION))
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_os_string();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Path of a job's log file (it may not exist yet)
+pub fn job_log_path(host: &dyn JobHost, job_id: &str) -> Result<PathBuf, String> {
+    validate_log_job_id(job_id)?;
+    Ok(log_file_path(&job_logs_dir(host)?, job_id))
+}
+
+struct OpenLog {
+    job_id: String,
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+/// Appends lines to each job's log, keeping one open file per job
+struct JobLogWriter {
+    open: HashMap<String, OpenLog>,
+    max_bytes: u64,
+}
+
+impl JobLogWriter {
+    fn new(max_bytes: u64) -> Self {
+        Self {
+            open: HashMap::new(),
+            max_bytes,
+        }
+    }
+
+    /// Appends a line to `job_id`'s log; lines without a job are dropped.
+    /// `logs_dir` is only resolved when a log is opened.
+    fn append(
+        &mut self,
+        logs_dir: impl FnOnce() -> Result<PathBuf, String>,
+        job_id: &str,
+        stream: &str,
+        line: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if job_id.is_empty() {
+            return Ok(());
+        }
+        let log = match self.open.entry(job_id.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                validate_log_job_id(job_id)?;
+                let logs_dir = logs_dir()?;
+                fs::create_dir_all(&logs_dir)
+                    .map_err(|e| format!("Failed to create {}: {}", logs_dir.display(), e))?;
+                let path = log_file_path(&logs_dir, job_id);
+                // A job starting its log is the one time old logs are swept
+                if !path.exists() {
+                    prune_job_logs(&logs_dir, now, LOG_RETENTION_DAYS, MAX_JOB_LOGS);
+                }
+                entry.insert(open_log(job_id, path)?)
+            }
+        };
+
+        if log.size >= self.max_bytes {
+            let rotated = rotated_path(&log.path);
+            let _ = fs::remove_file(&rotated);
+            fs::rename(&log.path, &rotated)
+                .map_err(|e| format!("Failed to rotate {}: {}", log.path.display(), e))?;
+            *log = open_log(&log.job_id.clone(), log.path.clone())?;
+        }
+
+        let entry = format!(
+            "{} [{}] {}\n",
+            now.to_rfc3339_opts(SecondsFormat::Millis, true),
+            stream,
+            line
+        );
+        log.file
+            .write_all(entry.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", log.path.display(), e))?;
+        log.size += entry.len() as u64;
+        Ok(())
+    }
+
+    /// Closes a job's log file; lines logged later reopen it
+    fn close(&mut self, job_id: &str) {
+        self.open.remove(job_id);
+    }
+}
+
+fn open_log(job_id: &str, path: PathBuf) -> Result<OpenLog, String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    Ok(OpenLog {
+        job_id: job_id.to_string(),
+        path,
+        file,
+        size,
+    })
+}
+
+fn writer() -> &'static Mutex<JobLogWriter> {
+    static WRITER: OnceLock<Mutex<JobLogWriter>> = OnceLock::new();
+    WRITER.get_or_init(|| Mutex::new(JobLogWriter::new(MAX_LOG_BYTES)))
+}
+
+/// Appends an (already redacted) line to a job's log file. Output that doesn't
+/// belong to a job (empty `job_id`) isn't logged.
+pub fn append_job_log(host: &dyn JobHost, job_id: &str, stream: &str, line: &str) {
+    let Ok(mut writer) = writer().lock() else {
+        return;
+    };
+    if let Err(err) = writer.append(|| job_logs_dir(host), job_id, stream, line, Utc::now()) {
+        eprintln!("Job log: {err}");
+    }
+}
+
+/// Appends a status transition to a job's log, closing the file once the job
+/// reaches a final status
+pub fn append_job_status(host: &dyn JobHost, job_id: &str, status: &str, code: Option<i32>) {
+    append_job_log(host, job_id, "status", &status_line(status, code));
+    if matches!(
+        status,
+        "completed" | "error" | "exited" | "cancelled" | "skipped" | "finalization_failed"
+    ) {
+        if let Ok(mut writer) = writer().lock() {
+            writer.close(job_id);
+        }
+    }
+}
+
+/// A job log as listed in the UI
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobLogInfo {
+    pub job_id: String,
+    pub path: String,
+    /// Size including the rotated part
+    pub size_bytes: u64,
+    pub modified_at: Option<DateTime<Utc>>,
+    /// Older lines were rotated to `<job_id>.log.1`
+    pub rotated: bool,
+}
+
+/// Job logs in `logs_dir`, newest first
+fn scan_job_logs(logs_dir: &Path) -> Vec<JobLogInfo> {
+    let Ok(entries) = fs::read_dir(logs_dir) else {
+        return Vec::new();
+    };
+
+    let mut logs: Vec<JobLogInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(LOG_EXTENSION) {
+                return None;
+            }
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            let job_id = path.file_stem()?.to_string_lossy().to_string();
+            let rotated = fs::metadata(rotated_path(&path)).ok();
+            Some(JobLogInfo {
+                job_id,
+                path: path.to_string_lossy().to_string(),
+                size_bytes: metadata.len() + rotated.as_ref().map_or(0, |rotated| rotated.len()),
+                modified_at: metadata.modified().ok().map(DateTime::<Utc>::from),
+                rotated: rotated.is_some(),
+            })
+        })
+        .collect();
+    logs.sort_by_key(|log| std::cmp::Reverse(log.modified_at));
+    logs
+}
+
+/// Deletes logs older than `max_age_days` and all but the newest `max_logs`
+fn prune_job_logs(logs_dir: &Path, now: DateTime<Utc>, max_age_days: i64, max_logs: usize) {
+    let cutoff = now - Duration::days(max_age_days);
+    for (index, log) in scan_job_logs(logs_dir).into_iter().enumerate() {
+        let expired = log.modified_at.is_some_and(|modified| modified < cutoff);
+        if index >= max_logs || expired {
+            let path = PathBuf::from(&log.path);
+            let _ = fs::remove_file(rotated_path(&path));
+            if let Err(err) = fs::remove_file(&path) {
+                eprintln!("Failed to remove old job log {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+fn read_log_file(logs_dir: &Path, job_id: &str) -> Result<String, String> {
+    validate_log_job_id(job_id)?;
+    let path = log_file_path(logs_dir, job_id);
+    if !path.exists() {
+        return Err(format!("No log found for job {}", job_id));
+    }
+
+    let mut text = fs::read_to_string(rotated_path(&path)).unwrap_or_default();
+    text.push_str(
+        &fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+    );
+    Ok(text)
+}
+
+#[tauri::command]
+pub fn list_job_logs(app_handle: AppHandle) -> Result<Vec<JobLogInfo>, String> {
+    Ok(scan_job_logs(&job_logs_dir(&app_handle)?))
+}
+
+/// Returns a job's full log, including the rotated part
+#[tauri::command]
+pub fn read_job_log(app_handle: AppHandle, job_id: String) -> Result<String, String> {
+    read_log_file(&job_logs_dir(&app_handle)?, &job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_logs_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "omnipacker_job_log_{}_{}",
+            name,
+            crate::job_staging::generate_job_id()
+        ))
+    }
+
+    fn timestamp(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_lines_go_to_their_jobs_log() {
+        let dir = temp_logs_dir("append");
+        let now = timestamp("2026-10-17T12:00:00Z");
+        let mut writer = JobLogWriter::new(MAX_LOG_BYTES);
+        let logs_dir = || Ok(dir.clone());
+
+        // Output that doesn't belong to a job has nowhere to go
+        writer.append(logs_dir, "", "7z:stdout", "orphan", now).unwrap();
+        writer.append(logs_dir, "job_a", "system", "Job ID: job_a", now).unwrap();
+        writer.append(logs_dir, "job_b", "stdout", "other job", now).unwrap();
+        writer.append(logs_dir, "job_a", "7z:stdout", "Everything is Ok", now).unwrap();
+        writer.append(logs_dir, "job_b", "7z:stdout", "Testing", now).unwrap();
+        writer.append(logs_dir, "job_a", "status", "completed (0)", now).unwrap();
+        assert_eq!(writer.open.len(), 2);
+
+        assert_eq!(
+            read_log_file(&dir, "job_a").unwrap(),
+            "2026-10-17T12:00:00.000Z [system] Job ID: job_a\n\
+             2026-10-17T12:00:00.000Z [7z:stdout] Everything is Ok\n\
+             2026-10-17T12:00:00.000Z [status] completed (0)\n"
+        );
+        assert_eq!(
+            read_log_file(&dir, "job_b").unwrap(),
+            "2026-10-17T12:00:00.000Z [stdout] other job\n\
+             2026-10-17T12:00:00.000Z [7z:stdout] Testing\n"
+        );
+        assert!(read_log_file(&dir, "job_c").is_err());
+        assert!(read_log_file(&dir, "../job_a").is_err());
+        assert!(writer
+            .append(logs_dir, "../escape", "system", "x", now)
+            .is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_old_logs_are_pruned_when_a_job_starts_its_log() {
+        let dir = temp_logs_dir("start");
+        fs::create_dir_all(&dir).unwrap();
+        let later = Utc::now() + Duration::days(LOG_RETENTION_DAYS + 1);
+        let mut writer = JobLogWriter::new(MAX_LOG_BYTES);
+        let logs_dir = || Ok(dir.clone());
+        let old_log = log_file_path(&dir, "old");
+
+        fs::write(&old_log, "line\n").unwrap();
+        writer.append(logs_dir, "job_a", "system", "start", later).unwrap();
+        assert!(!old_log.exists());
+
+        // Further lines, even after the file was closed, don't sweep again
+        fs::write(&old_log, "line\n").unwrap();
+        writer.append(logs_dir, "job_a", "stdout", "more", later).unwrap();
+        writer.close("job_a");
+        assert!(writer.open.is_empty());
+        writer.append(logs_dir, "job_a", "status", "completed", later).unwrap();
+        assert!(old_log.exists());
+
+        writer.append(logs_dir, "job_b", "system", "start", later).unwrap();
+        assert!(!old_log.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_large_logs_are_rotated_once() {
+        let dir = temp_logs_dir("rotate");
+        let now = timestamp("2026-10-17T12:00:00Z");
+        let mut writer = JobLogWriter::new(64);
+        for index in 0..6 {
+            writer
+                .append(|| Ok(dir.clone()), "job", "stdout", &format!("line {index}"), now)
+                .unwrap();
+        }
+
+        let logs = scan_job_logs(&dir);
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].rotated);
+        let text = read_log_file(&dir, "job").unwrap();
+        assert!(text.ends_with("[stdout] line 5\n"));
+        assert!(!text.contains("line 0"), "the oldest lines were rotated out");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prune_removes_old_and_excess_logs() {
+        let dir = temp_logs_dir("prune");
+        fs::create_dir_all(dir.join("share-safe")).unwrap();
+        for job_id in ["one", "two", "three"] {
+            fs::write(log_file_path(&dir, job_id), "line\n").unwrap();
+        }
+        fs::write(rotated_path(&log_file_path(&dir, "one")), "older\n").unwrap();
+
+        let now = Utc::now();
+        prune_job_logs(&dir, now, 30, 10);
+        assert_eq!(scan_job_logs(&dir).len(), 3);
+
+        prune_job_logs(&dir, now, 30, 1);
+        assert_eq!(scan_job_logs(&dir).len(), 1);
+
+        prune_job_logs(&dir, now + Duration::days(31), 30, 10);
+        assert!(scan_job_logs(&dir).is_empty());
+        assert!(!rotated_path(&log_file_path(&dir, "one")).exists());
+        assert!(dir.join("share-safe").is_dir(), "other files are left alone");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -38,7 +38,7 @@ pub struct DepotInfo {
 /// - Crash safety
 /// - Reproducible finalization
 ///
-/// NOT for resume support.
+/// Resume matching uses staging.json (see `job_staging::StagingInfo`), not this file.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct JobMetadataFile {
     /// Unique job identifier (matches staging directory name)
@@ -67,10 +67,20 @@ pub struct JobMetadataFile {
     /// Metadata schema version
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata_version: Option<String>,
+    /// Steam language of language-specific depots (None = English)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Low-violence depots were selected
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub low_violence: bool,
+    /// Log file of the job that produced this build (`logs/<job_id>.log`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_path: Option<String>,
 }
 
 impl JobMetadataFile {
     /// Creates a new JobMetadataFile with required fields
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         job_id: String,
         appid: String,
@@ -96,6 +106,9 @@ impl JobMetadataFile {
             depots,
             appinfo_fetched_at: Utc::now(),
             metadata_version: Some(METADATA_VERSION.to_string()),
+            language: None,
+            low_violence: false,
+            log_path: None,
         }
     }
 
@@ -0,0 +1,672 @@
+//! Backend-owned download queue
+//!
+//! Queued jobs are kept in memory with their secrets and mirrored to
+//! `downloads/queue.json` without them (see `JobMetadata`), so the queue
+//! survives crashes and restarts. A dispatcher thread feeds entries to the
+//! depot runner one at a time. Only the newest `MAX_FINISHED_ENTRIES` finished
+//! entries are kept.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::accounts::apply_login;
+use crate::depot_runner::{
+    begin_job, resolve_auth_cache_dir, run_job_pipeline, DepotRunnerState, JobMetadata,
+};
+use crate::job_host::{JobHost, SharedJobHost};
+use crate::job_staging::generate_job_id;
+use crate::login_store::LoginData;
+use crate::redaction::redact_log_line;
+use crate::zip_runner::SevenZipRunnerState;
+
+pub const QUEUE_FILE_NAME: &str = "queue.json";
+
+/// How long the dispatcher waits before re-checking when the runner is busy
+const DISPATCH_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Done and failed entries kept for review before the oldest are dropped
+const MAX_FINISHED_ENTRIES: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueEntryStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueEntry {
+    pub id: String,
+    pub job: JobMetadata,
+    /// Username of the job's one-off login, which the job itself doesn't persist.
+    /// Its password is kept in memory only.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+    pub status: QueueEntryStatus,
+    /// Runner job ID of the most recent attempt
+    #[serde(default)]
+    pub job_id: Option<String>,
+    /// Final outputs, one per platform
+    #[serde(default)]
+    pub output_paths: Vec<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// Serialized form of the queue, used both for queue.json and `queue:changed` events
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSnapshot {
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub entries: Vec<QueueEntry>,
+}
+
+#[derive(Debug, Default)]
+struct JobQueue {
+    entries: Vec<QueueEntry>,
+    paused: bool,
+}
+
+impl JobQueue {
+    /// Rebuilds a queue from disk. Entries that were running when the app
+    /// stopped are put back in line and continue their interrupted download.
+    fn from_snapshot(snapshot: QueueSnapshot) -> Self {
+        let mut entries = snapshot.entries;
+        for entry in entries.iter_mut() {
+            entry.job.username = entry.username.clone();
+            if entry.status == QueueEntryStatus::Running {
+                entry.status = QueueEntryStatus::Queued;
+                entry.job_id = None;
+                entry.job.resume = true;
+            }
+        }
+        Self {
+            entries,
+            paused: snapshot.paused,
+        }
+    }
+
+    fn snapshot(&self) -> QueueSnapshot {
+        QueueSnapshot {
+            paused: self.paused,
+            entries: self.entries.clone(),
+        }
+    }
+
+    fn enqueue(&mut self, mut job: JobMetadata) -> String {
+        // An enabled-but-empty compression password means "no password"; normalize it
+        // so a missing secret after a restart can be told apart from an intentional blank.
+        if job.compression_password_enabled && job.compression_password.trim().is_empty() {
+            job.compression_password_enabled = false;
+            job.compression_password.clear();
+        }
+
+        let id = generate_job_id();
+        self.entries.push(QueueEntry {
+            id: id.clone(),
+            username: job.username.clone(),
+            job,
+            status: QueueEntryStatus::Queued,
+            job_id: None,
+            output_paths: Vec::new(),
+            error: None,
+            added_at: Utc::now(),
+        });
+        id
+    }
+
+    fn position(&self, entry_id: &str) -> Result<usize, String> {
+        self.entries
+            .iter()
+            .position(|entry| entry.id == entry_id)
+            .ok_or_else(|| format!("Queue entry not found: {}", entry_id))
+    }
+
+    fn move_entry(&mut self, entry_id: &str, index: usize) -> Result<(), String> {
+        let from = self.position(entry_id)?;
+        let entry = self.entries.remove(from);
+        let to = index.min(self.entries.len());
+        self.entries.insert(to, entry);
+        Ok(())
+    }
+
+    fn remove(&mut self, entry_id: &str) -> Result<(), String> {
+        let index = self.position(entry_id)?;
+        if self.entries[index].status == QueueEntryStatus::Running {
+            return Err("Cannot remove a running job. Cancel it first.".to_string());
+        }
+        self.entries.remove(index);
+        Ok(())
+    }
+
+    /// Puts a failed entry back in line, continuing its interrupted download
+    fn retry(&mut self, entry_id: &str) -> Result<(), String> {
+        let index = self.position(entry_id)?;
+        let entry = &mut self.entries[index];
+        if entry.status != QueueEntryStatus::Failed {
+            return Err("Only failed jobs can be retried.".to_string());
+        }
+        entry.status = QueueEntryStatus::Queued;
+        entry.job_id = None;
+        entry.error = None;
+        entry.job.resume = true;
+        Ok(())
+    }
+
+    /// Removes every entry except the running one
+    fn clear(&mut self) {
+        self.entries
+            .retain(|entry| entry.status == QueueEntryStatus::Running);
+    }
+
+    /// Drops the oldest finished entries beyond `MAX_FINISHED_ENTRIES`
+    fn prune_finished(&mut self) {
+        let is_finished = |entry: &QueueEntry| {
+            matches!(
+                entry.status,
+                QueueEntryStatus::Done | QueueEntryStatus::Failed
+            )
+        };
+        let mut excess = self
+            .entries
+            .iter()
+            .filter(|entry| is_finished(entry))
+            .count()
+            .saturating_sub(MAX_FINISHED_ENTRIES);
+        self.entries.retain(|entry| {
+            if excess > 0 && is_finished(entry) {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Returns the index of the next entry to run, if the queue is active
+    fn next_queued(&self) -> Option<usize> {
+        if self.paused
+            || self
+                .entries
+                .iter()
+                .any(|entry| entry.status == QueueEntryStatus::Running)
+        {
+            return None;
+        }
+        self.entries
+            .iter()
+            .position(|entry| entry.status == QueueEntryStatus::Queued)
+    }
+
+    fn finish(&mut self, entry_id: &str, result: &Result<Vec<PathBuf>, String>) {
+        let Ok(index) = self.position(entry_id) else {
+            return;
+        };
+        let entry = &mut self.entries[index];
+        match result {
+            Ok(paths) => {
+                entry.status = QueueEntryStatus::Done;
+                entry.output_paths = paths
+                    .iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect();
+                entry.error = None;
+            }
+            Err(err) => {
+                entry.status = QueueEntryStatus::Failed;
+                entry.error = Some(redact_log_line(err).into_owned());
+            }
+        }
+        self.prune_finished();
+    }
+}
+
+/// Checks that a queued job still has everything it needs to run.
+/// Secrets are not persisted, so entries restored after a restart may be missing them;
+/// a login without its password still works when DepotDownloader cached it (`auth_cached`).
+fn missing_secret_error(job: &JobMetadata, auth_cached: &dyn Fn(&str) -> bool) -> Option<String> {
+    if job.compression_password_enabled && job.compression_password.is_empty() {
+        return Some(
+            "Compression password is not stored on disk. Remove and re-add this job to the queue."
+                .to_string(),
+        );
+    }
+    let username = job.username.trim();
+    if !job.qr_enabled && !username.is_empty() && job.password.is_empty() && !auth_cached(username)
+    {
+        return Some(format!(
+            "Needs credentials: the Steam password for {} is not stored on disk and there is no cached login. Remove and re-add this job to the queue.",
+            username
+        ));
+    }
+    None
+}
+
+#[derive(Clone)]
+pub struct JobQueueState {
+    inner: Arc<(Mutex<JobQueue>, Condvar)>,
+    path: Option<PathBuf>,
+}
+
+impl JobQueueState {
+    /// Loads the queue from `path`, or starts empty when the file is missing or unreadable.
+    /// With no path the queue is kept in memory only.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let queue = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| match fs::read_to_string(path) {
+                Ok(content) => match serde_json::from_str::<QueueSnapshot>(&content) {
+                    Ok(snapshot) => Some(JobQueue::from_snapshot(snapshot)),
+                    Err(err) => {
+                        eprintln!("Failed to parse {}: {err}", path.display());
+                        None
+                    }
+                },
+                Err(err) => {
+                    eprintln!("Failed to read {}: {err}", path.display());
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            inner: Arc::new((Mutex::new(queue), Condvar::new())),
+            path,
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, JobQueue>, String> {
+        self.inner
+            .0
+            .lock()
+            .map_err(|_| "Failed to lock job queue".to_string())
+    }
+
+    fn persist(&self, queue: &JobQueue) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(&queue.snapshot())
+            .map_err(|e| format!("Failed to serialize queue: {}", e))
+            .and_then(|json| {
+                let temp_path = path.with_extension("json.tmp");
+                fs::write(&temp_path, json)
+                    .and_then(|_| fs::rename(&temp_path, path))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+            });
+        if let Err(err) = result {
+            eprintln!("{err}");
+        }
+    }
+
+    /// Applies a change, persists the queue and wakes the dispatcher
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut JobQueue) -> Result<T, String>,
+    ) -> Result<(T, QueueSnapshot), String> {
+        let mut guard = self.lock()?;
+        let value = change(&mut guard)?;
+        self.persist(&guard);
+        let snapshot = guard.snapshot();
+        drop(guard);
+        self.inner.1.notify_all();
+        Ok((value, snapshot))
+    }
+
+    pub fn snapshot(&self) -> Result<QueueSnapshot, String> {
+        Ok(self.lock()?.snapshot())
+    }
+
+    /// Adds a job to the end of the queue and notifies the frontend.
+    /// Returns the new entry's ID.
+    pub fn enqueue(&self, app_handle: &AppHandle, job: JobMetadata) -> Result<String, String> {
+        let (entry_id, snapshot) = self.update(|queue| Ok(queue.enqueue(job)))?;
+        emit_queue_changed(app_handle, &snapshot);
+        Ok(entry_id)
+    }
+
+    /// Blocks until an entry can be started, reserves a runner job for it and marks it running.
+    /// Returns (entry ID, job, runner job ID).
+    fn wait_for_next(
+        &self,
+        runner: &DepotRunnerState,
+        app_handle: &AppHandle,
+    ) -> (String, JobMetadata, String) {
+        let mut guard = match self.inner.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        loop {
+            if let Some(index) = guard.next_queued() {
+                let auth_cached = |username: &str| {
+                    resolve_auth_cache_dir(app_handle, username)
+                        .map(|dir| dir.is_dir())
+                        .unwrap_or(false)
+                };
+                if let Some(err) = missing_secret_error(&guard.entries[index].job, &auth_cached) {
+                    guard.entries[index].status = QueueEntryStatus::Failed;
+                    guard.entries[index].error = Some(err);
+                    self.persist(&guard);
+                    emit_queue_changed(app_handle, &guard.snapshot());
+                    continue;
+                }
+
+                // The runner may be busy with a job started outside the queue
+                if let Ok(job_id) = begin_job(runner) {
+                    let entry = &mut guard.entries[index];
+                    entry.status = QueueEntryStatus::Running;
+                    entry.job_id = Some(job_id.clone());
+                    entry.output_paths.clear();
+                    entry.error = None;
+                    let claimed = (entry.id.clone(), entry.job.clone(), job_id);
+                    self.persist(&guard);
+                    emit_queue_changed(app_handle, &guard.snapshot());
+                    return claimed;
+                }
+
+                guard = match self.inner.1.wait_timeout(guard, DISPATCH_RETRY_INTERVAL) {
+                    Ok((guard, _)) => guard,
+                    Err(poisoned) => poisoned.into_inner().0,
+                };
+                continue;
+            }
+
+            guard = match self.inner.1.wait(guard) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+    }
+}
+
+fn emit_queue_changed(app_handle: &AppHandle, snapshot: &QueueSnapshot) {
+    let _ = app_handle.emit("queue:changed", snapshot);
+}
+
+/// Resolves downloads/queue.json
+pub fn resolve_queue_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.downloads_dir()?.join(QUEUE_FILE_NAME))
+}
+
+/// Starts the background thread that runs queued jobs in order
+pub fn spawn_queue_dispatcher(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let queue = app_handle.state::<JobQueueState>().inner().clone();
+        let runner = app_handle.state::<DepotRunnerState>().inner().clone();
+        let zip_state = app_handle.state::<SevenZipRunnerState>().inner().clone();
+        let host: SharedJobHost = Arc::new(app_handle.clone());
+
+        loop {
+            let (entry_id, job, job_id) = queue.wait_for_next(&runner, &app_handle);
+
+            host.emit_status("starting", None, &job_id);
+            let result = run_job_pipeline(&host, &runner, &zip_state, &job, &job_id);
+
+            if let Ok((_, snapshot)) = queue.update(|queue| {
+                queue.finish(&entry_id, &result);
+                Ok(())
+            }) {
+                emit_queue_changed(&app_handle, &snapshot);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_job_queue(state: State<'_, JobQueueState>) -> Result<QueueSnapshot, String> {
+    state.snapshot()
+}
+
+#[tauri::command]
+pub fn enqueue_job(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+    mut job: JobMetadata,
+    login: Option<LoginData>,
+) -> Result<String, String> {
+    apply_login(&mut job, login)?;
+    state.enqueue(&app_handle, job)
+}
+
+#[tauri::command]
+pub fn move_queue_entry(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+    entry_id: String,
+    index: usize,
+) -> Result<(), String> {
+    let (_, snapshot) = state.update(|queue| queue.move_entry(&entry_id, index))?;
+    emit_queue_changed(&app_handle, &snapshot);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_queue_entry(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+    entry_id: String,
+) -> Result<(), String> {
+    let (_, snapshot) = state.update(|queue| queue.remove(&entry_id))?;
+    emit_queue_changed(&app_handle, &snapshot);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn retry_queue_entry(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+    entry_id: String,
+) -> Result<(), String> {
+    let (_, snapshot) = state.update(|queue| queue.retry(&entry_id))?;
+    emit_queue_changed(&app_handle, &snapshot);
+    Ok(())
+}
+
+/// Removes all queued and finished entries; a running job is left alone
+#[tauri::command]
+pub fn clear_job_queue(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+) -> Result<(), String> {
+    let (_, snapshot) = state.update(|queue| {
+        queue.clear();
+        Ok(())
+    })?;
+    emit_queue_changed(&app_handle, &snapshot);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_job_queue(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+) -> Result<(), String> {
+    let (_, snapshot) = state.update(|queue| {
+        queue.paused = true;
+        Ok(())
+    })?;
+    emit_queue_changed(&app_handle, &snapshot);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_job_queue(
+    app_handle: AppHandle,
+    state: State<'_, JobQueueState>,
+) -> Result<(), String> {
+    let (_, snapshot) = state.update(|queue| {
+        queue.paused = false;
+        Ok(())
+    })?;
+    emit_queue_changed(&app_handle, &snapshot);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(app_id: &str) -> JobMetadata {
+        JobMetadata {
+            app_id: app_id.to_string(),
+            os: "Windows x64".to_string(),
+            branch: "public".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn app_ids(queue: &JobQueue) -> Vec<&str> {
+        queue
+            .entries
+            .iter()
+            .map(|entry| entry.job.app_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_move_and_remove_entries() {
+        let mut queue = JobQueue::default();
+        let first = queue.enqueue(job("10"));
+        queue.enqueue(job("20"));
+        let third = queue.enqueue(job("30"));
+
+        queue.move_entry(&third, 0).unwrap();
+        assert_eq!(app_ids(&queue), vec!["30", "10", "20"]);
+
+        queue.move_entry(&third, 99).unwrap();
+        assert_eq!(app_ids(&queue), vec!["10", "20", "30"]);
+
+        queue.remove(&first).unwrap();
+        assert_eq!(app_ids(&queue), vec!["20", "30"]);
+        assert!(queue.remove(&first).is_err());
+    }
+
+    #[test]
+    fn test_next_queued_respects_pause_and_running() {
+        let mut queue = JobQueue::default();
+        queue.enqueue(job("10"));
+        queue.enqueue(job("20"));
+        assert_eq!(queue.next_queued(), Some(0));
+
+        queue.paused = true;
+        assert_eq!(queue.next_queued(), None);
+        queue.paused = false;
+
+        queue.entries[0].status = QueueEntryStatus::Running;
+        assert_eq!(queue.next_queued(), None);
+        assert!(queue.remove(&queue.entries[0].id.clone()).is_err());
+
+        queue.entries[0].status = QueueEntryStatus::Done;
+        assert_eq!(queue.next_queued(), Some(1));
+    }
+
+    #[test]
+    fn test_snapshot_omits_secrets_and_restores_running_as_queued() {
+        let mut queue = JobQueue::default();
+        let mut secret_job = job("10");
+        secret_job.username = "user".to_string();
+        secret_job.password = "hunter2".to_string();
+        secret_job.compression_password_enabled = true;
+        secret_job.compression_password = "archive-secret".to_string();
+        queue.enqueue(secret_job);
+        queue.entries[0].status = QueueEntryStatus::Running;
+        queue.entries[0].job_id = Some("job".to_string());
+
+        let json = serde_json::to_string(&queue.snapshot()).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert!(!json.contains("archive-secret"));
+
+        let restored = JobQueue::from_snapshot(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.entries[0].status, QueueEntryStatus::Queued);
+        assert_eq!(restored.entries[0].job_id, None);
+        assert!(restored.entries[0].job.resume);
+        assert_eq!(restored.entries[0].job.username, "user");
+        assert!(restored.entries[0].job.password.is_empty());
+        assert!(missing_secret_error(&restored.entries[0].job, &|_| true).is_some());
+    }
+
+    #[test]
+    fn test_restored_login_needs_password_or_cached_login() {
+        let mut queue = JobQueue::default();
+        let mut login_job = job("10");
+        login_job.username = "user".to_string();
+        login_job.password = "hunter2".to_string();
+        queue.enqueue(login_job);
+        queue.enqueue(job("20"));
+        let no_cache = |_: &str| false;
+        assert!(missing_secret_error(&queue.entries[0].job, &no_cache).is_none());
+
+        let json = serde_json::to_string(&queue.snapshot()).unwrap();
+        let restored = JobQueue::from_snapshot(serde_json::from_str(&json).unwrap());
+        let error = missing_secret_error(&restored.entries[0].job, &no_cache).unwrap();
+        assert!(error.starts_with("Needs credentials"));
+        let cached = |username: &str| username == "user";
+        assert!(missing_secret_error(&restored.entries[0].job, &cached).is_none());
+        // Anonymous jobs need nothing
+        assert!(missing_secret_error(&restored.entries[1].job, &no_cache).is_none());
+    }
+
+    #[test]
+    fn test_retry_and_clear_entries() {
+        let mut queue = JobQueue::default();
+        let failed = queue.enqueue(job("10"));
+        let running = queue.enqueue(job("20"));
+        queue.enqueue(job("30"));
+        queue.finish(
+            &failed,
+            &Err("DepotDownloader exited with code 1".to_string()),
+        );
+        queue.entries[1].status = QueueEntryStatus::Running;
+
+        assert!(queue.retry(&running).is_err());
+        queue.retry(&failed).unwrap();
+        assert_eq!(queue.entries[0].status, QueueEntryStatus::Queued);
+        assert_eq!(queue.entries[0].error, None);
+        assert!(queue.entries[0].job.resume);
+
+        queue.clear();
+        assert_eq!(app_ids(&queue), vec!["20"]);
+    }
+
+    #[test]
+    fn test_finished_entries_are_pruned_oldest_first() {
+        let mut queue = JobQueue::default();
+        let ids: Vec<String> = (0..MAX_FINISHED_ENTRIES + 2)
+            .map(|index| queue.enqueue(job(&index.to_string())))
+            .collect();
+        let waiting = queue.enqueue(job("waiting"));
+        for id in &ids {
+            queue.finish(id, &Ok(Vec::new()));
+        }
+
+        assert_eq!(queue.entries.len(), MAX_FINISHED_ENTRIES + 1);
+        assert_eq!(queue.entries[0].job.app_id, "2");
+        assert!(queue.position(&waiting).is_ok());
+    }
+
+    #[test]
+    fn test_enqueue_normalizes_blank_compression_password() {
+        let mut queue = JobQueue::default();
+        let mut blank = job("10");
+        blank.compression_password_enabled = true;
+        blank.compression_password = "  ".to_string();
+        queue.enqueue(blank);
+
+        assert!(!queue.entries[0].job.compression_password_enabled);
+        assert!(missing_secret_error(&queue.entries[0].job, &|_| false).is_none());
+    }
+}
@@ -1,10 +1,112 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::depot_manifest::read_depot_manifest;
+use crate::depot_runner::{DepotManifestPin, JobMetadata};
+use crate::job_host::JobHost;
+
+/// Name of the file describing what a staging directory is downloading
+pub const STAGING_INFO_FILE_NAME: &str = "staging.json";
+
+/// Interrupted staging directories older than this are considered abandoned
+pub const STAGING_RETENTION_DAYS: i64 = 7;
+
+/// Identity of the download held in a staging directory, used to match
+/// interrupted downloads to new jobs for resume.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagingInfo {
+    pub app_id: String,
+    pub branch: String,
+    pub os: String,
+    #[serde(default)]
+    pub manifest_pins: Vec<DepotManifestPin>,
+    #[serde(default)]
+    pub depot_ids: Vec<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub low_violence: bool,
+    pub created_at: DateTime<Utc>,
+    /// Last time a job ran against this staging directory
+    pub updated_at: DateTime<Utc>,
+}
 
-use chrono::Utc;
-use tauri::AppHandle;
+impl StagingInfo {
+    pub fn new(job: &JobMetadata) -> Self {
+        let now = Utc::now();
+        Self {
+            app_id: job.app_id.clone(),
+            branch: job.branch.clone(),
+            os: job.os.clone(),
+            manifest_pins: job.manifest_pins.clone(),
+            depot_ids: job.depot_ids.clone(),
+            language: job.language(),
+            low_violence: job.low_violence,
+            created_at: now,
+            updated_at: now,
+        }
+    }
 
-use crate::output_dir::resolve_downloads_dir;
+    /// Returns true when this staging directory holds the same download as `job`
+    pub fn matches(&self, job: &JobMetadata) -> bool {
+        let sorted_pins = |pins: &[DepotManifestPin]| {
+            let mut pins = pins.to_vec();
+            pins.sort_by(|a, b| a.depot_id.cmp(&b.depot_id));
+            pins
+        };
+        let sorted_ids = |ids: &[String]| {
+            let mut ids = ids.to_vec();
+            ids.sort();
+            ids
+        };
+        self.app_id == job.app_id
+            && self.branch.eq_ignore_ascii_case(&job.branch)
+            && self.os == job.os
+            && sorted_pins(&self.manifest_pins) == sorted_pins(&job.manifest_pins)
+            && sorted_ids(&self.depot_ids) == sorted_ids(&job.depot_ids)
+            && self.language == job.language()
+            && self.low_violence == job.low_violence
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now - self.updated_at > Duration::days(STAGING_RETENTION_DAYS)
+    }
+
+    /// Writes staging.json to the specified directory
+    pub fn write_to_dir(&self, staging_dir: &Path) -> Result<(), String> {
+        let path = staging_dir.join(STAGING_INFO_FILE_NAME);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize staging info: {}", e))?;
+        fs::write(&path, json)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Reads staging.json from the specified directory
+    pub fn read_from_dir(staging_dir: &Path) -> Result<Self, String> {
+        let path = staging_dir.join(STAGING_INFO_FILE_NAME);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+}
+
+/// An interrupted download that can be continued
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumableStaging {
+    /// Staging directory name (the job ID of the interrupted run)
+    pub staging_id: String,
+    pub path: PathBuf,
+    pub info: StagingInfo,
+    /// Bytes already downloaded
+    pub size_bytes: u64,
+}
 
 /// Generates a unique job ID in the format: <ISO8601_UTC_timestamp>_<short_unique_id>
 /// Example: 2026-01-05T11-30-02Z_a1b2c3
@@ -15,18 +117,28 @@ pub fn generate_job_id() -> String {
 }
 
 /// Generates a short 6-character alphanumeric ID
+///
+/// IDs count up from a per-process starting point, so IDs generated by one
+/// process never repeat (within 36^6 calls) however quickly they are requested.
 fn generate_short_id() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::OnceLock;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    let nanos = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_nanos())
-        .unwrap_or(0);
+    static START: OnceLock<u64> = OnceLock::new();
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
 
-    // Use process ID and nanoseconds for uniqueness
-    let seed = nanos ^ (std::process::id() as u128);
+    let start = *START.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        // Use process ID and nanoseconds so separate runs start far apart
+        (nanos ^ (std::process::id() as u128)) as u64
+    });
+    let seed = start.wrapping_add(COUNTER.fetch_add(1, Ordering::Relaxed)) % 36u64.pow(6);
 
-    // Convert to base36 (0-9, a-z) and take last 6 chars
+    // Convert to base36 (0-9, a-z), 6 chars
     let chars: Vec<char> = "0123456789abcdefghijklmnopqrstuvwxyz".chars().collect();
     let mut result = String::new();
     let mut n = seed;
@@ -41,15 +153,22 @@ fn generate_short_id() -> String {
 
 /// Resolves the staging directory path for a job
 /// Returns: downloads/staging/<job_id>/
-pub fn resolve_staging_dir(app_handle: &AppHandle, job_id: &str) -> Result<PathBuf, String> {
-    let downloads_dir = resolve_downloads_dir(app_handle)?;
+pub fn resolve_staging_dir(host: &dyn JobHost, job_id: &str) -> Result<PathBuf, String> {
+    if job_id.is_empty()
+        || job_id.contains(['/', '\\'])
+        || job_id == "."
+        || job_id == ".."
+    {
+        return Err(format!("Invalid job ID: {}", job_id));
+    }
+    let downloads_dir = host.downloads_dir()?;
     Ok(downloads_dir.join("staging").join(job_id))
 }
 
 /// Creates the staging directory for a job
 /// Returns the path to the created staging directory
-pub fn create_staging_dir(app_handle: &AppHandle, job_id: &str) -> Result<PathBuf, String> {
-    let staging_dir = resolve_staging_dir(app_handle, job_id)?;
+pub fn create_staging_dir(host: &dyn JobHost, job_id: &str) -> Result<PathBuf, String> {
+    let staging_dir = resolve_staging_dir(host, job_id)?;
 
     if staging_dir.exists() {
         return Err(format!(
@@ -70,8 +189,8 @@ pub fn create_staging_dir(app_handle: &AppHandle, job_id: &str) -> Result<PathBu
 }
 
 /// Deletes the staging directory for a job (used on failure)
-pub fn cleanup_staging_dir(app_handle: &AppHandle, job_id: &str) -> Result<(), String> {
-    let staging_dir = resolve_staging_dir(app_handle, job_id)?;
+pub fn cleanup_staging_dir(host: &dyn JobHost, job_id: &str) -> Result<(), String> {
+    let staging_dir = resolve_staging_dir(host, job_id)?;
 
     if !staging_dir.exists() {
         // Already cleaned up or never created
@@ -89,9 +208,204 @@ pub fn cleanup_staging_dir(app_handle: &AppHandle, job_id: &str) -> Result<(), S
     Ok(())
 }
 
-/// Deletes any orphaned staging directories left behind by interrupted runs.
-pub fn cleanup_orphaned_staging(app_handle: &AppHandle) -> Result<usize, String> {
-    let downloads_dir = resolve_downloads_dir(app_handle)?;
+/// Returns true when DepotDownloader has written any depot content to the staging directory
+pub fn has_depot_content(staging_dir: &Path) -> bool {
+    fs::read_dir(staging_dir.join("depots"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .any(|entry| entry.path().is_dir())
+        })
+        .unwrap_or(false)
+}
+
+/// A staging directory can be resumed when it describes its download and holds content
+fn read_resumable_info(staging_dir: &Path) -> Option<StagingInfo> {
+    if !has_depot_content(staging_dir) {
+        return None;
+    }
+    StagingInfo::read_from_dir(staging_dir).ok()
+}
+
+/// Total size of the files below a directory
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Finds the most recent interrupted download of the same app, branch, OS and pins.
+/// `exclude` skips a staging directory that is currently in use.
+pub fn find_resumable_staging(
+    host: &dyn JobHost,
+    job: &JobMetadata,
+    exclude: Option<&str>,
+) -> Result<Option<ResumableStaging>, String> {
+    let staging_root = host.downloads_dir()?.join("staging");
+    if !staging_root.exists() {
+        return Ok(None);
+    }
+
+    let entries = fs::read_dir(&staging_root).map_err(|err| {
+        format!(
+            "Failed to read staging directory {}: {}",
+            staging_root.display(),
+            err
+        )
+    })?;
+
+    let now = Utc::now();
+    let best = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let staging_id = entry.file_name().to_string_lossy().to_string();
+            if exclude == Some(staging_id.as_str()) {
+                return None;
+            }
+            let path = entry.path();
+            let info = read_resumable_info(&path)?;
+            if !info.matches(job) || info.is_expired(now) {
+                return None;
+            }
+            Some((staging_id, path, info))
+        })
+        .max_by_key(|(_, _, info)| info.updated_at);
+
+    Ok(best.map(|(staging_id, path, info)| ResumableStaging {
+        size_bytes: dir_size(&path),
+        staging_id,
+        path,
+        info,
+    }))
+}
+
+/// Moves an interrupted staging directory under a new job ID so the new run
+/// continues from its content. Returns the new staging directory.
+pub fn adopt_staging_dir(
+    host: &dyn JobHost,
+    staging_id: &str,
+    job_id: &str,
+) -> Result<PathBuf, String> {
+    let from = resolve_staging_dir(host, staging_id)?;
+    let to = resolve_staging_dir(host, job_id)?;
+
+    if to.exists() {
+        return Err(format!(
+            "Staging directory already exists: {}",
+            to.display()
+        ));
+    }
+
+    fs::rename(&from, &to).map_err(|err| {
+        format!(
+            "Failed to move staging directory {} to {}: {}",
+            from.display(),
+            to.display(),
+            err
+        )
+    })?;
+
+    Ok(to)
+}
+
+/// After a resumed download, removes manifest directories left over from an
+/// older build so each depot holds exactly one. `resolved` maps depot IDs to the
+/// manifest ID this run downloaded; the directory whose saved manifest carries
+/// that ID is kept. Depots without a resolved ID, or where no directory matches,
+/// are left untouched. Returns the number of directories removed.
+pub fn prune_superseded_manifest_dirs(
+    staging_dir: &Path,
+    resolved: &HashMap<String, String>,
+) -> Result<usize, String> {
+    let depots_dir = staging_dir.join("depots");
+    let depot_entries = fs::read_dir(&depots_dir)
+        .map_err(|e| format!("Failed to read depots directory: {}", e))?;
+
+    let mut removed = 0usize;
+    for depot_entry in depot_entries.filter_map(|entry| entry.ok()) {
+        let depot_path = depot_entry.path();
+        if !depot_path.is_dir() || depot_entry.file_name() == ".DepotDownloader" {
+            continue;
+        }
+        let depot_id = depot_entry.file_name().to_string_lossy().to_string();
+        let Some(manifest_id) = resolved.get(&depot_id) else {
+            continue;
+        };
+
+        let manifest_dirs: Vec<PathBuf> = fs::read_dir(&depot_path)
+            .map_err(|e| format!("Failed to read depot {}: {}", depot_path.display(), e))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+
+        if manifest_dirs.len() < 2
+            || !manifest_dirs
+                .iter()
+                .any(|dir| saved_manifest_ids(dir).contains(manifest_id))
+        {
+            continue;
+        }
+
+        for path in manifest_dirs
+            .into_iter()
+            .filter(|dir| !saved_manifest_ids(dir).contains(manifest_id))
+        {
+            fs::remove_dir_all(&path).map_err(|err| {
+                format!(
+                    "Failed to remove superseded manifest directory {}: {}",
+                    path.display(),
+                    err
+                )
+            })?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Manifest IDs of the manifests DepotDownloader saved in a download directory
+fn saved_manifest_ids(download_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(download_dir.join(".DepotDownloader")) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "manifest"))
+        .filter_map(|path| read_depot_manifest(&path).ok())
+        .filter_map(|manifest| manifest.manifest_id)
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// Returns true when a staging entry cannot or should no longer be resumed
+fn is_abandoned_staging(path: &Path, now: DateTime<Utc>) -> bool {
+    if !path.is_dir() {
+        return true;
+    }
+    match read_resumable_info(path) {
+        Some(info) => info.is_expired(now),
+        None => true,
+    }
+}
+
+/// Deletes abandoned staging entries left behind by interrupted runs.
+///
+/// Interrupted downloads that still describe themselves (staging.json), hold
+/// depot content and are newer than `STAGING_RETENTION_DAYS` are kept so they
+/// can be resumed; everything else is removed.
+pub fn cleanup_orphaned_staging(host: &dyn JobHost) -> Result<usize, String> {
+    let downloads_dir = host.downloads_dir()?;
     let staging_root = downloads_dir.join("staging");
 
     if !staging_root.exists() {
@@ -106,6 +420,7 @@ pub fn cleanup_orphaned_staging(app_handle: &AppHandle) -> Result<usize, String>
         )
     })?;
 
+    let now = Utc::now();
     let mut removed = 0usize;
     let mut errors = Vec::new();
 
@@ -118,6 +433,9 @@ pub fn cleanup_orphaned_staging(app_handle: &AppHandle) -> Result<usize, String>
             }
         };
         let path = entry.path();
+        if !is_abandoned_staging(&path, now) {
+            continue;
+        }
         if path.is_dir() {
             match fs::remove_dir_all(&path) {
                 Ok(()) => removed += 1,
@@ -173,12 +491,118 @@ mod tests {
 
     #[test]
     fn test_generate_short_id_uniqueness() {
-        let id1 = generate_short_id();
-        // Small delay to ensure different nanosecond values
-        std::thread::sleep(std::time::Duration::from_millis(1));
-        let id2 = generate_short_id();
+        let ids: std::collections::HashSet<String> =
+            (0..1000).map(|_| generate_short_id()).collect();
+        assert_eq!(ids.len(), 1000, "Generated IDs should be unique");
+    }
 
-        // IDs should be different (high probability)
-        assert_ne!(id1, id2, "Generated IDs should be unique");
+    fn temp_staging_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "omnipacker_staging_{}_{}",
+            name,
+            generate_short_id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_staging_info_matching_and_expiry() {
+        let job = |branch: &str, os: &str| JobMetadata {
+            app_id: "730".to_string(),
+            branch: branch.to_string(),
+            os: os.to_string(),
+            ..Default::default()
+        };
+        let mut info = StagingInfo::new(&job("public", "Windows x64"));
+        assert!(info.matches(&job("Public", "Windows x64")));
+        assert!(!info.matches(&job("beta", "Windows x64")));
+        assert!(!info.matches(&job("public", "Linux")));
+
+        let mut pinned = job("public", "Windows x64");
+        pinned.manifest_pins.push(DepotManifestPin {
+            depot_id: "731".to_string(),
+            manifest_id: "123".to_string(),
+        });
+        assert!(!info.matches(&pinned));
+
+        let now = Utc::now();
+        assert!(!info.is_expired(now));
+        info.updated_at = now - Duration::days(STAGING_RETENTION_DAYS + 1);
+        assert!(info.is_expired(now));
+    }
+
+    #[test]
+    fn test_abandoned_staging_detection() {
+        let now = Utc::now();
+
+        // No staging.json: cannot be matched to a job
+        let unknown = temp_staging_dir("unknown");
+        fs::create_dir_all(unknown.join("depots").join("731")).unwrap();
+        assert!(is_abandoned_staging(&unknown, now));
+
+        // Described but nothing downloaded yet
+        let empty = temp_staging_dir("empty");
+        StagingInfo::new(&JobMetadata {
+            app_id: "730".to_string(),
+            branch: "public".to_string(),
+            os: "Linux".to_string(),
+            ..Default::default()
+        })
+            .write_to_dir(&empty)
+            .unwrap();
+        assert!(is_abandoned_staging(&empty, now));
+
+        // Described with content: resumable
+        fs::create_dir_all(empty.join("depots").join("731").join("123")).unwrap();
+        assert!(!is_abandoned_staging(&empty, now));
+
+        let _ = fs::remove_dir_all(&unknown);
+        let _ = fs::remove_dir_all(&empty);
+    }
+
+    #[test]
+    fn test_prune_superseded_manifest_dirs() {
+        use crate::depot_manifest::test_support::manifest_with_id;
+
+        let staging = temp_staging_dir("prune");
+        let save_manifest = |depot_id: u32, build: &str, manifest_id: u64| {
+            let dir = staging
+                .join("depots")
+                .join(depot_id.to_string())
+                .join(build)
+                .join(".DepotDownloader");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join(format!("{}_{}.manifest", depot_id, manifest_id)),
+                manifest_with_id(depot_id, manifest_id, 0, &[]),
+            )
+            .unwrap();
+        };
+        // The directory resolved by this run is kept even when it is not the newest
+        save_manifest(731, "200", 222);
+        save_manifest(731, "100", 111);
+        // Unresolved depots are left alone
+        save_manifest(732, "100", 333);
+        save_manifest(732, "200", 444);
+        // No directory matches the resolved manifest: nothing is removed
+        save_manifest(733, "100", 555);
+        save_manifest(733, "200", 666);
+
+        let resolved = HashMap::from([
+            ("731".to_string(), "111".to_string()),
+            ("733".to_string(), "777".to_string()),
+        ]);
+        assert_eq!(prune_superseded_manifest_dirs(&staging, &resolved).unwrap(), 1);
+
+        let depots = staging.join("depots");
+        assert!(depots.join("731").join("100").exists());
+        assert!(!depots.join("731").join("200").exists());
+        assert!(depots.join("732").join("100").exists());
+        assert!(depots.join("732").join("200").exists());
+        assert!(depots.join("733").join("100").exists());
+        assert!(depots.join("733").join("200").exists());
+
+        let _ = fs::remove_dir_all(&staging);
     }
 }
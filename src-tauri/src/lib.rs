@@ -1,31 +1,73 @@
+mod accounts;
 mod acf_generator;
+mod archive_format;
+mod archive_verification;
+mod checksums;
+mod cli;
+mod compression_profile;
 mod debug_console;
+mod depot_manifest;
 mod depot_runner;
+mod depot_validation;
 mod job_finalization;
+mod job_host;
+mod job_log;
 mod job_metadata;
+mod job_queue;
 mod job_staging;
 mod login_store;
 mod manifest_preflight;
 mod output_conflict;
 mod output_dir;
+mod output_history;
+mod redaction;
 mod appimage_integration;
 mod steam_api;
+mod steam_library;
 mod steamdb_api;
+mod tar_archive;
 mod template_metadata;
 mod template_renderer;
 mod template_store;
+mod update_pack;
+mod vdf;
+mod watch_list;
 mod zip_runner;
 
+use accounts::{
+    add_saved_account, list_saved_accounts, remove_saved_account, set_default_account,
+};
+use compression_profile::{get_compression_profiles, save_compression_profiles};
 use debug_console::{debug_console_enabled, debug_console_log, DebugConsoleState};
 use depot_runner::{
-    cancel_depotdownloader, run_depotdownloader, submit_steam_guard_code, DepotRunnerState,
+    cancel_depotdownloader, create_update_pack, discard_resumable_download, discover_depots,
+    find_resumable_download, run_depotdownloader, submit_steam_guard_code, DepotRunnerState,
+};
+use depot_validation::validate_output;
+use job_queue::{
+    clear_job_queue, enqueue_job, get_job_queue, move_queue_entry, pause_job_queue,
+    remove_queue_entry, resolve_queue_path, resume_job_queue, retry_queue_entry,
+    spawn_queue_dispatcher, JobQueueState,
 };
+use job_log::{list_job_logs, read_job_log};
 use job_staging::cleanup_orphaned_staging;
-use login_store::{delete_login_data, load_login_data, save_login_data};
+use login_store::{
+    delete_login_data, get_login_storage_status, load_login_data, save_login_data,
+};
 use output_conflict::{resolve_output_conflict, OutputConflictState};
 use output_dir::{get_output_folder, open_output_folder};
+use output_history::{list_output_history, open_history_output, regenerate_release_notes};
+use redaction::{
+    apply_redaction_settings, export_share_safe_log, get_redaction_settings,
+    save_redaction_settings,
+};
+use steam_library::list_steam_libraries;
 use template_metadata::{get_template_metadata, TemplateMetadataState};
 use template_store::{load_template_data, save_template_data};
+use watch_list::{
+    add_watch_entry, check_watch_list_now, get_watch_list, remove_watch_entry,
+    resolve_watch_path, set_watch_entry_paused, spawn_watch_scheduler, WatchListState,
+};
 use zip_runner::{cancel_7zip, run_7zip, SevenZipRunnerState};
 use std::sync::OnceLock;
 use tauri::Manager;
@@ -45,6 +87,10 @@ fn debug_console_from_args() -> bool {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if cli::headless_requested() {
+        std::process::exit(cli::run_headless());
+    }
+
     let debug_console_flag = debug_console_from_args();
     tauri::Builder::default()
         .manage(DepotRunnerState::new())
@@ -54,7 +100,7 @@ pub fn run() {
         .manage(DebugConsoleState::new(debug_console_flag))
         .setup(|app| {
             let app_handle = app.handle();
-            match cleanup_orphaned_staging(&app_handle) {
+            match cleanup_orphaned_staging(app_handle) {
                 Ok(count) => {
                     if count > 0 {
                         eprintln!("Cleaned up {count} orphaned staging entries.");
@@ -64,7 +110,28 @@ pub fn run() {
                     eprintln!("Failed to clean staging directory on startup: {err}");
                 }
             }
-            appimage_integration::maybe_install_appimage_integration(&app_handle);
+            if let Err(err) = apply_redaction_settings(app_handle) {
+                eprintln!("Failed to load redaction settings, using defaults: {err}");
+            }
+            let queue_path = match resolve_queue_path(app_handle) {
+                Ok(path) => Some(path),
+                Err(err) => {
+                    eprintln!("Failed to resolve queue file, queue will not persist: {err}");
+                    None
+                }
+            };
+            app.manage(JobQueueState::load(queue_path));
+            spawn_queue_dispatcher(app_handle.clone());
+            let watch_path = match resolve_watch_path(app_handle) {
+                Ok(path) => Some(path),
+                Err(err) => {
+                    eprintln!("Failed to resolve watch list file, watch list will not persist: {err}");
+                    None
+                }
+            };
+            app.manage(WatchListState::load(watch_path));
+            spawn_watch_scheduler(app_handle.clone());
+            appimage_integration::maybe_install_appimage_integration(app_handle);
             if let Some(icon) = load_window_icon() {
                 // Set icon on all windows
                 for (_, window) in app.webview_windows() {
@@ -92,17 +159,51 @@ pub fn run() {
             run_depotdownloader,
             cancel_depotdownloader,
             submit_steam_guard_code,
+            find_resumable_download,
+            discover_depots,
+            discard_resumable_download,
+            create_update_pack,
+            validate_output,
+            list_steam_libraries,
             run_7zip,
             cancel_7zip,
+            get_compression_profiles,
+            save_compression_profiles,
             open_output_folder,
             get_output_folder,
             save_login_data,
             load_login_data,
             delete_login_data,
+            get_login_storage_status,
+            list_saved_accounts,
+            add_saved_account,
+            remove_saved_account,
+            set_default_account,
+            get_redaction_settings,
+            save_redaction_settings,
+            export_share_safe_log,
+            list_job_logs,
+            read_job_log,
             get_template_metadata,
+            list_output_history,
+            open_history_output,
+            regenerate_release_notes,
             save_template_data,
             load_template_data,
-            resolve_output_conflict
+            resolve_output_conflict,
+            get_job_queue,
+            enqueue_job,
+            move_queue_entry,
+            remove_queue_entry,
+            retry_queue_entry,
+            clear_job_queue,
+            pause_job_queue,
+            resume_job_queue,
+            get_watch_list,
+            add_watch_entry,
+            remove_watch_entry,
+            set_watch_entry_paused,
+            check_watch_list_now
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,10 +1,33 @@
+//! Saved Steam login
+//!
+//! The login is kept in the OS credential store (Secret Service on Linux,
+//! Keychain on macOS, Credential Manager on Windows). Where none is available it
+//! is written to `login.dat`, encrypted with a key derived from a passphrase the
+//! user provides (Argon2id + XChaCha20-Poly1305, `OP2:` prefix); without a
+//! passphrase the login isn't saved. Files in the format older versions wrote
+//! (`OP1:`, XOR-masked with a fixed key) are only read, and are moved to the
+//! credential store or re-encrypted when they are loaded.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tauri::Manager;
 
 const LOGIN_FILE_NAME: &str = "login.dat";
-const LOGIN_PREFIX: &str = "OP1:";
-const XOR_KEY: &[u8] = b"omnipacker-login-key";
+const LOGIN_PREFIX: &str = "OP2:";
+const LEGACY_PREFIX: &str = "OP1:";
+const LEGACY_XOR_KEY: &[u8] = b"omnipacker-login-key";
+
+/// Service name entries are filed under in the OS credential store
+const KEYRING_SERVICE: &str = "OmniPacker";
+/// Credential store entry holding the saved login
+pub const LOGIN_SECRET_KEY: &str = "steam-login";
+
+const KDF_NAME: &str = "argon2id";
+const SALT_LEN: usize = 16;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginData {
@@ -12,6 +35,80 @@ pub struct LoginData {
     pub password: String,
 }
 
+/// Somewhere secrets can be kept by name. `Err` means the store itself is
+/// unavailable (no Secret Service running, locked keychain, ...).
+pub trait SecretStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn set(&self, key: &str, secret: &str) -> Result<(), String>;
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// The OS credential store
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(key: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(KEYRING_SERVICE, key)
+            .map_err(|e| format!("Credential store unavailable: {}", e))
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        match Self::entry(key)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Credential store unavailable: {}", e)),
+        }
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<(), String> {
+        Self::entry(key)?
+            .set_password(secret)
+            .map_err(|e| format!("Credential store unavailable: {}", e))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        match Self::entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to delete credential: {}", e)),
+        }
+    }
+}
+
+/// Where the saved login lives
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginBackend {
+    SecretService,
+    EncryptedFile,
+    /// `OP1:` file from an older version, waiting to be migrated
+    LegacyFile,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginStorageStatus {
+    /// Where the login is (or would be) stored
+    pub backend: LoginBackend,
+    pub saved: bool,
+    /// Loading or saving needs the file passphrase
+    pub needs_passphrase: bool,
+}
+
+/// `login.dat` contents after the `OP2:` prefix
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedLogin {
+    kdf: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
 fn login_file_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
     app_handle
         .path()
@@ -32,13 +129,6 @@ fn ensure_parent_dir(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn xor_bytes(data: &[u8]) -> Vec<u8> {
-    data.iter()
-        .enumerate()
-        .map(|(index, byte)| byte ^ XOR_KEY[index % XOR_KEY.len()])
-        .collect()
-}
-
 fn encode_hex(bytes: &[u8]) -> String {
     let mut out = String::with_capacity(bytes.len() * 2);
     for byte in bytes {
@@ -49,7 +139,7 @@ fn encode_hex(bytes: &[u8]) -> String {
 }
 
 fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
-    if input.len() % 2 != 0 {
+    if !input.len().is_multiple_of(2) {
         return Err("Invalid hex payload length.".to_string());
     }
     let mut out = Vec::with_capacity(input.len() / 2);
@@ -63,19 +153,213 @@ fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
     Ok(out)
 }
 
-fn encrypt_payload(plain_text: &str) -> String {
-    let masked = xor_bytes(plain_text.as_bytes());
-    format!("{}{}", LOGIN_PREFIX, encode_hex(&masked))
+fn xor_legacy(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .enumerate()
+        .map(|(index, byte)| byte ^ LEGACY_XOR_KEY[index % LEGACY_XOR_KEY.len()])
+        .collect()
 }
 
-fn decrypt_payload(payload: &str) -> Result<String, String> {
-    let trimmed = payload.trim();
-    let hex = trimmed
-        .strip_prefix(LOGIN_PREFIX)
+/// Reads an `OP1:` payload written by older versions
+fn decrypt_legacy_payload(payload: &str) -> Result<String, String> {
+    let hex = payload
+        .trim()
+        .strip_prefix(LEGACY_PREFIX)
         .ok_or_else(|| "Unsupported login data format.".to_string())?;
     let bytes = decode_hex(hex)?;
-    let unmasked = xor_bytes(&bytes);
-    String::from_utf8(unmasked).map_err(|e| format!("Invalid login data: {}", e))
+    String::from_utf8(xor_legacy(&bytes)).map_err(|e| format!("Invalid login data: {}", e))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: Params) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt_payload(plain_text: &str, passphrase: &str, params: Params) -> Result<String, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, params.clone())?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plain_text.as_bytes(),
+                aad: LOGIN_PREFIX.as_bytes(),
+            },
+        )
+        .map_err(|_| "Failed to encrypt login data.".to_string())?;
+
+    let envelope = EncryptedLogin {
+        kdf: KDF_NAME.to_string(),
+        memory_kib: params.m_cost(),
+        iterations: params.t_cost(),
+        parallelism: params.p_cost(),
+        salt: encode_hex(&salt),
+        nonce: encode_hex(&nonce),
+        ciphertext: encode_hex(&ciphertext),
+    };
+    let json = serde_json::to_string(&envelope)
+        .map_err(|e| format!("Failed to encode login data: {}", e))?;
+    Ok(format!("{}{}", LOGIN_PREFIX, json))
+}
+
+fn decrypt_payload(payload: &str, passphrase: &str) -> Result<String, String> {
+    let json = payload
+        .trim()
+        .strip_prefix(LOGIN_PREFIX)
+        .ok_or_else(|| "Unsupported login data format.".to_string())?;
+    let envelope: EncryptedLogin =
+        serde_json::from_str(json).map_err(|e| format!("Invalid login data: {}", e))?;
+    if envelope.kdf != KDF_NAME {
+        return Err(format!("Unsupported key derivation: {}", envelope.kdf));
+    }
+    let params = Params::new(
+        envelope.memory_kib,
+        envelope.iterations,
+        envelope.parallelism,
+        None,
+    )
+    .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+    let nonce = decode_hex(&envelope.nonce)?;
+    if nonce.len() != 24 {
+        return Err("Invalid login data nonce.".to_string());
+    }
+
+    let key = derive_key(passphrase, &decode_hex(&envelope.salt)?, params)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let plain = cipher
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &decode_hex(&envelope.ciphertext)?,
+                aad: LOGIN_PREFIX.as_bytes(),
+            },
+        )
+        .map_err(|_| "Wrong passphrase, or the login data is damaged.".to_string())?;
+    String::from_utf8(plain).map_err(|e| format!("Invalid login data: {}", e))
+}
+
+fn non_empty(passphrase: Option<&str>) -> Option<&str> {
+    passphrase.filter(|passphrase| !passphrase.is_empty())
+}
+
+/// Stores a secret in `store`, or when that is unavailable, in a file at `path`
+/// encrypted with `passphrase`. A file left over from the other backend is removed.
+pub fn save_secret(
+    store: &dyn SecretStore,
+    key: &str,
+    path: &Path,
+    secret: &str,
+    passphrase: Option<&str>,
+) -> Result<LoginBackend, String> {
+    let store_error = match store.set(key, secret) {
+        Ok(()) => {
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            }
+            return Ok(LoginBackend::SecretService);
+        }
+        Err(err) => err,
+    };
+
+    let passphrase = non_empty(passphrase).ok_or_else(|| {
+        format!("{store_error}; enter a passphrase to save the login to an encrypted file.")
+    })?;
+    let encoded = encrypt_payload(secret, passphrase, Params::DEFAULT)?;
+    ensure_parent_dir(path)?;
+    std::fs::write(path, encoded)
+        .map_err(|e| format!("Failed to write login data to {}: {}", path.display(), e))?;
+    Ok(LoginBackend::EncryptedFile)
+}
+
+/// Loads a secret saved by `save_secret`. `OP2:` files need the passphrase; legacy
+/// `OP1:` files are migrated as they are read, which needs the passphrase too when
+/// there is no credential store to move them to.
+pub fn load_secret(
+    store: &dyn SecretStore,
+    key: &str,
+    path: &Path,
+    passphrase: Option<&str>,
+) -> Result<Option<String>, String> {
+    match store.get(key) {
+        Ok(Some(secret)) => return Ok(Some(secret)),
+        Ok(None) => {}
+        Err(err) => eprintln!("{err}; looking for {}", path.display()),
+    }
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read login data from {}: {}", path.display(), e))?;
+    if content.trim_start().starts_with(LEGACY_PREFIX) {
+        if store.get(key).is_err() && non_empty(passphrase).is_none() {
+            return Err(
+                "The saved login is not encrypted; enter a passphrase to encrypt it.".to_string(),
+            );
+        }
+        let secret = decrypt_legacy_payload(&content)?;
+        save_secret(store, key, path, &secret, passphrase)?;
+        return Ok(Some(secret));
+    }
+
+    let passphrase = non_empty(passphrase)
+        .ok_or_else(|| "The saved login is encrypted; enter its passphrase.".to_string())?;
+    decrypt_payload(&content, passphrase).map(Some)
+}
+
+pub fn delete_secret(store: &dyn SecretStore, key: &str, path: &Path) -> Result<(), String> {
+    // An unavailable store can't be holding the secret, so only the file matters then
+    if let Err(err) = store.delete(key) {
+        eprintln!("{err}");
+    }
+    if path.exists() {
+        std::fs::remove_file(path)
+            .map_err(|e| format!("Failed to delete login data at {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+pub fn secret_status(store: &dyn SecretStore, key: &str, path: &Path) -> LoginStorageStatus {
+    let store_result = store.get(key);
+    if let Ok(Some(_)) = store_result {
+        return LoginStorageStatus {
+            backend: LoginBackend::SecretService,
+            saved: true,
+            needs_passphrase: false,
+        };
+    }
+
+    let store_available = store_result.is_ok();
+    match std::fs::read_to_string(path) {
+        // Migrating it needs a passphrase unless the store can take it
+        Ok(content) if content.trim_start().starts_with(LEGACY_PREFIX) => LoginStorageStatus {
+            backend: LoginBackend::LegacyFile,
+            saved: true,
+            needs_passphrase: !store_available,
+        },
+        Ok(_) => LoginStorageStatus {
+            backend: LoginBackend::EncryptedFile,
+            saved: true,
+            needs_passphrase: true,
+        },
+        Err(_) if store_available => LoginStorageStatus {
+            backend: LoginBackend::SecretService,
+            saved: false,
+            needs_passphrase: false,
+        },
+        Err(_) => LoginStorageStatus {
+            backend: LoginBackend::EncryptedFile,
+            saved: false,
+            needs_passphrase: true,
+        },
+    }
 }
 
 #[tauri::command]
@@ -83,7 +367,8 @@ pub fn save_login_data(
     app_handle: tauri::AppHandle,
     username: String,
     password: String,
-) -> Result<(), String> {
+    passphrase: Option<String>,
+) -> Result<LoginBackend, String> {
     if username.trim().is_empty() || password.is_empty() {
         return Err("Username and password are required.".to_string());
     }
@@ -91,23 +376,26 @@ pub fn save_login_data(
     let login_data = LoginData { username, password };
     let json = serde_json::to_string(&login_data)
         .map_err(|e| format!("Failed to encode login data: {}", e))?;
-    let encoded = encrypt_payload(&json);
     let path = login_file_path(&app_handle)?;
-    ensure_parent_dir(&path)?;
-    std::fs::write(&path, encoded)
-        .map_err(|e| format!("Failed to write login data to {}: {}", path.display(), e))?;
-    Ok(())
+    save_secret(
+        &KeyringStore,
+        LOGIN_SECRET_KEY,
+        &path,
+        &json,
+        passphrase.as_deref(),
+    )
 }
 
 #[tauri::command]
-pub fn load_login_data(app_handle: tauri::AppHandle) -> Result<Option<LoginData>, String> {
+pub fn load_login_data(
+    app_handle: tauri::AppHandle,
+    passphrase: Option<String>,
+) -> Result<Option<LoginData>, String> {
     let path = login_file_path(&app_handle)?;
-    if !path.exists() {
+    let Some(json) = load_secret(&KeyringStore, LOGIN_SECRET_KEY, &path, passphrase.as_deref())?
+    else {
         return Ok(None);
-    }
-    let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read login data from {}: {}", path.display(), e))?;
-    let json = decrypt_payload(&content)?;
+    };
     let login_data =
         serde_json::from_str(&json).map_err(|e| format!("Failed to parse login data: {}", e))?;
     Ok(Some(login_data))
@@ -116,9 +404,218 @@ pub fn load_login_data(app_handle: tauri::AppHandle) -> Result<Option<LoginData>
 #[tauri::command]
 pub fn delete_login_data(app_handle: tauri::AppHandle) -> Result<(), String> {
     let path = login_file_path(&app_handle)?;
-    if path.exists() {
-        std::fs::remove_file(&path)
-            .map_err(|e| format!("Failed to delete login data at {}: {}", path.display(), e))?;
+    delete_secret(&KeyringStore, LOGIN_SECRET_KEY, &path)
+}
+
+/// Reports where the login is stored and whether a passphrase is needed
+#[tauri::command]
+pub fn get_login_storage_status(app_handle: tauri::AppHandle) -> Result<LoginStorageStatus, String> {
+    let path = login_file_path(&app_handle)?;
+    Ok(secret_status(&KeyringStore, LOGIN_SECRET_KEY, &path))
+}
+
+/// In-memory stand-in for the OS credential store
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::SecretStore;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// `available: false` behaves like a missing Secret Service
+    pub(crate) struct MemoryStore {
+        available: bool,
+        secrets: RefCell<HashMap<String, String>>,
+    }
+
+    impl MemoryStore {
+        pub(crate) fn new(available: bool) -> Self {
+            Self {
+                available,
+                secrets: RefCell::new(HashMap::new()),
+            }
+        }
+
+        fn check(&self) -> Result<(), String> {
+            if self.available {
+                Ok(())
+            } else {
+                Err("Credential store unavailable: no Secret Service".to_string())
+            }
+        }
+    }
+
+    impl Default for MemoryStore {
+        fn default() -> Self {
+            Self::new(true)
+        }
+    }
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<String>, String> {
+            self.check()?;
+            Ok(self.secrets.borrow().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, secret: &str) -> Result<(), String> {
+            self.check()?;
+            self.secrets
+                .borrow_mut()
+                .insert(key.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), String> {
+            self.check()?;
+            self.secrets.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::MemoryStore;
+    use super::*;
+
+    fn fast_params() -> Params {
+        Params::new(256, 1, 1, None).unwrap()
+    }
+
+    fn temp_login_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "omnipacker_login_{}_{}",
+            name,
+            crate::job_staging::generate_job_id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(LOGIN_FILE_NAME)
+    }
+
+    /// Writes a secret the way older versions did
+    fn encode_legacy_payload(plain_text: &str) -> String {
+        format!("{}{}", LEGACY_PREFIX, encode_hex(&xor_legacy(plain_text.as_bytes())))
+    }
+
+    fn remove_temp_login(path: &Path) {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn test_encrypted_payload_round_trips_and_rejects_wrong_passphrase() {
+        let first = encrypt_payload("secret", "correct horse", fast_params()).unwrap();
+        let second = encrypt_payload("secret", "correct horse", fast_params()).unwrap();
+        assert!(first.starts_with(LOGIN_PREFIX));
+        assert!(!first.contains("secret"));
+        assert_ne!(first, second, "salt and nonce are random");
+
+        assert_eq!(decrypt_payload(&first, "correct horse").unwrap(), "secret");
+        assert!(decrypt_payload(&first, "wrong").is_err());
+
+        let tampered = first.replacen("\"ciphertext\":\"", "\"ciphertext\":\"00", 1);
+        assert!(decrypt_payload(&tampered, "correct horse").is_err());
+    }
+
+    #[test]
+    fn test_legacy_payload_is_still_readable() {
+        let payload = encode_legacy_payload(r#"{"username":"user","password":"hunter2"}"#);
+        assert_eq!(
+            decrypt_legacy_payload(&payload).unwrap(),
+            r#"{"username":"user","password":"hunter2"}"#
+        );
+        assert!(decrypt_legacy_payload("OP9:00").is_err());
+    }
+
+    #[test]
+    fn test_save_prefers_the_credential_store() {
+        let store = MemoryStore::new(true);
+        let path = temp_login_path("store");
+        std::fs::write(&path, encode_legacy_payload("old")).unwrap();
+
+        let backend = save_secret(&store, LOGIN_SECRET_KEY, &path, "secret", None).unwrap();
+        assert_eq!(backend, LoginBackend::SecretService);
+        assert!(!path.exists(), "the old file is removed");
+        assert_eq!(
+            load_secret(&store, LOGIN_SECRET_KEY, &path, None).unwrap().as_deref(),
+            Some("secret")
+        );
+
+        delete_secret(&store, LOGIN_SECRET_KEY, &path).unwrap();
+        assert_eq!(load_secret(&store, LOGIN_SECRET_KEY, &path, None).unwrap(), None);
+        remove_temp_login(&path);
+    }
+
+    #[test]
+    fn test_save_without_credential_store_encrypts_with_a_passphrase() {
+        let store = MemoryStore::new(false);
+        let path = temp_login_path("file");
+
+        // Nothing is written without a passphrase
+        for passphrase in [None, Some("")] {
+            let error =
+                save_secret(&store, LOGIN_SECRET_KEY, &path, "secret", passphrase).unwrap_err();
+            assert!(error.contains("passphrase"));
+            assert!(!path.exists());
+        }
+
+        let backend =
+            save_secret(&store, LOGIN_SECRET_KEY, &path, "secret", Some("passphrase")).unwrap();
+        assert_eq!(backend, LoginBackend::EncryptedFile);
+        let status = secret_status(&store, LOGIN_SECRET_KEY, &path);
+        assert_eq!(status.backend, LoginBackend::EncryptedFile);
+        assert!(status.saved && status.needs_passphrase);
+
+        assert!(load_secret(&store, LOGIN_SECRET_KEY, &path, None).is_err());
+        assert_eq!(
+            load_secret(&store, LOGIN_SECRET_KEY, &path, Some("passphrase"))
+                .unwrap()
+                .as_deref(),
+            Some("secret")
+        );
+
+        delete_secret(&store, LOGIN_SECRET_KEY, &path).unwrap();
+        assert!(!path.exists());
+        remove_temp_login(&path);
+    }
+
+    #[test]
+    fn test_legacy_files_are_migrated_on_load() {
+        // Into the credential store when there is one
+        let store = MemoryStore::new(true);
+        let path = temp_login_path("migrate_store");
+        std::fs::write(&path, encode_legacy_payload("secret")).unwrap();
+        assert_eq!(
+            secret_status(&store, LOGIN_SECRET_KEY, &path).backend,
+            LoginBackend::LegacyFile
+        );
+        assert_eq!(
+            load_secret(&store, LOGIN_SECRET_KEY, &path, None).unwrap().as_deref(),
+            Some("secret")
+        );
+        assert!(!path.exists());
+        assert_eq!(
+            store.get(LOGIN_SECRET_KEY).unwrap().as_deref(),
+            Some("secret")
+        );
+        remove_temp_login(&path);
+
+        // Into an encrypted file otherwise, which needs a passphrase
+        let store = MemoryStore::new(false);
+        let path = temp_login_path("migrate_file");
+        std::fs::write(&path, encode_legacy_payload("secret")).unwrap();
+        let status = secret_status(&store, LOGIN_SECRET_KEY, &path);
+        assert_eq!(status.backend, LoginBackend::LegacyFile);
+        assert!(status.saved && status.needs_passphrase);
+        assert!(load_secret(&store, LOGIN_SECRET_KEY, &path, None).is_err());
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with(LEGACY_PREFIX));
+        assert_eq!(
+            load_secret(&store, LOGIN_SECRET_KEY, &path, Some("passphrase"))
+                .unwrap()
+                .as_deref(),
+            Some("secret")
+        );
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with(LOGIN_PREFIX));
+        remove_temp_login(&path);
     }
-    Ok(())
 }
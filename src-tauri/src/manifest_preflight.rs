@@ -1,32 +1,40 @@
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use regex::Regex;
+use serde::Serialize;
 
-use crate::depot_runner::JobMetadata;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::depot_manifest::{find_saved_manifest, read_depot_manifest, DepotManifest};
+use crate::depot_runner::{depot_selection_args, JobMetadata};
+use crate::steam_api::is_shared_depot;
 
 /// Information about a depot extracted from DepotDownloader output
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PreflightDepotInfo {
     pub depot_id: String,
-    #[allow(dead_code)] // Captured but not currently used; kept for debugging/future use
     pub manifest_id: String,
     pub depot_name: Option<String>,
+    /// Shared redistributable depot (DirectX, VC++ runtimes, ...)
+    pub shared: bool,
 }
 
 /// Result of the manifest-only preflight operation
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PreflightResult {
     /// List of depots discovered
-    #[allow(dead_code)] // Captured but not currently used; kept for debugging/future use
     pub depots: Vec<PreflightDepotInfo>,
     /// Primary depot ID (from installdir detection)
-    #[allow(dead_code)] // Captured but not currently used; kept for debugging/future use
     pub primary_depot_id: Option<String>,
     /// Build ID if found in output
-    #[allow(dead_code)] // Captured but not currently used; kept for debugging/future use
     pub build_id: Option<String>,
     /// Build release datetime if found in output
     pub build_datetime_utc: Option<DateTime<Utc>>,
     /// Raw output lines for debugging
+    #[serde(skip)]
     #[allow(dead_code)] // Captured but not currently used; kept for debugging/future use
     pub raw_output: Vec<String>,
 }
@@ -52,17 +60,16 @@ pub fn build_preflight_args(job: &JobMetadata) -> Result<Vec<String>, String> {
     args.push("-osarch".to_string());
     args.push(arch.to_string());
 
+    // Selected depots and pinned manifests, so preflight reports the same depots/manifests as the download
+    args.extend(depot_selection_args(job)?);
+
     // Authentication
     if job.qr_enabled {
         args.push("-qr".to_string());
     } else if !job.username.is_empty() {
+        // The password goes to DepotDownloader's prompt over stdin, never argv
         args.push("-username".to_string());
         args.push(job.username.clone());
-
-        if !job.password.is_empty() {
-            args.push("-password".to_string());
-            args.push(job.password.clone());
-        }
         args.push("-remember-password".to_string());
     }
     // If both username and password are empty, attempt anonymous download (no auth args)
@@ -289,10 +296,12 @@ pub fn parse_preflight_output(lines: &[String]) -> PreflightResult {
     // Convert to Vec with depot names if available
     for (depot_id, manifest_id) in depot_manifest_map {
         let depot_name = depot_name_map.get(&depot_id).cloned();
+        let shared = is_shared_depot(&depot_id);
         depots.push(PreflightDepotInfo {
             depot_id,
             manifest_id,
             depot_name,
+            shared,
         });
     }
 
@@ -307,7 +316,6 @@ pub fn parse_preflight_output(lines: &[String]) -> PreflightResult {
     // Fallback: if no primary depot was detected via installdir, use first non-shared depot
     // Priority: 1) installdir detection, 2) first non-shared depot, 3) first depot
     if !depots.is_empty() && primary_depot_id.is_none() {
-        use crate::steam_api::is_shared_depot;
         // Find first non-shared depot as fallback
         primary_depot_id = depots
             .iter()
@@ -335,6 +343,75 @@ pub fn parse_preflight_output(lines: &[String]) -> PreflightResult {
     }
 }
 
+/// Corrects a parsed preflight with the manifests DepotDownloader saved under
+/// `<work_dir>/depots/`: their depot and manifest IDs replace the ones scraped from
+/// the console, and their creation times stand in for a missing build date.
+/// The download folder is named after the build, and that name is what job.json
+/// records, so it also replaces a scraped build ID.
+pub fn apply_saved_manifests(result: &mut PreflightResult, work_dir: &Path) {
+    let Ok(depot_dirs) = fs::read_dir(work_dir.join("depots")) else {
+        return;
+    };
+    let downloads: Vec<_> = depot_dirs
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| fs::read_dir(entry.path()).ok())
+        .flat_map(|downloads| downloads.filter_map(|entry| entry.ok()))
+        .filter(|download| {
+            download.path().is_dir() && !download.file_name().to_string_lossy().starts_with('.')
+        })
+        .collect();
+    if let Some(download) = downloads.first() {
+        result.build_id = Some(download.file_name().to_string_lossy().to_string());
+    }
+    let manifests: Vec<DepotManifest> = downloads
+        .iter()
+        .filter_map(|download| find_saved_manifest(&download.path()))
+        .filter_map(|path| read_depot_manifest(&path).ok())
+        .collect();
+    merge_manifests(result, &manifests);
+}
+
+fn merge_manifests(result: &mut PreflightResult, manifests: &[DepotManifest]) {
+    let mut creation_times = HashMap::new();
+    for manifest in manifests {
+        let (Some(depot_id), Some(manifest_id)) = (manifest.depot_id, manifest.manifest_id) else {
+            continue;
+        };
+        let depot_id = depot_id.to_string();
+        if let Some(created) = manifest.creation_time {
+            creation_times.insert(depot_id.clone(), created);
+        }
+
+        match result.depots.iter_mut().find(|depot| depot.depot_id == depot_id) {
+            Some(depot) => depot.manifest_id = manifest_id.to_string(),
+            None => result.depots.push(PreflightDepotInfo {
+                shared: is_shared_depot(&depot_id),
+                depot_id,
+                manifest_id: manifest_id.to_string(),
+                depot_name: None,
+            }),
+        }
+    }
+
+    result.depots.sort_by_key(|depot| depot.depot_id.parse::<u64>().unwrap_or(0));
+    if result.primary_depot_id.is_none() {
+        result.primary_depot_id = result
+            .depots
+            .iter()
+            .find(|depot| !depot.shared)
+            .or(result.depots.first())
+            .map(|depot| depot.depot_id.clone());
+    }
+    if result.build_datetime_utc.is_none() {
+        result.build_datetime_utc = result
+            .primary_depot_id
+            .as_ref()
+            .and_then(|primary| creation_times.get(primary).copied())
+            .or_else(|| creation_times.values().max().copied());
+    }
+}
+
 fn parse_epoch_timestamp(value: Option<&str>) -> Option<DateTime<Utc>> {
     let seconds: i64 = value?.trim().parse().ok()?;
     Utc.timestamp_opt(seconds, 0).single()
@@ -518,4 +595,140 @@ mod tests {
         assert_eq!(timestamp.day(), 15);
         assert_eq!(timestamp.year(), 2024);
     }
+
+    #[test]
+    fn test_saved_manifests_override_scraped_ids() {
+        let lines = vec!["Depot 481 - Manifest 111".to_string()];
+        let mut result = parse_preflight_output(&lines);
+        let manifest = |depot_id, manifest_id, created| DepotManifest {
+            depot_id: Some(depot_id),
+            manifest_id: Some(manifest_id),
+            creation_time: Utc.timestamp_opt(created, 0).single(),
+            ..Default::default()
+        };
+
+        merge_manifests(
+            &mut result,
+            &[manifest(481, 222, 1_600_000_000), manifest(228990, 333, 1_700_000_000)],
+        );
+
+        let ids: Vec<(&str, &str)> = result
+            .depots
+            .iter()
+            .map(|depot| (depot.depot_id.as_str(), depot.manifest_id.as_str()))
+            .collect();
+        assert_eq!(ids, vec![("481", "222"), ("228990", "333")]);
+        assert!(result.depots[1].shared);
+        assert_eq!(result.primary_depot_id.as_deref(), Some("481"));
+        // The primary depot's manifest dates the build
+        assert_eq!(result.build_datetime_utc.map(|ts| ts.timestamp()), Some(1_600_000_000));
+    }
+
+    #[test]
+    fn test_preflight_args_include_manifest_pins() {
+        let job = JobMetadata {
+            app_id: "480".to_string(),
+            os: "Windows x64".to_string(),
+            branch: "public".to_string(),
+            manifest_pins: vec![
+                crate::depot_runner::DepotManifestPin {
+                    depot_id: "481".to_string(),
+                    manifest_id: "111".to_string(),
+                },
+                crate::depot_runner::DepotManifestPin {
+                    depot_id: "482".to_string(),
+                    manifest_id: "222".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        let args = build_preflight_args(&job).unwrap();
+        let joined = args.join(" ");
+        assert!(joined.contains("-depot 481 482 -manifest 111 222"));
+    }
+
+    #[test]
+    fn test_preflight_args_include_depot_selection() {
+        let mut job = JobMetadata {
+            app_id: "480".to_string(),
+            os: "Windows x64".to_string(),
+            branch: "public".to_string(),
+            depot_ids: vec!["481".to_string(), "483".to_string()],
+            ..Default::default()
+        };
+
+        let joined = build_preflight_args(&job).unwrap().join(" ");
+        assert!(joined.contains("-depot 481 483"));
+        assert!(!joined.contains("-manifest"));
+
+        // Pins for selected depots are kept, pins for excluded depots are dropped
+        job.manifest_pins = vec![
+            crate::depot_runner::DepotManifestPin {
+                depot_id: "481".to_string(),
+                manifest_id: "111".to_string(),
+            },
+            crate::depot_runner::DepotManifestPin {
+                depot_id: "482".to_string(),
+                manifest_id: "222".to_string(),
+            },
+            crate::depot_runner::DepotManifestPin {
+                depot_id: "483".to_string(),
+                manifest_id: "333".to_string(),
+            },
+        ];
+        let joined = build_preflight_args(&job).unwrap().join(" ");
+        assert!(joined.contains("-depot 481 483 -manifest 111 333"));
+
+        // Mixing pinned and unpinned selected depots cannot be expressed to DepotDownloader
+        job.manifest_pins.pop();
+        assert!(build_preflight_args(&job).is_err());
+    }
+
+    #[test]
+    fn test_preflight_args_include_language_and_low_violence() {
+        let mut job = JobMetadata {
+            app_id: "480".to_string(),
+            os: "Windows x64".to_string(),
+            branch: "public".to_string(),
+            language: " German ".to_string(),
+            low_violence: true,
+            ..Default::default()
+        };
+
+        let joined = build_preflight_args(&job).unwrap().join(" ");
+        assert!(joined.contains("-language german"));
+        assert!(joined.contains("-lowviolence"));
+
+        job.language = "german; rm".to_string();
+        assert!(build_preflight_args(&job).is_err());
+    }
+
+    #[test]
+    fn test_preflight_args_keep_password_off_command_line() {
+        let job = JobMetadata {
+            app_id: "480".to_string(),
+            os: "Linux".to_string(),
+            username: "builder".to_string(),
+            password: "hunter2".to_string(),
+            ..Default::default()
+        };
+
+        let args = build_preflight_args(&job).unwrap();
+        assert!(args.iter().any(|arg| arg == "builder"));
+        assert!(!args.iter().any(|arg| arg == "-password" || arg.contains("hunter2")));
+    }
+
+    #[test]
+    fn test_shared_depots_are_flagged() {
+        let lines = vec![
+            "Depot 228987 - Manifest 111".to_string(),
+            "Depot 481 - Manifest 222".to_string(),
+        ];
+
+        let result = parse_preflight_output(&lines);
+        assert!(!result.depots[0].shared);
+        assert_eq!(result.depots[0].depot_id, "481");
+        assert!(result.depots[1].shared);
+    }
 }
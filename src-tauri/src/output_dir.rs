@@ -22,6 +22,7 @@ fn ensure_writable_dir(path: &Path) -> Result<(), String> {
     let write_result = OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(true)
         .open(&test_path);
 
     if let Ok(mut file) = write_result {
@@ -152,6 +153,12 @@ pub fn resolve_downloads_dir(app_handle: &AppHandle) -> Result<PathBuf, String>
         .resolve("downloads", tauri::path::BaseDirectory::AppData)
         .map_err(|err| format!("Failed to resolve app data downloads directory: {err}"))?;
 
+    resolve_downloads_dir_with_fallback(fallback_dir)
+}
+
+/// Picks the portable `downloads/` next to the executable when writable,
+/// otherwise the given app data fallback (always the fallback in debug builds).
+pub fn resolve_downloads_dir_with_fallback(fallback_dir: PathBuf) -> Result<PathBuf, String> {
     if cfg!(debug_assertions) {
         ensure_writable_dir(&fallback_dir)?;
         return Ok(fallback_dir);
@@ -180,9 +187,14 @@ pub fn get_output_folder(app_handle: AppHandle) -> Result<String, String> {
 #[tauri::command]
 pub fn open_output_folder(app_handle: AppHandle) -> Result<(), String> {
     let path = resolve_downloads_dir(&app_handle)?;
+    open_path(&app_handle, &path)
+}
+
+/// Opens a folder with the system file manager
+pub fn open_path(app_handle: &AppHandle, path: &Path) -> Result<(), String> {
     #[cfg(target_os = "linux")]
     if is_appimage_env() {
-        return open_path_appimage(&path)
+        return open_path_appimage(path)
             .map_err(|err| format!("Failed to open output folder: {err}"));
     }
     app_handle
@@ -0,0 +1,579 @@
+//! Catalogue of past outputs
+//!
+//! Every finished job (and every failed one that got far enough to be
+//! identified) is appended as one JSON line to `history.jsonl` in the app data
+//! directory. Unlike `TemplateMetadataState`, which only knows the most recent
+//! job, the catalogue survives restarts and can be searched, re-opened and used
+//! to regenerate release notes for any past release.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::archive_format::{existing_volumes, strip_archive_extension};
+use crate::job_host::JobHost;
+use crate::job_metadata::JobMetadataFile;
+use crate::job_staging::{dir_size, generate_job_id};
+use crate::output_dir::open_path;
+use crate::redaction::redact_log_line;
+use crate::template_metadata::TemplateMetadata;
+use crate::template_renderer::{create_default_template, render_template, write_template_file};
+use crate::template_store::load_template_data_internal;
+
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryStatus {
+    Completed,
+    Failed,
+}
+
+/// Archive checksums recorded for an output; empty when none were written
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryChecksums {
+    pub sha256: String,
+    pub blake3: String,
+    pub crc32: String,
+    /// File name of the primary checksum manifest
+    pub manifest: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    /// Runner job ID that produced the output
+    pub job_id: String,
+    pub appid: String,
+    pub game_name: String,
+    pub branch: String,
+    /// Output platform label (`Win64`, `Linux64`, ...)
+    pub platform: String,
+    pub build_id: String,
+    /// Build an update pack starts from; empty for full builds
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub previous_build_id: String,
+    /// Contents of the job's job.json, when the job got far enough to write one
+    #[serde(default)]
+    pub job: Option<JobMetadataFile>,
+    /// Output folder (removed once compressed) or, for Steam library installs
+    /// that moved the output, the installed appmanifest
+    #[serde(default)]
+    pub output_path: Option<String>,
+    #[serde(default)]
+    pub archive_path: Option<String>,
+    #[serde(default)]
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub checksums: HistoryChecksums,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    #[serde(default)]
+    pub duration_secs: u64,
+    pub status: HistoryStatus,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Starts an entry for one platform of a job; identity fields come from
+    /// job.json when available, otherwise from the caller's request
+    pub fn new(
+        job_id: &str,
+        appid: &str,
+        branch: &str,
+        platform: &str,
+        job: Option<JobMetadataFile>,
+        started_at: DateTime<Utc>,
+    ) -> Self {
+        let pick = |from_job: Option<&String>, fallback: &str| {
+            from_job
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .unwrap_or_else(|| fallback.to_string())
+        };
+        Self {
+            id: generate_job_id(),
+            job_id: job_id.to_string(),
+            appid: pick(job.as_ref().map(|job| &job.appid), appid),
+            game_name: pick(job.as_ref().map(|job| &job.game_name), ""),
+            branch: pick(job.as_ref().map(|job| &job.branch), branch),
+            platform: pick(job.as_ref().map(|job| &job.platform), platform),
+            build_id: pick(job.as_ref().map(|job| &job.build_id), ""),
+            previous_build_id: String::new(),
+            job,
+            output_path: None,
+            archive_path: None,
+            size_bytes: 0,
+            checksums: HistoryChecksums::default(),
+            started_at,
+            finished_at: started_at,
+            duration_secs: 0,
+            status: HistoryStatus::Failed,
+            error: None,
+        }
+    }
+
+    /// Marks the entry completed with its final output (folder, archive or
+    /// installed appmanifest) and the checksums gathered while packaging
+    pub fn complete(&mut self, final_path: &Path, template_metadata: Option<&TemplateMetadata>) {
+        let (output_path, archive_path) = split_output_path(final_path);
+        self.output_path = Some(output_path.to_string_lossy().to_string());
+        self.archive_path = archive_path.map(|path| path.to_string_lossy().to_string());
+        self.size_bytes = output_size(final_path);
+        if let Some(metadata) = template_metadata {
+            self.previous_build_id = metadata.previous_build_id.clone();
+            self.checksums = HistoryChecksums {
+                sha256: metadata.archive_sha256.clone(),
+                blake3: metadata.archive_blake3.clone(),
+                crc32: metadata.archive_crc32.clone(),
+                manifest: metadata.checksums_file.clone(),
+            };
+        }
+        self.status = HistoryStatus::Completed;
+        self.error = None;
+        self.finish();
+    }
+
+    pub fn fail(&mut self, error: &str) {
+        self.status = HistoryStatus::Failed;
+        self.error = Some(redact_log_line(error).into_owned());
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        self.finished_at = Utc::now();
+        self.duration_secs = (self.finished_at - self.started_at)
+            .num_seconds()
+            .max(0) as u64;
+    }
+
+    /// Rebuilds the template metadata the output was released with
+    pub fn template_metadata(&self) -> Option<TemplateMetadata> {
+        let mut metadata = TemplateMetadata::from_job_metadata(self.job.as_ref()?);
+        metadata.archive_sha256 = self.checksums.sha256.clone();
+        metadata.archive_blake3 = self.checksums.blake3.clone();
+        metadata.archive_crc32 = self.checksums.crc32.clone();
+        metadata.checksums_file = self.checksums.manifest.clone();
+        metadata.previous_build_id = self.previous_build_id.clone();
+        Some(metadata)
+    }
+
+    /// Whether this is a completed full output of the build `metadata` names:
+    /// same app, build ID, platform, branch, language and low-violence selection
+    pub fn packs_build(&self, metadata: &JobMetadataFile) -> bool {
+        let Some(job) = self.job.as_ref() else {
+            return false;
+        };
+        let language = |language: Option<&str>| {
+            language
+                .filter(|language| !language.eq_ignore_ascii_case("english"))
+                .map(str::to_ascii_lowercase)
+        };
+        self.status == HistoryStatus::Completed
+            && self.previous_build_id.is_empty()
+            && self.appid == metadata.appid
+            && self.build_id == metadata.build_id
+            && self.platform.eq_ignore_ascii_case(&metadata.platform)
+            && self.branch.eq_ignore_ascii_case(&metadata.branch)
+            && language(job.language.as_deref()) == language(metadata.language.as_deref())
+            && job.low_violence == metadata.low_violence
+    }
+
+    /// The archive if it is still on disk, otherwise the output folder
+    pub fn existing_output(&self) -> Option<PathBuf> {
+        [self.archive_path.as_ref(), self.output_path.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(PathBuf::from)
+            .find(|path| path.exists() || !existing_volumes(path).is_empty())
+    }
+}
+
+/// Narrows a history listing; unset fields match everything
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryFilter {
+    /// Case-insensitive text matched against game name, AppID, build ID and paths
+    pub query: Option<String>,
+    pub appid: Option<String>,
+    pub branch: Option<String>,
+    pub platform: Option<String>,
+    pub status: Option<HistoryStatus>,
+    /// Only entries finished at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only entries finished at or before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Maximum number of entries to return (newest first)
+    pub limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let field_matches = |filter: &Option<String>, value: &str| {
+            filter
+                .as_deref()
+                .map(str::trim)
+                .filter(|filter| !filter.is_empty())
+                .is_none_or(|filter| filter.eq_ignore_ascii_case(value))
+        };
+        if !field_matches(&self.appid, &entry.appid)
+            || !field_matches(&self.branch, &entry.branch)
+            || !field_matches(&self.platform, &entry.platform)
+        {
+            return false;
+        }
+        if self.status.is_some_and(|status| status != entry.status) {
+            return false;
+        }
+        if self.since.is_some_and(|since| entry.finished_at < since)
+            || self.until.is_some_and(|until| entry.finished_at > until)
+        {
+            return false;
+        }
+
+        let query = self.query.as_deref().unwrap_or("").trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        [
+            Some(&entry.game_name),
+            Some(&entry.appid),
+            Some(&entry.build_id),
+            Some(&entry.branch),
+            Some(&entry.platform),
+            entry.output_path.as_ref(),
+            entry.archive_path.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|value| value.to_lowercase().contains(&query))
+    }
+
+    /// Returns the matching entries, newest first
+    pub fn apply(&self, entries: Vec<HistoryEntry>) -> Vec<HistoryEntry> {
+        let mut matching: Vec<HistoryEntry> =
+            entries.into_iter().filter(|entry| self.matches(entry)).collect();
+        matching.sort_by_key(|entry| std::cmp::Reverse(entry.finished_at));
+        if let Some(limit) = self.limit {
+            matching.truncate(limit);
+        }
+        matching
+    }
+}
+
+pub fn history_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.app_data_dir()?.join(HISTORY_FILE_NAME))
+}
+
+/// Appends an entry to the catalogue
+pub fn record_history(host: &dyn JobHost, entry: &HistoryEntry) -> Result<(), String> {
+    let path = history_path(host)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize history entry: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Reads every entry of the catalogue; a missing file is an empty history
+pub fn read_history(host: &dyn JobHost) -> Result<Vec<HistoryEntry>, String> {
+    let path = history_path(host)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(parse_history(&text))
+}
+
+/// Parses history lines, skipping ones that can't be read (e.g. a line cut
+/// short by a crash) so one bad line doesn't hide the rest of the catalogue
+fn parse_history(text: &str) -> Vec<HistoryEntry> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn find_history_entry(host: &dyn JobHost, entry_id: &str) -> Result<HistoryEntry, String> {
+    read_history(host)?
+        .into_iter()
+        .find(|entry| entry.id == entry_id)
+        .ok_or_else(|| format!("History entry not found: {}", entry_id))
+}
+
+/// Splits a final output into (output folder, archive). Archives map back to
+/// the folder they were packed from; anything else is its own output.
+fn split_output_path(final_path: &Path) -> (PathBuf, Option<PathBuf>) {
+    if final_path.is_dir() {
+        return (final_path.to_path_buf(), None);
+    }
+    match strip_archive_extension(final_path) {
+        Some(folder) => (folder, Some(final_path.to_path_buf())),
+        None => (final_path.to_path_buf(), None),
+    }
+}
+
+/// Size of an output folder, an archive, or every volume of a split archive
+fn output_size(path: &Path) -> u64 {
+    if path.is_dir() {
+        return dir_size(path);
+    }
+    if let Ok(metadata) = fs::metadata(path) {
+        return metadata.len();
+    }
+    existing_volumes(path)
+        .iter()
+        .filter_map(|volume| fs::metadata(volume).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Lists past outputs, newest first, optionally filtered
+#[tauri::command]
+pub fn list_output_history(
+    app_handle: AppHandle,
+    filter: Option<HistoryFilter>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let entries = read_history(&app_handle)?;
+    Ok(filter.unwrap_or_default().apply(entries))
+}
+
+/// Opens a past output: the output folder itself, or the folder holding its archive
+#[tauri::command]
+pub fn open_history_output(app_handle: AppHandle, entry_id: String) -> Result<(), String> {
+    let entry = find_history_entry(&app_handle, &entry_id)?;
+    let output = entry
+        .existing_output()
+        .ok_or_else(|| "The output of this entry no longer exists".to_string())?;
+    let target = if output.is_dir() {
+        output
+    } else {
+        output
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| format!("Invalid output path: {}", output.display()))?
+    };
+    open_path(&app_handle, &target)
+}
+
+/// Renders release notes for a past entry with the current template. When the
+/// output is still on disk the release-notes file next to it is rewritten too.
+#[tauri::command]
+pub fn regenerate_release_notes(app_handle: AppHandle, entry_id: String) -> Result<String, String> {
+    let entry = find_history_entry(&app_handle, &entry_id)?;
+    let metadata = entry
+        .template_metadata()
+        .ok_or_else(|| "This entry has no job metadata to render release notes from".to_string())?;
+    let blocks = load_template_data_internal(&app_handle)
+        .map(|payload| payload.blocks)
+        .unwrap_or_else(create_default_template);
+    let rendered = render_template(&blocks, &metadata)?;
+    if let Some(output) = entry.existing_output() {
+        write_template_file(&output, &metadata, Some(&blocks))?;
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job_metadata::{BuildIdSource, DepotInfo};
+    use chrono::TimeZone;
+
+    fn job_file(appid: &str, build_id: &str) -> JobMetadataFile {
+        JobMetadataFile::new(
+            "job".to_string(),
+            appid.to_string(),
+            "public".to_string(),
+            "Win64".to_string(),
+            "1001".to_string(),
+            "Test Game".to_string(),
+            build_id.to_string(),
+            BuildIdSource::AppBuildid,
+            None,
+            vec![DepotInfo {
+                depot_id: "1001".to_string(),
+                depot_name: "Content".to_string(),
+                manifest_id: "555".to_string(),
+                manifest_id_used: None,
+            }],
+        )
+    }
+
+    fn entry(appid: &str, branch: &str, platform: &str, day: u32) -> HistoryEntry {
+        let started_at = Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+        let mut entry = HistoryEntry::new("job", appid, branch, platform, None, started_at);
+        entry.finished_at = started_at;
+        entry
+    }
+
+    #[test]
+    fn test_new_entry_prefers_job_json_identity() {
+        let started_at = Utc::now();
+        let entry = HistoryEntry::new(
+            "job",
+            "1",
+            "beta",
+            "Linux64",
+            Some(job_file("440", "12345")),
+            started_at,
+        );
+        assert_eq!(entry.appid, "440");
+        assert_eq!(entry.branch, "public");
+        assert_eq!(entry.platform, "Win64");
+        assert_eq!(entry.build_id, "12345");
+        assert_eq!(entry.game_name, "Test Game");
+
+        let entry = HistoryEntry::new("job", "1", "beta", "Linux64", None, started_at);
+        assert_eq!(entry.appid, "1");
+        assert_eq!(entry.branch, "beta");
+        assert_eq!(entry.platform, "Linux64");
+        assert_eq!(entry.status, HistoryStatus::Failed);
+    }
+
+    #[test]
+    fn test_filter_matches_fields_dates_and_query() {
+        let mut first = entry("440", "public", "Win64", 1);
+        first.game_name = "Team Fortress 2".to_string();
+        let second = entry("440", "beta", "Linux64", 10);
+        let third = entry("570", "public", "Win64", 20);
+        let entries = vec![first, second, third];
+
+        let by_app = HistoryFilter {
+            appid: Some("440".to_string()),
+            ..Default::default()
+        };
+        let result = by_app.apply(entries.clone());
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].branch, "beta", "newest first");
+
+        let by_platform = HistoryFilter {
+            platform: Some("win64".to_string()),
+            branch: Some("public".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(by_platform.apply(entries.clone()).len(), 2);
+
+        let by_date = HistoryFilter {
+            since: Some(Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0).unwrap()),
+            until: Some(Utc.with_ymd_and_hms(2024, 3, 15, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        let result = by_date.apply(entries.clone());
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].appid, "440");
+
+        let by_query = HistoryFilter {
+            query: Some("fortress".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(by_query.apply(entries.clone()).len(), 1);
+
+        let limited = HistoryFilter {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(limited.apply(entries)[0].appid, "570");
+    }
+
+    #[test]
+    fn test_parse_history_skips_broken_lines() {
+        let mut completed = entry("440", "public", "Win64", 1);
+        completed.status = HistoryStatus::Completed;
+        let line = serde_json::to_string(&completed).unwrap();
+        let text = format!("{line}\n{{\"id\": \"trunc\n\n{line}\n");
+        let entries = parse_history(&text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].status, HistoryStatus::Completed);
+    }
+
+    #[test]
+    fn test_template_metadata_restores_checksums_and_update_range() {
+        let mut entry = HistoryEntry::new(
+            "job",
+            "440",
+            "public",
+            "Win64",
+            Some(job_file("440", "200")),
+            Utc::now(),
+        );
+        assert!(entry.template_metadata().is_some());
+        entry.previous_build_id = "100".to_string();
+        entry.checksums.sha256 = "abc".to_string();
+        entry.checksums.manifest = "Game.sha256".to_string();
+
+        let metadata = entry.template_metadata().unwrap();
+        assert_eq!(metadata.build_id, "200");
+        assert_eq!(metadata.previous_build_id, "100");
+        assert_eq!(metadata.archive_sha256, "abc");
+        assert_eq!(metadata.checksums_file, "Game.sha256");
+
+        entry.job = None;
+        assert!(entry.template_metadata().is_none());
+    }
+
+    #[test]
+    fn test_packs_build_matches_identity_of_completed_full_outputs() {
+        let mut entry = HistoryEntry::new(
+            "job",
+            "440",
+            "public",
+            "Win64",
+            Some(job_file("440", "200")),
+            Utc::now(),
+        );
+        let mut wanted = job_file("440", "200");
+        wanted.branch = "Public".to_string();
+        wanted.language = Some("english".to_string());
+        assert!(!entry.packs_build(&wanted), "failed entries never count");
+
+        entry.status = HistoryStatus::Completed;
+        assert!(entry.packs_build(&wanted));
+
+        let mut other_build = wanted.clone();
+        other_build.build_id = "201".to_string();
+        assert!(!entry.packs_build(&other_build));
+
+        let mut german = wanted.clone();
+        german.language = Some("german".to_string());
+        assert!(!entry.packs_build(&german));
+
+        let mut low_violence = wanted.clone();
+        low_violence.low_violence = true;
+        assert!(!entry.packs_build(&low_violence));
+
+        entry.previous_build_id = "100".to_string();
+        assert!(!entry.packs_build(&wanted), "update packs are not full builds");
+    }
+
+    #[test]
+    fn test_split_output_path_maps_archives_to_their_folder() {
+        let (output, archive) = split_output_path(Path::new("/outputs/Game.Build.1.Win64.public.7z"));
+        assert_eq!(output, PathBuf::from("/outputs/Game.Build.1.Win64.public"));
+        assert_eq!(
+            archive,
+            Some(PathBuf::from("/outputs/Game.Build.1.Win64.public.7z"))
+        );
+
+        let (output, archive) =
+            split_output_path(Path::new("/library/steamapps/appmanifest_440.acf"));
+        assert_eq!(output, PathBuf::from("/library/steamapps/appmanifest_440.acf"));
+        assert_eq!(archive, None);
+    }
+}
@@ -0,0 +1,530 @@
+//! Keeps secrets and account details out of logs
+//!
+//! Every log line goes through `redact_log_line` on its way to the UI, the debug
+//! console or the CLI. Which rules apply (password arguments, emails, usernames,
+//! Steam IDs, tokens) and any extra patterns are configured in `redaction.json`
+//! in the app data directory; password arguments are masked regardless. A
+//! share-safe export always applies every rule.
+//!
+//! `redact_password_args` masks `-password`/`--password` in an argument list
+//! before it is logged.
+
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use tauri::AppHandle;
+
+use crate::accounts::load_account_settings;
+use crate::job_host::JobHost;
+
+const MASK: &str = "********";
+const SETTINGS_FILE_NAME: &str = "redaction.json";
+/// Share-safe exports are written here, below the downloads directory
+const SHARE_SAFE_DIR: &str = "logs/share-safe";
+/// Shorter usernames would mask ordinary words
+const MIN_USERNAME_LEN: usize = 3;
+/// Names of letters only and shorter than this could be ordinary words ("steam",
+/// "depot"), so they are only masked where a log line names them as a login
+const WORD_LIKE_USERNAME_LEN: usize = 8;
+
+/// Kind of detail masked in log lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RedactionRule {
+    /// `-password <value>` / `--password=<value>`
+    PasswordArgs,
+    Emails,
+    /// `-username <name>`, DepotDownloader's login lines and known account names
+    Usernames,
+    /// SteamID64 (`7656119…`) and SteamID3 (`[U:1:…]`)
+    SteamIds,
+    /// JWTs and `token=`/`guard data:` style values
+    Tokens,
+}
+
+impl RedactionRule {
+    pub const ALL: [RedactionRule; 5] = [
+        RedactionRule::PasswordArgs,
+        RedactionRule::Emails,
+        RedactionRule::Usernames,
+        RedactionRule::SteamIds,
+        RedactionRule::Tokens,
+    ];
+}
+
+fn default_rules() -> Vec<RedactionRule> {
+    RedactionRule::ALL.to_vec()
+}
+
+/// Saved redaction settings (`redaction.json`)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionSettings {
+    /// Rules applied to live logs
+    #[serde(default = "default_rules")]
+    pub rules: Vec<RedactionRule>,
+    /// Extra regular expressions; matches are masked in live logs and exports
+    #[serde(default)]
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+struct RulePattern {
+    rule: RedactionRule,
+    regex: Regex,
+    /// Replacement; `$keep` groups are kept around the masked value
+    replacement: &'static str,
+}
+
+fn rule_patterns() -> &'static [RulePattern] {
+    static PATTERNS: OnceLock<Vec<RulePattern>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let pattern = |rule, regex: &str, replacement| RulePattern {
+            rule,
+            regex: Regex::new(regex).expect("valid redaction pattern"),
+            replacement,
+        };
+        vec![
+            pattern(
+                RedactionRule::PasswordArgs,
+                r"(?i)(?P<keep>(?:^|\s)--?password(?:=|\s+))\S+",
+                "${keep}********",
+            ),
+            pattern(
+                RedactionRule::Emails,
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+                "<email>",
+            ),
+            pattern(
+                RedactionRule::Usernames,
+                r"(?i)(?P<keep>(?:^|\s)--?username(?:=|\s+))\S+",
+                "${keep}<username>",
+            ),
+            pattern(
+                RedactionRule::Usernames,
+                r"(?P<keep>Logging ')[^']+(?P<tail>' into Steam)",
+                "${keep}<username>${tail}",
+            ),
+            pattern(
+                RedactionRule::Usernames,
+                r#"(?P<keep>Enter account password for ")[^"]+"#,
+                "${keep}<username>",
+            ),
+            pattern(
+                RedactionRule::Usernames,
+                r#"(?P<keep>"AccountName"\s+")[^"]+"#,
+                "${keep}<username>",
+            ),
+            pattern(RedactionRule::SteamIds, r"\b7656119\d{10}\b", "<steamid>"),
+            pattern(RedactionRule::SteamIds, r"\[U:1:\d+\]", "<steamid>"),
+            pattern(
+                RedactionRule::Tokens,
+                r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+                "<token>",
+            ),
+            pattern(
+                RedactionRule::Tokens,
+                r"(?i)(?P<keep>\b(?:access|refresh|auth|login)[_ ]?token\s*[:=]\s*|\bguard ?data\s*[:=]\s*)\S+",
+                "${keep}<token>",
+            ),
+        ]
+    })
+}
+
+/// Compiled redaction settings
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    rules: Vec<RedactionRule>,
+    custom: Vec<Regex>,
+    usernames: Vec<String>,
+    username_pattern: Option<Regex>,
+}
+
+impl Redactor {
+    /// Redactor for live logs; password arguments are masked even when not listed
+    pub fn new(settings: &RedactionSettings) -> Result<Self, String> {
+        let mut rules = settings.rules.clone();
+        if !rules.contains(&RedactionRule::PasswordArgs) {
+            rules.push(RedactionRule::PasswordArgs);
+        }
+        let custom = settings
+            .custom_patterns
+            .iter()
+            .filter(|pattern| !pattern.trim().is_empty())
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid redaction pattern {:?}: {}", pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            rules,
+            custom,
+            usernames: Vec::new(),
+            username_pattern: None,
+        })
+    }
+
+    /// Redactor for share-safe exports: every rule plus the custom patterns
+    pub fn share_safe(settings: &RedactionSettings) -> Result<Self, String> {
+        let mut redactor = Self::new(settings)?;
+        redactor.rules = default_rules();
+        Ok(redactor)
+    }
+
+    /// Masks this account name wherever it appears as a whole word (with the
+    /// `Usernames` rule). Names that could be ordinary words are skipped.
+    pub fn add_username(&mut self, username: &str) {
+        let username = username.trim();
+        if !masks_everywhere(username)
+            || self
+                .usernames
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(username))
+        {
+            return;
+        }
+        self.usernames.push(username.to_string());
+        let alternatives = self
+            .usernames
+            .iter()
+            .map(|name| regex::escape(name))
+            .collect::<Vec<_>>()
+            .join("|");
+        self.username_pattern = Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives)).ok();
+    }
+
+    fn applies(&self, rule: RedactionRule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    pub fn redact<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(line);
+        for pattern in rule_patterns() {
+            if self.applies(pattern.rule) {
+                if let Cow::Owned(replaced) =
+                    pattern.regex.replace_all(&line, pattern.replacement)
+                {
+                    line = Cow::Owned(replaced);
+                }
+            }
+        }
+        if self.applies(RedactionRule::Usernames) {
+            if let Some(pattern) = &self.username_pattern {
+                if let Cow::Owned(replaced) = pattern.replace_all(&line, "<username>") {
+                    line = Cow::Owned(replaced);
+                }
+            }
+        }
+        for pattern in &self.custom {
+            if let Cow::Owned(replaced) = pattern.replace_all(&line, MASK) {
+                line = Cow::Owned(replaced);
+            }
+        }
+        line
+    }
+}
+
+/// Whether a known account name can be masked wherever it appears without
+/// mangling ordinary words
+fn masks_everywhere(username: &str) -> bool {
+    let word_like = username.len() < WORD_LIKE_USERNAME_LEN
+        && username.chars().all(|c| c.is_ascii_alphabetic());
+    username.len() >= MIN_USERNAME_LEN && !word_like
+}
+
+fn active_redactor() -> &'static RwLock<Redactor> {
+    static ACTIVE: OnceLock<RwLock<Redactor>> = OnceLock::new();
+    ACTIVE.get_or_init(|| {
+        RwLock::new(
+            Redactor::new(&RedactionSettings::default()).expect("default redaction settings"),
+        )
+    })
+}
+
+/// Applies the active redaction settings to a log line
+pub fn redact_log_line(line: &str) -> Cow<'_, str> {
+    match active_redactor().read() {
+        Ok(redactor) => Cow::Owned(redactor.redact(line).into_owned()),
+        Err(_) => Cow::Borrowed(line),
+    }
+}
+
+/// Adds an account name to the ones masked in live logs (e.g. when a job logs in)
+pub fn remember_username(username: &str) {
+    if let Ok(mut redactor) = active_redactor().write() {
+        redactor.add_username(username);
+    }
+}
+
+fn install_redactor(mut redactor: Redactor) {
+    if let Ok(mut active) = active_redactor().write() {
+        for username in &active.usernames {
+            redactor.add_username(username);
+        }
+        *active = redactor;
+    }
+}
+
+fn masks_password_flag(arg: &str) -> bool {
+    let flag = arg.trim_start_matches('-');
+    arg.starts_with('-') && flag.eq_ignore_ascii_case("password")
+}
+
+/// Masks the value following `-password` (or `--password`) and `-password=<value>`
+pub fn redact_password_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut mask_next = false;
+    for arg in args {
+        if mask_next {
+            redacted.push(MASK.to_string());
+            mask_next = false;
+            continue;
+        }
+        match arg.split_once('=') {
+            Some((flag, _)) if masks_password_flag(flag) => {
+                redacted.push(format!("{flag}={MASK}"))
+            }
+            _ => {
+                mask_next = masks_password_flag(arg);
+                redacted.push(arg.clone());
+            }
+        }
+    }
+    redacted
+}
+
+fn settings_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.app_data_dir()?.join(SETTINGS_FILE_NAME))
+}
+
+fn read_settings_file(path: &Path) -> Result<RedactionSettings, String> {
+    if !path.exists() {
+        return Ok(RedactionSettings::default());
+    }
+
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read redaction settings: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse redaction settings: {}", e))
+}
+
+/// Saved account names, so they are masked before any job mentions them
+fn saved_usernames(host: &dyn JobHost) -> Vec<String> {
+    load_account_settings(host)
+        .map(|settings| {
+            settings
+                .accounts
+                .into_iter()
+                .map(|account| account.username)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Loads the saved redaction settings and makes them the active ones
+pub fn apply_redaction_settings(host: &dyn JobHost) -> Result<(), String> {
+    let settings = read_settings_file(&settings_path(host)?)?;
+    let mut redactor = Redactor::new(&settings)?;
+    for username in saved_usernames(host) {
+        redactor.add_username(&username);
+    }
+    install_redactor(redactor);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_redaction_settings(app_handle: AppHandle) -> Result<RedactionSettings, String> {
+    read_settings_file(&settings_path(&app_handle)?)
+}
+
+#[tauri::command]
+pub fn save_redaction_settings(
+    app_handle: AppHandle,
+    settings: RedactionSettings,
+) -> Result<(), String> {
+    // Reject bad patterns before saving
+    Redactor::new(&settings)?;
+    let path = settings_path(&app_handle)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize redaction settings: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write redaction settings: {}", e))?;
+    apply_redaction_settings(&app_handle)
+}
+
+/// Redacts `text` with every rule, whatever the live settings are
+pub fn share_safe_text(host: &dyn JobHost, text: &str) -> Result<String, String> {
+    let settings = read_settings_file(&settings_path(host)?)?;
+    let mut redactor = Redactor::share_safe(&settings)?;
+    for username in saved_usernames(host) {
+        redactor.add_username(&username);
+    }
+    if let Ok(active) = active_redactor().read() {
+        for username in &active.usernames {
+            redactor.add_username(username);
+        }
+    }
+    Ok(text
+        .lines()
+        .map(|line| redactor.redact(line))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Writes a share-safe copy of a log to `logs/share-safe/` in the downloads
+/// directory and returns its path
+#[tauri::command]
+pub fn export_share_safe_log(app_handle: AppHandle, text: String) -> Result<PathBuf, String> {
+    let redacted = share_safe_text(&app_handle, &text)?;
+    let dir = app_handle.downloads_dir()?.join(SHARE_SAFE_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.log", Utc::now().format("%Y-%m-%dT%H-%M-%SZ")));
+    fs::write(&path, redacted + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn redactor(rules: &[RedactionRule]) -> Redactor {
+        Redactor::new(&RedactionSettings {
+            rules: rules.to_vec(),
+            custom_patterns: Vec::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_redacts_password_arguments() {
+        let redacted = redact_password_args(&args(&[
+            "-app", "480", "-username", "user", "-password", "hunter2", "--password=hunter2",
+            "-remember-password",
+        ]));
+        assert_eq!(
+            redacted,
+            args(&[
+                "-app", "480", "-username", "user", "-password", MASK, "--password=********",
+                "-remember-password",
+            ])
+        );
+        // A trailing flag has nothing to mask
+        assert_eq!(redact_password_args(&args(&["-password"])), args(&["-password"]));
+    }
+
+    #[test]
+    fn test_password_arguments_are_always_masked() {
+        let redactor = redactor(&[]);
+        assert_eq!(
+            redactor.redact("DepotDownloader args: -app 480 -username user -password hunter2 -os linux"),
+            "DepotDownloader args: -app 480 -username user -password ******** -os linux"
+        );
+        assert_eq!(redactor.redact("--PASSWORD=hunter2"), "--PASSWORD=********");
+        assert_eq!(
+            redactor.redact("-remember-password kept; -passwordless too"),
+            "-remember-password kept; -passwordless too"
+        );
+        assert_eq!(redactor.redact("Got depot key for 481"), "Got depot key for 481");
+    }
+
+    #[test]
+    fn test_redacts_emails_usernames_steam_ids_and_tokens() {
+        let mut redactor = redactor(&RedactionRule::ALL);
+        redactor.add_username("builder_bot");
+
+        assert_eq!(
+            redactor.redact("STEAM GUARD! Please enter the auth code sent to the email at team@example.co.uk"),
+            "STEAM GUARD! Please enter the auth code sent to the email at <email>"
+        );
+        assert_eq!(
+            redactor.redact("Logging 'someone' into Steam3..."),
+            "Logging '<username>' into Steam3..."
+        );
+        assert_eq!(
+            redactor.redact("args: -username someone -remember-password"),
+            "args: -username <username> -remember-password"
+        );
+        assert_eq!(
+            redactor.redact("Cached login for Builder_Bot restored"),
+            "Cached login for <username> restored"
+        );
+        assert_eq!(
+            redactor.redact("SteamID 76561198012345678 aka [U:1:52079950]"),
+            "SteamID <steamid> aka <steamid>"
+        );
+        assert_eq!(
+            redactor.redact("refresh_token=abc123 eyJhbGciOiJFZERTQSJ9.eyJzdWIiOiI3NjUifQ.c2ln"),
+            "refresh_token=<token> <token>"
+        );
+        // Manifest and depot IDs stay readable
+        assert_eq!(
+            redactor.redact("Depot 481 - Manifest 7293843242512368384"),
+            "Depot 481 - Manifest 7293843242512368384"
+        );
+    }
+
+    #[test]
+    fn test_disabled_rules_and_custom_patterns() {
+        let mut settings = RedactionSettings {
+            rules: vec![RedactionRule::SteamIds],
+            custom_patterns: vec![r"build-server-\d+".to_string()],
+        };
+        let live = Redactor::new(&settings).unwrap();
+        let line = "team@example.com on build-server-7 as 76561198012345678";
+        assert_eq!(live.redact(line), "team@example.com on ******** as <steamid>");
+
+        let shared = Redactor::share_safe(&settings).unwrap();
+        assert_eq!(shared.redact(line), "<email> on ******** as <steamid>");
+
+        settings.custom_patterns = vec!["(unclosed".to_string()];
+        assert!(Redactor::new(&settings).is_err());
+    }
+
+    #[test]
+    fn test_short_usernames_are_not_masked_everywhere() {
+        let mut redactor = redactor(&[RedactionRule::Usernames]);
+        redactor.add_username("ab");
+        assert_eq!(redactor.redact("ab testing"), "ab testing");
+    }
+
+    #[test]
+    fn test_word_like_usernames_are_only_masked_as_logins() {
+        let mut redactor = redactor(&[RedactionRule::Usernames]);
+        redactor.add_username("steam");
+        redactor.add_username("depot42");
+        assert_eq!(
+            redactor.redact("Downloading steam depot 481 to steamapps"),
+            "Downloading steam depot 481 to steamapps"
+        );
+        assert_eq!(
+            redactor.redact("Logging 'steam' into Steam3..."),
+            "Logging '<username>' into Steam3..."
+        );
+        assert_eq!(
+            redactor.redact("depot42 logged in; depot 42 and depot420 untouched"),
+            "<username> logged in; depot 42 and depot420 untouched"
+        );
+    }
+}
@@ -0,0 +1,621 @@
+//! Installing finished outputs into a Steam library
+//!
+//! A finalized output already mirrors a library (`steamapps/common/`,
+//! `steamapps/appmanifest_<appid>.acf`, `depotcache/`), so installing is a
+//! matter of finding the library and merging the tree into it. Libraries are
+//! read from `libraryfolders.vdf` under the usual Steam install paths or a
+//! user-given one. An existing appmanifest is never replaced unless the job
+//! asks for it; when it is, Steam's own keys in it are kept.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+use crate::acf_generator::merge_acf;
+use crate::archive_verification::source_stats;
+use crate::vdf::{self, VdfValue};
+
+/// How the output tree gets into the library
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallMode {
+    /// Leave the output in place and copy it into the library
+    #[default]
+    Copy,
+    /// Move the output into the library (no archive is produced)
+    Move,
+}
+
+impl InstallMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "copy" => Ok(InstallMode::Copy),
+            "move" => Ok(InstallMode::Move),
+            other => Err(format!("Unknown install mode: {} (expected copy or move)", other)),
+        }
+    }
+}
+
+/// The "install to Steam library" finalization target of a job
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SteamInstallOptions {
+    /// Library folder to install into; empty picks Steam's main library
+    #[serde(default)]
+    pub library_path: String,
+    /// Steam install directory used to look up libraries; empty tries the usual paths
+    #[serde(default)]
+    pub steam_path: String,
+    #[serde(default)]
+    pub mode: InstallMode,
+    /// Replace an existing appmanifest (and files) for the same app
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// A Steam library folder
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SteamLibrary {
+    pub path: PathBuf,
+    pub label: String,
+    /// AppIDs Steam lists as installed here (empty for the legacy file format)
+    pub apps: Vec<String>,
+    /// Free space on the library's drive, when it could be determined
+    pub free_bytes: Option<u64>,
+}
+
+/// What an install put where
+#[derive(Clone, Debug)]
+pub struct InstallReport {
+    pub library_path: PathBuf,
+    pub appmanifest_paths: Vec<PathBuf>,
+    pub bytes: u64,
+    pub merged_appmanifest: bool,
+}
+
+/// Steam install directories to look in when none was given
+pub fn default_steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if cfg!(windows) {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = std::env::var_os(var) {
+                roots.push(PathBuf::from(dir).join("Steam"));
+            }
+        }
+        roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+    } else if cfg!(target_os = "macos") {
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join("Library").join("Application Support").join("Steam"));
+        }
+    } else if let Some(home) = dirs::home_dir() {
+        roots.push(home.join(".steam").join("steam"));
+        roots.push(home.join(".steam").join("root"));
+        roots.push(home.join(".local").join("share").join("Steam"));
+        roots.push(
+            home.join(".var")
+                .join("app")
+                .join("com.valvesoftware.Steam")
+                .join(".local")
+                .join("share")
+                .join("Steam"),
+        );
+        roots.push(
+            home.join("snap")
+                .join("steam")
+                .join("common")
+                .join(".local")
+                .join("share")
+                .join("Steam"),
+        );
+    }
+    roots
+}
+
+/// Lists the Steam libraries known to `steam_root`, or to every Steam install
+/// found in the usual places when it is `None`
+pub fn find_steam_libraries(steam_root: Option<&Path>) -> Result<Vec<SteamLibrary>, String> {
+    let roots = match steam_root {
+        Some(root) => {
+            if !root.is_dir() {
+                return Err(format!("Steam directory not found: {}", root.display()));
+            }
+            vec![root.to_path_buf()]
+        }
+        None => default_steam_roots()
+            .into_iter()
+            .filter(|root| root.join("steamapps").is_dir())
+            .collect(),
+    };
+
+    let disks = Disks::new_with_refreshed_list();
+    let mut libraries: Vec<SteamLibrary> = Vec::new();
+    let mut seen: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        let mut folders = Vec::new();
+        for candidate in [
+            root.join("steamapps").join("libraryfolders.vdf"),
+            root.join("config").join("libraryfolders.vdf"),
+        ] {
+            if let Ok(text) = fs::read_to_string(&candidate) {
+                folders = parse_library_folders(&text)
+                    .map_err(|e| format!("Failed to parse {}: {}", candidate.display(), e))?;
+                break;
+            }
+        }
+        // The Steam directory is always a library, even if the file doesn't say so
+        if !folders.iter().any(|folder| same_path(&folder.path, &root)) {
+            folders.insert(
+                0,
+                LibraryFolder {
+                    path: root.clone(),
+                    label: String::new(),
+                    apps: Vec::new(),
+                },
+            );
+        }
+
+        for folder in folders {
+            let key = fs::canonicalize(&folder.path).unwrap_or_else(|_| folder.path.clone());
+            if seen.contains(&key) || !folder.path.is_dir() {
+                continue;
+            }
+            seen.push(key);
+            libraries.push(SteamLibrary {
+                free_bytes: free_space(&disks, &folder.path),
+                path: folder.path,
+                label: folder.label,
+                apps: folder.apps,
+            });
+        }
+    }
+
+    if libraries.is_empty() {
+        return Err("No Steam library found; set the Steam install path".to_string());
+    }
+    Ok(libraries)
+}
+
+/// One entry of `libraryfolders.vdf`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibraryFolder {
+    pub path: PathBuf,
+    pub label: String,
+    pub apps: Vec<String>,
+}
+
+/// Reads `libraryfolders.vdf`, both the current layout (`"0" { "path" ... }`)
+/// and the legacy one (`"1" "D:\\SteamLibrary"`)
+pub fn parse_library_folders(text: &str) -> Result<Vec<LibraryFolder>, String> {
+    let root = vdf::parse(text)?;
+    let folders = root
+        .get_section("libraryfolders")
+        .ok_or_else(|| "Missing \"libraryfolders\" section".to_string())?;
+
+    let mut result = Vec::new();
+    for (key, value) in folders.entries() {
+        // Other keys (contentstatsid, TimeNextStatsReport) aren't libraries
+        if key.is_empty() || !key.chars().all(|ch| ch.is_ascii_digit()) {
+            continue;
+        }
+        match value {
+            VdfValue::String(path) => result.push(LibraryFolder {
+                path: PathBuf::from(path),
+                label: String::new(),
+                apps: Vec::new(),
+            }),
+            VdfValue::Section(folder) => {
+                let Some(path) = folder.get_str("path") else {
+                    continue;
+                };
+                let apps = folder
+                    .get_section("apps")
+                    .map(|apps| apps.entries().iter().map(|(id, _)| id.clone()).collect())
+                    .unwrap_or_default();
+                result.push(LibraryFolder {
+                    path: PathBuf::from(path),
+                    label: folder.get_str("label").unwrap_or_default().to_string(),
+                    apps,
+                });
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Picks the library named in `options`, or Steam's main library
+pub fn resolve_install_library(options: &SteamInstallOptions) -> Result<PathBuf, String> {
+    let library_path = options.library_path.trim();
+    if !library_path.is_empty() {
+        let path = PathBuf::from(library_path);
+        if !path.is_dir() {
+            return Err(format!("Steam library not found: {}", path.display()));
+        }
+        return Ok(path);
+    }
+
+    let steam_path = options.steam_path.trim();
+    let steam_root = (!steam_path.is_empty()).then(|| Path::new(steam_path));
+    find_steam_libraries(steam_root)?
+        .into_iter()
+        .next()
+        .map(|library| library.path)
+        .ok_or_else(|| "No Steam library found".to_string())
+}
+
+/// Copies or moves the finalized output folder into a Steam library.
+///
+/// Refuses to touch an existing `appmanifest_<appid>.acf` or game folder
+/// unless `options.overwrite` is set, in which case the old game folder is
+/// removed first so no stale files survive. Checks the library's drive has room
+/// first. In move mode the output folder is gone afterwards.
+pub fn install_to_library(
+    output_dir: &Path,
+    options: &SteamInstallOptions,
+) -> Result<InstallReport, String> {
+    let library = resolve_install_library(options)?;
+    let source_steamapps = output_dir.join("steamapps");
+    let target_steamapps = library.join("steamapps");
+
+    let appmanifests = list_appmanifests(&source_steamapps)?;
+    if appmanifests.is_empty() {
+        return Err(format!("No appmanifest found in {}", source_steamapps.display()));
+    }
+    let common_dirs = list_dir(&source_steamapps.join("common"))?;
+
+    // Conflicts are checked up front so nothing is half-installed
+    if !options.overwrite {
+        let existing: Vec<PathBuf> = appmanifests
+            .iter()
+            .chain(common_dirs.iter())
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&source_steamapps).ok()?;
+                let target = target_steamapps.join(relative);
+                target.exists().then_some(target)
+            })
+            .collect();
+        if !existing.is_empty() {
+            return Err(format!(
+                "The library already contains {}; enable overwrite to replace it",
+                existing
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    let bytes = source_stats(output_dir, false)?.bytes;
+    let disks = Disks::new_with_refreshed_list();
+    let needs_space = options.mode == InstallMode::Copy
+        || mount_point(&disks, output_dir) != mount_point(&disks, &library);
+    if needs_space {
+        if let Some(free) = free_space(&disks, &library) {
+            check_free_space(bytes, free)?;
+        }
+    }
+
+    let moving = options.mode == InstallMode::Move;
+    let mut installed = Vec::new();
+    transfer_output(
+        output_dir,
+        &library,
+        &common_dirs,
+        options.overwrite,
+        moving,
+        &mut installed,
+    )
+    .map_err(|err| partial_install_error(&err, output_dir, &installed, moving))?;
+
+    let mut appmanifest_paths = Vec::new();
+    let mut merged_appmanifest = false;
+    for source in &appmanifests {
+        let target = target_steamapps.join(file_name(source)?);
+        merged_appmanifest |= write_appmanifest(source, &target)?;
+        appmanifest_paths.push(target);
+    }
+
+    if moving {
+        fs::remove_dir_all(output_dir)
+            .map_err(|e| format!("Failed to remove {}: {}", output_dir.display(), e))?;
+    }
+
+    Ok(InstallReport {
+        library_path: library,
+        appmanifest_paths,
+        bytes,
+        merged_appmanifest,
+    })
+}
+
+/// Moves or copies the game folders and depotcache into the library, recording
+/// each target once it is complete. Appmanifests are written afterwards, so
+/// Steam does not pick up a half-installed game.
+fn transfer_output(
+    output_dir: &Path,
+    library: &Path,
+    common_dirs: &[PathBuf],
+    overwrite: bool,
+    moving: bool,
+    installed: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    for dir in common_dirs {
+        let target = library.join("steamapps").join("common").join(file_name(dir)?);
+        if overwrite && target.exists() && !same_path(dir, &target) {
+            fs::remove_dir_all(&target)
+                .map_err(|e| format!("Failed to remove {}: {}", target.display(), e))?;
+        }
+        transfer(dir, &target, moving)?;
+        installed.push(target);
+    }
+    let depotcache = output_dir.join("depotcache");
+    if depotcache.is_dir() {
+        let target = library.join("depotcache");
+        transfer(&depotcache, &target, moving)?;
+        installed.push(target);
+    }
+    Ok(())
+}
+
+/// Describes what is where after an install stopped partway
+fn partial_install_error(
+    err: &str,
+    output_dir: &Path,
+    installed: &[PathBuf],
+    moving: bool,
+) -> String {
+    let completed = if installed.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " (completed: {})",
+            installed
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    if moving {
+        format!(
+            "{} after moving part of the output{}; the rest is still in {}, neither copy is complete and no appmanifest was written",
+            err,
+            completed,
+            output_dir.display()
+        )
+    } else {
+        format!(
+            "{} after copying part of the output{}; the library holds a partial copy and no appmanifest was written",
+            err, completed
+        )
+    }
+}
+
+fn check_free_space(required: u64, free: u64) -> Result<(), String> {
+    if required > free {
+        return Err(format!(
+            "Not enough free space in the Steam library: {} bytes needed, {} bytes free",
+            required, free
+        ));
+    }
+    Ok(())
+}
+
+/// Writes our appmanifest over an existing one by merging, so keys Steam added
+/// survive. Returns true when an existing file was merged.
+fn write_appmanifest(source: &Path, target: &Path) -> Result<bool, String> {
+    let text = fs::read_to_string(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    let mut acf = vdf::parse(&text)?;
+
+    let mut merged = false;
+    if let Some(mut existing) = fs::read_to_string(target)
+        .ok()
+        .and_then(|existing| vdf::parse(&existing).ok())
+        .filter(|existing| existing.get_section("AppState").is_some())
+    {
+        merge_acf(&mut existing, &acf);
+        acf = existing;
+        merged = true;
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(target, vdf::to_string(&acf))
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    Ok(merged)
+}
+
+/// Moves (renaming where possible) or copies `src` onto `dst`, merging into
+/// directories that already exist
+fn transfer(src: &Path, dst: &Path, moving: bool) -> Result<(), String> {
+    if moving && !dst.exists() {
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        if fs::rename(src, dst).is_ok() {
+            return Ok(());
+        }
+    }
+
+    let metadata = fs::symlink_metadata(src)
+        .map_err(|e| format!("Failed to inspect {}: {}", src.display(), e))?;
+    if metadata.is_dir() {
+        fs::create_dir_all(dst)
+            .map_err(|e| format!("Failed to create {}: {}", dst.display(), e))?;
+        for entry in list_dir(src)? {
+            transfer(&entry, &dst.join(file_name(&entry)?), moving)?;
+        }
+        if moving {
+            let _ = fs::remove_dir(src);
+        }
+        return Ok(());
+    }
+
+    fs::copy(src, dst).map_err(|e| {
+        format!(
+            "Failed to copy {} to {}: {}",
+            src.display(),
+            dst.display(),
+            e
+        )
+    })?;
+    if moving {
+        let _ = fs::remove_file(src);
+    }
+    Ok(())
+}
+
+fn list_appmanifests(steamapps: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(list_dir(steamapps)?
+        .into_iter()
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("appmanifest_") && name.ends_with(".acf"))
+        })
+        .collect())
+}
+
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn file_name(path: &Path) -> Result<&std::ffi::OsStr, String> {
+    path.file_name()
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Mount point of the disk holding `path` (the longest matching one)
+fn mount_point(disks: &Disks, path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    disks
+        .list()
+        .iter()
+        .map(|disk| disk.mount_point())
+        .filter(|mount| path.starts_with(mount))
+        .max_by_key(|mount| mount.as_os_str().len())
+        .map(Path::to_path_buf)
+}
+
+fn free_space(disks: &Disks, path: &Path) -> Option<u64> {
+    let mount = mount_point(disks, path)?;
+    disks
+        .list()
+        .iter()
+        .find(|disk| disk.mount_point() == mount)
+        .map(|disk| disk.available_space())
+}
+
+#[tauri::command]
+pub fn list_steam_libraries(steam_path: Option<String>) -> Result<Vec<SteamLibrary>, String> {
+    let steam_path = steam_path.filter(|path| !path.trim().is_empty());
+    find_steam_libraries(steam_path.as_deref().map(Path::new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_current_library_folders() {
+        let text = "\"libraryfolders\"\n{\n\t\"0\"\n\t{\n\t\t\"path\"\t\t\"/home/user/.local/share/Steam\"\n\t\t\"label\"\t\t\"\"\n\t\t\"apps\"\n\t\t{\n\t\t\t\"228980\"\t\t\"123\"\n\t\t\t\"620\"\t\t\"456\"\n\t\t}\n\t}\n\t\"1\"\n\t{\n\t\t\"path\"\t\t\"D:\\\\SteamLibrary\"\n\t\t\"label\"\t\t\"Games\"\n\t}\n}\n";
+        let folders = parse_library_folders(text).unwrap();
+        assert_eq!(folders.len(), 2);
+        assert_eq!(folders[0].path, PathBuf::from("/home/user/.local/share/Steam"));
+        assert_eq!(folders[0].apps, vec!["228980".to_string(), "620".to_string()]);
+        assert_eq!(folders[1].path, PathBuf::from("D:\\SteamLibrary"));
+        assert_eq!(folders[1].label, "Games");
+    }
+
+    #[test]
+    fn test_parses_legacy_library_folders() {
+        let text = "\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\t\"1600000000\"\n\t\"ContentStatsID\"\t\t\"-123\"\n\t\"1\"\t\t\"E:\\\\Games\\\\Steam\"\n}\n";
+        let folders = parse_library_folders(text).unwrap();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].path, PathBuf::from("E:\\Games\\Steam"));
+        assert!(parse_library_folders("\"other\" { }").is_err());
+    }
+
+    #[test]
+    fn test_free_space_check() {
+        assert!(check_free_space(10, 10).is_ok());
+        assert!(check_free_space(11, 10).unwrap_err().contains("11 bytes needed"));
+    }
+
+    #[test]
+    fn test_overwrite_replaces_the_existing_game_folder() {
+        let dir = std::env::temp_dir().join(format!(
+            "omnipacker_steam_library_{}",
+            crate::job_staging::generate_job_id()
+        ));
+        let output = dir.join("output");
+        let library = dir.join("library");
+        let acf = "\"AppState\"\n{\n\t\"appid\"\t\t\"620\"\n\t\"installdir\"\t\t\"Portal 2\"\n}\n";
+        fs::create_dir_all(output.join("steamapps").join("common").join("Portal 2")).unwrap();
+        fs::write(output.join("steamapps").join("common").join("Portal 2").join("new.txt"), "new")
+            .unwrap();
+        fs::write(output.join("steamapps").join("appmanifest_620.acf"), acf).unwrap();
+        let old_game = library.join("steamapps").join("common").join("Portal 2");
+        fs::create_dir_all(&old_game).unwrap();
+        fs::write(old_game.join("stale.txt"), "old").unwrap();
+
+        let mut options = SteamInstallOptions {
+            library_path: library.display().to_string(),
+            ..Default::default()
+        };
+        assert!(install_to_library(&output, &options)
+            .unwrap_err()
+            .contains("enable overwrite"));
+
+        options.overwrite = true;
+        install_to_library(&output, &options).unwrap();
+        assert!(old_game.join("new.txt").exists());
+        assert!(!old_game.join("stale.txt").exists());
+        assert!(library.join("steamapps").join("appmanifest_620.acf").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_partial_install_error_says_what_is_where() {
+        let installed = vec![PathBuf::from("/library/steamapps/common/Game")];
+        let moved = partial_install_error("disk full", Path::new("/out"), &installed, true);
+        assert!(moved.contains("disk full"));
+        assert!(moved.contains("/library/steamapps/common/Game"));
+        assert!(moved.contains("still in /out"));
+        let copied = partial_install_error("disk full", Path::new("/out"), &[], false);
+        assert!(copied.contains("partial copy"));
+        assert!(!copied.contains("completed"));
+    }
+
+    #[test]
+    fn test_parses_install_mode() {
+        assert_eq!(InstallMode::parse("Move").unwrap(), InstallMode::Move);
+        assert_eq!(InstallMode::parse("copy").unwrap(), InstallMode::Copy);
+        assert!(InstallMode::parse("link").is_err());
+    }
+}
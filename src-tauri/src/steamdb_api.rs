@@ -29,8 +29,30 @@ pub fn fetch_build_date(app_id: &str, build_id: Option<&str>) -> Result<DateTime
         }
     }
 
+    let body = fetch_patchnotes_rss(app_id)?;
+
+    // Parse the RSS XML
+    let build_date = parse_patchnotes_rss(&body, build_id)?;
+
+    // Cache the result
+    if let Ok(mut guard) = BUILD_DATE_CACHE.lock() {
+        let cache = guard.get_or_insert_with(HashMap::new);
+        cache.insert(cache_key, build_date);
+    }
+
+    Ok(build_date)
+}
+
+/// Fetches the most recent build ID of an app's public branch from SteamDB's
+/// patchnotes RSS feed. Never cached, so repeated calls notice new builds.
+pub fn fetch_latest_build_id(app_id: &str) -> Result<String, String> {
+    let body = fetch_patchnotes_rss(app_id)?;
+    parse_latest_build_id(&body).ok_or_else(|| "No builds found in SteamDB RSS feed".to_string())
+}
+
+fn fetch_patchnotes_rss(app_id: &str) -> Result<String, String> {
     let url = format!("https://steamdb.info/api/PatchnotesRSS/?appid={}", app_id);
-    eprintln!("[STEAMDB] Fetching build date from: {}", url);
+    eprintln!("[STEAMDB] Fetching patch notes from: {}", url);
 
     // Use reqwest blocking client for HTTP request
     let client = reqwest::blocking::Client::new();
@@ -40,20 +62,17 @@ pub fn fetch_build_date(app_id: &str, build_id: Option<&str>) -> Result<DateTime
         .send()
         .map_err(|e| format!("HTTP request failed: {}", e))?;
 
-    let body = response
+    response
         .text()
-        .map_err(|e| format!("Failed to read response body: {}", e))?;
-
-    // Parse the RSS XML
-    let build_date = parse_patchnotes_rss(&body, build_id)?;
-
-    // Cache the result
-    if let Ok(mut guard) = BUILD_DATE_CACHE.lock() {
-        let cache = guard.get_or_insert_with(HashMap::new);
-        cache.insert(cache_key, build_date);
-    }
+        .map_err(|e| format!("Failed to read response body: {}", e))
+}
 
-    Ok(build_date)
+/// Returns the build ID of the first (newest) item in a patchnotes RSS feed
+fn parse_latest_build_id(xml: &str) -> Option<String> {
+    let title_regex = regex::Regex::new(r"<item>[\s\S]*?<title>[^<]*?Build\s+(\d+)").ok()?;
+    title_regex
+        .captures(xml)
+        .map(|caps| caps[1].to_string())
 }
 
 /// Parses SteamDB patchnotes RSS feed to extract build date
@@ -220,5 +239,25 @@ mod tests {
         assert_eq!(dt.day(), 20);
     }
 
+    #[test]
+    fn test_parse_latest_build_id() {
+        let xml = r#"
+        <rss version="2.0">
+          <channel>
+            <title>Patches for app 480</title>
+            <item>
+              <title>Update - Build 18674832</title>
+              <pubDate>Mon, 24 Feb 2025 22:02:36 GMT</pubDate>
+            </item>
+            <item>
+              <title>Update - Build 18674000</title>
+            </item>
+          </channel>
+        </rss>
+        "#;
+        assert_eq!(parse_latest_build_id(xml).as_deref(), Some("18674832"));
+        assert_eq!(parse_latest_build_id("<rss></rss>"), None);
+    }
+
     use chrono::Datelike;
 }
@@ -0,0 +1,509 @@
+//! In-process tar.zst / tar.xz writer
+//!
+//! Mirrors what `7zz a <archive> <folder>` produces: the output folder itself is
+//! the single top-level entry. Progress and log lines go through the same 7z
+//! events the UI already listens to, and `cancel_7zip` stops the writer.
+//! Compression profiles apply their level and thread cap; method, dictionary,
+//! solid and filter overrides are 7z-only and ignored here.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::archive_format::{existing_volumes, volume_path, volume_set_path, ArchiveFormat};
+use crate::archive_verification::ArchiveStats;
+use crate::compression_profile::CompressionProfile;
+use crate::job_host::SharedJobHost;
+use crate::zip_runner::{compression_budget, SevenZipRunnerState};
+
+/// zstd level for release archives; 19 is the highest non-"ultra" level
+const ZSTD_LEVEL: i32 = 19;
+
+/// Writes `source_dir` into a compressed tarball at `archive_path`, split into
+/// volumes when `volume_size_mb` is set.
+/// A partial archive is left behind on error; callers remove it.
+#[allow(clippy::too_many_arguments)]
+pub fn write_tar_archive(
+    host: &SharedJobHost,
+    state: &SevenZipRunnerState,
+    source_dir: &Path,
+    archive_path: &Path,
+    format: ArchiveFormat,
+    volume_size_mb: Option<u64>,
+    profile: Option<&CompressionProfile>,
+    job_id: &str,
+) -> Result<(), String> {
+    let _running = state.begin_native()?;
+
+    let root_name = source_dir
+        .file_name()
+        .ok_or_else(|| format!("Invalid source directory: {}", source_dir.display()))?
+        .to_os_string();
+    let entries = collect_entries(source_dir)?;
+    let total_bytes = entries
+        .iter()
+        .filter(|entry| entry.is_file)
+        .map(|entry| entry.size)
+        .sum();
+
+    let budget = compression_budget();
+    let threads = profile.map_or(budget.threads, |profile| profile.cap_threads(budget.threads));
+    let level = profile.map(|profile| profile.effective_level());
+    host.emit_7z_log(
+        "stdout",
+        &format!(
+            "Creating {} archive: {} ({} files, {} threads)",
+            format.extension(),
+            archive_path.display(),
+            entries.iter().filter(|entry| entry.is_file).count(),
+            threads
+        ),
+        job_id,
+    );
+
+    let file = VolumeWriter::new(archive_path, volume_size_mb)?;
+
+    let mut progress = Progress {
+        host,
+        state,
+        total_bytes,
+        written_bytes: 0,
+        last_percent: None,
+    };
+    let root = Path::new(&root_name);
+
+    match format {
+        ArchiveFormat::TarZst => {
+            let zstd_level = level.map_or(ZSTD_LEVEL, zstd_level);
+            let mut encoder = zstd::stream::write::Encoder::new(file, zstd_level)
+                .map_err(|e| format!("Failed to start zstd encoder: {}", e))?;
+            encoder
+                .multithread(threads as u32)
+                .map_err(|e| format!("Failed to enable zstd threads: {}", e))?;
+            encoder
+                .long_distance_matching(true)
+                .map_err(|e| format!("Failed to enable zstd long matching: {}", e))?;
+            let encoder = write_entries(encoder, source_dir, root, &entries, &mut progress)?;
+            encoder
+                .finish()
+                .and_then(|mut file| file.flush())
+                .map_err(|e| format!("Failed to finish zstd stream: {}", e))?;
+        }
+        ArchiveFormat::TarXz => {
+            // A profile level never raises the preset above what free memory allows
+            let preset = xz_preset(budget.dict_bytes);
+            let preset = level.map_or(preset, |level| u32::from(level).min(preset));
+            let stream = xz2::stream::MtStreamBuilder::new()
+                .threads(threads as u32)
+                .preset(preset)
+                .check(xz2::stream::Check::Crc64)
+                .encoder()
+                .map_err(|e| format!("Failed to start xz encoder: {}", e))?;
+            let encoder = xz2::write::XzEncoder::new_stream(file, stream);
+            let encoder = write_entries(encoder, source_dir, root, &entries, &mut progress)?;
+            encoder
+                .finish()
+                .and_then(|mut file| file.flush())
+                .map_err(|e| format!("Failed to finish xz stream: {}", e))?;
+        }
+        ArchiveFormat::SevenZip | ArchiveFormat::Zip => {
+            return Err(format!(
+                "{} archives are written by 7-Zip, not the tar writer",
+                format.extension()
+            ));
+        }
+    }
+
+    host.emit_7z_progress(100);
+    host.emit_7z_log("stdout", "Everything is Ok", job_id);
+    Ok(())
+}
+
+/// Reads a tarball written by `write_tar_archive` back from disk, joining split
+/// volumes, and totals its files. Every entry is fully decompressed, so a
+/// truncated or corrupt archive fails here.
+pub fn read_tar_archive_stats(
+    host: &SharedJobHost,
+    state: &SevenZipRunnerState,
+    archive_path: &Path,
+    format: ArchiveFormat,
+    expected_bytes: u64,
+) -> Result<ArchiveStats, String> {
+    let _running = state.begin_native()?;
+
+    // `archive_path` may name the archive or any of its volumes
+    let volumes = existing_volumes(&volume_set_path(archive_path));
+    let parts = if volumes.is_empty() {
+        vec![archive_path.to_path_buf()]
+    } else {
+        volumes
+    };
+    let mut reader: Box<dyn Read> = Box::new(io::empty());
+    for part in &parts {
+        let file = File::open(part)
+            .map_err(|e| format!("Failed to open archive {}: {}", part.display(), e))?;
+        reader = Box::new(reader.chain(BufReader::new(file)));
+    }
+
+    let decoder: Box<dyn Read> = match format {
+        ArchiveFormat::TarZst => Box::new(
+            zstd::stream::read::Decoder::new(reader)
+                .map_err(|e| format!("Failed to start zstd decoder: {}", e))?,
+        ),
+        ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new(reader)),
+        ArchiveFormat::SevenZip | ArchiveFormat::Zip => {
+            return Err(format!(
+                "{} archives are tested by 7-Zip, not the tar reader",
+                format.extension()
+            ));
+        }
+    };
+
+    let mut progress = Progress {
+        host,
+        state,
+        total_bytes: expected_bytes,
+        written_bytes: 0,
+        last_percent: None,
+    };
+    let mut archive = tar::Archive::new(decoder);
+    let mut stats = ArchiveStats::default();
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| progress.describe_read_error(e))?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_file() {
+            let mut reader = ProgressReader {
+                inner: &mut entry,
+                progress: &mut progress,
+            };
+            let bytes = io::copy(&mut reader, &mut io::sink())
+                .map_err(|e| progress.describe_read_error(e))?;
+            stats.files += 1;
+            stats.bytes += bytes;
+        } else if entry_type.is_symlink() {
+            stats.files += 1;
+        }
+    }
+
+    host.emit_7z_progress(100);
+    Ok(stats)
+}
+
+/// Maps a 0-9 profile level onto zstd's 1-19 range (0, store, becomes the fastest level)
+fn zstd_level(level: u8) -> i32 {
+    const LEVELS: [i32; 10] = [1, 1, 3, 5, 7, 9, 12, 15, 17, ZSTD_LEVEL];
+    LEVELS[usize::from(level.min(9))]
+}
+
+/// Maps the LZMA dictionary budget onto the xz preset with that dictionary size
+/// (6 = 8 MiB, 7 = 16 MiB, 8 = 32 MiB, 9 = 64 MiB)
+fn xz_preset(dict_bytes: u64) -> u32 {
+    const MB: u64 = 1024 * 1024;
+    match dict_bytes {
+        bytes if bytes >= 64 * MB => 9,
+        bytes if bytes >= 32 * MB => 8,
+        bytes if bytes >= 16 * MB => 7,
+        _ => 6,
+    }
+}
+
+struct TarEntry {
+    /// Path relative to the source directory
+    relative: PathBuf,
+    is_file: bool,
+    size: u64,
+}
+
+/// Lists everything below `source_dir` in a stable order, parents before children
+fn collect_entries(source_dir: &Path) -> Result<Vec<TarEntry>, String> {
+    let mut entries = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(relative_dir) = pending.pop() {
+        let dir = source_dir.join(&relative_dir);
+        let mut children = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        children.sort_by_key(|entry| entry.file_name());
+
+        let mut subdirs = Vec::new();
+        for child in children {
+            let relative = relative_dir.join(child.file_name());
+            let metadata = fs::symlink_metadata(child.path())
+                .map_err(|e| format!("Failed to inspect {}: {}", child.path().display(), e))?;
+            if metadata.is_dir() {
+                subdirs.push(relative.clone());
+            }
+            entries.push(TarEntry {
+                relative,
+                is_file: metadata.is_file(),
+                size: if metadata.is_file() { metadata.len() } else { 0 },
+            });
+        }
+        // Reverse so the stack pops subdirectories in name order
+        pending.extend(subdirs.into_iter().rev());
+    }
+
+    Ok(entries)
+}
+
+fn write_entries<W: Write>(
+    writer: W,
+    source_dir: &Path,
+    root: &Path,
+    entries: &[TarEntry],
+    progress: &mut Progress,
+) -> Result<W, String> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+
+    builder
+        .append_dir(root, source_dir)
+        .map_err(|e| format!("Failed to add {}: {}", root.display(), e))?;
+
+    for entry in entries {
+        let path = source_dir.join(&entry.relative);
+        let name = root.join(&entry.relative);
+
+        if entry.is_file {
+            let file = File::open(&path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            let metadata = file
+                .metadata()
+                .map_err(|e| format!("Failed to inspect {}: {}", path.display(), e))?;
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(&metadata);
+            builder
+                .append_data(&mut header, &name, ProgressReader { inner: file, progress })
+                .map_err(|e| progress.describe_error(&name, e))?;
+        } else {
+            builder
+                .append_path_with_name(&path, &name)
+                .map_err(|e| progress.describe_error(&name, e))?;
+        }
+    }
+
+    builder
+        .into_inner()
+        .map_err(|e| format!("Failed to finish tar stream: {}", e))
+}
+
+/// Output sink that writes either a single archive file or, with a volume size,
+/// `<archive>.001`, `<archive>.002`, ... the same way 7-Zip's `-v` does
+struct VolumeWriter {
+    archive_path: PathBuf,
+    volume_bytes: Option<u64>,
+    volume_index: u32,
+    current: BufWriter<File>,
+    current_bytes: u64,
+}
+
+impl VolumeWriter {
+    fn new(archive_path: &Path, volume_size_mb: Option<u64>) -> Result<Self, String> {
+        let volume_bytes = volume_size_mb.map(|size| size.saturating_mul(1024 * 1024).max(1));
+        let first_path = match volume_bytes {
+            Some(_) => volume_path(archive_path, 1),
+            None => archive_path.to_path_buf(),
+        };
+        let file = File::create(&first_path)
+            .map_err(|e| format!("Failed to create archive {}: {}", first_path.display(), e))?;
+
+        Ok(Self {
+            archive_path: archive_path.to_path_buf(),
+            volume_bytes,
+            volume_index: 1,
+            current: BufWriter::new(file),
+            current_bytes: 0,
+        })
+    }
+
+    fn start_next_volume(&mut self) -> io::Result<()> {
+        self.current.flush()?;
+        self.volume_index += 1;
+        let file = File::create(volume_path(&self.archive_path, self.volume_index))?;
+        self.current = BufWriter::new(file);
+        self.current_bytes = 0;
+        Ok(())
+    }
+}
+
+impl Write for VolumeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(volume_bytes) = self.volume_bytes else {
+            return self.current.write(buf);
+        };
+        if self.current_bytes >= volume_bytes {
+            self.start_next_volume()?;
+        }
+        let room = (volume_bytes - self.current_bytes).min(buf.len() as u64) as usize;
+        let written = self.current.write(&buf[..room])?;
+        self.current_bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current.flush()
+    }
+}
+
+struct Progress<'a> {
+    host: &'a SharedJobHost,
+    state: &'a SevenZipRunnerState,
+    total_bytes: u64,
+    written_bytes: u64,
+    last_percent: Option<u8>,
+}
+
+impl Progress<'_> {
+    fn advance(&mut self, bytes: usize) -> io::Result<()> {
+        if self.state.native_cancel_requested() {
+            return Err(io::Error::other("Cancelled"));
+        }
+        self.written_bytes += bytes as u64;
+        let percent = (self.written_bytes * 100)
+            .checked_div(self.total_bytes)
+            .map_or(0, |percent| percent.min(99) as u8);
+        if self.last_percent != Some(percent) {
+            self.last_percent = Some(percent);
+            self.host.emit_7z_progress(percent);
+        }
+        Ok(())
+    }
+
+    fn describe_read_error(&self, err: io::Error) -> String {
+        if self.state.native_cancel_requested() {
+            "Verification cancelled".to_string()
+        } else {
+            format!("Failed to read archive: {}", err)
+        }
+    }
+
+    fn describe_error(&self, name: &Path, err: io::Error) -> String {
+        if self.state.native_cancel_requested() {
+            "Compression cancelled".to_string()
+        } else {
+            format!("Failed to add {}: {}", name.display(), err)
+        }
+    }
+}
+
+struct ProgressReader<'a, 'b, R> {
+    inner: R,
+    progress: &'a mut Progress<'b>,
+}
+
+impl<R: Read> Read for ProgressReader<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.advance(read)?;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::archive_verification::source_stats;
+    use crate::job_host::JobHost;
+    use crate::output_conflict::OutputConflictChoice;
+
+    /// Host that drops every event; the tar writer only reports progress to it
+    struct SilentHost;
+
+    impl JobHost for SilentHost {
+        fn emit_log(&self, _stream: &str, _line: &str, _job_id: &str) {}
+
+        fn emit_status(&self, _status: &str, _code: Option<i32>, _job_id: &str) {}
+
+        fn emit_7z_log(&self, _stream: &str, _line: &str, _job_id: &str) {}
+
+        fn emit_7z_progress(&self, _percent: u8) {}
+
+        fn downloads_dir(&self) -> Result<PathBuf, String> {
+            Err("not used".to_string())
+        }
+
+        fn app_data_dir(&self) -> Result<PathBuf, String> {
+            Err("not used".to_string())
+        }
+
+        fn resource_path(&self, _relative: &str) -> Result<PathBuf, String> {
+            Err("not used".to_string())
+        }
+
+        fn resolve_output_conflict(
+            &self,
+            _job_id: &str,
+            _output_path: &Path,
+        ) -> Result<OutputConflictChoice, String> {
+            Err("not used".to_string())
+        }
+    }
+
+    /// Bytes zstd cannot shrink, so a small tree still spans several volumes
+    fn noise(len: usize) -> Vec<u8> {
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_tar_zst_round_trip() {
+        let dir = std::env::temp_dir().join(format!(
+            "omnipacker_tar_{}",
+            crate::job_staging::generate_job_id()
+        ));
+        let source = dir.join("Game");
+        fs::create_dir_all(source.join("data")).unwrap();
+        fs::write(source.join("readme.txt"), "hello").unwrap();
+        fs::write(source.join("data").join("pak0.bin"), noise(1536 * 1024)).unwrap();
+        fs::create_dir_all(source.join("empty")).unwrap();
+
+        let host: SharedJobHost = Arc::new(SilentHost);
+        let state = SevenZipRunnerState::new();
+        let archive = dir.join("Game.tar.zst");
+        write_tar_archive(
+            &host,
+            &state,
+            &source,
+            &archive,
+            ArchiveFormat::TarZst,
+            Some(1),
+            None,
+            "",
+        )
+        .unwrap();
+
+        assert!(!archive.exists());
+        assert!(volume_path(&archive, 1).exists());
+        assert!(volume_path(&archive, 2).exists());
+        assert!(!volume_path(&archive, 3).exists());
+        assert_eq!(fs::metadata(volume_path(&archive, 1)).unwrap().len(), 1024 * 1024);
+
+        let expected = source_stats(&source, false).unwrap();
+        assert_eq!(expected, ArchiveStats { files: 2, bytes: 5 + 1536 * 1024 });
+        let stats = read_tar_archive_stats(
+            &host,
+            &state,
+            &volume_path(&archive, 1),
+            ArchiveFormat::TarZst,
+            expected.bytes,
+        )
+        .unwrap();
+        assert_eq!(stats, expected);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
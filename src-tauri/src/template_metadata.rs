@@ -3,6 +3,7 @@ use serde::Serialize;
 use std::sync::Mutex;
 use tauri::State;
 
+use crate::job_finalization::language_label;
 use crate::job_metadata::JobMetadataFile;
 
 #[derive(Clone, Debug, Serialize)]
@@ -12,14 +13,25 @@ pub struct TemplateDepot {
     pub manifest_id: String,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct TemplateMetadata {
     pub game_name: String,
     pub os: String,
     pub branch: String,
     pub build_datetime_utc: String,
     pub build_id: String,
+    pub language: String,
     pub depots: Vec<TemplateDepot>,
+    /// Archive hashes, filled in once the output is packed; empty for folder
+    /// outputs. Split archives list one `<hash>  <volume>` line per volume.
+    pub archive_sha256: String,
+    pub archive_blake3: String,
+    pub archive_crc32: String,
+    /// File name of the primary checksum manifest, if one was written
+    pub checksums_file: String,
+    /// Build an update pack starts from; empty for full builds. When set,
+    /// `{{build_id}}` renders as `<previous> → <build>`.
+    pub previous_build_id: String,
 }
 
 impl TemplateMetadata {
@@ -34,7 +46,10 @@ impl TemplateMetadata {
             .map(|depot| TemplateDepot {
                 depot_id: depot.depot_id.clone(),
                 depot_name: depot.depot_name.clone(),
-                manifest_id: depot.manifest_id.clone(),
+                manifest_id: depot
+                    .manifest_id_used
+                    .clone()
+                    .unwrap_or_else(|| depot.manifest_id.clone()),
             })
             .collect();
 
@@ -55,7 +70,9 @@ impl TemplateMetadata {
             branch: metadata.branch.clone(),
             build_datetime_utc,
             build_id: metadata.build_id.clone(),
+            language: language_label(metadata.language.as_deref().unwrap_or("english")),
             depots,
+            ..Default::default()
         }
     }
 }
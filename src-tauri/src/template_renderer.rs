@@ -1,8 +1,9 @@
+use crate::archive_format::strip_archive_extension;
 use crate::template_metadata::TemplateMetadata;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 
 /// Template block types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,7 +94,19 @@ pub fn render_template(
     base_values.insert("os".to_string(), metadata.os.clone());
     base_values.insert("branch".to_string(), metadata.branch.clone());
     base_values.insert("build_datetime_utc".to_string(), metadata.build_datetime_utc.clone());
-    base_values.insert("build_id".to_string(), metadata.build_id.clone());
+    // Update packs show the build range wherever the build is mentioned
+    let build_id = if metadata.previous_build_id.is_empty() {
+        metadata.build_id.clone()
+    } else {
+        format!("{} → {}", metadata.previous_build_id, metadata.build_id)
+    };
+    base_values.insert("build_id".to_string(), build_id);
+    base_values.insert("previous_build_id".to_string(), metadata.previous_build_id.clone());
+    base_values.insert("language".to_string(), metadata.language.clone());
+    base_values.insert("archive_sha256".to_string(), metadata.archive_sha256.clone());
+    base_values.insert("archive_blake3".to_string(), metadata.archive_blake3.clone());
+    base_values.insert("archive_crc32".to_string(), metadata.archive_crc32.clone());
+    base_values.insert("checksums_file".to_string(), metadata.checksums_file.clone());
 
     for block in blocks {
         let part = match block {
@@ -208,7 +221,7 @@ pub fn create_default_template() -> Vec<TemplateBlock> {
 
 /// Writes rendered template to a text file next to the output
 pub fn write_template_file(
-    output_path: &PathBuf,
+    output_path: &Path,
     metadata: &TemplateMetadata,
     template_blocks: Option<&[TemplateBlock]>,
 ) -> Result<(), String> {
@@ -227,11 +240,18 @@ pub fn write_template_file(
             .ok_or("Invalid output path")?
             .to_string_lossy();
         output_path.with_file_name(format!("{}.txt", dir_name))
-    } else if output_path.extension().and_then(|e| e.to_str()) == Some("7z") {
-        // If it's a .7z file, replace extension with .txt
-        output_path.with_extension("txt")
+    } else if let Some(base_path) = strip_archive_extension(output_path) {
+        // If it's an archive, replace the whole archive extension (.7z, .tar.zst, ...) with .txt
+        let base_name = base_path
+            .file_name()
+            .ok_or("Invalid output path")?
+            .to_string_lossy();
+        base_path.with_file_name(format!("{}.txt", base_name))
     } else {
-        return Err("Output path must be a directory or .7z file".to_string());
+        return Err(
+            "Output path must be a directory or a .7z, .zip, .tar.zst or .tar.xz archive"
+                .to_string(),
+        );
     };
 
     // Write file
@@ -241,6 +261,34 @@ pub fn write_template_file(
     Ok(())
 }
 
+/// Renders release notes for a multi-platform job: a shared header followed by
+/// each platform's depot list
+pub fn render_combined_release_notes(platforms: &[TemplateMetadata]) -> String {
+    let Some(first) = platforms.first() else {
+        return String::new();
+    };
+
+    let mut output = format!(
+        "{} [Branch: {}] [Language: {}]\nVersion: {} [Build {}]\n",
+        first.game_name, first.branch, first.language, first.build_datetime_utc, first.build_id
+    );
+
+    for platform in platforms {
+        output.push_str(&format!("\n[{}]\n", platform.os));
+        if platform.build_id != first.build_id {
+            output.push_str(&format!("Build {}\n", platform.build_id));
+        }
+        for depot in &platform.depots {
+            output.push_str(&format!(
+                "{} - {} [Manifest {}]\n",
+                depot.depot_id, depot.depot_name, depot.manifest_id
+            ));
+        }
+    }
+
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,6 +314,7 @@ mod tests {
             branch: "Public".to_string(),
             build_datetime_utc: "February 24, 2025 - 22:02:36 UTC".to_string(),
             build_id: "18674832".to_string(),
+            language: "German".to_string(),
             depots: vec![
                 TemplateDepot {
                     depot_id: "2923300".to_string(),
@@ -273,12 +322,14 @@ mod tests {
                     manifest_id: "4851806656204679952".to_string(),
                 },
             ],
+            archive_sha256: "ab12".to_string(),
+            ..Default::default()
         };
 
         let blocks = vec![
             TemplateBlock::Title {
                 config: TitleConfig {
-                    template: "{{game_name}} [{{os}}]".to_string(),
+                    template: "{{game_name}} [{{os}}] [{{language}}]".to_string(),
                 },
             },
             TemplateBlock::DepotList {
@@ -289,11 +340,66 @@ mod tests {
                     max_depots: Some(100),
                 },
             },
+            TemplateBlock::FreeText {
+                config: FreeTextConfig {
+                    text: "SHA-256: {{archive_sha256}}".to_string(),
+                },
+            },
         ];
 
         let result = render_template(&blocks, &metadata).unwrap();
-        assert!(result.contains("Balatro [Win64]"));
+        assert!(result.contains("Balatro [Win64] [German]"));
+        assert!(result.contains("SHA-256: ab12"));
         assert!(result.contains("[spoiler=Test Depots]"));
         assert!(result.contains("Balatro Content: 4851806656204679952"));
     }
+
+    #[test]
+    fn test_render_template_update_pack_build_range() {
+        let mut metadata = TemplateMetadata {
+            game_name: "Balatro".to_string(),
+            build_id: "18674832".to_string(),
+            ..Default::default()
+        };
+        let blocks = vec![TemplateBlock::Version {
+            config: VersionConfig {
+                template: "[Build {{build_id}}] from {{previous_build_id}}".to_string(),
+            },
+        }];
+
+        assert_eq!(render_template(&blocks, &metadata).unwrap(), "[Build 18674832] from ");
+
+        metadata.previous_build_id = "17000000".to_string();
+        assert_eq!(
+            render_template(&blocks, &metadata).unwrap(),
+            "[Build 17000000 → 18674832] from 17000000"
+        );
+    }
+
+    #[test]
+    fn test_render_combined_release_notes() {
+        let platform = |os: &str, depot_id: &str| TemplateMetadata {
+            game_name: "Balatro".to_string(),
+            os: os.to_string(),
+            branch: "Public".to_string(),
+            build_datetime_utc: "February 24, 2025 - 22:02:36 UTC".to_string(),
+            build_id: "18674832".to_string(),
+            language: "English".to_string(),
+            depots: vec![TemplateDepot {
+                depot_id: depot_id.to_string(),
+                depot_name: format!("Balatro {}", os),
+                manifest_id: "111".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let notes = render_combined_release_notes(&[
+            platform("Win64", "2923301"),
+            platform("Linux64", "2923302"),
+        ]);
+        assert!(notes.starts_with("Balatro [Branch: Public] [Language: English]\n"));
+        assert!(notes.contains("[Win64]\n2923301 - Balatro Win64 [Manifest 111]"));
+        assert!(notes.contains("[Linux64]\n2923302 - Balatro Linux64 [Manifest 111]"));
+        assert!(render_combined_release_notes(&[]).is_empty());
+    }
 }
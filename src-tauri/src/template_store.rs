@@ -1,16 +1,30 @@
+use crate::job_host::JobHost;
 use crate::template_renderer::TemplatePayload;
-use serde_json;
 use std::fs;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+const TEMPLATE_FILE_NAME: &str = "template.json";
 
 /// Gets the path to the template data file
 fn get_template_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
-    Ok(app_data_dir.join("template.json"))
+    let app_data_dir = JobHost::app_data_dir(app_handle)?;
+    Ok(app_data_dir.join(TEMPLATE_FILE_NAME))
+}
+
+/// Reads and parses the template file at the given path, if present
+fn read_template_file(path: &Path) -> Result<Option<TemplatePayload>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read template file: {}", e))?;
+
+    let payload: TemplatePayload = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse template file: {}", e))?;
+
+    Ok(Some(payload))
 }
 
 /// Saves template data to disk
@@ -39,21 +53,13 @@ pub fn save_template_data(
 #[tauri::command]
 pub fn load_template_data(app_handle: AppHandle) -> Result<Option<TemplatePayload>, String> {
     let path = get_template_path(&app_handle)?;
-
-    if !path.exists() {
-        return Ok(None);
-    }
-
-    let json = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read template file: {}", e))?;
-
-    let payload: TemplatePayload = serde_json::from_str(&json)
-        .map_err(|e| format!("Failed to parse template file: {}", e))?;
-
-    Ok(Some(payload))
+    read_template_file(&path)
 }
 
 /// Loads template data for internal use (not a command)
-pub fn load_template_data_internal(app_handle: &AppHandle) -> Option<TemplatePayload> {
-    load_template_data(app_handle.clone()).ok().flatten()
+pub fn load_template_data_internal(host: &dyn JobHost) -> Option<TemplatePayload> {
+    let app_data_dir = host.app_data_dir().ok()?;
+    read_template_file(&app_data_dir.join(TEMPLATE_FILE_NAME))
+        .ok()
+        .flatten()
 }
@@ -0,0 +1,726 @@
+//! Differential update packs between two builds
+//!
+//! Compares two builds of the same app and branch depot by depot, using the
+//! file hashes in their Steam manifests (files are hashed on disk for depots
+//! without one), and writes a patch folder with only the added and changed
+//! files. `patch.json` next to them lists what changed and every file to
+//! delete, so applying a pack is "delete the listed files, then extract".
+//!
+//! Either build can be a finalized output folder or a staging directory
+//! (`job.json` + `depots/<id>/<manifest>/`). The old build may also be a plain
+//! folder of `.manifest` files, since only its hashes are needed.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::depot_manifest::{find_saved_manifest, read_depot_manifest, DepotManifest};
+use crate::depot_validation::{
+    best_content_dir, depot_id_from_manifest_name, manifest_entry_path, manifest_files,
+};
+use crate::job_metadata::{BuildIdSource, DepotInfo, JobMetadataFile};
+use crate::steam_api::sanitize_game_name;
+use crate::vdf;
+
+/// Name of the change list written into every update pack
+pub const PATCH_FILE_NAME: &str = "patch.json";
+
+/// What a file looks like in one build
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileState {
+    pub size: u64,
+    /// SHA-1 of the whole file, as in Steam manifests
+    pub sha: Vec<u8>,
+    pub link_target: Option<String>,
+}
+
+/// One depot of a build
+#[derive(Clone, Debug, Default)]
+pub struct DepotSnapshot {
+    pub depot_id: String,
+    /// Folder name under `steamapps/common/`
+    pub depot_name: String,
+    /// Where the files are on disk; `None` for a bare manifest
+    pub content_dir: Option<PathBuf>,
+    /// Manifest to ship in `depotcache/`, with its Steam file name
+    pub manifest: Option<(PathBuf, String)>,
+    /// Files by path relative to the depot root (`/` separators)
+    pub files: BTreeMap<String, FileState>,
+}
+
+/// Everything the diff needs to know about one build
+#[derive(Clone, Debug)]
+pub struct BuildSnapshot {
+    pub metadata: JobMetadataFile,
+    /// The build's appmanifest, if it has one
+    pub appmanifest: Option<PathBuf>,
+    pub depots: Vec<DepotSnapshot>,
+}
+
+/// Changes between two builds, as paths relative to the pack root
+/// (`steamapps/common/<Depot>/<file>`)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Contents of `patch.json`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchManifest {
+    pub appid: String,
+    pub game_name: String,
+    pub branch: String,
+    pub platform: String,
+    pub from_build_id: String,
+    pub to_build_id: String,
+    #[serde(flatten)]
+    pub diff: BuildDiff,
+}
+
+/// Size of an update pack once written
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UpdatePackSummary {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+    pub bytes: u64,
+}
+
+/// Reads a build from an output folder, a staging directory or a folder of manifests
+pub fn load_build(path: &Path) -> Result<BuildSnapshot, String> {
+    if path.join("job.json").is_file() && path.join("depots").is_dir() {
+        load_staging_build(path)
+    } else if path.join("depotcache").is_dir() && path.join("steamapps").is_dir() {
+        load_output_build(path)
+    } else if path.is_dir() && !manifest_files(path)?.is_empty() {
+        load_manifest_set(path)
+    } else {
+        Err(format!(
+            "{} is not an output folder, staging directory or folder of depot manifests",
+            path.display()
+        ))
+    }
+}
+
+fn load_staging_build(staging_dir: &Path) -> Result<BuildSnapshot, String> {
+    let metadata = JobMetadataFile::read_from_dir(staging_dir)?;
+    let mut depots = Vec::new();
+
+    for depot_dir in sorted_entries(&staging_dir.join("depots"))? {
+        let depot_id = file_name(&depot_dir);
+        if !depot_dir.is_dir() || depot_id == ".DepotDownloader" {
+            continue;
+        }
+        let Some(content_dir) = sorted_entries(&depot_dir)?
+            .into_iter()
+            .find(|path| path.is_dir())
+        else {
+            continue;
+        };
+
+        // Same fallback name finalization uses for depots missing from job.json
+        let depot_name = metadata
+            .depots
+            .iter()
+            .find(|depot| depot.depot_id == depot_id)
+            .map(|depot| depot.depot_name.clone())
+            .unwrap_or_else(|| format!("depot_{}", depot_id));
+
+        let mut depot = DepotSnapshot {
+            depot_id,
+            depot_name,
+            ..Default::default()
+        };
+        match find_saved_manifest(&content_dir) {
+            Some(manifest_path) => {
+                let manifest = read_depot_manifest(&manifest_path)?;
+                depot.files = manifest_file_states(&manifest, &manifest_path)?;
+                let name = manifest
+                    .depotcache_file_name()
+                    .unwrap_or_else(|| file_name(&manifest_path));
+                depot.manifest = Some((manifest_path, name));
+            }
+            None => depot.files = hash_directory(&content_dir)?,
+        }
+        depot.content_dir = Some(content_dir);
+        depots.push(depot);
+    }
+
+    Ok(BuildSnapshot {
+        metadata,
+        appmanifest: None,
+        depots,
+    })
+}
+
+fn load_output_build(output_dir: &Path) -> Result<BuildSnapshot, String> {
+    let steamapps_dir = output_dir.join("steamapps");
+    let common_dir = steamapps_dir.join("common");
+    let appmanifest = sorted_entries(&steamapps_dir)?.into_iter().find(|path| {
+        let name = file_name(path);
+        name.starts_with("appmanifest_") && name.ends_with(".acf")
+    });
+    let app_state = match appmanifest.as_ref() {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            vdf::parse(&text)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+                .get_section("AppState")
+                .cloned()
+                .unwrap_or_default()
+        }
+        None => vdf::VdfObject::new(),
+    };
+
+    let content_dirs: Vec<PathBuf> = sorted_entries(&common_dir)?
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect();
+    let mut claimed = Vec::new();
+    let mut depots = Vec::new();
+    for manifest_path in manifest_files(&output_dir.join("depotcache"))? {
+        let manifest = read_depot_manifest(&manifest_path)?;
+        let content_dir = best_content_dir(&content_dirs, &manifest);
+        let depot_id = manifest
+            .depot_id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| depot_id_from_manifest_name(&manifest_path));
+        claimed.extend(content_dir.clone());
+        depots.push(DepotSnapshot {
+            depot_id,
+            depot_name: content_dir.as_deref().map(file_name).unwrap_or_default(),
+            files: manifest_file_states(&manifest, &manifest_path)?,
+            manifest: Some((manifest_path.clone(), file_name(&manifest_path))),
+            content_dir,
+        });
+    }
+    // Folders no manifest claims are compared by name, hashing their files
+    for content_dir in content_dirs.iter().filter(|dir| !claimed.contains(dir)) {
+        let depot_name = file_name(content_dir);
+        depots.push(DepotSnapshot {
+            depot_id: depot_name.clone(),
+            depot_name,
+            content_dir: Some(content_dir.clone()),
+            manifest: None,
+            files: hash_directory(content_dir)?,
+        });
+    }
+
+    let folder_name = file_name(output_dir);
+    let get = |key: &str| app_state.get_str(key).unwrap_or_default().to_string();
+    let build_id = get("buildid");
+    let (platform, branch) = parse_output_folder_name(&folder_name, &build_id);
+    let build_datetime_utc: Option<DateTime<Utc>> = get("LastUpdated")
+        .parse::<i64>()
+        .ok()
+        .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single());
+    let mut metadata = JobMetadataFile::new(
+        folder_name,
+        get("appid"),
+        branch,
+        platform,
+        depots.first().map(|depot| depot.depot_id.clone()).unwrap_or_default(),
+        get("name"),
+        build_id,
+        BuildIdSource::AppBuildid,
+        build_datetime_utc,
+        depot_infos(&depots),
+    );
+    metadata.language = app_state
+        .get_path(&["UserConfig"])
+        .and_then(|config| config.get_str("language"))
+        .map(str::to_string);
+
+    Ok(BuildSnapshot {
+        metadata,
+        appmanifest,
+        depots,
+    })
+}
+
+fn load_manifest_set(dir: &Path) -> Result<BuildSnapshot, String> {
+    let mut depots = Vec::new();
+    let mut newest: Option<DateTime<Utc>> = None;
+    let mut primary_manifest_id = None;
+    for manifest_path in manifest_files(dir)? {
+        let manifest = read_depot_manifest(&manifest_path)?;
+        newest = newest.max(manifest.creation_time);
+        primary_manifest_id = primary_manifest_id.or(manifest.manifest_id);
+        depots.push(DepotSnapshot {
+            depot_id: manifest
+                .depot_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| depot_id_from_manifest_name(&manifest_path)),
+            files: manifest_file_states(&manifest, &manifest_path)?,
+            manifest: Some((manifest_path.clone(), file_name(&manifest_path))),
+            ..Default::default()
+        });
+    }
+
+    // A manifest set carries no build ID; label it like job.json does without
+    // appinfo, by the primary depot's manifest ID
+    let metadata = JobMetadataFile::new(
+        file_name(dir),
+        String::new(),
+        String::new(),
+        String::new(),
+        depots.first().map(|depot| depot.depot_id.clone()).unwrap_or_default(),
+        String::new(),
+        primary_manifest_id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| file_name(dir)),
+        BuildIdSource::PrimaryManifestId,
+        newest,
+        depot_infos(&depots),
+    );
+    Ok(BuildSnapshot {
+        metadata,
+        appmanifest: None,
+        depots,
+    })
+}
+
+/// Checks that two builds can be diffed: same app and branch (where known),
+/// different builds, and content on disk for the new one
+pub fn check_compatible(old: &BuildSnapshot, new: &BuildSnapshot) -> Result<(), String> {
+    let (old_meta, new_meta) = (&old.metadata, &new.metadata);
+    let known = |a: &str, b: &str| !a.is_empty() && !b.is_empty();
+    if known(&old_meta.appid, &new_meta.appid) && old_meta.appid != new_meta.appid {
+        return Err(format!(
+            "Builds belong to different apps ({} and {})",
+            old_meta.appid, new_meta.appid
+        ));
+    }
+    if known(&old_meta.branch, &new_meta.branch)
+        && !old_meta.branch.eq_ignore_ascii_case(&new_meta.branch)
+    {
+        return Err(format!(
+            "Builds are from different branches ({} and {})",
+            old_meta.branch, new_meta.branch
+        ));
+    }
+    if known(&old_meta.build_id, &new_meta.build_id) && old_meta.build_id == new_meta.build_id {
+        return Err(format!("Both builds are build {}", new_meta.build_id));
+    }
+    if let Some(depot) = new
+        .depots
+        .iter()
+        .find(|depot| depot.content_dir.is_none() && !depot.files.is_empty())
+    {
+        return Err(format!(
+            "The new build has no files for depot {}; use an output folder or staging directory",
+            depot.depot_id
+        ));
+    }
+    Ok(())
+}
+
+/// Lists the files added, changed and removed going from `old` to `new`.
+/// Depots are matched by ID; a depot dropped from the new build is removed entirely.
+pub fn diff_builds(old: &BuildSnapshot, new: &BuildSnapshot) -> BuildDiff {
+    let mut diff = BuildDiff::default();
+
+    for new_depot in &new.depots {
+        // A depot whose folder was renamed has to be shipped in full
+        let old_depot = old.depots.iter().find(|depot| {
+            depot.depot_id == new_depot.depot_id
+                && (depot.depot_name.is_empty() || depot.depot_name == new_depot.depot_name)
+        });
+        for (name, state) in &new_depot.files {
+            let path = pack_path(&new_depot.depot_name, name);
+            match old_depot.and_then(|depot| depot.files.get(name)) {
+                None => diff.added.push(path),
+                Some(old_state) if old_state != state => diff.changed.push(path),
+                Some(_) => {}
+            }
+        }
+    }
+
+    for old_depot in &old.depots {
+        let new_depot = new
+            .depots
+            .iter()
+            .find(|depot| depot.depot_id == old_depot.depot_id);
+        // A bare manifest has no folder name; the new build's is the same folder
+        let depot_name = if old_depot.depot_name.is_empty() {
+            new_depot
+                .map(|depot| depot.depot_name.clone())
+                .unwrap_or_else(|| format!("depot_{}", old_depot.depot_id))
+        } else {
+            old_depot.depot_name.clone()
+        };
+        for name in old_depot.files.keys() {
+            let renamed = new_depot.is_some_and(|depot| depot.depot_name != depot_name);
+            if renamed || !new_depot.is_some_and(|depot| depot.files.contains_key(name)) {
+                diff.removed.push(pack_path(&depot_name, name));
+            }
+        }
+    }
+
+    diff.added.sort();
+    diff.changed.sort();
+    diff.removed.sort();
+    diff
+}
+
+/// Folder name for an update pack:
+/// `<Game>.Update.<old>-to-<new>.<Platform>.<Branch>`
+pub fn update_pack_name(old: &BuildSnapshot, new: &BuildSnapshot) -> String {
+    let mut parts = vec![
+        sanitize_game_name(&new.metadata.game_name),
+        "Update".to_string(),
+        format!("{}-to-{}", old.metadata.build_id, new.metadata.build_id),
+    ];
+    parts.extend(
+        [&new.metadata.platform, &new.metadata.branch]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .cloned(),
+    );
+    parts.join(".")
+}
+
+/// Writes the pack into `pack_dir`: changed files under `steamapps/common/`,
+/// the new appmanifest and depot manifests, and `patch.json`
+pub fn write_update_pack(
+    old: &BuildSnapshot,
+    new: &BuildSnapshot,
+    diff: &BuildDiff,
+    pack_dir: &Path,
+) -> Result<UpdatePackSummary, String> {
+    fs::create_dir_all(pack_dir)
+        .map_err(|e| format!("Failed to create {}: {}", pack_dir.display(), e))?;
+
+    let mut summary = UpdatePackSummary {
+        added: diff.added.len(),
+        changed: diff.changed.len(),
+        removed: diff.removed.len(),
+        bytes: 0,
+    };
+
+    for path in diff.added.iter().chain(diff.changed.iter()) {
+        let (depot, name) = new
+            .depots
+            .iter()
+            .find_map(|depot| {
+                let prefix = pack_path(&depot.depot_name, "");
+                path.strip_prefix(&prefix).map(|name| (depot, name))
+            })
+            .ok_or_else(|| format!("No depot holds {}", path))?;
+        let content_dir = depot
+            .content_dir
+            .as_deref()
+            .ok_or_else(|| format!("No files on disk for depot {}", depot.depot_id))?;
+        let source = manifest_entry_path(content_dir, name);
+        let target = manifest_entry_path(pack_dir, path);
+        summary.bytes += copy_entry(&source, &target)?;
+    }
+
+    let steamapps_dir = pack_dir.join("steamapps");
+    fs::create_dir_all(steamapps_dir.join("common"))
+        .map_err(|e| format!("Failed to create {}: {}", steamapps_dir.display(), e))?;
+    if let Some(appmanifest) = new.appmanifest.as_ref() {
+        fs::copy(appmanifest, steamapps_dir.join(file_name(appmanifest)))
+            .map_err(|e| format!("Failed to copy {}: {}", appmanifest.display(), e))?;
+    }
+    let depotcache_dir = pack_dir.join("depotcache");
+    for (manifest_path, name) in new.depots.iter().filter_map(|depot| depot.manifest.as_ref()) {
+        fs::create_dir_all(&depotcache_dir)
+            .map_err(|e| format!("Failed to create {}: {}", depotcache_dir.display(), e))?;
+        fs::copy(manifest_path, depotcache_dir.join(name))
+            .map_err(|e| format!("Failed to copy {}: {}", manifest_path.display(), e))?;
+    }
+
+    let patch = PatchManifest {
+        appid: new.metadata.appid.clone(),
+        game_name: new.metadata.game_name.clone(),
+        branch: new.metadata.branch.clone(),
+        platform: new.metadata.platform.clone(),
+        from_build_id: old.metadata.build_id.clone(),
+        to_build_id: new.metadata.build_id.clone(),
+        diff: diff.clone(),
+    };
+    let json = serde_json::to_string_pretty(&patch)
+        .map_err(|e| format!("Failed to serialize {}: {}", PATCH_FILE_NAME, e))?;
+    fs::write(pack_dir.join(PATCH_FILE_NAME), json)
+        .map_err(|e| format!("Failed to write {}: {}", PATCH_FILE_NAME, e))?;
+
+    Ok(summary)
+}
+
+fn pack_path(depot_name: &str, name: &str) -> String {
+    format!("steamapps/common/{}/{}", depot_name, name)
+}
+
+/// Reads the platform and branch back out of an output folder name
+/// (`<Game>.Build.<id>.<Platform>.<Branch>[...]`)
+fn parse_output_folder_name(folder_name: &str, build_id: &str) -> (String, String) {
+    let marker = format!(".Build.{}.", build_id);
+    let Some((_, rest)) = folder_name.split_once(&marker) else {
+        return (String::new(), String::new());
+    };
+    let mut parts = rest.split('.');
+    let platform = parts.next().unwrap_or_default().to_string();
+    let branch = parts.next().unwrap_or_default().to_string();
+    (platform, branch)
+}
+
+fn manifest_file_states(
+    manifest: &DepotManifest,
+    path: &Path,
+) -> Result<BTreeMap<String, FileState>, String> {
+    if manifest.filenames_encrypted {
+        return Err(format!(
+            "{} has encrypted file names and can't be compared",
+            path.display()
+        ));
+    }
+    Ok(manifest
+        .files
+        .iter()
+        .filter(|file| !file.is_directory())
+        .map(|file| {
+            let state = FileState {
+                size: file.size,
+                sha: if file.is_symlink() {
+                    Vec::new()
+                } else {
+                    file.sha_content.clone()
+                },
+                link_target: file.link_target.clone(),
+            };
+            (file.name.clone(), state)
+        })
+        .collect())
+}
+
+/// Hashes every file below `dir` the way Steam manifests record them
+fn hash_directory(dir: &Path) -> Result<BTreeMap<String, FileState>, String> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((current, prefix)) = pending.pop() {
+        for path in sorted_entries(&current)? {
+            let name = format!("{}{}", prefix, file_name(&path));
+            if name == ".DepotDownloader" {
+                continue;
+            }
+            let metadata = fs::symlink_metadata(&path)
+                .map_err(|e| format!("Failed to inspect {}: {}", path.display(), e))?;
+            if metadata.is_symlink() {
+                let target = fs::read_link(&path)
+                    .map_err(|e| format!("Failed to read link {}: {}", path.display(), e))?;
+                files.insert(
+                    name,
+                    FileState {
+                        size: 0,
+                        sha: Vec::new(),
+                        link_target: Some(target.to_string_lossy().replace('\\', "/")),
+                    },
+                );
+            } else if metadata.is_dir() {
+                pending.push((path, format!("{}/", name)));
+            } else {
+                let mut file = fs::File::open(&path)
+                    .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+                let mut hasher = Sha1::new();
+                std::io::copy(&mut file, &mut hasher)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                files.insert(
+                    name,
+                    FileState {
+                        size: metadata.len(),
+                        sha: hasher.finalize().to_vec(),
+                        link_target: None,
+                    },
+                );
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Copies a file (or recreates a symlink) into the pack; returns the bytes copied
+fn copy_entry(source: &Path, target: &Path) -> Result<u64, String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    #[cfg(unix)]
+    if fs::symlink_metadata(source).is_ok_and(|metadata| metadata.is_symlink()) {
+        let link = fs::read_link(source)
+            .map_err(|e| format!("Failed to read link {}: {}", source.display(), e))?;
+        std::os::unix::fs::symlink(&link, target)
+            .map_err(|e| format!("Failed to create link {}: {}", target.display(), e))?;
+        return Ok(0);
+    }
+    fs::copy(source, target).map_err(|e| {
+        format!(
+            "Failed to copy {} to {}: {}",
+            source.display(),
+            target.display(),
+            e
+        )
+    })
+}
+
+fn depot_infos(depots: &[DepotSnapshot]) -> Vec<DepotInfo> {
+    depots
+        .iter()
+        .map(|depot| DepotInfo {
+            depot_id: depot.depot_id.clone(),
+            depot_name: depot.depot_name.clone(),
+            manifest_id: depot
+                .manifest
+                .as_ref()
+                .and_then(|(_, name)| name.strip_suffix(".manifest"))
+                .and_then(|stem| stem.split_once('_'))
+                .map(|(_, manifest_id)| manifest_id.to_string())
+                .unwrap_or_default(),
+            manifest_id_used: None,
+        })
+        .collect()
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(sha: u8) -> FileState {
+        FileState {
+            size: 1,
+            sha: vec![sha],
+            link_target: None,
+        }
+    }
+
+    fn build(build_id: &str, depots: Vec<DepotSnapshot>) -> BuildSnapshot {
+        BuildSnapshot {
+            metadata: JobMetadataFile::new(
+                "job".to_string(),
+                "480".to_string(),
+                "public".to_string(),
+                "Win64".to_string(),
+                "481".to_string(),
+                "Spacewar".to_string(),
+                build_id.to_string(),
+                BuildIdSource::AppBuildid,
+                None,
+                Vec::new(),
+            ),
+            appmanifest: None,
+            depots,
+        }
+    }
+
+    fn depot(id: &str, name: &str, files: &[(&str, u8)]) -> DepotSnapshot {
+        DepotSnapshot {
+            depot_id: id.to_string(),
+            depot_name: name.to_string(),
+            content_dir: Some(PathBuf::from("/content")),
+            manifest: None,
+            files: files
+                .iter()
+                .map(|(name, sha)| (name.to_string(), state(*sha)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_lists_added_changed_and_removed_files() {
+        let old = build(
+            "100",
+            vec![
+                depot("481", "Spacewar", &[("game.exe", 1), ("data/a.pak", 2), ("old.dll", 3)]),
+                depot("482", "Spacewar Extras", &[("extra.txt", 4)]),
+            ],
+        );
+        let new = build(
+            "200",
+            vec![depot(
+                "481",
+                "Spacewar",
+                &[("game.exe", 9), ("data/a.pak", 2), ("data/b.pak", 5)],
+            )],
+        );
+
+        let diff = diff_builds(&old, &new);
+        assert_eq!(diff.added, vec!["steamapps/common/Spacewar/data/b.pak"]);
+        assert_eq!(diff.changed, vec!["steamapps/common/Spacewar/game.exe"]);
+        assert_eq!(
+            diff.removed,
+            vec![
+                "steamapps/common/Spacewar Extras/extra.txt",
+                "steamapps/common/Spacewar/old.dll"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bare_manifests_take_the_new_depot_folder() {
+        let mut old_depot = depot("481", "", &[("gone.txt", 1)]);
+        old_depot.content_dir = None;
+        let old = build("100", vec![old_depot]);
+        let new = build("200", vec![depot("481", "Spacewar", &[])]);
+
+        let diff = diff_builds(&old, &new);
+        assert_eq!(diff.removed, vec!["steamapps/common/Spacewar/gone.txt"]);
+        assert!(check_compatible(&old, &new).is_ok());
+        // The new build must have files to pack
+        assert!(check_compatible(&new, &old).is_err());
+    }
+
+    #[test]
+    fn test_rejects_mismatched_builds() {
+        let old = build("100", vec![]);
+        let mut other_app = build("200", vec![]);
+        other_app.metadata.appid = "620".to_string();
+        assert!(check_compatible(&old, &other_app).unwrap_err().contains("different apps"));
+
+        let mut other_branch = build("200", vec![]);
+        other_branch.metadata.branch = "beta".to_string();
+        assert!(check_compatible(&old, &other_branch).is_err());
+        assert!(check_compatible(&old, &build("100", vec![])).is_err());
+    }
+
+    #[test]
+    fn test_names_packs_and_parses_output_folders() {
+        let old = build("100", vec![]);
+        let new = build("200", vec![]);
+        assert_eq!(update_pack_name(&old, &new), "Spacewar.Update.100-to-200.Win64.public");
+        assert_eq!(
+            parse_output_folder_name("Spacewar.Build.200.Linux64.beta.German", "200"),
+            ("Linux64".to_string(), "beta".to_string())
+        );
+        assert_eq!(
+            parse_output_folder_name("Something else", "200"),
+            (String::new(), String::new())
+        );
+    }
+}
@@ -0,0 +1,509 @@
+//! Valve KeyValues (VDF) text format
+//!
+//! Parses and writes the text files Steam keeps its state in
+//! (`appmanifest_*.acf`, `libraryfolders.vdf`, `loginusers.vdf`, `config.vdf`).
+//! Objects keep their key order so a file can be read, edited and written back
+//! without reshuffling it; key lookups are case-insensitive, as in Steam.
+
+/// A value in a KeyValues tree: either a string or a nested section
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VdfValue {
+    String(String),
+    Section(VdfObject),
+}
+
+/// An ordered list of key/value pairs (duplicate keys are allowed)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VdfObject {
+    entries: Vec<(String, VdfValue)>,
+}
+
+impl VdfObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[(String, VdfValue)] {
+        &self.entries
+    }
+
+    /// First value stored under `key` (case-insensitive)
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            VdfValue::String(value) => Some(value),
+            VdfValue::Section(_) => None,
+        }
+    }
+
+    pub fn get_section(&self, key: &str) -> Option<&VdfObject> {
+        match self.get(key)? {
+            VdfValue::Section(section) => Some(section),
+            VdfValue::String(_) => None,
+        }
+    }
+
+    pub fn get_section_mut(&mut self, key: &str) -> Option<&mut VdfObject> {
+        self.entries
+            .iter_mut()
+            .find(|(name, value)| {
+                name.eq_ignore_ascii_case(key) && matches!(value, VdfValue::Section(_))
+            })
+            .and_then(|(_, value)| match value {
+                VdfValue::Section(section) => Some(section),
+                VdfValue::String(_) => None,
+            })
+    }
+
+    /// Follows a path of section names, e.g. `["Software", "Valve", "Steam"]`
+    pub fn get_path(&self, path: &[&str]) -> Option<&VdfObject> {
+        path.iter()
+            .try_fold(self, |object, key| object.get_section(key))
+    }
+
+    /// Replaces the first value stored under `key`, or appends it
+    pub fn set(&mut self, key: &str, value: VdfValue) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+
+    /// Appends an entry without replacing existing ones
+    pub fn push(&mut self, key: &str, value: VdfValue) {
+        self.entries.push((key.to_string(), value));
+    }
+
+    pub fn push_str(&mut self, key: &str, value: &str) {
+        self.push(key, VdfValue::String(value.to_string()));
+    }
+
+    /// Removes every entry stored under `key` and returns the first one
+    pub fn remove(&mut self, key: &str) -> Option<VdfValue> {
+        let mut removed = None;
+        let mut index = 0;
+        while index < self.entries.len() {
+            if self.entries[index].0.eq_ignore_ascii_case(key) {
+                let (_, value) = self.entries.remove(index);
+                removed.get_or_insert(value);
+            } else {
+                index += 1;
+            }
+        }
+        removed
+    }
+
+    /// Overlays `other` onto this object: strings replace, sections merge
+    /// recursively and keys only present here are kept in place
+    pub fn merge(&mut self, other: &VdfObject) {
+        for (key, value) in &other.entries {
+            match (self.get_section_mut(key), value) {
+                (Some(existing), VdfValue::Section(section)) => existing.merge(section),
+                _ => self.set(key, value.clone()),
+            }
+        }
+    }
+}
+
+/// Parses KeyValues text into its top-level object
+pub fn parse(text: &str) -> Result<VdfObject, String> {
+    let mut tokenizer = Tokenizer::new(text);
+    let object = parse_object(&mut tokenizer, 0)?;
+    match tokenizer.next_token()? {
+        None => Ok(object),
+        Some(token) => Err(format!(
+            "Unexpected {} on line {}",
+            token.describe(),
+            tokenizer.line
+        )),
+    }
+}
+
+/// Writes an object in the layout Steam uses: tab indentation, `"key"\t\t"value"`
+/// pairs and braces on their own lines
+pub fn to_string(object: &VdfObject) -> String {
+    let mut output = String::new();
+    write_object(&mut output, object, 0);
+    output
+}
+
+/// Escapes `\`, `"` and control characters for use inside a quoted string
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape`]. Unknown escape sequences are kept as written, which is
+/// how Steam treats a stray backslash.
+pub fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('"') => unescaped.push('"'),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn write_object(output: &mut String, object: &VdfObject, depth: usize) {
+    let indent = "\t".repeat(depth);
+    for (key, value) in &object.entries {
+        match value {
+            VdfValue::String(value) => {
+                output.push_str(&format!(
+                    "{}\"{}\"\t\t\"{}\"\n",
+                    indent,
+                    escape(key),
+                    escape(value)
+                ));
+            }
+            VdfValue::Section(section) => {
+                output.push_str(&format!("{}\"{}\"\n{}{{\n", indent, escape(key), indent));
+                write_object(output, section, depth + 1);
+                output.push_str(&format!("{}}}\n", indent));
+            }
+        }
+    }
+}
+
+const MAX_DEPTH: usize = 64;
+
+fn parse_object(tokenizer: &mut Tokenizer, depth: usize) -> Result<VdfObject, String> {
+    if depth > MAX_DEPTH {
+        return Err(format!("Sections nested too deeply on line {}", tokenizer.line));
+    }
+    let mut object = VdfObject::new();
+    loop {
+        let key = match tokenizer.peek_token()? {
+            None => {
+                if depth > 0 {
+                    return Err("Unexpected end of file: missing '}'".to_string());
+                }
+                return Ok(object);
+            }
+            Some(Token::Close) => {
+                if depth == 0 {
+                    return Err(format!("Unexpected '}}' on line {}", tokenizer.line));
+                }
+                tokenizer.next_token()?;
+                return Ok(object);
+            }
+            Some(Token::Open) => {
+                return Err(format!("Expected a key on line {}, found '{{'", tokenizer.line))
+            }
+            Some(Token::Text(key)) => {
+                tokenizer.next_token()?;
+                key
+            }
+        };
+
+        let value = match tokenizer.next_token()? {
+            Some(Token::Text(value)) => VdfValue::String(value),
+            Some(Token::Open) => VdfValue::Section(parse_object(tokenizer, depth + 1)?),
+            Some(Token::Close) | None => {
+                return Err(format!(
+                    "Missing value for \"{}\" on line {}",
+                    key, tokenizer.line
+                ))
+            }
+        };
+        tokenizer.skip_conditional();
+        object.entries.push((key, value));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Text(String),
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Text(text) => format!("\"{}\"", text),
+            Token::Open => "'{'".to_string(),
+            Token::Close => "'}'".to_string(),
+        }
+    }
+}
+
+struct Tokenizer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    peeked: Option<Token>,
+    line: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(text: &'a str) -> Self {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        Self {
+            chars: text.chars().peekable(),
+            peeked: None,
+            line: 1,
+        }
+    }
+
+    fn peek_token(&mut self) -> Result<Option<Token>, String> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_token()?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, String> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.read_token(),
+        }
+    }
+
+    /// Drops a trailing platform conditional such as `[$WIN32]`
+    fn skip_conditional(&mut self) {
+        if self.peeked.is_some() {
+            return;
+        }
+        self.skip_whitespace_and_comments();
+        if self.chars.peek() == Some(&'[') {
+            for ch in self.chars.by_ref() {
+                if ch == ']' {
+                    break;
+                }
+                if ch == '\n' {
+                    self.line += 1;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match self.chars.peek() {
+                Some('\n') => {
+                    self.line += 1;
+                    self.chars.next();
+                }
+                Some(ch) if ch.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('/') => {
+                    let mut lookahead = self.chars.clone();
+                    lookahead.next();
+                    if lookahead.peek() != Some(&'/') {
+                        return;
+                    }
+                    while let Some(ch) = self.chars.peek() {
+                        if *ch == '\n' {
+                            break;
+                        }
+                        self.chars.next();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token>, String> {
+        self.skip_whitespace_and_comments();
+        let Some(ch) = self.chars.next() else {
+            return Ok(None);
+        };
+        match ch {
+            '{' => Ok(Some(Token::Open)),
+            '}' => Ok(Some(Token::Close)),
+            '"' => {
+                let start_line = self.line;
+                let mut raw = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            raw.push('\\');
+                            if let Some(next) = self.chars.next() {
+                                if next == '\n' {
+                                    self.line += 1;
+                                }
+                                raw.push(next);
+                            }
+                        }
+                        Some(ch) => {
+                            if ch == '\n' {
+                                self.line += 1;
+                            }
+                            raw.push(ch);
+                        }
+                        None => {
+                            return Err(format!(
+                                "Unterminated string starting on line {}",
+                                start_line
+                            ))
+                        }
+                    }
+                }
+                Ok(Some(Token::Text(unescape(&raw))))
+            }
+            _ => {
+                let mut raw = String::from(ch);
+                while let Some(next) = self.chars.peek() {
+                    if next.is_whitespace() || matches!(next, '"' | '{' | '}') {
+                        break;
+                    }
+                    raw.push(*next);
+                    self.chars.next();
+                }
+                Ok(Some(Token::Text(unescape(&raw))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parses_nested_sections_and_comments() {
+        let text = "// generated\n\"libraryfolders\"\n{\n\t\"0\"\n\t{\n\t\t\"path\"\t\t\"C:\\\\Program Files (x86)\\\\Steam\"\n\t\t\"apps\"\n\t\t{\n\t\t\t\"228980\"\t\t\"123\"\n\t\t}\n\t}\n}\n";
+        let root = parse(text).unwrap();
+        let folder = root.get_path(&["LibraryFolders", "0"]).unwrap();
+        assert_eq!(folder.get_str("path"), Some("C:\\Program Files (x86)\\Steam"));
+        assert_eq!(
+            folder.get_section("apps").unwrap().get_str("228980"),
+            Some("123")
+        );
+    }
+
+    #[test]
+    fn test_parses_unquoted_tokens_and_conditionals() {
+        let root = parse("Root { key value [$WIN32] other \"x\" }").unwrap();
+        let section = root.get_section("root").unwrap();
+        assert_eq!(section.get_str("key"), Some("value"));
+        assert_eq!(section.get_str("other"), Some("x"));
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        assert!(parse("\"a\"\n{\n\"b\"\t\"c\"\n").is_err());
+        assert!(parse("\"a\"\t\"unterminated").is_err());
+        assert!(parse("\"a\"").is_err());
+        assert!(parse("}").is_err());
+    }
+
+    #[test]
+    fn test_writes_steam_layout() {
+        let mut inner = VdfObject::new();
+        inner.push_str("appid", "12345");
+        let mut root = VdfObject::new();
+        root.push("AppState", VdfValue::Section(inner));
+        assert_eq!(
+            to_string(&root),
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"12345\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_unknown_keys() {
+        let mut existing =
+            parse("\"AppState\" { \"appid\" \"1\" \"StagingSize\" \"0\" \"UserConfig\" { \"betakey\" \"beta\" } }")
+                .unwrap();
+        let generated =
+            parse("\"AppState\" { \"appid\" \"2\" \"UserConfig\" { \"language\" \"german\" } }")
+                .unwrap();
+        existing.merge(&generated);
+
+        let state = existing.get_section("AppState").unwrap();
+        assert_eq!(state.get_str("appid"), Some("2"));
+        assert_eq!(state.get_str("StagingSize"), Some("0"));
+        let config = state.get_section("UserConfig").unwrap();
+        assert_eq!(config.get_str("betakey"), Some("beta"));
+        assert_eq!(config.get_str("language"), Some("german"));
+    }
+
+    fn arb_object() -> impl Strategy<Value = VdfObject> {
+        let leaf = prop::collection::vec((any::<String>(), any::<String>()), 0..4).prop_map(
+            |pairs| VdfObject {
+                entries: pairs
+                    .into_iter()
+                    .map(|(key, value)| (key, VdfValue::String(value)))
+                    .collect(),
+            },
+        );
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            prop::collection::vec(
+                (
+                    any::<String>(),
+                    prop_oneof![
+                        any::<String>().prop_map(VdfValue::String),
+                        inner.prop_map(VdfValue::Section),
+                    ],
+                ),
+                0..4,
+            )
+            .prop_map(|entries| VdfObject { entries })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_unescape_reverses_escape(value in any::<String>()) {
+            prop_assert_eq!(unescape(&escape(&value)), value);
+        }
+
+        #[test]
+        fn test_escaped_strings_have_no_bare_quotes_or_newlines(value in any::<String>()) {
+            let escaped = escape(&value);
+            prop_assert!(!escaped.contains(['\n', '\r']));
+            // every quote is preceded by an odd run of backslashes
+            let mut backslashes = 0;
+            for ch in escaped.chars() {
+                if ch == '"' {
+                    prop_assert!(backslashes % 2 == 1);
+                }
+                backslashes = if ch == '\\' { backslashes + 1 } else { 0 };
+            }
+        }
+
+        #[test]
+        fn test_written_objects_parse_back(object in arb_object()) {
+            prop_assert_eq!(parse(&to_string(&object)).unwrap(), object);
+        }
+    }
+}
@@ -0,0 +1,797 @@
+//! Watch mode
+//!
+//! A list of AppID/branch/platform entries that are polled for new builds. When
+//! the latest build differs from the last one packed (or queued), a job for it is
+//! added to the download queue. Entries and their last results are kept in
+//! `watch.json` in the app data directory, so watching survives restarts.
+//!
+//! Build lookups go through `BuildSource`, so the polling logic can run against
+//! a fake source instead of Steam.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::accounts::resolve_job_account;
+use crate::depot_runner::{
+    active_job_id, build_identity, discover_depots_blocking, DepotRunnerState, JobMetadata,
+};
+use crate::job_host::{JobHost, SharedJobHost};
+use crate::job_queue::JobQueueState;
+use crate::job_staging::generate_job_id;
+use crate::output_history::{read_history, HistoryEntry};
+use crate::redaction::redact_log_line;
+use crate::steamdb_api::fetch_latest_build_id;
+
+pub const WATCH_FILE_NAME: &str = "watch.json";
+
+/// Shortest polling interval, to stay friendly with SteamDB and Steam
+pub const MIN_INTERVAL_MINUTES: u64 = 5;
+
+/// How often the scheduler looks for due entries
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where the latest build of a watched app comes from
+pub trait BuildSource {
+    /// Latest build ID for the job's app and branch. `Ok(None)` means the source
+    /// can't answer right now (e.g. the runner is busy) and the check is retried
+    /// on the next tick instead of waiting a full interval.
+    fn latest_build_id(&self, job: &JobMetadata) -> Result<Option<String>, String>;
+}
+
+/// Looks builds up on Steam: SteamDB's patch notes feed for the public branch,
+/// otherwise (or when the feed fails) a manifest-only DepotDownloader preflight
+pub struct SteamBuildSource {
+    host: SharedJobHost,
+    runner: DepotRunnerState,
+}
+
+impl SteamBuildSource {
+    pub fn new(host: SharedJobHost, runner: DepotRunnerState) -> Self {
+        Self { host, runner }
+    }
+}
+
+impl BuildSource for SteamBuildSource {
+    fn latest_build_id(&self, job: &JobMetadata) -> Result<Option<String>, String> {
+        let branch = job.branch.trim();
+        let rss_error = if branch.is_empty() || branch.eq_ignore_ascii_case("public") {
+            // SteamDB's feed only follows the public branch
+            match fetch_latest_build_id(&job.app_id) {
+                Ok(build_id) => return Ok(Some(build_id)),
+                Err(err) => Some(err),
+            }
+        } else {
+            None
+        };
+
+        // Preflight needs the runner; try again once the current job is done
+        if active_job_id(&self.runner).is_some() {
+            return Ok(None);
+        }
+        match discover_depots_blocking(&self.host, &self.runner, job) {
+            Ok(preflight) => preflight
+                .build_id
+                .map(Some)
+                .ok_or_else(|| "Preflight did not report a build ID".to_string()),
+            Err(err) => Err(match rss_error {
+                Some(rss_error) => format!("{}; preflight: {}", rss_error, err),
+                None => err,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchEntry {
+    pub id: String,
+    /// Job queued for each new build; its app, branch and OS are what is watched
+    pub job: JobMetadata,
+    pub interval_minutes: u64,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Latest build the source reported
+    #[serde(default)]
+    pub latest_build_id: Option<String>,
+    /// Last build queued for packing; a build is queued at most once
+    #[serde(default)]
+    pub queued_build_id: Option<String>,
+    #[serde(default)]
+    pub queued_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl WatchEntry {
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        !self.paused
+            && self.last_checked_at.is_none_or(|checked| {
+                now - checked >= chrono::Duration::minutes(self.interval_minutes as i64)
+            })
+    }
+
+    /// The job to queue for a new build: skips quietly if the build turns out to
+    /// be packed already, and never resumes someone else's download
+    fn queued_job(&self) -> JobMetadata {
+        let mut job = self.job.clone();
+        job.skip_packed = true;
+        job.resume = false;
+        job
+    }
+}
+
+/// Checks a job template before it is watched. Watched jobs run unattended and
+/// after restarts, so they can't rely on QR login or on secrets that are not
+/// stored on disk: they log in through a saved account (checked by
+/// `account_login`, see `accounts::resolve_job_account`) or anonymously.
+fn validate_watch_job(
+    job: &JobMetadata,
+    account_login: &dyn Fn(&JobMetadata) -> Result<(), String>,
+) -> Result<(), String> {
+    if job.app_id.trim().is_empty() {
+        return Err("Watch entries need an AppID".to_string());
+    }
+    if job.platform_list().len() > 1 {
+        return Err("Watch entries cover one platform; add one entry per platform".to_string());
+    }
+    if job.qr_enabled {
+        return Err(
+            "QR login needs interaction; watch with a saved login or anonymous access".to_string(),
+        );
+    }
+    if job.compression_password_enabled && !job.compression_password.trim().is_empty() {
+        return Err(
+            "Archive passwords are not stored on disk, so watched jobs can't use them".to_string(),
+        );
+    }
+    if !job.username.trim().is_empty() || !job.password.is_empty() {
+        return Err(
+            "Watched jobs can't keep a typed login; save it as an account and watch with that"
+                .to_string(),
+        );
+    }
+    account_login(job)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchList {
+    #[serde(default)]
+    entries: Vec<WatchEntry>,
+}
+
+impl WatchList {
+    fn add(
+        &mut self,
+        mut job: JobMetadata,
+        interval_minutes: u64,
+        account_login: &dyn Fn(&JobMetadata) -> Result<(), String>,
+    ) -> Result<String, String> {
+        validate_watch_job(&job, account_login)?;
+        job.platforms.clear();
+        let duplicate = self.entries.iter().any(|entry| {
+            entry.job.app_id == job.app_id
+                && entry.job.branch.eq_ignore_ascii_case(&job.branch)
+                && entry.job.os == job.os
+                && entry.job.language() == job.language()
+                && entry.job.low_violence == job.low_violence
+        });
+        if duplicate {
+            return Err("This app, branch and platform are already watched".to_string());
+        }
+
+        let id = generate_job_id();
+        self.entries.push(WatchEntry {
+            id: id.clone(),
+            job,
+            interval_minutes: interval_minutes.max(MIN_INTERVAL_MINUTES),
+            paused: false,
+            last_checked_at: None,
+            latest_build_id: None,
+            queued_build_id: None,
+            queued_at: None,
+            last_error: None,
+        });
+        Ok(id)
+    }
+
+    fn entry_mut(&mut self, entry_id: &str) -> Result<&mut WatchEntry, String> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.id == entry_id)
+            .ok_or_else(|| format!("Watch entry not found: {}", entry_id))
+    }
+
+    fn remove(&mut self, entry_id: &str) -> Result<(), String> {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.id != entry_id);
+        if self.entries.len() == before {
+            return Err(format!("Watch entry not found: {}", entry_id));
+        }
+        Ok(())
+    }
+
+    fn due_entries(&self, now: DateTime<Utc>) -> Vec<WatchEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_due(now))
+            .cloned()
+            .collect()
+    }
+
+    /// Records the result of checking an entry. When the build is new (not queued
+    /// before and not found packed in the history), or its pack has been deleted,
+    /// its job is handed to `enqueue`, and the build counts as queued only once
+    /// that succeeds. Returns the queued job.
+    fn apply_check(
+        &mut self,
+        entry_id: &str,
+        result: Result<Option<String>, String>,
+        history: &[HistoryEntry],
+        now: DateTime<Utc>,
+        enqueue: &dyn Fn(JobMetadata) -> Result<(), String>,
+    ) -> Option<JobMetadata> {
+        // The entry may have been removed while its build was looked up
+        let entry = self.entry_mut(entry_id).ok()?;
+        let build_id = match result {
+            Ok(Some(build_id)) => build_id,
+            Ok(None) => return None,
+            Err(err) => {
+                entry.last_checked_at = Some(now);
+                entry.last_error = Some(redact_log_line(&err).into_owned());
+                return None;
+            }
+        };
+
+        entry.last_checked_at = Some(now);
+        entry.last_error = None;
+        entry.latest_build_id = Some(build_id.clone());
+
+        let identity = build_identity(&entry.job, &entry.id, build_id.clone());
+        let packs: Vec<&HistoryEntry> = history
+            .iter()
+            .filter(|packed| packed.packs_build(&identity))
+            .collect();
+        if packs.iter().any(|packed| packed.existing_output().is_some()) {
+            entry.queued_build_id = Some(build_id);
+            return None;
+        }
+        // A pack that finished after the build was last queued but has since been
+        // deleted is packed again
+        let pack_deleted = packs.iter().any(|packed| {
+            entry
+                .queued_at
+                .is_none_or(|queued_at| packed.finished_at >= queued_at)
+        });
+        if entry.queued_build_id.as_deref() == Some(build_id.as_str()) && !pack_deleted {
+            return None;
+        }
+
+        let job = entry.queued_job();
+        if let Err(err) = enqueue(job.clone()) {
+            // Left unrecorded, so the build is offered again on the next check
+            let err = format!("Failed to queue build {}: {}", build_id, err);
+            entry.last_error = Some(redact_log_line(&err).into_owned());
+            return None;
+        }
+        entry.queued_build_id = Some(build_id);
+        entry.queued_at = Some(now);
+        Some(job)
+    }
+}
+
+#[derive(Clone)]
+pub struct WatchListState {
+    inner: Arc<(Mutex<WatchList>, Condvar)>,
+    path: Option<PathBuf>,
+}
+
+impl WatchListState {
+    /// Loads the watch list from `path`, or starts empty when the file is missing
+    /// or unreadable. With no path the list is kept in memory only.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let list = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| match fs::read_to_string(path) {
+                Ok(content) => match serde_json::from_str::<WatchList>(&content) {
+                    Ok(list) => Some(list),
+                    Err(err) => {
+                        eprintln!("Failed to parse {}: {err}", path.display());
+                        None
+                    }
+                },
+                Err(err) => {
+                    eprintln!("Failed to read {}: {err}", path.display());
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            inner: Arc::new((Mutex::new(list), Condvar::new())),
+            path,
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, WatchList>, String> {
+        self.inner
+            .0
+            .lock()
+            .map_err(|_| "Failed to lock watch list".to_string())
+    }
+
+    fn persist(&self, list: &WatchList) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(list)
+            .map_err(|e| format!("Failed to serialize watch list: {}", e))
+            .and_then(|json| {
+                if let Some(parent) = path.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                let temp_path = path.with_extension("json.tmp");
+                fs::write(&temp_path, json)
+                    .and_then(|_| fs::rename(&temp_path, path))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+            });
+        if let Err(err) = result {
+            eprintln!("{err}");
+        }
+    }
+
+    /// Applies a change, persists the list and wakes the scheduler.
+    /// Returns the change's value and the entries afterwards.
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut WatchList) -> Result<T, String>,
+    ) -> Result<(T, Vec<WatchEntry>), String> {
+        let mut guard = self.lock()?;
+        let value = change(&mut guard)?;
+        self.persist(&guard);
+        let entries = guard.entries.clone();
+        drop(guard);
+        self.inner.1.notify_all();
+        Ok((value, entries))
+    }
+
+    pub fn entries(&self) -> Result<Vec<WatchEntry>, String> {
+        Ok(self.lock()?.entries.clone())
+    }
+
+    /// Checks every due entry against `source` and queues new builds through
+    /// `enqueue`. Lookups run without holding the lock, since a preflight can take
+    /// a while. Returns the jobs queued and, when any entry changed, the entries
+    /// afterwards; watch.json is only rewritten in that case.
+    fn run_checks(
+        &self,
+        source: &dyn BuildSource,
+        history: &[HistoryEntry],
+        now: DateTime<Utc>,
+        enqueue: &dyn Fn(JobMetadata) -> Result<(), String>,
+    ) -> Result<(Vec<JobMetadata>, Option<Vec<WatchEntry>>), String> {
+        let due = self.lock()?.due_entries(now);
+        if due.is_empty() {
+            return Ok((Vec::new(), None));
+        }
+        let results: Vec<_> = due
+            .iter()
+            .map(|entry| (entry.id.clone(), source.latest_build_id(&entry.job)))
+            .collect();
+
+        let mut guard = self.lock()?;
+        let before = guard.entries.clone();
+        let queued = results
+            .into_iter()
+            .filter_map(|(entry_id, result)| {
+                guard.apply_check(&entry_id, result, history, now, enqueue)
+            })
+            .collect();
+        if guard.entries == before {
+            return Ok((queued, None));
+        }
+        self.persist(&guard);
+        Ok((queued, Some(guard.entries.clone())))
+    }
+
+    /// Sleeps until the next tick, or until the list changes
+    fn wait_for_tick(&self) {
+        let guard = match self.inner.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let _ = self.inner.1.wait_timeout(guard, TICK_INTERVAL);
+    }
+}
+
+fn emit_watch_changed(app_handle: &AppHandle, entries: &[WatchEntry]) {
+    let _ = app_handle.emit("watch:changed", entries);
+}
+
+/// Resolves watch.json in the app data directory
+pub fn resolve_watch_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    Ok(host.app_data_dir()?.join(WATCH_FILE_NAME))
+}
+
+/// Starts the background thread that polls watched apps and queues new builds
+pub fn spawn_watch_scheduler(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let watch = app_handle.state::<WatchListState>().inner().clone();
+        let queue = app_handle.state::<JobQueueState>().inner().clone();
+        let runner = app_handle.state::<DepotRunnerState>().inner().clone();
+        let host: SharedJobHost = Arc::new(app_handle.clone());
+        let source = SteamBuildSource::new(host.clone(), runner);
+
+        loop {
+            watch.wait_for_tick();
+
+            let history = read_history(host.as_ref()).unwrap_or_default();
+            let enqueue = |job| queue.enqueue(&app_handle, job).map(|_| ());
+            match watch.run_checks(&source, &history, Utc::now(), &enqueue) {
+                Ok((_, Some(entries))) => emit_watch_changed(&app_handle, &entries),
+                Ok((_, None)) => {}
+                Err(err) => eprintln!("Watch check failed: {err}"),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_watch_list(state: State<'_, WatchListState>) -> Result<Vec<WatchEntry>, String> {
+    state.entries()
+}
+
+#[tauri::command]
+pub fn add_watch_entry(
+    app_handle: AppHandle,
+    state: State<'_, WatchListState>,
+    job: JobMetadata,
+    interval_minutes: u64,
+) -> Result<String, String> {
+    let account_login = |job: &JobMetadata| resolve_job_account(&app_handle, job).map(|_| ());
+    let (entry_id, entries) =
+        state.update(|list| list.add(job, interval_minutes, &account_login))?;
+    emit_watch_changed(&app_handle, &entries);
+    Ok(entry_id)
+}
+
+#[tauri::command]
+pub fn remove_watch_entry(
+    app_handle: AppHandle,
+    state: State<'_, WatchListState>,
+    entry_id: String,
+) -> Result<(), String> {
+    let (_, entries) = state.update(|list| list.remove(&entry_id))?;
+    emit_watch_changed(&app_handle, &entries);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_watch_entry_paused(
+    app_handle: AppHandle,
+    state: State<'_, WatchListState>,
+    entry_id: String,
+    paused: bool,
+) -> Result<(), String> {
+    let (_, entries) = state.update(|list| {
+        list.entry_mut(&entry_id)?.paused = paused;
+        Ok(())
+    })?;
+    emit_watch_changed(&app_handle, &entries);
+    Ok(())
+}
+
+/// Makes every active entry due, so the scheduler checks them on its next wake-up
+#[tauri::command]
+pub fn check_watch_list_now(
+    app_handle: AppHandle,
+    state: State<'_, WatchListState>,
+) -> Result<(), String> {
+    let (_, entries) = state.update(|list| {
+        for entry in list.entries.iter_mut() {
+            entry.last_checked_at = None;
+        }
+        Ok(())
+    })?;
+    emit_watch_changed(&app_handle, &entries);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job_metadata::JobMetadataFile;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// Answers from a fixed table of AppID → build and counts lookups
+    #[derive(Default)]
+    struct FakeSource {
+        builds: HashMap<String, Result<Option<String>, String>>,
+        lookups: RefCell<usize>,
+    }
+
+    impl FakeSource {
+        fn set(&mut self, app_id: &str, result: Result<Option<&str>, &str>) {
+            self.builds.insert(
+                app_id.to_string(),
+                result.map(|build| build.map(str::to_string)).map_err(str::to_string),
+            );
+        }
+    }
+
+    impl BuildSource for FakeSource {
+        fn latest_build_id(&self, job: &JobMetadata) -> Result<Option<String>, String> {
+            *self.lookups.borrow_mut() += 1;
+            self.builds
+                .get(&job.app_id)
+                .cloned()
+                .unwrap_or_else(|| Err("unknown app".to_string()))
+        }
+    }
+
+    fn job(app_id: &str) -> JobMetadata {
+        JobMetadata {
+            app_id: app_id.to_string(),
+            os: "Windows x64".to_string(),
+            branch: "public".to_string(),
+            resume: true,
+            ..Default::default()
+        }
+    }
+
+    fn login_ok(_: &JobMetadata) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn enqueue_ok(_: JobMetadata) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn packed(app_id: &str, build_id: &str, archive: &std::path::Path) -> HistoryEntry {
+        let metadata = JobMetadataFile::new(
+            "old".to_string(),
+            app_id.to_string(),
+            "Public".to_string(),
+            "Win64".to_string(),
+            String::new(),
+            "Game".to_string(),
+            build_id.to_string(),
+            crate::job_metadata::BuildIdSource::PrimaryManifestId,
+            None,
+            Vec::new(),
+        );
+        let mut entry = HistoryEntry::new("old", app_id, "Public", "Win64", Some(metadata), Utc::now());
+        entry.complete(archive, None);
+        entry
+    }
+
+    #[test]
+    fn test_new_builds_are_queued_once() {
+        let state = WatchListState::load(None);
+        let (entry_id, _) = state.update(|list| list.add(job("480"), 1, &login_ok)).unwrap();
+        assert_eq!(state.entries().unwrap()[0].interval_minutes, MIN_INTERVAL_MINUTES);
+
+        let mut source = FakeSource::default();
+        source.set("480", Ok(Some("100")));
+        let now = Utc::now();
+
+        let (jobs, entries) = state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        let entries = entries.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert!(jobs[0].skip_packed);
+        assert!(!jobs[0].resume);
+        assert_eq!(entries[0].queued_build_id.as_deref(), Some("100"));
+
+        // Not due again until the interval has passed
+        let (jobs, _) = state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        assert!(jobs.is_empty());
+        assert_eq!(*source.lookups.borrow(), 1);
+
+        // Same build on the next poll: nothing new
+        let later = now + chrono::Duration::minutes(MIN_INTERVAL_MINUTES as i64);
+        let (jobs, _) = state.run_checks(&source, &[], later, &enqueue_ok).unwrap();
+        assert!(jobs.is_empty());
+
+        // A new build is queued
+        source.set("480", Ok(Some("101")));
+        let later = later + chrono::Duration::minutes(MIN_INTERVAL_MINUTES as i64);
+        let (jobs, entries) = state.run_checks(&source, &[], later, &enqueue_ok).unwrap();
+        let entries = entries.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(entries[0].latest_build_id.as_deref(), Some("101"));
+        assert_eq!(entries[0].id, entry_id);
+    }
+
+    #[test]
+    fn test_builds_in_the_history_are_not_queued() {
+        let state = WatchListState::load(None);
+        state.update(|list| list.add(job("480"), 10, &login_ok)).unwrap();
+        let mut source = FakeSource::default();
+        source.set("480", Ok(Some("100")));
+
+        let archive =
+            std::env::temp_dir().join(format!("omnipacker_watch_{}.7z", generate_job_id()));
+        fs::write(&archive, "packed").unwrap();
+
+        let (jobs, entries) = state
+            .run_checks(&source, &[packed("480", "100", &archive)], Utc::now(), &enqueue_ok)
+            .unwrap();
+        let entries = entries.unwrap();
+        assert!(jobs.is_empty());
+        assert_eq!(entries[0].queued_build_id.as_deref(), Some("100"));
+
+        let _ = fs::remove_file(&archive);
+    }
+
+    #[test]
+    fn test_deleted_packs_are_queued_again_once() {
+        let state = WatchListState::load(None);
+        state.update(|list| list.add(job("480"), 10, &login_ok)).unwrap();
+        let mut source = FakeSource::default();
+        source.set("480", Ok(Some("100")));
+        let now = Utc::now();
+
+        let (jobs, _) = state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        assert_eq!(jobs.len(), 1);
+
+        // The queued job finished, then its archive was deleted
+        let mut deleted = packed("480", "100", std::path::Path::new("/nonexistent/Game.7z"));
+        deleted.finished_at = now + chrono::Duration::minutes(5);
+        let history = [deleted];
+        let later = now + chrono::Duration::minutes(10);
+        let (jobs, _) = state.run_checks(&source, &history, later, &enqueue_ok).unwrap();
+        assert_eq!(jobs.len(), 1);
+
+        // Queued again already; the old pack does not trigger another job
+        let later = later + chrono::Duration::minutes(10);
+        let (jobs, _) = state.run_checks(&source, &history, later, &enqueue_ok).unwrap();
+        assert!(jobs.is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_checks_do_not_report_entries() {
+        let state = WatchListState::load(None);
+        state.update(|list| list.add(job("480"), 10, &login_ok)).unwrap();
+        let mut source = FakeSource::default();
+        source.set("480", Ok(None));
+
+        // Nothing due, or a busy source: the list is left as it was
+        let (_, entries) = state.run_checks(&source, &[], Utc::now(), &enqueue_ok).unwrap();
+        assert!(entries.is_none());
+        source.set("480", Ok(Some("100")));
+        let now = Utc::now();
+        let (_, entries) = state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        assert!(entries.is_some());
+        let (_, entries) = state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        assert!(entries.is_none());
+    }
+
+    #[test]
+    fn test_errors_are_recorded_and_busy_sources_retry_on_the_next_tick() {
+        let state = WatchListState::load(None);
+        state.update(|list| list.add(job("480"), 10, &login_ok)).unwrap();
+        state.update(|list| list.add(job("570"), 10, &login_ok)).unwrap();
+        let mut source = FakeSource::default();
+        source.set("480", Err("feed unavailable"));
+        source.set("570", Ok(None));
+        let now = Utc::now();
+
+        let (jobs, entries) = state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        let entries = entries.unwrap();
+        assert!(jobs.is_empty());
+        assert_eq!(entries[0].last_error.as_deref(), Some("feed unavailable"));
+        assert!(entries[0].last_checked_at.is_some());
+        assert!(entries[1].last_checked_at.is_none());
+
+        // Only the busy entry is looked up again right away
+        state.run_checks(&source, &[], now, &enqueue_ok).unwrap();
+        assert_eq!(*source.lookups.borrow(), 3);
+    }
+
+    #[test]
+    fn test_paused_entries_are_skipped() {
+        let state = WatchListState::load(None);
+        let (entry_id, _) = state.update(|list| list.add(job("480"), 10, &login_ok)).unwrap();
+        state
+            .update(|list| {
+                list.entry_mut(&entry_id)?.paused = true;
+                Ok(())
+            })
+            .unwrap();
+        let mut source = FakeSource::default();
+        source.set("480", Ok(Some("100")));
+
+        let (jobs, _) = state.run_checks(&source, &[], Utc::now(), &enqueue_ok).unwrap();
+        assert!(jobs.is_empty());
+        assert_eq!(*source.lookups.borrow(), 0);
+    }
+
+    #[test]
+    fn test_watch_jobs_are_validated() {
+        let mut list = WatchList::default();
+        list.add(job("480"), 10, &login_ok).unwrap();
+        assert!(list.add(job("480"), 10, &login_ok).is_err(), "duplicate");
+
+        let mut qr = job("570");
+        qr.qr_enabled = true;
+        assert!(list.add(qr, 10, &login_ok).is_err());
+
+        let mut multi = job("570");
+        multi.platforms = vec!["Windows x64".to_string(), "Linux".to_string()];
+        assert!(list.add(multi, 10, &login_ok).is_err());
+
+        let mut password = job("570");
+        password.compression_password_enabled = true;
+        password.compression_password = "secret".to_string();
+        assert!(list.add(password, 10, &login_ok).is_err());
+
+        let mut beta = job("480");
+        beta.branch = "beta".to_string();
+        assert!(list.add(beta, 10, &login_ok).is_ok());
+    }
+
+    #[test]
+    fn test_watched_jobs_log_in_with_an_account_or_anonymously() {
+        let mut list = WatchList::default();
+        let mut typed = job("480");
+        typed.username = "user".to_string();
+        typed.password = "hunter2".to_string();
+        assert!(list.add(typed, 10, &login_ok).is_err());
+        let mut cached_username = job("480");
+        cached_username.username = "user".to_string();
+        assert!(list.add(cached_username, 10, &login_ok).is_err());
+
+        let no_login = |job: &JobMetadata| {
+            if job.account_id.is_empty() {
+                Ok(())
+            } else {
+                Err("no password and no cached login".to_string())
+            }
+        };
+        let mut account = job("480");
+        account.account_id = "main".to_string();
+        assert!(list.add(account.clone(), 10, &no_login).is_err());
+        assert!(list.add(job("570"), 10, &no_login).is_ok());
+        list.add(account, 10, &login_ok).unwrap();
+
+        let json = serde_json::to_string(&list).unwrap();
+        let restored: WatchList = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.entries.len(), 2);
+        assert_eq!(restored.entries[1].job.account_id, "main");
+    }
+
+    #[test]
+    fn test_builds_that_fail_to_queue_are_offered_again() {
+        let state = WatchListState::load(None);
+        state.update(|list| list.add(job("480"), 10, &login_ok)).unwrap();
+        let mut source = FakeSource::default();
+        source.set("480", Ok(Some("100")));
+        let now = Utc::now();
+
+        let queue_full = |_: JobMetadata| Err("queue unavailable".to_string());
+        let (jobs, entries) = state.run_checks(&source, &[], now, &queue_full).unwrap();
+        let entries = entries.unwrap();
+        assert!(jobs.is_empty());
+        assert_eq!(entries[0].queued_build_id, None);
+        assert_eq!(entries[0].queued_at, None);
+        assert!(entries[0].last_error.as_deref().unwrap().contains("queue unavailable"));
+
+        let later = now + chrono::Duration::minutes(10);
+        let (jobs, entries) = state.run_checks(&source, &[], later, &enqueue_ok).unwrap();
+        let entries = entries.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(entries[0].queued_build_id.as_deref(), Some("100"));
+        assert_eq!(entries[0].last_error, None);
+    }
+}
@@ -3,50 +3,83 @@ use std::{
     io::{BufReader, Read},
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    sync::{Arc, Mutex},
-    thread,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
     time::Duration,
 };
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, State};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
-use crate::debug_console::DebugConsoleState;
+use crate::archive_format::ArchiveFormat;
+use crate::compression_profile::{CompressionMethod, CompressionProfile};
+use crate::job_host::{JobHost, SharedJobHost};
 
 #[derive(Clone)]
 pub struct SevenZipRunnerState {
     child: Arc<Mutex<Option<Child>>>,
+    /// Set while an in-process archive writer (tar formats) is running
+    native_running: Arc<AtomicBool>,
+    /// Asks the in-process writer to stop at the next read
+    native_cancel: Arc<AtomicBool>,
 }
 
 impl SevenZipRunnerState {
     pub fn new() -> Self {
         Self {
             child: Arc::new(Mutex::new(None)),
+            native_running: Arc::new(AtomicBool::new(false)),
+            native_cancel: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    /// Claims the runner for an in-process writer; released when the guard drops
+    pub fn begin_native(&self) -> Result<NativeArchiveGuard, String> {
+        let guard = self
+            .child
+            .lock()
+            .map_err(|_| "Failed to lock 7-Zip state".to_string())?;
+        if guard.is_some() || self.native_running.swap(true, Ordering::SeqCst) {
+            return Err("7-Zip is already running".to_string());
+        }
+        self.native_cancel.store(false, Ordering::SeqCst);
+        Ok(NativeArchiveGuard {
+            running: self.native_running.clone(),
+        })
+    }
+
+    /// Whether `cancel_7zip` was called for the running in-process writer
+    pub fn native_cancel_requested(&self) -> bool {
+        self.native_cancel.load(Ordering::SeqCst)
+    }
 }
 
-#[derive(Clone, Serialize)]
-struct StatusPayload {
-    status: String,
-    code: Option<i32>,
+pub struct NativeArchiveGuard {
+    running: Arc<AtomicBool>,
 }
 
-#[derive(Clone, Serialize)]
-struct LogPayload {
-    stream: String,
-    line: String,
+impl Drop for NativeArchiveGuard {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
 }
 
+/// Output lines collected from a 7-Zip run
+type CapturedOutput = Arc<Mutex<Vec<String>>>;
+
 #[derive(Clone, Serialize)]
-struct ProgressPayload {
-    percent: u8,
+struct StatusPayload {
+    status: String,
+    code: Option<i32>,
 }
 
-pub fn resolve_7zip_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
-    match resolve_bundled_7zip_path(app_handle) {
+pub fn resolve_7zip_path(host: &dyn JobHost) -> Result<PathBuf, String> {
+    match resolve_bundled_7zip_path(host) {
         Ok(path) => Ok(path),
         Err(bundled_error) => resolve_system_7zip_path().ok_or_else(|| {
             format!(
@@ -67,7 +100,7 @@ pub fn run_7zip(
         .lock()
         .map_err(|_| "Failed to lock 7-Zip state".to_string())?;
 
-    if guard.is_some() {
+    if guard.is_some() || state.native_running.load(Ordering::SeqCst) {
         return Err("7-Zip is already running".to_string());
     }
 
@@ -109,12 +142,13 @@ pub fn run_7zip(
 
     emit_status(&app_handle, "running", None);
 
+    let host: SharedJobHost = Arc::new(app_handle.clone());
     if let Some(stream) = stdout {
-        spawn_log_reader(app_handle.clone(), stream, "stdout");
+        spawn_log_reader(host.clone(), stream, "stdout", String::new(), None);
     }
 
     if let Some(stream) = stderr {
-        spawn_log_reader(app_handle.clone(), stream, "stderr");
+        spawn_log_reader(host.clone(), stream, "stderr", String::new(), None);
     }
 
     let state_handle = state.child.clone();
@@ -171,6 +205,11 @@ pub fn cancel_7zip(
         .map_err(|_| "Failed to lock 7-Zip state".to_string())?;
 
     let Some(child) = guard.as_mut() else {
+        if state.native_running.load(Ordering::SeqCst) {
+            state.native_cancel.store(true, Ordering::SeqCst);
+            emit_status(&app_handle, "exited", None);
+            return Ok(());
+        }
         return Err("7-Zip is not running".to_string());
     };
 
@@ -190,27 +229,58 @@ pub fn cancel_7zip(
 
 /// Runs 7-Zip synchronously and waits for completion.
 /// Unlike `run_7zip()`, this blocks until the process exits and returns the exit code.
-/// Used for compression in the job finalization pipeline.
+/// Used for compression in the job finalization pipeline; output is logged
+/// under `job_id`.
 pub fn run_7zip_blocking(
-    app_handle: &AppHandle,
+    host: &SharedJobHost,
+    state: &SevenZipRunnerState,
+    args: Vec<String>,
+    job_id: &str,
+) -> Result<i32, String> {
+    run_7zip_blocking_inner(host, state, args, job_id, None)
+}
+
+/// Like `run_7zip_blocking()`, but also returns 7-Zip's output lines (progress
+/// lines excluded) so the caller can parse its summary.
+pub fn run_7zip_blocking_captured(
+    host: &SharedJobHost,
     state: &SevenZipRunnerState,
     args: Vec<String>,
+    job_id: &str,
+) -> Result<(i32, Vec<String>), String> {
+    let captured: CapturedOutput = Arc::new(Mutex::new(Vec::new()));
+    let code = run_7zip_blocking_inner(host, state, args, job_id, Some(captured.clone()))?;
+    let lines = captured
+        .lock()
+        .map(|lines| lines.clone())
+        .map_err(|_| "Failed to read 7-Zip output".to_string())?;
+    Ok((code, lines))
+}
+
+fn run_7zip_blocking_inner(
+    host: &SharedJobHost,
+    state: &SevenZipRunnerState,
+    args: Vec<String>,
+    job_id: &str,
+    captured: Option<CapturedOutput>,
 ) -> Result<i32, String> {
     let mut guard = state
         .child
         .lock()
         .map_err(|_| "Failed to lock 7-Zip state".to_string())?;
 
-    if guard.is_some() {
+    if guard.is_some() || state.native_running.load(Ordering::SeqCst) {
         return Err("7-Zip is already running".to_string());
     }
 
-    let path = resolve_7zip_path(app_handle)?;
+    let path = resolve_7zip_path(host.as_ref())?;
 
     let mut command = Command::new(&path);
     command.args(&args);
     command.stdout(Stdio::piped());
     command.stderr(Stdio::piped());
+    // Never wait on a password prompt nobody can answer
+    command.stdin(Stdio::null());
 
     // Hide console window on Windows
     #[cfg(windows)]
@@ -228,11 +298,24 @@ pub fn run_7zip_blocking(
     drop(guard);
 
     // Spawn log readers that emit events
+    let mut readers = Vec::new();
     if let Some(stream) = stdout {
-        spawn_log_reader(app_handle.clone(), stream, "stdout");
+        readers.push(spawn_log_reader(
+            host.clone(),
+            stream,
+            "stdout",
+            job_id.to_string(),
+            captured.clone(),
+        ));
     }
     if let Some(stream) = stderr {
-        spawn_log_reader(app_handle.clone(), stream, "stderr");
+        readers.push(spawn_log_reader(
+            host.clone(),
+            stream,
+            "stderr",
+            job_id.to_string(),
+            captured.clone(),
+        ));
     }
 
     loop {
@@ -260,6 +343,12 @@ pub fn run_7zip_blocking(
         };
 
         if let Some(code) = status_code {
+            // Captured output must be complete before the caller reads it
+            if captured.is_some() {
+                for reader in readers {
+                    let _ = reader.join();
+                }
+            }
             return Ok(code);
         }
 
@@ -268,13 +357,131 @@ pub fn run_7zip_blocking(
 }
 
 /// Calculates optimal 7-Zip compression arguments based on CPU cores.
-/// Prioritizes smallest file size with `-mx9` (ultra compression).
+/// Prioritizes smallest file size with `-mx9` (ultra compression) unless a
+/// compression profile overrides it.
 /// Thread count is adapted to prevent system lockup on weak hardware.
+///
+/// Only formats 7-Zip writes itself (`7z`, `zip`) are handled here; tar formats
+/// go through `tar_archive`.
 pub fn calculate_7z_compression_args(
     source_dir: &std::path::Path,
     output_archive: &std::path::Path,
     password: Option<&str>,
+    format: ArchiveFormat,
+    volume_size_mb: Option<u64>,
+    profile: Option<&CompressionProfile>,
 ) -> Vec<String> {
+    let settings = SevenZipSettings::new(compression_budget(), format, profile);
+
+    let mut args = vec![
+        "a".to_string(),                                    // Add to archive
+        format!("-t{}", format.seven_zip_type()),           // Archive type
+    ];
+    args.extend(settings.args());
+    args.push("-bsp1".to_string());                         // Progress output to stdout
+    if let Some(size) = volume_size_mb {
+        args.push(format!("-v{}m", size));                  // Split into .001, .002, ... volumes
+    }
+
+    if let Some(password) = password {
+        if !password.is_empty() {
+            args.push(format!("-p{}", password));
+            if format == ArchiveFormat::Zip {
+                // ZipCrypto is trivially broken; ask for AES like the 7z format uses
+                args.push("-mem=AES256".to_string());
+            }
+        }
+    }
+
+    args.push(output_archive.to_string_lossy().to_string()); // Archive path
+    args.push(source_dir.to_string_lossy().to_string()); // Source directory
+    args
+}
+
+/// Level/method/thread switches for one 7-Zip run: the adaptive budget with any
+/// profile overrides applied
+struct SevenZipSettings<'a> {
+    budget: CompressionBudget,
+    format: ArchiveFormat,
+    profile: Option<&'a CompressionProfile>,
+}
+
+impl<'a> SevenZipSettings<'a> {
+    fn new(
+        budget: CompressionBudget,
+        format: ArchiveFormat,
+        profile: Option<&'a CompressionProfile>,
+    ) -> Self {
+        Self {
+            budget,
+            format,
+            profile,
+        }
+    }
+
+    fn args(&self) -> Vec<String> {
+        let level = self.profile.map_or(9, |profile| profile.effective_level());
+        let threads = self
+            .profile
+            .map_or(self.budget.threads, |profile| profile.cap_threads(self.budget.threads));
+        let method = self.profile.and_then(|profile| profile.method);
+
+        let mut args = vec![
+            format!("-mx{}", level),                        // Compression level
+            format!("-mmt{}", threads),                     // Multi-threading
+        ];
+
+        match self.format {
+            ArchiveFormat::SevenZip => {
+                if let Some(method) = method {
+                    args.push(format!("-m0={}", method.seven_zip_name()));
+                }
+                if !matches!(method, Some(CompressionMethod::Copy | CompressionMethod::Zstd)) {
+                    // Dictionary size tuned by resources; a profile can pin a smaller one
+                    let dictionary = self
+                        .profile
+                        .filter(|profile| {
+                            profile
+                                .dictionary_bytes()
+                                .is_some_and(|bytes| bytes <= self.budget.dict_bytes)
+                        })
+                        .and_then(|profile| profile.dictionary_size.as_deref())
+                        .unwrap_or(self.budget.dict_label);
+                    args.push(format!("-md={}", dictionary));
+                }
+                if let Some(profile) = self.profile {
+                    if let Some(solid) = profile.solid_block_size.as_deref() {
+                        args.push(format!("-ms={}", solid));
+                    }
+                    if let Some(filter) = profile.filter {
+                        args.push(format!("-mf={}", filter.seven_zip_name()));
+                    }
+                }
+            }
+            ArchiveFormat::Zip => {
+                if method == Some(CompressionMethod::Copy) {
+                    args.push("-mm=Copy".to_string());
+                }
+            }
+            ArchiveFormat::TarZst | ArchiveFormat::TarXz => {}
+        }
+
+        args
+    }
+}
+
+/// Compressor resources chosen for the current machine load
+#[derive(Clone, Copy, Debug)]
+pub struct CompressionBudget {
+    pub threads: usize,
+    /// LZMA dictionary size in bytes
+    pub dict_bytes: u64,
+    /// Dictionary size in 7-Zip's `-md=` notation
+    pub dict_label: &'static str,
+}
+
+/// Picks a thread count and LZMA dictionary size that leave headroom for the system
+pub fn compression_budget() -> CompressionBudget {
     const MB: u64 = 1024 * 1024;
     const GB: u64 = 1024 * MB;
     let cpu_cores = num_cpus::get();
@@ -335,7 +542,7 @@ pub fn calculate_7z_compression_args(
     }
 
     let min_per_thread_bytes = if high_memory_pressure {
-        1 * GB
+        GB
     } else if medium_memory_pressure {
         768 * MB
     } else {
@@ -358,7 +565,7 @@ pub fn calculate_7z_compression_args(
     ];
 
     let mut threads = max_threads.max(1);
-    let dict_label = loop {
+    let (dict_bytes, dict_label) = loop {
         let per_thread_budget = if usable_bytes == 0 {
             0
         } else {
@@ -368,37 +575,24 @@ pub fn calculate_7z_compression_args(
         let mut selected = None;
         for (size, label) in DICT_SIZES.iter().rev() {
             if *size <= max_dict_bytes {
-                selected = Some(*label);
+                selected = Some((*size, *label));
                 break;
             }
         }
-        if let Some(label) = selected {
-            break label;
+        if let Some(selected) = selected {
+            break selected;
         }
         if threads <= 1 {
-            break DICT_SIZES[0].1;
+            break DICT_SIZES[0];
         }
         threads = threads.saturating_sub(1).max(1);
     };
 
-    let mut args = vec![
-        "a".to_string(),                                    // Add to archive
-        "-t7z".to_string(),                                 // 7z format (best compression)
-        "-mx9".to_string(),                                 // Ultra compression level
-        format!("-mmt{}", threads),                         // Multi-threading
-        format!("-md={}", dict_label),                      // Dictionary size tuned by resources
-        "-bsp1".to_string(),                                // Progress output to stdout
-    ];
-
-    if let Some(password) = password {
-        if !password.is_empty() {
-            args.push(format!("-p{}", password));
-        }
+    CompressionBudget {
+        threads,
+        dict_bytes,
+        dict_label,
     }
-
-    args.push(output_archive.to_string_lossy().to_string()); // Archive path
-    args.push(source_dir.to_string_lossy().to_string()); // Source directory
-    args
 }
 
 fn emit_status(app_handle: &AppHandle, status: &str, code: Option<i32>) {
@@ -411,10 +605,6 @@ fn emit_status(app_handle: &AppHandle, status: &str, code: Option<i32>) {
     );
 }
 
-fn emit_progress(app_handle: &AppHandle, percent: u8) {
-    let _ = app_handle.emit("7z:progress", ProgressPayload { percent });
-}
-
 fn extract_percent(line: &str) -> Option<u8> {
     let bytes = line.as_bytes();
     for idx in (0..bytes.len()).rev() {
@@ -450,22 +640,38 @@ fn is_progress_line(line: &str) -> bool {
     number.parse::<u8>().is_ok()
 }
 
-fn spawn_log_reader(app_handle: AppHandle, stream: impl std::io::Read + Send + 'static, tag: &str) {
+/// Forwards 7-Zip output as log/progress events. Non-progress lines are also
+/// appended to `captured` when given, for callers that parse 7-Zip's summary.
+/// Lines are logged under `job_id` (empty outside a job).
+fn spawn_log_reader(
+    host: SharedJobHost,
+    stream: impl std::io::Read + Send + 'static,
+    tag: &str,
+    job_id: String,
+    captured: Option<CapturedOutput>,
+) -> JoinHandle<()> {
     let stream_name = tag.to_string();
 
     thread::spawn(move || {
+        let emit_line = |line: &str| {
+            if is_progress_line(line) {
+                return;
+            }
+            if let Some(captured) = captured.as_ref() {
+                if let Ok(mut lines) = captured.lock() {
+                    lines.push(line.to_string());
+                }
+            }
+            host.emit_7z_log(&stream_name, line, &job_id);
+        };
+
         let mut reader = BufReader::new(stream);
         let mut buffer = [0u8; 1024];
         let mut current_line = String::new();
         let mut last_percent: Option<u8> = None;
         let mut last_was_cr = false;
 
-        loop {
-            let n = match reader.read(&mut buffer) {
-                Ok(n) => n,
-                Err(_) => break,
-            };
-
+        while let Ok(n) = reader.read(&mut buffer) {
             if n == 0 {
                 break;
             }
@@ -477,19 +683,7 @@ fn spawn_log_reader(app_handle: AppHandle, stream: impl std::io::Read + Send + '
                         if !current_line.is_empty() {
                             let line = current_line.clone();
                             current_line.clear();
-                            if !is_progress_line(&line) {
-                                let debug_state = app_handle.state::<DebugConsoleState>();
-                                if debug_state.enabled() {
-                                    debug_state.write_line(&format!("[7z:{stream_name}] {line}"));
-                                }
-                                let _ = app_handle.emit(
-                                    "7z:log",
-                                    LogPayload {
-                                        stream: stream_name.clone(),
-                                        line,
-                                    },
-                                );
-                            }
+                            emit_line(&line);
                         }
                         last_was_cr = true;
                         continue;
@@ -498,19 +692,7 @@ fn spawn_log_reader(app_handle: AppHandle, stream: impl std::io::Read + Send + '
                         if !last_was_cr && !current_line.is_empty() {
                             let line = current_line.clone();
                             current_line.clear();
-                            if !is_progress_line(&line) {
-                                let debug_state = app_handle.state::<DebugConsoleState>();
-                                if debug_state.enabled() {
-                                    debug_state.write_line(&format!("[7z:{stream_name}] {line}"));
-                                }
-                                let _ = app_handle.emit(
-                                    "7z:log",
-                                    LogPayload {
-                                        stream: stream_name.clone(),
-                                        line,
-                                    },
-                                );
-                            }
+                            emit_line(&line);
                         }
                         last_was_cr = false;
                         continue;
@@ -528,7 +710,7 @@ fn spawn_log_reader(app_handle: AppHandle, stream: impl std::io::Read + Send + '
                 if let Some(percent) = extract_percent(&current_line) {
                     if Some(percent) != last_percent {
                         last_percent = Some(percent);
-                        emit_progress(&app_handle, percent);
+                        host.emit_7z_progress(percent);
                     }
                 }
             }
@@ -536,21 +718,9 @@ fn spawn_log_reader(app_handle: AppHandle, stream: impl std::io::Read + Send + '
 
         if !current_line.is_empty() {
             let line = current_line.trim_end_matches('\r').to_string();
-            if !is_progress_line(&line) {
-                let debug_state = app_handle.state::<DebugConsoleState>();
-                if debug_state.enabled() {
-                    debug_state.write_line(&format!("[7z:{stream_name}] {line}"));
-                }
-                let _ = app_handle.emit(
-                    "7z:log",
-                    LogPayload {
-                        stream: stream_name.clone(),
-                        line,
-                    },
-                );
-            }
+            emit_line(&line);
         }
-    });
+    })
 }
 
 /// Determines the platform-specific subdirectory name for binaries
@@ -588,7 +758,7 @@ fn get_platform_subdir() -> &'static str {
     return "unknown";
 }
 
-fn resolve_bundled_7zip_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+fn resolve_bundled_7zip_path(host: &dyn JobHost) -> Result<PathBuf, String> {
     // Determine platform-specific binary name with extension
     #[cfg(windows)]
     let binary_name = "7za.exe";
@@ -597,13 +767,9 @@ fn resolve_bundled_7zip_path(app_handle: &AppHandle) -> Result<PathBuf, String>
 
     let platform_subdir = get_platform_subdir();
 
-    // Use Tauri's path resolution with platform-specific subdirectory
-    let sidecar_path = app_handle
-        .path()
-        .resolve(
-            format!("binaries/{}/{}", platform_subdir, binary_name),
-            tauri::path::BaseDirectory::Resource,
-        )
+    // Resolve via the host's resources directory with platform-specific subdirectory
+    let sidecar_path = host
+        .resource_path(&format!("binaries/{}/{}", platform_subdir, binary_name))
         .map_err(|e| format!("Failed to resolve 7-Zip sidecar: {}", e))?;
 
     if !sidecar_path.exists() {
@@ -686,3 +852,71 @@ fn is_executable(path: &Path) -> bool {
 fn is_executable(path: &Path) -> bool {
     path.is_file()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression_profile::builtin_profiles;
+
+    const BUDGET: CompressionBudget = CompressionBudget {
+        threads: 8,
+        dict_bytes: 64 * 1024 * 1024,
+        dict_label: "64m",
+    };
+
+    fn profile(name: &str) -> CompressionProfile {
+        builtin_profiles()
+            .into_iter()
+            .find(|profile| profile.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_adaptive_settings_keep_ultra_defaults() {
+        let args = SevenZipSettings::new(BUDGET, ArchiveFormat::SevenZip, None).args();
+        assert_eq!(args, vec!["-mx9", "-mmt8", "-md=64m"]);
+
+        let zip = SevenZipSettings::new(BUDGET, ArchiveFormat::Zip, None).args();
+        assert_eq!(zip, vec!["-mx9", "-mmt8"]);
+    }
+
+    #[test]
+    fn test_profiles_override_level_method_and_threads() {
+        let store = profile("store");
+        let args = SevenZipSettings::new(BUDGET, ArchiveFormat::SevenZip, Some(&store)).args();
+        assert_eq!(args, vec!["-mx0", "-mmt8", "-m0=Copy"]);
+        let zip = SevenZipSettings::new(BUDGET, ArchiveFormat::Zip, Some(&store)).args();
+        assert_eq!(zip, vec!["-mx0", "-mmt8", "-mm=Copy"]);
+
+        let fast = profile("fast");
+        let args = SevenZipSettings::new(BUDGET, ArchiveFormat::SevenZip, Some(&fast)).args();
+        assert_eq!(args, vec!["-mx1", "-mmt8", "-md=64m"]);
+
+        let ultra = profile("ultra");
+        let args = SevenZipSettings::new(BUDGET, ArchiveFormat::SevenZip, Some(&ultra)).args();
+        assert_eq!(
+            args,
+            vec!["-mx9", "-mmt2", "-m0=LZMA2", "-md=64m", "-ms=on", "-mf=BCJ2"]
+        );
+    }
+
+    #[test]
+    fn test_pinned_dictionary_is_capped_by_the_budget() {
+        let small = CompressionProfile {
+            name: "small".to_string(),
+            dictionary_size: Some("16m".to_string()),
+            ..Default::default()
+        };
+        let args = SevenZipSettings::new(BUDGET, ArchiveFormat::SevenZip, Some(&small)).args();
+        assert_eq!(args, vec!["-mx9", "-mmt8", "-md=16m"]);
+
+        let roomy = CompressionBudget {
+            threads: 2,
+            dict_bytes: 2 * 1024 * 1024 * 1024,
+            dict_label: "2g",
+        };
+        let ultra = profile("ultra");
+        let args = SevenZipSettings::new(roomy, ArchiveFormat::SevenZip, Some(&ultra)).args();
+        assert!(args.contains(&"-md=1024m".to_string()));
+    }
+}
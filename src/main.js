@@ -11,7 +11,11 @@ const steamPasswordInput = document.getElementById("steam-password");
 const startButton = document.querySelector(".start-button");
 const openOutputButton = document.querySelector(".open-output-button");
 const addToQueueButton = document.querySelector(".add-to-queue");
+const discoverDepotsButton = document.querySelector(".discover-depots-button");
+const depotSummary = document.querySelector(".depot-summary");
+const depotList = document.querySelector(".depot-list");
 const clearQueueButton = document.querySelector(".queue-clear-button");
+const pauseQueueButton = document.querySelector(".queue-pause-button");
 const queueList = document.getElementById("queue-list");
 const consoleOutput = document.querySelector(".console-output");
 const qrModalOverlay = document.querySelector(".qr-modal-overlay");
@@ -32,6 +36,11 @@ const defaultQrToggle = document.getElementById("default-qr-toggle");
 const languageSelect = document.getElementById("language-select");
 const saveLoginButton = document.querySelector(".save-login-button");
 const deleteLoginButton = document.querySelector(".settings-delete-login-button");
+const loginPassphraseInput = document.getElementById("login-passphrase");
+const accountSelect = document.getElementById("account-select");
+const saveAccountButton = document.querySelector(".save-account-button");
+const removeAccountButton = document.querySelector(".remove-account-button");
+const unlockLoginButton = document.querySelector(".unlock-login-button");
 const steamGuardModalOverlay = document.querySelector(".steam-guard-modal-overlay");
 const steamGuardEmailOverlay = document.querySelector(".steam-guard-email-overlay");
 const steamGuardEmailMessage = document.querySelector(".steam-guard-email-message");
@@ -70,16 +79,19 @@ const outputConflictCancelButton = document.querySelector(
   ".output-conflict-cancel",
 );
 
+// Mirror of the backend queue (`queue:changed`), plus each job's logs
 const jobState = {
   jobs: new Map(),
   runningJobId: null,
   selectedJobId: null,
   order: [],
+  paused: false,
 };
 
 const authState = {
   rememberedUsername: null,
   savedLogin: null,
+  accounts: [],
 };
 
 const settingsState = {
@@ -130,8 +142,16 @@ const translations = {
     "tab.console": "Console",
     "queue.label": "Queue:",
     "queue.clear": "Clear Queue",
+    "queue.pause": "Pause Queue",
+    "queue.resume": "Resume Queue",
+    "queue.retry": "Retry",
+    "queue.addFailed": "Failed to add the job to the queue: {{error}}",
+    "queue.commandFailed": "[system] Queue update failed: {{error}}",
+    "queue.failedReason": "[system] Job failed: {{error}}",
+    "resume.prompt": "An interrupted download of this job was found ({{size}} MB, last run {{date}}). Continue it?",
+    "resume.discard": "Delete the interrupted download? Cancel keeps it and starts a new download.",
+    "resume.discardFailed": "Failed to delete the interrupted download: {{error}}",
     "queue.empty": "No downloads queued.",
-    "queue.running": "Queue is running",
     "queue.noReorder": "Cannot reorder/remove while running",
     "queue.atTop": "Already at top",
     "queue.atBottom": "Already at bottom",
@@ -152,10 +172,32 @@ const translations = {
     "auth.loadFailed": "Failed to load saved login data: {{error}}",
     "auth.tauriUnavailable": "Tauri invoke API unavailable.",
     "auth.reuseQr": "[system] Reusing QR login for {{username}}.",
+    "auth.account": "Saved Account:",
+    "auth.noAccount": "None (use the login below)",
+    "auth.saveAccount": "Save as Account",
+    "auth.removeAccount": "Remove Account",
+    "auth.accountNeedsLogin": "needs a login",
+    "auth.accountMissing": "Enter a Steam username to save as an account.",
+    "auth.accountSaveFailed": "Failed to save account: {{error}}",
+    "auth.accountRemoveFailed": "Failed to remove account: {{error}}",
+    "auth.passphrase": "Login Passphrase:",
+    "auth.unlock": "Unlock Saved Login",
+    "auth.passphraseMissing":
+      "Enter the passphrase the saved login was encrypted with.",
+    "auth.passphraseRequired":
+      "No credential store is available. Enter a passphrase to encrypt the saved login.",
     "game.title": "Game Manager",
     "game.appid": "AppID:",
     "game.add": "Add to Queue",
     "game.os": "OS:",
+    "game.depots": "Depots:",
+    "game.discoverDepots": "Choose Depots",
+    "depots.all": "All depots",
+    "depots.loading": "Looking up depots...",
+    "depots.selected": "{{count}} of {{total}} depots",
+    "depots.shared": "shared",
+    "depots.failed": "Failed to look up depots: {{error}}",
+    "depots.noneSelected": "Select at least one depot, or choose depots again.",
     "branch.title": "Branch Manager",
     "branch.label": "Branch to download:",
     "branch.password": "Branch password? (Enter below)",
@@ -259,17 +301,14 @@ const translations = {
     "os.macos": "MacOS",
     "os.macosX64": "MacOS",
     "os.macosArm64": "MacOS",
-    "alert.clearQueue": "Cannot clear queue while a job is running. Cancel the job first.",
     "dd.noDepots":
       "[system] DepotDownloader reported no depots. Verify the OS dropdown matches the target app (Windows x64 is typical).",
-    "job.runningMessage": "A download is already running. Please wait or cancel.",
     "job.tauriUnavailable": "Tauri invoke API unavailable. Unable to start job.",
     "job.noQueued": "No queued jobs.",
     "job.outputDir": "Output directory: {{path}}",
     "job.outputDirError": "Output directory lookup failed: {{error}}",
     "job.selectedOs": "[system] Selected OS: {{os}}",
     "job.starting": "Starting DepotDownloader for AppID {{appId}}...",
-    "job.startFailed": "Start failed: {{error}}",
     "job.canceling": "[system] Cancelling job...",
     "job.cancelled": "[system] Job cancelled by user.",
     "job.cancelFailed": "[system] Cancel failed: {{error}}",
@@ -282,8 +321,16 @@ const translations = {
     "tab.console": "Consola",
     "queue.label": "Cola:",
     "queue.clear": "Limpiar cola",
+    "queue.pause": "Pausar cola",
+    "queue.resume": "Reanudar cola",
+    "queue.retry": "Reintentar",
+    "queue.addFailed": "No se pudo añadir la tarea a la cola: {{error}}",
+    "queue.commandFailed": "[system] No se pudo actualizar la cola: {{error}}",
+    "queue.failedReason": "[system] La tarea falló: {{error}}",
+    "resume.prompt": "Se encontró una descarga interrumpida de esta tarea ({{size}} MB, última ejecución {{date}}). ¿Continuarla?",
+    "resume.discard": "¿Eliminar la descarga interrumpida? Cancelar la conserva e inicia una descarga nueva.",
+    "resume.discardFailed": "No se pudo eliminar la descarga interrumpida: {{error}}",
     "queue.empty": "No hay descargas en cola.",
-    "queue.running": "La cola está en ejecución",
     "queue.noReorder": "No se puede reordenar/eliminar mientras se ejecuta",
     "queue.atTop": "Ya está arriba",
     "queue.atBottom": "Ya está abajo",
@@ -308,10 +355,33 @@ const translations = {
       "No se pudieron cargar los datos de inicio de sesión guardados: {{error}}",
     "auth.tauriUnavailable": "La API invoke de Tauri no está disponible.",
     "auth.reuseQr": "[system] Reutilizando el inicio de sesión por QR para {{username}}.",
+    "auth.account": "Cuenta guardada:",
+    "auth.noAccount": "Ninguna (usar el inicio de sesión de abajo)",
+    "auth.saveAccount": "Guardar como cuenta",
+    "auth.removeAccount": "Eliminar cuenta",
+    "auth.accountNeedsLogin": "requiere iniciar sesión",
+    "auth.accountMissing":
+      "Ingrese un usuario de Steam para guardarlo como cuenta.",
+    "auth.accountSaveFailed": "No se pudo guardar la cuenta: {{error}}",
+    "auth.accountRemoveFailed": "No se pudo eliminar la cuenta: {{error}}",
+    "auth.passphrase": "Frase de contraseña del inicio de sesión:",
+    "auth.unlock": "Desbloquear inicio de sesión guardado",
+    "auth.passphraseMissing":
+      "Ingrese la frase de contraseña con la que se cifró el inicio de sesión guardado.",
+    "auth.passphraseRequired":
+      "No hay un almacén de credenciales disponible. Ingrese una frase de contraseña para cifrar el inicio de sesión guardado.",
     "game.title": "Gestor de juegos",
     "game.appid": "AppID:",
     "game.add": "Agregar a la cola",
     "game.os": "SO:",
+    "game.depots": "Depots:",
+    "game.discoverDepots": "Elegir depots",
+    "depots.all": "Todos los depots",
+    "depots.loading": "Buscando depots...",
+    "depots.selected": "{{count}} de {{total}} depots",
+    "depots.shared": "compartido",
+    "depots.failed": "No se pudieron buscar los depots: {{error}}",
+    "depots.noneSelected": "Selecciona al menos un depot o vuelve a elegir los depots.",
     "branch.title": "Gestor de ramas",
     "branch.label": "Rama para descargar:",
     "branch.password": "¿Contraseña de la rama? (Ingrese abajo)",
@@ -415,11 +485,8 @@ const translations = {
     "os.macos": "MacOS",
     "os.macosX64": "MacOS",
     "os.macosArm64": "MacOS",
-    "alert.clearQueue":
-      "No se puede limpiar la cola mientras hay una tarea en ejecución. Cancele la tarea primero.",
     "dd.noDepots":
       "[system] DepotDownloader no encontró depósitos. Verifique que el desplegable de SO coincida con la app (Windows x64 suele ser lo habitual).",
-    "job.runningMessage": "Ya hay una descarga en ejecución. Espere o cancele.",
     "job.tauriUnavailable":
       "La API invoke de Tauri no está disponible. No se puede iniciar la tarea.",
     "job.noQueued": "No hay tareas en cola.",
@@ -427,7 +494,6 @@ const translations = {
     "job.outputDirError": "No se pudo obtener el directorio de salida: {{error}}",
     "job.selectedOs": "[system] SO seleccionado: {{os}}",
     "job.starting": "Iniciando DepotDownloader para AppID {{appId}}...",
-    "job.startFailed": "El inicio falló: {{error}}",
     "job.canceling": "[system] Cancelando tarea...",
     "job.cancelled": "[system] Tarea cancelada por el usuario.",
     "job.cancelFailed": "[system] Cancelación fallida: {{error}}",
@@ -441,8 +507,16 @@ const translations = {
     "tab.console": "Console",
     "queue.label": "File:",
     "queue.clear": "Vider la file",
+    "queue.pause": "Suspendre la file",
+    "queue.resume": "Reprendre la file",
+    "queue.retry": "Réessayer",
+    "queue.addFailed": "Impossible d'ajouter la tâche à la file : {{error}}",
+    "queue.commandFailed": "[system] Échec de la mise à jour de la file : {{error}}",
+    "queue.failedReason": "[system] La tâche a échoué : {{error}}",
+    "resume.prompt": "Un téléchargement interrompu de cette tâche a été trouvé ({{size}} Mo, dernière exécution {{date}}). Le reprendre ?",
+    "resume.discard": "Supprimer le téléchargement interrompu ? Annuler le conserve et démarre un nouveau téléchargement.",
+    "resume.discardFailed": "Impossible de supprimer le téléchargement interrompu : {{error}}",
     "queue.empty": "Aucun téléchargement en file.",
-    "queue.running": "La file est en cours",
     "queue.noReorder": "Impossible de réorganiser/supprimer pendant l'exécution",
     "queue.atTop": "Déjà en haut",
     "queue.atBottom": "Déjà en bas",
@@ -467,10 +541,33 @@ const translations = {
       "Impossible de charger les identifiants enregistrés: {{error}}",
     "auth.tauriUnavailable": "L'API invoke de Tauri est indisponible.",
     "auth.reuseQr": "[system] Réutilisation de la connexion QR pour {{username}}.",
+    "auth.account": "Compte enregistré:",
+    "auth.noAccount": "Aucun (utiliser les identifiants ci-dessous)",
+    "auth.saveAccount": "Enregistrer comme compte",
+    "auth.removeAccount": "Supprimer le compte",
+    "auth.accountNeedsLogin": "connexion requise",
+    "auth.accountMissing":
+      "Saisissez un nom d'utilisateur Steam pour l'enregistrer comme compte.",
+    "auth.accountSaveFailed": "Impossible d'enregistrer le compte: {{error}}",
+    "auth.accountRemoveFailed": "Impossible de supprimer le compte: {{error}}",
+    "auth.passphrase": "Phrase secrète des identifiants:",
+    "auth.unlock": "Déverrouiller les identifiants",
+    "auth.passphraseMissing":
+      "Saisissez la phrase secrète utilisée pour chiffrer les identifiants enregistrés.",
+    "auth.passphraseRequired":
+      "Aucun gestionnaire d'identifiants n'est disponible. Saisissez une phrase secrète pour chiffrer les identifiants enregistrés.",
     "game.title": "Gestionnaire de jeux",
     "game.appid": "AppID:",
     "game.add": "Ajouter à la file",
     "game.os": "OS:",
+    "game.depots": "Dépôts :",
+    "game.discoverDepots": "Choisir les dépôts",
+    "depots.all": "Tous les dépôts",
+    "depots.loading": "Recherche des dépôts...",
+    "depots.selected": "{{count}} dépôts sur {{total}}",
+    "depots.shared": "partagé",
+    "depots.failed": "Impossible de récupérer les dépôts : {{error}}",
+    "depots.noneSelected": "Sélectionnez au moins un dépôt, ou choisissez à nouveau les dépôts.",
     "branch.title": "Gestionnaire de branches",
     "branch.label": "Branche à télécharger:",
     "branch.password": "Mot de passe de branche ? (Saisir ci-dessous)",
@@ -574,12 +671,8 @@ const translations = {
     "os.macos": "MacOS",
     "os.macosX64": "MacOS",
     "os.macosArm64": "MacOS",
-    "alert.clearQueue":
-      "Impossible de vider la file pendant qu'une tâche est en cours. Annulez d'abord la tâche.",
     "dd.noDepots":
       "[system] DepotDownloader n'a trouvé aucun dépôt. Vérifiez que la liste OS correspond à l'application (Windows x64 est généralement le bon choix).",
-    "job.runningMessage":
-      "Un téléchargement est déjà en cours. Veuillez patienter ou annuler.",
     "job.tauriUnavailable":
       "L'API invoke de Tauri est indisponible. Impossible de démarrer la tâche.",
     "job.noQueued": "Aucune tâche en file.",
@@ -587,7 +680,6 @@ const translations = {
     "job.outputDirError": "Impossible d'obtenir le dossier de sortie: {{error}}",
     "job.selectedOs": "[system] OS sélectionné: {{os}}",
     "job.starting": "Démarrage de DepotDownloader pour l'AppID {{appId}}...",
-    "job.startFailed": "Échec du démarrage: {{error}}",
     "job.canceling": "[system] Annulation de la tâche...",
     "job.cancelled": "[system] Tâche annulée par l'utilisateur.",
     "job.cancelFailed": "[system] Échec de l'annulation: {{error}}",
@@ -601,8 +693,16 @@ const translations = {
     "tab.console": "Konsole",
     "queue.label": "Warteschlange:",
     "queue.clear": "Warteschlange leeren",
+    "queue.pause": "Warteschlange pausieren",
+    "queue.resume": "Warteschlange fortsetzen",
+    "queue.retry": "Erneut versuchen",
+    "queue.addFailed": "Job konnte nicht zur Warteschlange hinzugefügt werden: {{error}}",
+    "queue.commandFailed": "[system] Aktualisieren der Warteschlange fehlgeschlagen: {{error}}",
+    "queue.failedReason": "[system] Job fehlgeschlagen: {{error}}",
+    "resume.prompt": "Ein unterbrochener Download dieses Jobs wurde gefunden ({{size}} MB, zuletzt ausgeführt {{date}}). Fortsetzen?",
+    "resume.discard": "Unterbrochenen Download löschen? Abbrechen behält ihn und startet einen neuen Download.",
+    "resume.discardFailed": "Unterbrochener Download konnte nicht gelöscht werden: {{error}}",
     "queue.empty": "Keine Downloads in der Warteschlange.",
-    "queue.running": "Warteschlange läuft",
     "queue.noReorder": "Reihenfolge/Entfernen während der Ausführung nicht möglich",
     "queue.atTop": "Bereits ganz oben",
     "queue.atBottom": "Bereits ganz unten",
@@ -626,10 +726,33 @@ const translations = {
       "Gespeicherte Login-Daten konnten nicht geladen werden: {{error}}",
     "auth.tauriUnavailable": "Tauri invoke API nicht verfügbar.",
     "auth.reuseQr": "[system] QR-Login für {{username}} wird wiederverwendet.",
+    "auth.account": "Gespeichertes Konto:",
+    "auth.noAccount": "Keines (Login unten verwenden)",
+    "auth.saveAccount": "Als Konto speichern",
+    "auth.removeAccount": "Konto entfernen",
+    "auth.accountNeedsLogin": "Login erforderlich",
+    "auth.accountMissing":
+      "Bitte einen Steam-Benutzernamen eingeben, um ihn als Konto zu speichern.",
+    "auth.accountSaveFailed": "Konto konnte nicht gespeichert werden: {{error}}",
+    "auth.accountRemoveFailed": "Konto konnte nicht entfernt werden: {{error}}",
+    "auth.passphrase": "Login-Passphrase:",
+    "auth.unlock": "Gespeicherten Login entsperren",
+    "auth.passphraseMissing":
+      "Bitte die Passphrase eingeben, mit der der gespeicherte Login verschlüsselt wurde.",
+    "auth.passphraseRequired":
+      "Kein Anmeldeinformationsspeicher verfügbar. Bitte eine Passphrase eingeben, um den gespeicherten Login zu verschlüsseln.",
     "game.title": "Spiel-Manager",
     "game.appid": "AppID:",
     "game.add": "Einreihen",
     "game.os": "OS:",
+    "game.depots": "Depots:",
+    "game.discoverDepots": "Depots wählen",
+    "depots.all": "Alle Depots",
+    "depots.loading": "Depots werden abgefragt...",
+    "depots.selected": "{{count}} von {{total}} Depots",
+    "depots.shared": "geteilt",
+    "depots.failed": "Depots konnten nicht abgefragt werden: {{error}}",
+    "depots.noneSelected": "Wähle mindestens ein Depot aus oder frage die Depots erneut ab.",
     "branch.title": "Branch-Manager",
     "branch.label": "Branch zum Download:",
     "branch.password": "Branch-Passwort? (Unten eingeben)",
@@ -733,11 +856,8 @@ const translations = {
     "os.macos": "MacOS",
     "os.macosX64": "MacOS",
     "os.macosArm64": "MacOS",
-    "alert.clearQueue":
-      "Warteschlange kann nicht geleert werden, während ein Job läuft. Brechen Sie den Job zuerst ab.",
     "dd.noDepots":
       "[system] DepotDownloader hat keine Depots gefunden. Stellen Sie sicher, dass das OS-Dropdown zur App passt (Windows x64 ist üblich).",
-    "job.runningMessage": "Ein Download läuft bereits. Bitte warten oder abbrechen.",
     "job.tauriUnavailable":
       "Tauri invoke API nicht verfügbar. Job kann nicht gestartet werden.",
     "job.noQueued": "Keine Jobs in der Warteschlange.",
@@ -746,7 +866,6 @@ const translations = {
       "Ausgabeverzeichnis konnte nicht ermittelt werden: {{error}}",
     "job.selectedOs": "[system] Ausgewähltes OS: {{os}}",
     "job.starting": "DepotDownloader wird für AppID {{appId}} gestartet...",
-    "job.startFailed": "Start fehlgeschlagen: {{error}}",
     "job.canceling": "[system] Job wird abgebrochen...",
     "job.cancelled": "[system] Job vom Benutzer abgebrochen.",
     "job.cancelFailed": "[system] Abbruch fehlgeschlagen: {{error}}",
@@ -760,8 +879,16 @@ const translations = {
     "tab.console": "Консоль",
     "queue.label": "Очередь:",
     "queue.clear": "Очистить очередь",
+    "queue.pause": "Приостановить очередь",
+    "queue.resume": "Возобновить очередь",
+    "queue.retry": "Повторить",
+    "queue.addFailed": "Не удалось добавить задачу в очередь: {{error}}",
+    "queue.commandFailed": "[system] Не удалось обновить очередь: {{error}}",
+    "queue.failedReason": "[system] Задача завершилась с ошибкой: {{error}}",
+    "resume.prompt": "Найдена прерванная загрузка этой задачи ({{size}} МБ, последний запуск {{date}}). Продолжить её?",
+    "resume.discard": "Удалить прерванную загрузку? Отмена сохранит её и начнёт новую загрузку.",
+    "resume.discardFailed": "Не удалось удалить прерванную загрузку: {{error}}",
     "queue.empty": "Нет загрузок в очереди.",
-    "queue.running": "Очередь выполняется",
     "queue.noReorder": "Нельзя менять порядок/удалять во время выполнения",
     "queue.atTop": "Уже вверху",
     "queue.atBottom": "Уже внизу",
@@ -783,10 +910,33 @@ const translations = {
     "auth.loadFailed": "Не удалось загрузить сохраненные данные: {{error}}",
     "auth.tauriUnavailable": "Tauri invoke API недоступен.",
     "auth.reuseQr": "[system] Повторный вход по QR для {{username}}.",
+    "auth.account": "Сохраненный аккаунт:",
+    "auth.noAccount": "Нет (использовать данные входа ниже)",
+    "auth.saveAccount": "Сохранить как аккаунт",
+    "auth.removeAccount": "Удалить аккаунт",
+    "auth.accountNeedsLogin": "нужен вход",
+    "auth.accountMissing":
+      "Введите имя пользователя Steam, чтобы сохранить его как аккаунт.",
+    "auth.accountSaveFailed": "Не удалось сохранить аккаунт: {{error}}",
+    "auth.accountRemoveFailed": "Не удалось удалить аккаунт: {{error}}",
+    "auth.passphrase": "Парольная фраза входа:",
+    "auth.unlock": "Разблокировать сохраненный вход",
+    "auth.passphraseMissing":
+      "Введите парольную фразу, которой зашифрованы сохраненные данные входа.",
+    "auth.passphraseRequired":
+      "Хранилище учетных данных недоступно. Введите парольную фразу, чтобы зашифровать сохраненные данные входа.",
     "game.title": "Менеджер игр",
     "game.appid": "AppID:",
     "game.add": "Добавить в очередь",
     "game.os": "ОС:",
+    "game.depots": "Депо:",
+    "game.discoverDepots": "Выбрать депо",
+    "depots.all": "Все депо",
+    "depots.loading": "Поиск депо...",
+    "depots.selected": "Депо: {{count}} из {{total}}",
+    "depots.shared": "общее",
+    "depots.failed": "Не удалось получить список депо: {{error}}",
+    "depots.noneSelected": "Выберите хотя бы одно депо или запросите список заново.",
     "branch.title": "Менеджер веток",
     "branch.label": "Ветка для загрузки:",
     "branch.password": "Пароль ветки? (Введите ниже)",
@@ -890,18 +1040,14 @@ const translations = {
     "os.macos": "MacOS",
     "os.macosX64": "MacOS",
     "os.macosArm64": "MacOS",
-    "alert.clearQueue":
-      "Нельзя очистить очередь во время выполнения. Сначала отмените задание.",
     "dd.noDepots":
       "[system] DepotDownloader не нашел депоты. Проверьте, что выбранная ОС соответствует приложению (обычно Windows x64).",
-    "job.runningMessage": "Загрузка уже выполняется. Подождите или отмените.",
     "job.tauriUnavailable": "Tauri invoke API недоступен. Невозможно запустить задачу.",
     "job.noQueued": "В очереди нет заданий.",
     "job.outputDir": "Каталог вывода: {{path}}",
     "job.outputDirError": "Не удалось определить каталог вывода: {{error}}",
     "job.selectedOs": "[system] Выбранная ОС: {{os}}",
     "job.starting": "Запуск DepotDownloader для AppID {{appId}}...",
-    "job.startFailed": "Запуск не удался: {{error}}",
     "job.canceling": "[system] Отмена задания...",
     "job.cancelled": "[system] Задание отменено пользователем.",
     "job.cancelFailed": "[system] Не удалось отменить: {{error}}",
@@ -1714,6 +1860,8 @@ const applyTranslations = () => {
     }
   });
   refreshQrModalText();
+  renderAccountOptions();
+  renderDepotPicker();
   if (templateModalOverlay?.classList.contains("active")) {
     populateTemplateBlockSelect();
     renderTemplateBuilder();
@@ -1840,15 +1988,18 @@ const getFormSnapshot = () => ({
   username: steamUsernameInput?.value?.trim() || "",
   password: steamPasswordInput?.value || "",
   qrEnabled: Boolean(qrLoginToggle?.checked),
+  accountId: accountSelect?.value || "",
 });
 
 const syncAuthFromForm = (job) => {
   if (!job) {
     return;
   }
-  job.username = steamUsernameInput?.value?.trim() || "";
-  job.password = steamPasswordInput?.value || "";
-  job.qrEnabled = Boolean(qrLoginToggle?.checked);
+  job.accountId = accountSelect?.value || "";
+  // A saved account brings its own login
+  job.username = job.accountId ? "" : steamUsernameInput?.value?.trim() || "";
+  job.password = job.accountId ? "" : steamPasswordInput?.value || "";
+  job.qrEnabled = !job.accountId && Boolean(qrLoginToggle?.checked);
   job.rememberPassword = false;
 };
 
@@ -1879,7 +2030,7 @@ const applyRememberedAuth = (job) => {
   if (!job) {
     return;
   }
-  if (job.qrEnabled && authState.rememberedUsername) {
+  if (!job.accountId && job.qrEnabled && authState.rememberedUsername) {
     job.qrEnabled = false;
     job.username = authState.rememberedUsername;
     job.password = "";
@@ -1900,31 +2051,80 @@ const setSavedLogin = (login) => {
 
 const hasSavedLogin = () => Boolean(authState.savedLogin);
 
-const createJob = ({ appId, os, branch, username, password, qrEnabled }) => {
-  const job = {
-    id: createJobId(),
-    appId,
-    os,
-    branch,
-    username,
-    password,
-    qrEnabled,
-    rememberPassword: false,
-    status: "queued",
-    logs: [],
-    compressionProgress: null,
-    qrText: null,
-    qrCaptureActive: false,
-    qrCaptureLines: [],
-    steamGuardEmailPending: false,
-    backendJobId: null, // Job ID assigned by backend (staging directory name)
-    stagingDir: null, // Staging directory path
-  };
-  jobState.jobs.set(job.id, job);
-  jobState.order.push(job.id);
-  return job;
+const renderAccountOptions = () => {
+  if (!accountSelect) {
+    return;
+  }
+  const selected = accountSelect.value;
+  accountSelect.replaceChildren();
+  const none = document.createElement("option");
+  none.value = "";
+  none.textContent = t("auth.noAccount");
+  accountSelect.append(none);
+  authState.accounts.forEach((account) => {
+    const option = document.createElement("option");
+    option.value = account.id;
+    option.textContent = `${account.name} (${account.username})`;
+    if (!account.passwordSaved && !account.authCached) {
+      option.textContent += ` - ${t("auth.accountNeedsLogin")}`;
+    }
+    accountSelect.append(option);
+  });
+  accountSelect.value = authState.accounts.some((account) => account.id === selected)
+    ? selected
+    : "";
+  syncAccountSelection();
+};
+
+const syncAccountSelection = () => {
+  const credentialsPanel = accountSelect?.closest(".credentials");
+  credentialsPanel?.classList.toggle("account-selected", Boolean(accountSelect?.value));
+  if (removeAccountButton) {
+    removeAccountButton.disabled = !accountSelect?.value;
+  }
 };
 
+const loadSavedAccounts = async (selectId = null) => {
+  if (!tauriInvoke) {
+    return;
+  }
+
+  try {
+    const list = await tauriInvoke("list_saved_accounts");
+    authState.accounts = list?.accounts ?? [];
+  } catch (error) {
+    console.debug("[OmniPacker] Failed to load saved accounts:", error);
+    authState.accounts = [];
+  }
+  if (selectId !== null && accountSelect) {
+    accountSelect.value = selectId;
+  }
+  renderAccountOptions();
+};
+
+// Local view of a queue entry; logs and login prompt state only live here
+const createJob = ({ id, appId, os, branch, username, password, qrEnabled, accountId }) => ({
+  id,
+  appId,
+  os,
+  branch,
+  username,
+  password,
+  qrEnabled,
+  accountId,
+  rememberPassword: false,
+  status: "queued",
+  error: null,
+  logs: [],
+  compressionProgress: null,
+  qrText: null,
+  qrCaptureActive: false,
+  qrCaptureLines: [],
+  steamGuardEmailPending: false,
+  backendJobId: null, // Job ID assigned by backend (staging directory name)
+  stagingDir: null, // Staging directory path
+});
+
 const getRunningJob = () =>
   jobState.runningJobId
     ? jobState.jobs.get(jobState.runningJobId) || null
@@ -1963,109 +2163,72 @@ const resolveEventJob = (payload) => {
   return getRunningJob();
 };
 
-const getNextQueuedJob = () => {
-  for (const jobId of jobState.order) {
-    const job = jobState.jobs.get(jobId);
-    if (job?.status === "queued") {
-      return job;
-    }
-  }
-  return null;
-};
-
 const hasQueuedJobs = () =>
   jobState.order.some((jobId) => jobState.jobs.get(jobId)?.status === "queued");
 
 const isRunningJob = (jobId) => jobState.runningJobId === jobId;
 const isQueueRunning = () => Boolean(jobState.runningJobId);
+const isActiveStatus = (status) => status === "running" || status === "compressing";
 
 const updateFormInputState = () => {
-  const running = isQueueRunning();
   const qrEnabled = Boolean(qrLoginToggle?.checked);
   const loginLocked = hasSavedLogin();
 
-  // Disable form inputs
-  if (appIdInput) appIdInput.disabled = running;
-  if (osSelect) osSelect.disabled = running;
-  if (branchInput) branchInput.disabled = running;
-  if (branchPassword) branchPassword.disabled = running || !branchToggle?.checked;
-  if (addToQueueButton) addToQueueButton.disabled = running;
+  // Jobs take the form's values when they are queued, so it stays editable while one runs
+  if (branchPassword) branchPassword.disabled = !branchToggle?.checked;
 
   // Disable credentials (QR or saved login lock)
   if (steamUsernameInput) {
-    steamUsernameInput.disabled = running || qrEnabled || loginLocked;
+    steamUsernameInput.disabled = qrEnabled || loginLocked;
   }
   if (steamPasswordInput) {
-    steamPasswordInput.disabled = running || qrEnabled || loginLocked;
+    steamPasswordInput.disabled = qrEnabled || loginLocked;
   }
-
-  // Visual feedback
-  const gameManager = document.querySelector(".game-manager");
-  const branchManager = document.querySelector('[aria-label="Branch Manager"]');
-  const credentials = document.querySelector(".credentials");
-
-  if (gameManager) gameManager.classList.toggle("form-inputs-disabled", running);
-  if (branchManager) branchManager.classList.toggle("form-inputs-disabled", running);
-  if (credentials) credentials.classList.toggle("form-inputs-disabled", running);
+  if (removeAccountButton) removeAccountButton.disabled = !accountSelect?.value;
 };
 
-const moveJob = (jobId, direction) => {
-  if (isQueueRunning()) {
-    return;
+// Queue changes are made by the backend and come back through `queue:changed`
+const invokeQueueCommand = async (command, args = {}) => {
+  if (!tauriInvoke) {
+    appendSystemMessage(t("job.tauriUnavailable"));
+    return false;
   }
-  if (isRunningJob(jobId)) {
-    return;
+  try {
+    await tauriInvoke(command, args);
+    return true;
+  } catch (error) {
+    appendSystemMessage(t("queue.commandFailed", { error }));
+    return false;
   }
+};
+
+const moveJob = async (jobId, direction) => {
   const index = jobState.order.indexOf(jobId);
-  if (index === -1) {
-    return;
-  }
   const nextIndex = index + direction;
-  if (nextIndex < 0 || nextIndex >= jobState.order.length) {
+  if (index === -1 || nextIndex < 0 || nextIndex >= jobState.order.length) {
     return;
   }
-  const order = jobState.order;
-  [order[index], order[nextIndex]] = [order[nextIndex], order[index]];
+  await invokeQueueCommand("move_queue_entry", { entryId: jobId, index: nextIndex });
 };
 
-const removeJob = (jobId) => {
-  if (isQueueRunning()) {
-    return;
-  }
+const removeJob = async (jobId) => {
   if (isRunningJob(jobId)) {
     return;
   }
-  const index = jobState.order.indexOf(jobId);
-  if (index === -1) {
-    return;
-  }
-  jobState.order.splice(index, 1);
-  jobState.jobs.delete(jobId);
-
-  if (jobState.selectedJobId === jobId) {
-    if (jobState.order.length === 0) {
-      jobState.selectedJobId = null;
-      return;
-    }
-    const nextIndex = Math.min(index, jobState.order.length - 1);
-    jobState.selectedJobId = jobState.order[nextIndex] ?? null;
-  }
+  await invokeQueueCommand("remove_queue_entry", { entryId: jobId });
 };
 
-const clearQueuedJobs = () => {
-  if (isQueueRunning()) {
-    alert(t("alert.clearQueue"));
-    return;
-  }
+const retryJob = async (jobId) => {
+  await invokeQueueCommand("retry_queue_entry", { entryId: jobId });
+};
 
-  if (jobState.order.length === 0) {
-    return;
-  }
+// Removes everything but the running job
+const clearQueuedJobs = async () => {
+  await invokeQueueCommand("clear_job_queue");
+};
 
-  // Clear all jobs
-  jobState.jobs.clear();
-  jobState.order = [];
-  jobState.selectedJobId = null;
+const toggleQueuePause = async () => {
+  await invokeQueueCommand(jobState.paused ? "resume_job_queue" : "pause_job_queue");
 };
 
 const trimJobLogs = (job) => {
@@ -2661,49 +2824,24 @@ if (tauriEvent?.listen) {
       return;
     }
     const nextStatus = mapStatusToJobState(event.payload);
-    if (nextStatus) {
-      const wasRunning = jobState.runningJobId === job.id;
-      const retryRequested =
-        steamGuardEmailRetryState.requested &&
-        steamGuardEmailRetryState.jobId === job.id;
-      const isTerminal = nextStatus === "done" || nextStatus === "failed";
-      if (retryRequested && wasRunning && isTerminal) {
-        resetJobForRetry(job);
-        jobState.runningJobId = null;
-        steamGuardEmailRetryState.requested = false;
-        steamGuardEmailRetryState.jobId = null;
-        closeQrModal();
-        closeSteamGuardModal();
-        closeSteamGuardEmailModal();
-        renderAll();
-        void startJob();
-        return;
-      }
-      const previousStatus = job.status;
-      job.status = nextStatus;
-      if (nextStatus === "compressing") {
-        if (!Number.isFinite(job.compressionProgress)) {
-          job.compressionProgress = 0;
-        }
-      } else if (previousStatus === "compressing") {
-        job.compressionProgress = null;
-      }
-      if (wasRunning && (nextStatus === "done" || nextStatus === "failed")) {
-        jobState.runningJobId = null;
-        closeQrModal();
-        closeSteamGuardModal();
-        closeSteamGuardEmailModal();
-      }
-      renderAll();
-      if (wasRunning && (nextStatus === "done" || nextStatus === "failed")) {
-        const nextJob = getNextQueuedJob();
-        if (nextJob) {
-          void startJob();
-        } else {
-          authState.rememberedUsername = null;
-        }
+    // Whether the whole job finished is reported by `queue:changed`
+    if (!isActiveStatus(nextStatus) || !isRunningJob(job.id)) {
+      return;
+    }
+    const previousStatus = job.status;
+    job.status = nextStatus;
+    if (nextStatus === "compressing") {
+      if (!Number.isFinite(job.compressionProgress)) {
+        job.compressionProgress = 0;
       }
+    } else if (previousStatus === "compressing") {
+      job.compressionProgress = null;
     }
+    renderAll();
+  });
+
+  tauriEvent.listen("queue:changed", (event) => {
+    syncQueue(event.payload);
   });
 
   tauriEvent.listen("dd:output_conflict", (event) => {
@@ -2766,8 +2904,7 @@ const buildJobMetadata = (job) => ({
   appId: job.appId || "unknown",
   os: job.os || "Windows x64",
   branch: job.branch || "public",
-  username: job.username || "",
-  password: job.password || "",
+  accountId: job.accountId || "",
   qrEnabled: Boolean(job.qrEnabled),
   rememberPassword: Boolean(job.rememberPassword),
   skipCompression: settingsState.skipCompression,
@@ -2775,79 +2912,132 @@ const buildJobMetadata = (job) => ({
   compressionPassword: settingsState.compressionPassword,
 });
 
-const startJob = async () => {
-  if (jobState.runningJobId) {
-    const selectedJob = getSelectedJob();
-    const message = t("job.runningMessage");
-    if (selectedJob) {
-      pushJobLog(selectedJob, message);
-      renderAll();
-    } else {
-      console.debug(`[OmniPacker] ${message}`);
-    }
-    return;
-  }
-
-  const selectedJob = getSelectedJob();
-  const jobToRun = getNextQueuedJob();
+// Typed credentials (or a reused QR login) travel beside the job, never inside it
+const buildJobLogin = (job) =>
+  !job.accountId && job.username
+    ? { username: job.username, password: job.password || "" }
+    : null;
 
-  if (!jobToRun) {
-    if (selectedJob) {
-      pushJobLog(selectedJob, t("job.noQueued"));
-      renderAll();
-    } else {
-      console.debug("[OmniPacker] No queued jobs.");
+const logOutputFolder = async (job) => {
+  try {
+    const outputFolder = await tauriInvoke("get_output_folder");
+    if (outputFolder) {
+      pushJobLog(job, t("job.outputDir", { path: outputFolder }));
     }
-    return;
+  } catch (error) {
+    pushJobLog(job, t("job.outputDirError", { error }));
   }
-
-  syncAuthFromForm(jobToRun);
-  applyRememberedAuth(jobToRun);
-  jobState.runningJobId = jobToRun.id;
-  jobState.selectedJobId = jobToRun.id;
   renderAll();
+};
 
-  if (jobToRun.qrEnabled) {
+const onJobStarted = (job) => {
+  jobState.selectedJobId = job.id;
+  if (job.qrEnabled) {
     openQrModal();
     updateQrModalText(t("qr.waiting"));
   }
+  pushJobLog(job, t("job.selectedOs", { os: formatOsLabel(job.os) }));
+  pushJobLog(job, t("job.starting", { appId: job.appId }));
+  void logOutputFolder(job);
+};
 
-  if (!tauriInvoke) {
-    jobToRun.status = "failed";
-    pushJobLog(jobToRun, t("job.tauriUnavailable"));
-    console.debug("[OmniPacker] Tauri invoke unavailable for Start.");
-    jobState.runningJobId = null;
-    closeQrModal();
-    closeSteamGuardModal();
-    renderAll();
-    return;
+const onJobFinished = (job) => {
+  job.compressionProgress = null;
+  closeQrModal();
+  closeSteamGuardModal();
+  closeSteamGuardEmailModal();
+
+  const retryRequested =
+    steamGuardEmailRetryState.requested && steamGuardEmailRetryState.jobId === job.id;
+  if (retryRequested) {
+    steamGuardEmailRetryState.requested = false;
+    steamGuardEmailRetryState.jobId = null;
+    if (job.status === "failed") {
+      resetJobForRetry(job);
+      void retryJob(job.id);
+      return;
+    }
   }
+  if (job.status === "failed" && job.error) {
+    pushJobLog(job, t("queue.failedReason", { error: job.error }));
+  }
+};
 
-  await syncTemplateStorage();
+// Applies a backend queue snapshot (`get_job_queue` / `queue:changed`)
+const syncQueue = (snapshot) => {
+  const entries = snapshot?.entries ?? [];
+  let runningJob = null;
 
-  try {
-    const jobMetadata = buildJobMetadata(jobToRun);
-    try {
-      const outputFolder = await tauriInvoke("get_output_folder");
-      if (outputFolder) {
-        pushJobLog(jobToRun, t("job.outputDir", { path: outputFolder }));
-        renderAll();
+  entries.forEach((entry) => {
+    let job = jobState.jobs.get(entry.id);
+    if (!job) {
+      job = createJob({
+        id: entry.id,
+        appId: entry.job?.appId || "unknown",
+        os: entry.job?.os || "Windows x64",
+        branch: entry.job?.branch || "public",
+        username: entry.username ?? "",
+        password: "",
+        qrEnabled: Boolean(entry.job?.qrEnabled),
+        accountId: entry.job?.accountId ?? "",
+      });
+      jobState.jobs.set(entry.id, job);
+    }
+    const previousStatus = job.status;
+    job.backendJobId = entry.jobId ?? null;
+    job.error = entry.error ?? null;
+    // "compressing" is only known from dd:status
+    if (entry.status !== "running" || !isActiveStatus(job.status)) {
+      job.status = entry.status;
+    }
+    if (entry.status === "running") {
+      runningJob = job;
+      if (!isActiveStatus(previousStatus)) {
+        onJobStarted(job);
       }
-    } catch (error) {
-      pushJobLog(jobToRun, t("job.outputDirError", { error }));
-      renderAll();
+    } else if (isActiveStatus(previousStatus)) {
+      onJobFinished(job);
     }
-    pushJobLog(jobToRun, t("job.selectedOs", { os: formatOsLabel(jobToRun.os) }));
-    pushJobLog(jobToRun, t("job.starting", { appId: jobToRun.appId }));
-    renderAll();
-    // Backend returns the job_id (staging directory name)
-    const backendJobId = await tauriInvoke("run_depotdownloader", { job: jobMetadata });
-    jobToRun.backendJobId = backendJobId;
+  });
+
+  const entryIds = new Set(entries.map((entry) => entry.id));
+  for (const jobId of [...jobState.jobs.keys()]) {
+    if (!entryIds.has(jobId)) {
+      jobState.jobs.delete(jobId);
+    }
+  }
+  jobState.order = entries.map((entry) => entry.id);
+  jobState.runningJobId = runningJob?.id ?? null;
+  jobState.paused = Boolean(snapshot?.paused);
+  if (!jobState.jobs.has(jobState.selectedJobId)) {
+    jobState.selectedJobId = jobState.order[0] ?? null;
+  }
+  if (!runningJob && !hasQueuedJobs()) {
+    authState.rememberedUsername = null;
+  }
+  renderAll();
+};
+
+const refreshQueue = async () => {
+  if (!tauriInvoke) {
+    return;
+  }
+  try {
+    syncQueue(await tauriInvoke("get_job_queue"));
   } catch (error) {
-    jobToRun.status = "failed";
-    pushJobLog(jobToRun, t("job.startFailed", { error }));
-    jobState.runningJobId = null;
-    renderAll();
+    console.debug("[OmniPacker] Failed to load the job queue:", error);
+  }
+};
+
+// Jobs run as soon as they are queued; Start resumes a paused queue
+const startQueue = async () => {
+  if (!hasQueuedJobs()) {
+    appendSystemMessage(t("job.noQueued"));
+    return;
+  }
+  if (jobState.paused) {
+    await syncTemplateStorage();
+    await invokeQueueCommand("resume_job_queue");
   }
 };
 
@@ -2890,6 +3080,9 @@ const updateStartButtonState = () => {
     startButton.textContent = t("action.start");
     startButton.classList.remove("cancel-mode");
   }
+  if (pauseQueueButton) {
+    pauseQueueButton.textContent = t(jobState.paused ? "queue.resume" : "queue.pause");
+  }
 };
 
 if (startButton) {
@@ -2897,7 +3090,7 @@ if (startButton) {
     if (isQueueRunning()) {
       void cancelJob();
     } else {
-      void startJob();
+      void startQueue();
     }
   });
 }
@@ -2921,6 +3114,30 @@ if (openOutputButton) {
   });
 }
 
+let loginStorageStatus = null;
+
+// Shows the passphrase field when the login lives in an encrypted file
+const refreshLoginStorageStatus = async () => {
+  if (!tauriInvoke) {
+    return null;
+  }
+
+  try {
+    loginStorageStatus = await tauriInvoke("get_login_storage_status");
+  } catch (error) {
+    console.debug("[OmniPacker] Failed to read login storage status:", error);
+    loginStorageStatus = null;
+  }
+  const credentialsPanel = steamUsernameInput?.closest(".credentials");
+  credentialsPanel?.classList.toggle(
+    "needs-passphrase",
+    Boolean(loginStorageStatus?.needsPassphrase),
+  );
+  return loginStorageStatus;
+};
+
+const getLoginPassphrase = () => loginPassphraseInput?.value ?? "";
+
 const saveLoginDetails = async () => {
   const username = steamUsernameInput?.value?.trim() ?? "";
   const password = steamPasswordInput?.value ?? "";
@@ -2936,28 +3153,65 @@ const saveLoginDetails = async () => {
   }
 
   try {
-    await tauriInvoke("save_login_data", { username, password });
+    const status = await refreshLoginStorageStatus();
+    // Without a credential store the login is only ever saved encrypted
+    const passphrase = status?.needsPassphrase ? getLoginPassphrase() : null;
+    if (status?.needsPassphrase && !passphrase) {
+      alert(t("auth.passphraseRequired"));
+      return;
+    }
+    await tauriInvoke("save_login_data", {
+      username,
+      password,
+      passphrase,
+    });
     setSavedLogin({ username, password });
     renderAll();
   } catch (error) {
     alert(t("auth.saveFailed", { error: String(error) }));
   }
+  await refreshLoginStorageStatus();
 };
 
-const loadSavedLoginDetails = async () => {
+// `interactive` is set when the user asked to unlock, so problems are reported
+const loadSavedLoginDetails = async ({ interactive = false } = {}) => {
   if (!tauriInvoke) {
     return;
   }
 
+  const status = await refreshLoginStorageStatus();
+  if (status && !status.saved) {
+    return;
+  }
+  const passphrase = status?.needsPassphrase ? getLoginPassphrase() : null;
+  if (status?.needsPassphrase && !passphrase) {
+    if (interactive) {
+      // Old unencrypted files are re-encrypted with the passphrase on load
+      alert(
+        t(
+          status.backend === "legacy_file"
+            ? "auth.passphraseRequired"
+            : "auth.passphraseMissing",
+        ),
+      );
+    }
+    return;
+  }
+
   try {
-    const saved = await tauriInvoke("load_login_data");
+    const saved = await tauriInvoke("load_login_data", { passphrase });
     if (saved?.username && saved?.password) {
       setSavedLogin(saved);
       renderAll();
     }
   } catch (error) {
-    console.debug("[OmniPacker] Failed to load login data:", error);
+    if (interactive) {
+      alert(t("auth.loadFailed", { error: String(error) }));
+    } else {
+      console.debug("[OmniPacker] Failed to load login data:", error);
+    }
   }
+  await refreshLoginStorageStatus();
 };
 
 const deleteSavedLoginDetails = async () => {
@@ -2973,29 +3227,260 @@ const deleteSavedLoginDetails = async () => {
   } catch (error) {
     alert(t("auth.deleteFailed", { error: String(error) }));
   }
+  await refreshLoginStorageStatus();
 };
 
-const addJobToQueue = () => {
-  const job = createJob(getFormSnapshot());
-  job.status = "queued";
-  jobState.selectedJobId = job.id;
+const saveAccount = async () => {
+  const username = steamUsernameInput?.value?.trim() ?? "";
+  const password = steamPasswordInput?.value ?? "";
+
+  if (!username) {
+    alert(t("auth.accountMissing"));
+    return;
+  }
+
+  if (!tauriInvoke) {
+    alert(t("auth.accountSaveFailed", { error: t("auth.tauriUnavailable") }));
+    return;
+  }
+
+  try {
+    const account = await tauriInvoke("add_saved_account", {
+      name: username,
+      username,
+      password: password || null,
+    });
+    await loadSavedAccounts(account.id);
+    renderAll();
+  } catch (error) {
+    alert(t("auth.accountSaveFailed", { error: String(error) }));
+  }
+};
+
+const removeSelectedAccount = async () => {
+  const accountId = accountSelect?.value;
+  if (!accountId) {
+    return;
+  }
+
+  if (!tauriInvoke) {
+    alert(t("auth.accountRemoveFailed", { error: t("auth.tauriUnavailable") }));
+    return;
+  }
+
+  try {
+    await tauriInvoke("remove_saved_account", { accountId });
+    await loadSavedAccounts("");
+    renderAll();
+  } catch (error) {
+    alert(t("auth.accountRemoveFailed", { error: String(error) }));
+  }
+};
+
+// Depots found for the form's app/branch/OS; unchecked depots are left out of the job
+const depotPicker = {
+  depots: [],
+  included: new Set(),
+  loading: false,
+};
+
+const renderDepotPicker = () => {
+  if (depotSummary) {
+    depotSummary.textContent = depotPicker.loading
+      ? t("depots.loading")
+      : depotPicker.depots.length
+        ? t("depots.selected", {
+            count: depotPicker.included.size,
+            total: depotPicker.depots.length,
+          })
+        : t("depots.all");
+  }
+  if (discoverDepotsButton) {
+    discoverDepotsButton.disabled = depotPicker.loading;
+  }
+  if (!depotList) {
+    return;
+  }
+  depotList.hidden = depotPicker.depots.length === 0;
+  depotList.replaceChildren(
+    ...depotPicker.depots.map((depot) => {
+      const row = document.createElement("label");
+      row.className = "checkbox-row";
+      const checkbox = document.createElement("input");
+      checkbox.type = "checkbox";
+      checkbox.checked = depotPicker.included.has(depot.depotId);
+      checkbox.addEventListener("change", () => {
+        if (checkbox.checked) {
+          depotPicker.included.add(depot.depotId);
+        } else {
+          depotPicker.included.delete(depot.depotId);
+        }
+        renderDepotPicker();
+      });
+      const text = document.createElement("span");
+      text.textContent = [
+        depot.depotId,
+        depot.depotName,
+        depot.shared ? `(${t("depots.shared")})` : null,
+      ]
+        .filter(Boolean)
+        .join(" ");
+      row.append(checkbox, text);
+      return row;
+    })
+  );
+};
+
+const resetDepotPicker = () => {
+  depotPicker.depots = [];
+  depotPicker.included.clear();
+  renderDepotPicker();
+};
+
+// Runs a manifest-only preflight for the form's job and lists its depots
+const discoverDepots = async () => {
+  if (!tauriInvoke) {
+    alert(t("depots.failed", { error: t("auth.tauriUnavailable") }));
+    return;
+  }
+
+  const draft = createJob({ id: null, ...getFormSnapshot() });
+  syncAuthFromForm(draft);
+  applyRememberedAuth(draft);
+
+  depotPicker.loading = true;
+  resetDepotPicker();
+  try {
+    const result = await tauriInvoke("discover_depots", {
+      job: buildJobMetadata(draft),
+      login: buildJobLogin(draft),
+    });
+    depotPicker.depots = result.depots || [];
+    depotPicker.included = new Set(depotPicker.depots.map((depot) => depot.depotId));
+  } catch (error) {
+    alert(t("depots.failed", { error: String(error) }));
+  } finally {
+    depotPicker.loading = false;
+    renderDepotPicker();
+  }
+};
+
+// Every depot checked means no filter, so DepotDownloader picks depots as usual
+const selectedDepotIds = () =>
+  depotPicker.included.size < depotPicker.depots.length
+    ? depotPicker.depots
+        .map((depot) => depot.depotId)
+        .filter((depotId) => depotPicker.included.has(depotId))
+    : [];
+
+if (discoverDepotsButton) {
+  discoverDepotsButton.addEventListener("click", () => {
+    void discoverDepots();
+  });
+}
+
+// A depot list only applies to the app/branch/OS it was looked up for
+appIdInput?.addEventListener("input", resetDepotPicker);
+branchInput?.addEventListener("input", resetDepotPicker);
+osSelect?.addEventListener("change", resetDepotPicker);
+
+// Offers to continue an interrupted download of the same app/branch/OS.
+// Declining offers to delete it; keeping it leaves it for a later job.
+const promptResume = async (metadata) => {
+  let resumable = null;
+  try {
+    resumable = await tauriInvoke("find_resumable_download", { job: metadata });
+  } catch (error) {
+    console.warn("[OmniPacker] Failed to look for an interrupted download:", error);
+  }
+  if (!resumable) {
+    return false;
+  }
+
+  const size = (resumable.sizeBytes / (1024 * 1024)).toFixed(1);
+  const date = new Date(resumable.info.updatedAt).toLocaleString();
+  if (window.confirm(t("resume.prompt", { size, date }))) {
+    return true;
+  }
+  if (window.confirm(t("resume.discard"))) {
+    try {
+      await tauriInvoke("discard_resumable_download", {
+        stagingId: resumable.stagingId,
+      });
+    } catch (error) {
+      alert(t("resume.discardFailed", { error: String(error) }));
+    }
+  }
+  return false;
+};
+
+// Queues the form's job in the backend; it runs right away unless the queue is paused
+const addJobToQueue = async () => {
+  if (!tauriInvoke) {
+    alert(t("queue.addFailed", { error: t("auth.tauriUnavailable") }));
+    return;
+  }
+
+  if (depotPicker.depots.length && depotPicker.included.size === 0) {
+    alert(t("depots.noneSelected"));
+    return;
+  }
+
+  const draft = createJob({ id: null, ...getFormSnapshot() });
+  syncAuthFromForm(draft);
+  applyRememberedAuth(draft);
+  await syncTemplateStorage();
+
+  const metadata = buildJobMetadata(draft);
+  metadata.depotIds = selectedDepotIds();
+  metadata.resume = await promptResume(metadata);
+
+  let entryId;
+  try {
+    entryId = await tauriInvoke("enqueue_job", {
+      job: metadata,
+      login: buildJobLogin(draft),
+    });
+  } catch (error) {
+    alert(t("queue.addFailed", { error: String(error) }));
+    return;
+  }
+
   if (appIdInput) {
     appIdInput.value = "";
   }
   if (branchInput) {
     branchInput.value = "public";
   }
+  resetDepotPicker();
+  await refreshQueue();
+  const job = jobState.jobs.get(entryId);
+  if (job) {
+    job.logs.unshift(...draft.logs);
+    job.password = draft.password;
+    job.rememberPassword = draft.rememberPassword;
+    if (!isQueueRunning()) {
+      jobState.selectedJobId = job.id;
+    }
+  }
   renderAll();
 };
 
 if (addToQueueButton) {
-  addToQueueButton.addEventListener("click", addJobToQueue);
+  addToQueueButton.addEventListener("click", () => {
+    void addJobToQueue();
+  });
 }
 
 if (clearQueueButton) {
   clearQueueButton.addEventListener("click", () => {
-    clearQueuedJobs();
-    renderAll();
+    void clearQueuedJobs();
+  });
+}
+
+if (pauseQueueButton) {
+  pauseQueueButton.addEventListener("click", () => {
+    void toggleQueuePause();
   });
 }
 
@@ -3003,7 +3488,7 @@ if (appIdInput) {
   appIdInput.addEventListener("keydown", (event) => {
     if (event.key === "Enter") {
       event.preventDefault();
-      addJobToQueue();
+      void addJobToQueue();
     }
   });
 }
@@ -3045,7 +3530,7 @@ const renderQueue = () => {
   }
 
   if (clearQueueButton) {
-    clearQueueButton.disabled = isQueueRunning();
+    clearQueueButton.disabled = jobState.order.every((jobId) => isRunningJob(jobId));
   }
 
   for (let index = 0; index < jobState.order.length; index += 1) {
@@ -3090,60 +3575,58 @@ const renderQueue = () => {
     controls.className = "queue-item-controls";
 
     const isRunning = isRunningJob(job.id);
-    const queueRunning = isQueueRunning();
-    const disableReason = queueRunning
-      ? t("queue.running")
-      : t("queue.noReorder");
+    const disableReason = t("queue.noReorder");
+
+    if (job.status === "failed") {
+      const retryButton = document.createElement("button");
+      retryButton.type = "button";
+      retryButton.className = "queue-btn queue-btn-retry";
+      retryButton.textContent = "↻";
+      retryButton.title = t("queue.retry");
+      retryButton.addEventListener("click", (event) => {
+        event.stopPropagation();
+        void retryJob(job.id);
+      });
+      controls.appendChild(retryButton);
+    }
 
     const upButton = document.createElement("button");
     upButton.type = "button";
     upButton.className = "queue-btn queue-btn-up";
     upButton.textContent = "▲";
-    upButton.disabled = queueRunning || isRunning || index === 0;
+    upButton.disabled = isRunning || index === 0;
     if (upButton.disabled) {
-      upButton.title = queueRunning
-        ? disableReason
-        : isRunning
-          ? disableReason
-          : t("queue.atTop");
+      upButton.title = isRunning ? disableReason : t("queue.atTop");
     }
     upButton.addEventListener("click", (event) => {
       event.stopPropagation();
-      moveJob(job.id, -1);
-      renderAll();
+      void moveJob(job.id, -1);
     });
 
     const downButton = document.createElement("button");
     downButton.type = "button";
     downButton.className = "queue-btn queue-btn-down";
     downButton.textContent = "▼";
-    downButton.disabled =
-      queueRunning || isRunning || index === jobState.order.length - 1;
+    downButton.disabled = isRunning || index === jobState.order.length - 1;
     if (downButton.disabled) {
-      downButton.title = queueRunning
-        ? disableReason
-        : isRunning
-          ? disableReason
-          : t("queue.atBottom");
+      downButton.title = isRunning ? disableReason : t("queue.atBottom");
     }
     downButton.addEventListener("click", (event) => {
       event.stopPropagation();
-      moveJob(job.id, 1);
-      renderAll();
+      void moveJob(job.id, 1);
     });
 
     const removeButton = document.createElement("button");
     removeButton.type = "button";
     removeButton.className = "queue-btn queue-btn-trash";
     removeButton.textContent = "🗑";
-    removeButton.disabled = queueRunning || isRunning;
+    removeButton.disabled = isRunning;
     if (removeButton.disabled) {
       removeButton.title = disableReason;
     }
     removeButton.addEventListener("click", (event) => {
       event.stopPropagation();
-      removeJob(job.id);
-      renderAll();
+      void removeJob(job.id);
     });
 
     controls.appendChild(upButton);
@@ -3164,6 +3647,13 @@ const renderQueue = () => {
     row.appendChild(top);
     row.appendChild(meta);
 
+    if (job.status === "failed" && job.error) {
+      const error = document.createElement("div");
+      error.className = "queue-item-error";
+      error.textContent = job.error;
+      row.appendChild(error);
+    }
+
     row.addEventListener("click", () => {
       jobState.selectedJobId = job.id;
       renderAll();
@@ -3435,6 +3925,39 @@ if (languageSelect) {
   });
 }
 
+if (accountSelect) {
+  accountSelect.addEventListener("change", () => {
+    syncAccountSelection();
+    renderAll();
+  });
+}
+
+if (saveAccountButton) {
+  saveAccountButton.addEventListener("click", () => {
+    void saveAccount();
+  });
+}
+
+if (removeAccountButton) {
+  removeAccountButton.addEventListener("click", () => {
+    void removeSelectedAccount();
+  });
+}
+
+if (unlockLoginButton) {
+  unlockLoginButton.addEventListener("click", () => {
+    void loadSavedLoginDetails({ interactive: true });
+  });
+}
+
+if (loginPassphraseInput) {
+  loginPassphraseInput.addEventListener("keydown", (event) => {
+    if (event.key === "Enter") {
+      void loadSavedLoginDetails({ interactive: true });
+    }
+  });
+}
+
 if (deleteLoginButton) {
   deleteLoginButton.addEventListener("click", () => {
     void deleteSavedLoginDetails();
@@ -3447,6 +3970,9 @@ void syncTemplateStorage();
 applySettingsToUI();
 applyDefaultQrLogin();
 void loadSavedLoginDetails();
+renderAccountOptions();
+void loadSavedAccounts();
+void refreshQueue();
 
 if (branchInput && !branchInput.value) {
   branchInput.value = "public";